@@ -0,0 +1,117 @@
+//! Compute cumulative along-track distance.
+//!
+//! pof 1.0 files, sbet files, and pos files don't record the distance traveled, which is needed
+//! for chainage-based deliverables. [AlongTrack] fills in each point's
+//! [distance](crate::Point::distance) as it streams.
+
+use crate::geodesy;
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+
+/// A source that sets each point's distance to the cumulative distance along the track.
+///
+/// Distances are in meters, starting at zero (or a configurable offset) at the first point, and
+/// replace any distance the source already provides.
+#[derive(Debug)]
+pub struct AlongTrack {
+    source: Box<dyn Source>,
+    horizontal: bool,
+    distance: f64,
+    previous: Option<Point>,
+}
+
+impl AlongTrack {
+    /// Creates a new along-track source that measures 3D distance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::along_track::AlongTrack;
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut along_track = AlongTrack::new(Box::new(reader));
+    /// assert_eq!(Some(0.0), along_track.source().unwrap().unwrap().distance);
+    /// assert!(along_track.source().unwrap().unwrap().distance.unwrap() > 0.0);
+    /// ```
+    pub fn new(source: Box<dyn Source>) -> AlongTrack {
+        AlongTrack {
+            source,
+            horizontal: false,
+            distance: 0.0,
+            previous: None,
+        }
+    }
+
+    /// Measures only horizontal distance, ignoring changes in altitude.
+    pub fn horizontal(mut self, horizontal: bool) -> AlongTrack {
+        self.horizontal = horizontal;
+        self
+    }
+
+    /// Sets the distance of the first point, e.g. to continue chainage from a previous file.
+    pub fn start(mut self, distance: f64) -> AlongTrack {
+        self.distance = distance;
+        self
+    }
+}
+
+impl Source for AlongTrack {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let mut point = match self.source.source()? {
+            Some(point) => point,
+            None => return Ok(None),
+        };
+        if let Some(previous) = &self.previous {
+            self.distance += if self.horizontal {
+                geodesy::horizontal_distance(previous, &point)
+            } else {
+                geodesy::distance(previous, &point)
+            };
+        }
+        point.distance = Some(self.distance);
+        self.previous = Some(point);
+        Ok(Some(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    fn distances(mut along_track: AlongTrack) -> Vec<f64> {
+        let mut distances = Vec::new();
+        while let Some(point) = along_track.source().unwrap() {
+            distances.push(point.distance.unwrap());
+        }
+        distances
+    }
+
+    fn source() -> Box<dyn Source> {
+        let points: Vec<_> = [0.0, 3.0, 1.0]
+            .into_iter()
+            .map(|altitude| Point {
+                altitude,
+                ..Default::default()
+            })
+            .collect();
+        Box::new(MemorySource::new(points))
+    }
+
+    #[test]
+    fn cumulative() {
+        let distances = distances(AlongTrack::new(source()).start(10.0));
+        assert_eq!(3, distances.len());
+        assert!((distances[0] - 10.0).abs() < 1e-6);
+        assert!((distances[1] - 13.0).abs() < 1e-6);
+        assert!((distances[2] - 15.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn horizontal() {
+        let distances = distances(AlongTrack::new(source()).horizontal(true));
+        assert_eq!(vec![0.0, 0.0, 0.0], distances);
+    }
+}
@@ -0,0 +1,435 @@
+//! Conversion of points into [Apache Arrow](https://arrow.apache.org/) record batches.
+//!
+//! Each field of [Point] becomes one column, named after the field, with the same layout as
+//! [dataframe](crate::dataframe) uses: angular values are stored in radians, the optional
+//! [Accuracy] is flattened into `accuracy_*` columns, and the upper triangle of the optional
+//! [Covariance] into `covariance_*` columns. The event and derived flag are stored in `event` and
+//! `derived` columns. Fix qualities aren't stored.
+//!
+//! [RecordBatches] streams a [Source] as record batches, so a trajectory can be handed to
+//! anything that takes a [RecordBatchReader], such as DataFusion or Polars, without collecting
+//! it first.
+
+use crate::point::{Accuracy, Covariance, Point, SatelliteCount};
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Float64Array, RecordBatch, RecordBatchReader, UInt32Array,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use std::sync::Arc;
+
+/// The default number of points in each record batch.
+pub const DEFAULT_BATCH_SIZE: usize = 65_536;
+
+/// The names of the columns holding a point's accuracy, in order, except for the satellite count.
+const ACCURACY_COLUMNS: [&str; 10] = [
+    "accuracy_time",
+    "accuracy_x",
+    "accuracy_y",
+    "accuracy_z",
+    "accuracy_roll",
+    "accuracy_pitch",
+    "accuracy_yaw",
+    "accuracy_pdop",
+    "accuracy_hdop",
+    "accuracy_vdop",
+];
+
+/// Streams points from a source as record batches.
+#[derive(Debug)]
+pub struct RecordBatches {
+    source: Box<dyn Source>,
+    schema: SchemaRef,
+    batch_size: usize,
+    done: bool,
+}
+
+impl Point {
+    /// Returns the Arrow schema of the record batches made from points.
+    ///
+    /// The time, position, and attitude columns aren't nullable, and every other column is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::Point;
+    /// let schema = Point::arrow_schema();
+    /// assert_eq!("time", schema.field(0).name());
+    /// assert!(schema.field_with_name("accuracy_x").unwrap().is_nullable());
+    /// ```
+    pub fn arrow_schema() -> Schema {
+        let mut fields: Vec<_> = Point::FIELDS
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| Field::new(*name, DataType::Float64, i >= 7))
+            .collect();
+        fields.extend(
+            ACCURACY_COLUMNS
+                .iter()
+                .map(|name| Field::new(*name, DataType::Float64, true)),
+        );
+        fields.push(Field::new(
+            "accuracy_satellite_count",
+            DataType::UInt32,
+            true,
+        ));
+        for name in covariance_columns() {
+            fields.push(Field::new(name, DataType::Float64, true));
+        }
+        fields.push(Field::new("event", DataType::UInt32, true));
+        fields.push(Field::new("derived", DataType::Boolean, false));
+        Schema::new(fields)
+    }
+}
+
+impl RecordBatches {
+    /// Creates a new stream of record batches from a source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::arrow::RecordBatches;
+    /// use pos::sbet::Reader;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let batches: Vec<_> = RecordBatches::new(Box::new(reader)).collect();
+    /// assert_eq!(1, batches.len());
+    /// assert_eq!(2, batches[0].as_ref().unwrap().num_rows());
+    /// ```
+    pub fn new(source: Box<dyn Source>) -> RecordBatches {
+        RecordBatches {
+            source,
+            schema: Arc::new(Point::arrow_schema()),
+            batch_size: DEFAULT_BATCH_SIZE,
+            done: false,
+        }
+    }
+
+    /// Sets the largest number of points in each record batch, which defaults to
+    /// [DEFAULT_BATCH_SIZE].
+    pub fn with_batch_size(mut self, batch_size: usize) -> RecordBatches {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, Error> {
+        let mut points = Vec::with_capacity(self.batch_size);
+        while points.len() < self.batch_size {
+            match self.source.source()? {
+                Some(point) => points.push(point),
+                None => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        if points.is_empty() {
+            Ok(None)
+        } else {
+            record_batch(self.schema.clone(), &points)
+                .map(Some)
+                .map_err(Error::from)
+        }
+    }
+}
+
+impl Iterator for RecordBatches {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_batch() {
+            Ok(batch) => batch.map(Ok),
+            Err(err) => {
+                self.done = true;
+                Some(Err(ArrowError::ExternalError(Box::new(err))))
+            }
+        }
+    }
+}
+
+impl RecordBatchReader for RecordBatches {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Creates a record batch from a slice of points.
+///
+/// # Examples
+///
+/// ```
+/// use pos::arrow;
+/// use pos::Point;
+/// let batch = arrow::to_record_batch(&[Point::default(); 2]).unwrap();
+/// assert_eq!(2, batch.num_rows());
+/// ```
+pub fn to_record_batch(points: &[Point]) -> Result<RecordBatch, Error> {
+    record_batch(Arc::new(Point::arrow_schema()), points).map_err(Error::from)
+}
+
+/// Reads the points from a record batch.
+///
+/// Only the time, position, and attitude columns are required. Any other column of
+/// [Point::arrow_schema] can be missing, in which case that field of every point is `None`.
+/// Satellite counts are read as [SatelliteCount::Unspecified].
+///
+/// # Examples
+///
+/// ```
+/// use pos::arrow;
+/// use pos::Point;
+/// let point = Point { time: 42.0, ..Default::default() };
+/// let batch = arrow::to_record_batch(&[point]).unwrap();
+/// assert_eq!(vec![point], arrow::to_points(&batch).unwrap());
+/// ```
+pub fn to_points(batch: &RecordBatch) -> Result<Vec<Point>, Error> {
+    points(batch).map_err(Error::from)
+}
+
+fn covariance_columns() -> Vec<String> {
+    let mut names = Vec::new();
+    for (i, row) in Covariance::AXES.iter().enumerate() {
+        for column in Covariance::AXES.iter().skip(i) {
+            names.push(format!("covariance_{}_{}", row, column));
+        }
+    }
+    names
+}
+
+pub(crate) fn record_batch(schema: SchemaRef, points: &[Point]) -> Result<RecordBatch, ArrowError> {
+    let fields: Vec<_> = points.iter().map(fields).collect();
+    let accuracies: Vec<_> = points
+        .iter()
+        .map(|p| p.accuracy.as_ref().map(accuracy).unwrap_or_default())
+        .collect();
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(schema.fields().len());
+    for i in 0..Point::FIELDS.len() {
+        columns.push(Arc::new(Float64Array::from_iter(
+            fields.iter().map(|f| f[i]),
+        )));
+    }
+    for i in 0..ACCURACY_COLUMNS.len() {
+        columns.push(Arc::new(Float64Array::from_iter(
+            accuracies.iter().map(|a| a[i]),
+        )));
+    }
+    columns.push(Arc::new(UInt32Array::from_iter(points.iter().map(|p| {
+        p.accuracy
+            .and_then(|a| a.satellite_count)
+            .map(|s| u32::from(s.total()))
+    }))));
+    for row in 0..Covariance::AXES.len() {
+        for column in row..Covariance::AXES.len() {
+            columns.push(Arc::new(Float64Array::from_iter(
+                points
+                    .iter()
+                    .map(|p| p.covariance.map(|c| c.get(row, column))),
+            )));
+        }
+    }
+    columns.push(Arc::new(UInt32Array::from_iter(
+        points.iter().map(|p| p.event),
+    )));
+    columns.push(Arc::new(BooleanArray::from_iter(
+        points.iter().map(|p| Some(p.derived)),
+    )));
+    RecordBatch::try_new(schema, columns)
+}
+
+fn fields(point: &Point) -> [Option<f64>; 20] {
+    [
+        Some(point.time),
+        Some(point.longitude.0),
+        Some(point.latitude.0),
+        Some(point.altitude),
+        Some(point.roll.0),
+        Some(point.pitch.0),
+        Some(point.yaw.0),
+        point.distance,
+        point.x_velocity,
+        point.y_velocity,
+        point.z_velocity,
+        point.wander_angle.map(|r| r.0),
+        point.x_acceleration,
+        point.y_acceleration,
+        point.z_acceleration,
+        point.x_angular_rate.map(|r| r.0),
+        point.y_angular_rate.map(|r| r.0),
+        point.z_angular_rate.map(|r| r.0),
+        point.height_above_ground,
+        point.heave,
+    ]
+}
+
+fn accuracy(accuracy: &Accuracy) -> [Option<f64>; 10] {
+    [
+        Some(accuracy.time),
+        Some(accuracy.x),
+        Some(accuracy.y),
+        Some(accuracy.z),
+        Some(accuracy.roll.0),
+        Some(accuracy.pitch.0),
+        Some(accuracy.yaw.0),
+        Some(accuracy.pdop),
+        accuracy.hdop,
+        accuracy.vdop,
+    ]
+}
+
+fn points(batch: &RecordBatch) -> Result<Vec<Point>, ArrowError> {
+    let fields = Point::FIELDS
+        .iter()
+        .map(|(name, _)| float64(batch, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (field, (name, _)) in fields.iter().zip(Point::FIELDS).take(7) {
+        if field.is_none() {
+            return Err(ArrowError::SchemaError(format!("missing column: {}", name)));
+        }
+    }
+    let accuracies = ACCURACY_COLUMNS
+        .iter()
+        .map(|name| float64(batch, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let satellite_count = uint32(batch, "accuracy_satellite_count")?;
+    let covariances = covariance_columns()
+        .iter()
+        .map(|name| float64(batch, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let event = uint32(batch, "event")?;
+    let derived = match batch.column_by_name("derived") {
+        Some(array) => Some(
+            array
+                .as_any()
+                .downcast_ref::<BooleanArray>()
+                .ok_or_else(|| mismatch("derived"))?,
+        ),
+        None => None,
+    };
+
+    let value = |array: Option<&Float64Array>, i: usize| {
+        array.filter(|a| a.is_valid(i)).map(|a| a.value(i))
+    };
+    let mut points = Vec::with_capacity(batch.num_rows());
+    for i in 0..batch.num_rows() {
+        let f = |n: usize| value(fields[n], i);
+        let required = |n: usize| f(n).unwrap_or_default();
+        let a = |n: usize| value(accuracies[n], i);
+        let accuracy = a(0).map(|time| Accuracy {
+            time,
+            x: a(1).unwrap_or_default(),
+            y: a(2).unwrap_or_default(),
+            z: a(3).unwrap_or_default(),
+            roll: Radians(a(4).unwrap_or_default()),
+            pitch: Radians(a(5).unwrap_or_default()),
+            yaw: Radians(a(6).unwrap_or_default()),
+            pdop: a(7).unwrap_or_default(),
+            hdop: a(8),
+            vdop: a(9),
+            satellite_count: satellite_count
+                .filter(|a| a.is_valid(i))
+                .map(|a| SatelliteCount::Unspecified(a.value(i) as u16)),
+        });
+        let covariance = if covariances.iter().all(|c| value(*c, i).is_some()) {
+            let mut upper = [0.0; 21];
+            for (value, c) in upper.iter_mut().zip(&covariances) {
+                *value = c.map(|c| c.value(i)).unwrap_or_default();
+            }
+            Some(Covariance::from_upper(upper))
+        } else {
+            None
+        };
+        points.push(Point {
+            time: required(0),
+            longitude: Radians(required(1)),
+            latitude: Radians(required(2)),
+            altitude: required(3),
+            roll: Radians(required(4)),
+            pitch: Radians(required(5)),
+            yaw: Radians(required(6)),
+            distance: f(7),
+            x_velocity: f(8),
+            y_velocity: f(9),
+            z_velocity: f(10),
+            wander_angle: f(11).map(Radians),
+            x_acceleration: f(12),
+            y_acceleration: f(13),
+            z_acceleration: f(14),
+            x_angular_rate: f(15).map(Radians),
+            y_angular_rate: f(16).map(Radians),
+            z_angular_rate: f(17).map(Radians),
+            height_above_ground: f(18),
+            heave: f(19),
+            accuracy,
+            covariance,
+            fix_quality: None,
+            event: event.filter(|a| a.is_valid(i)).map(|a| a.value(i)),
+            derived: derived.map(|a| a.value(i)).unwrap_or_default(),
+        });
+    }
+    Ok(points)
+}
+
+fn float64<'a>(batch: &'a RecordBatch, name: &str) -> Result<Option<&'a Float64Array>, ArrowError> {
+    match batch.column_by_name(name) {
+        Some(array) => array
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .map(Some)
+            .ok_or_else(|| mismatch(name)),
+        None => Ok(None),
+    }
+}
+
+fn uint32<'a>(batch: &'a RecordBatch, name: &str) -> Result<Option<&'a UInt32Array>, ArrowError> {
+    match batch.column_by_name(name) {
+        Some(array) => array
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .map(Some)
+            .ok_or_else(|| mismatch(name)),
+        None => Ok(None),
+    }
+}
+
+fn mismatch(name: &str) -> ArrowError {
+    ArrowError::SchemaError(format!("unexpected type for column: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    #[test]
+    fn batches() {
+        let points: Vec<_> = (0..5)
+            .map(|i| Point {
+                time: i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let batches: Vec<_> = RecordBatches::new(Box::new(MemorySource::new(points)))
+            .with_batch_size(2)
+            .map(Result::unwrap)
+            .collect();
+        let rows: Vec<_> = batches.iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(vec![2, 2, 1], rows);
+        assert_eq!(4.0, to_points(&batches[2]).unwrap()[0].time);
+    }
+
+    #[test]
+    fn missing_columns() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "time",
+            DataType::Float64,
+            false,
+        )]));
+        let time: ArrayRef = Arc::new(Float64Array::from(vec![1.0]));
+        let batch = RecordBatch::try_new(schema, vec![time]).unwrap();
+        assert!(to_points(&batch).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+//! Process every trajectory file in a directory tree.
+//!
+//! [discover] walks a directory and finds every file with a known [Format], along with its
+//! accuracy sidecar: a pof file's `.poq`, or an sbet file's `smrmsg` or `rmsmsg` file. [Batch]
+//! then applies an operation, such as a conversion, a summary, or [verify](crate::verify), to
+//! every file on a pool of threads, and collects each file's result into a [Report].
+
+use crate::format::Format;
+use crate::source::Source;
+use crate::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Prefixes of sbet accuracy files, which share the sbet's `.out` extension.
+const SBET_SIDECAR_PREFIXES: [&str; 2] = ["smrmsg", "rmsmsg"];
+
+/// A trajectory file found by [discover].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// The path of the file.
+    pub path: PathBuf,
+    /// The format of the file, detected from its extension.
+    pub format: Format,
+    /// The path of the file's accuracy sidecar, if one exists.
+    pub sidecar: Option<PathBuf>,
+}
+
+impl Entry {
+    /// Opens this entry as a source.
+    ///
+    /// A pof file is combined with its `.poq` sidecar, and an sbet file with its `rmsmsg` or
+    /// `smrmsg` sidecar, if it has one.
+    pub fn open(&self) -> Result<Box<dyn Source>, Error> {
+        let source = self.format.open(&self.path)?;
+        #[cfg(feature = "pof")]
+        if let (Format::Pof, Some(sidecar)) = (self.format, &self.sidecar) {
+            use crate::source::{CombinedSource, FileAccuracySource};
+            let accuracy_source = crate::poq::Reader::open_file_accuracy_source(sidecar)?;
+            return Ok(Box::new(CombinedSource::new(source, accuracy_source)?));
+        }
+        #[cfg(feature = "sbet")]
+        if let (Format::Sbet, Some(sidecar)) = (self.format, &self.sidecar) {
+            use crate::source::{CombinedSource, FileAccuracySource};
+            let accuracy_source = crate::rmsmsg::Reader::open_file_accuracy_source(sidecar)?;
+            return Ok(Box::new(CombinedSource::new(source, accuracy_source)?));
+        }
+        Ok(source)
+    }
+}
+
+/// Returns every trajectory file under a directory, sorted by path.
+///
+/// Files are detected by extension, as with [Format::from_path]. Accuracy files that share an
+/// sbet's extension, like `smrmsg_mission.out`, are reported as sidecars rather than as
+/// trajectories.
+///
+/// # Examples
+///
+/// ```
+/// use pos::batch;
+/// let entries = batch::discover("data").unwrap();
+/// assert!(entries.iter().any(|entry| entry.path.ends_with("2-points.sbet")));
+/// ```
+pub fn discover<P: AsRef<Path>>(root: P) -> Result<Vec<Entry>, Error> {
+    let mut paths = Vec::new();
+    walk(root.as_ref(), &mut paths)?;
+    paths.sort();
+    let entries: Vec<_> = paths
+        .into_iter()
+        .filter(|path| !is_sbet_sidecar(path))
+        .filter_map(|path| {
+            let format = Format::from_path(&path)
+                .ok()
+                .filter(|format| format.can_read())?;
+            let sidecar = sidecar(&path, format);
+            Some(Entry {
+                path,
+                format,
+                sidecar,
+            })
+        })
+        .collect();
+    #[cfg(feature = "tracing")]
+    tracing::debug!(count = entries.len(), "discovered trajectory files");
+    Ok(entries)
+}
+
+fn walk(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_sbet_sidecar(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    SBET_SIDECAR_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+fn sidecar(path: &Path, format: Format) -> Option<PathBuf> {
+    let candidates = match format {
+        Format::Pof => vec![path.with_extension("poq")],
+        Format::Sbet => {
+            let name = path.file_name()?.to_string_lossy();
+            let rest = name.strip_prefix("sbet").or(name.strip_prefix("SBET"))?;
+            SBET_SIDECAR_PREFIXES
+                .iter()
+                .map(|prefix| path.with_file_name(format!("{}{}", prefix, rest)))
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
+
+/// Runs an operation on many files in parallel.
+#[derive(Clone, Copy, Debug)]
+pub struct Batch {
+    threads: usize,
+}
+
+/// The result of an operation on one file.
+#[derive(Debug)]
+pub struct Outcome<T> {
+    /// The file.
+    pub entry: Entry,
+    /// What the operation returned.
+    pub result: Result<T, Error>,
+}
+
+/// The results of a batch, in the order of its entries.
+#[derive(Debug)]
+pub struct Report<T> {
+    /// The outcome for each file.
+    pub outcomes: Vec<Outcome<T>>,
+}
+
+impl Batch {
+    /// Creates a new batch that uses one thread per available core.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::batch::Batch;
+    /// let batch = Batch::new().with_threads(4);
+    /// ```
+    pub fn new() -> Batch {
+        Batch {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+
+    /// Sets the number of threads, which is at least one.
+    pub fn with_threads(mut self, threads: usize) -> Batch {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Runs an operation on every entry.
+    ///
+    /// An error from one file doesn't stop the others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::batch::{self, Batch};
+    /// use pos::verify;
+    /// let entries = batch::discover("data").unwrap();
+    /// let report = Batch::new().run(entries, |entry| verify::verify(entry.format, &entry.path));
+    /// for outcome in report.failures() {
+    ///     println!("{}: {}", outcome.entry.path.display(), outcome.result.as_ref().unwrap_err());
+    /// }
+    /// ```
+    pub fn run<T, F>(&self, entries: Vec<Entry>, operation: F) -> Report<T>
+    where
+        T: Send,
+        F: Fn(&Entry) -> Result<T, Error> + Sync,
+    {
+        let next = AtomicUsize::new(0);
+        let results: Vec<_> = entries.iter().map(|_| Mutex::new(None)).collect();
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(entries.len()) {
+                let _ = scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(entry) = entries.get(index) else {
+                        return;
+                    };
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(path = %entry.path.display(), "processing file");
+                    let result = operation(entry);
+                    *results[index].lock().unwrap() = Some(result);
+                });
+            }
+        });
+        Report {
+            outcomes: entries
+                .into_iter()
+                .zip(results)
+                .map(|(entry, result)| Outcome {
+                    entry,
+                    result: result
+                        .into_inner()
+                        .unwrap()
+                        .expect("every entry was processed"),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Default for Batch {
+    fn default() -> Batch {
+        Batch::new()
+    }
+}
+
+impl<T> Report<T> {
+    /// Returns true if the operation succeeded on every file.
+    pub fn is_ok(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.result.is_ok())
+    }
+
+    /// Returns the outcomes where the operation succeeded.
+    pub fn successes(&self) -> impl Iterator<Item = &Outcome<T>> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_ok())
+    }
+
+    /// Returns the outcomes where the operation failed.
+    pub fn failures(&self) -> impl Iterator<Item = &Outcome<T>> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_err())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tempdir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pos-rs-batch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn discover_sidecars() {
+        let dir = tempdir("discover");
+        for name in [
+            "a.pof",
+            "a.poq",
+            "nested/sbet_mission.out",
+            "nested/smrmsg_mission.out",
+            "b.pos",
+            "notes.txt",
+        ] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let entries = discover(&dir).unwrap();
+        assert_eq!(
+            vec![
+                Entry {
+                    path: dir.join("a.pof"),
+                    format: Format::Pof,
+                    sidecar: Some(dir.join("a.poq")),
+                },
+                Entry {
+                    path: dir.join("b.pos"),
+                    format: Format::Pos,
+                    sidecar: None,
+                },
+                Entry {
+                    path: dir.join("nested/sbet_mission.out"),
+                    format: Format::Sbet,
+                    sidecar: Some(dir.join("nested/smrmsg_mission.out")),
+                },
+            ],
+            entries
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn run() {
+        let entries: Vec<_> = (0..10)
+            .map(|i| Entry {
+                path: PathBuf::from(format!("{}.pos", i)),
+                format: Format::Pos,
+                sidecar: None,
+            })
+            .collect();
+        let report = Batch::new().with_threads(3).run(entries, |entry| {
+            let stem: usize = entry
+                .path
+                .file_stem()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            if stem == 7 {
+                Err(Error::OnePoint)
+            } else {
+                Ok(stem * 2)
+            }
+        });
+        assert!(!report.is_ok());
+        assert_eq!(9, report.successes().count());
+        assert_eq!(
+            vec![PathBuf::from("7.pos")],
+            report
+                .failures()
+                .map(|outcome| outcome.entry.path.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(8, *report.outcomes[4].result.as_ref().unwrap());
+    }
+}
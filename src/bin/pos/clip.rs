@@ -0,0 +1,65 @@
+use pos::clip::{self, BoundingBox, Window};
+use pos::pos::Profile;
+use pos::{Format, Radians};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The input file.
+    input: PathBuf,
+
+    /// The output file, which will be written in the same format as the input.
+    output: PathBuf,
+
+    /// The input format, detected from the file extension if not provided.
+    #[arg(short, long)]
+    format: Option<Format>,
+
+    /// The columns of a pos file: pos, inertial-explorer (or ie), or grafnav.
+    #[arg(long, default_value = "pos")]
+    profile: Profile,
+
+    /// Drop points before this time.
+    #[arg(long)]
+    start: Option<f64>,
+
+    /// Drop points after this time.
+    #[arg(long)]
+    end: Option<f64>,
+
+    /// Drop points outside of this bounding box, in degrees.
+    #[arg(long, num_args = 4, value_names = ["MIN_LON", "MIN_LAT", "MAX_LON", "MAX_LAT"], allow_negative_numbers = true)]
+    bbox: Option<Vec<f64>>,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let format = match args.format {
+        Some(format) => format,
+        None => Format::from_path(&args.input)?,
+    };
+    let window = Window {
+        start: args.start,
+        end: args.end,
+        bounding_box: args.bbox.map(|bbox| BoundingBox {
+            min_longitude: Radians::from_degrees(bbox[0]),
+            min_latitude: Radians::from_degrees(bbox[1]),
+            max_longitude: Radians::from_degrees(bbox[2]),
+            max_latitude: Radians::from_degrees(bbox[3]),
+        }),
+    };
+    let count = if format == Format::Pos {
+        clip::pos(
+            BufReader::new(File::open(&args.input)?),
+            BufWriter::new(File::create(&args.output)?),
+            &window,
+            args.profile,
+        )?
+    } else {
+        clip::clip(format, &args.input, &args.output, &window)?
+    };
+    eprintln!("wrote {} points to {}", count, args.output.display());
+    Ok(())
+}
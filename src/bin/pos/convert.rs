@@ -0,0 +1,180 @@
+use pos::convert::{self, ConvertOptions, TimeBase};
+use pos::decimate::Decimate;
+use pos::lever_arm::LeverArm;
+use pos::units::{AngleUnit, LengthUnit};
+use pos::{Format, Sink, Source};
+use std::error::Error;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The input file.
+    input: PathBuf,
+
+    /// The output file.
+    output: PathBuf,
+
+    /// The input format, detected from the file extension if not provided.
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// The output format, detected from the file extension if not provided.
+    #[arg(long)]
+    to: Option<Format>,
+
+    /// Skip points before this time.
+    #[arg(long)]
+    start: Option<f64>,
+
+    /// Stop at the first point after this time.
+    #[arg(long)]
+    end: Option<f64>,
+
+    /// Only write every nth point.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    every: u64,
+
+    /// The units used to write angles in text formats.
+    #[arg(long, default_value = "degrees")]
+    angle_units: AngleUnit,
+
+    /// The units used to write heights in text formats.
+    #[arg(long, value_enum, default_value_t = HeightUnits::Meters)]
+    height_units: HeightUnits,
+
+    /// Seconds added to every time, before rebasing.
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    time_offset: f64,
+
+    /// How to rebase times.
+    #[arg(long, value_enum, default_value_t = Rebase::Unchanged)]
+    time_base: Rebase,
+
+    /// Derive missing velocities, accelerations, and angular rates from the positions.
+    #[arg(long)]
+    derive: bool,
+
+    /// Replace distances with the distance along the track.
+    #[arg(long)]
+    distance: bool,
+
+    /// Offset positions by a body-frame lever arm (x forward, y right, z down), in meters.
+    #[arg(long, num_args = 3, value_names = ["X", "Y", "Z"], allow_negative_numbers = true)]
+    lever_arm: Option<Vec<f64>>,
+
+    /// Don't read accuracies from a pof file's .poq sidecar.
+    #[arg(long)]
+    no_sidecar: bool,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum HeightUnits {
+    Meters,
+    Feet,
+    UsSurveyFeet,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum Rebase {
+    /// Keep times as they are.
+    Unchanged,
+    /// Seconds since the first point.
+    Start,
+    /// Seconds of the day.
+    Day,
+    /// Seconds of the week.
+    Week,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let to = match args.to {
+        Some(format) => format,
+        None => Format::from_path(&args.output)?,
+    };
+    let from = match args.from {
+        Some(format) => format,
+        None => Format::from_path(&args.input)?,
+    };
+    let mut source = crate::open(&args.input, Some(from), !args.no_sidecar)?;
+    if let Some(lever_arm) = args.lever_arm {
+        source = Box::new(LeverArm::new(
+            source,
+            [lever_arm[0], lever_arm[1], lever_arm[2]],
+        ));
+    }
+    let height_unit = match args.height_units {
+        HeightUnits::Meters => LengthUnit::Meters,
+        HeightUnits::Feet => LengthUnit::Feet,
+        HeightUnits::UsSurveyFeet => LengthUnit::UsSurveyFeet,
+    };
+    if height_unit != LengthUnit::Meters && !matches!(to, Format::Pos | Format::Csv) {
+        return Err(format!("{} files store heights in meters", to).into());
+    }
+    let sink: Box<dyn Sink> = match to {
+        Format::Pos => {
+            let mut writer =
+                pos::pos::Writer::from_path(&args.output)?.with_angle_unit(args.angle_units);
+            if let Some(vertical_datum) = from.vertical_datum() {
+                writer = writer.with_vertical_datum(vertical_datum);
+            }
+            Box::new(writer)
+        }
+        Format::Csv => Box::new(
+            pos::csv::Writer::from_path(&args.output, pos::csv::point_columns())?
+                .with_angle_unit(args.angle_units),
+        ),
+        _ => to.create(&args.output)?,
+    };
+    let window = Decimate::new(
+        source.clip(
+            args.start.unwrap_or(f64::NEG_INFINITY),
+            args.end.unwrap_or(f64::INFINITY),
+        ),
+        usize::try_from(args.every)?,
+    );
+    let options = ConvertOptions::new()
+        .with_time_offset(args.time_offset)
+        .with_time_base(match args.time_base {
+            Rebase::Unchanged => TimeBase::Unchanged,
+            Rebase::Start => TimeBase::Start,
+            Rebase::Day => TimeBase::Day,
+            Rebase::Week => TimeBase::Week,
+        })
+        .with_height_unit(height_unit)
+        .with_derive(args.derive)
+        .with_distance(args.distance);
+    let summary = convert::convert(window, sink, options)?;
+    let zeroed: Vec<_> = summary
+        .missing
+        .into_iter()
+        .filter(|name| stored(to).contains(name))
+        .collect();
+    if !zeroed.is_empty() {
+        eprintln!(
+            "warning: wrote zeros for fields missing from the input: {}",
+            zeroed.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Returns the optional point fields that a format stores.
+fn stored(format: Format) -> &'static [&'static str] {
+    match format {
+        Format::Pos => &[],
+        Format::Sbet => &[
+            "x_velocity",
+            "y_velocity",
+            "z_velocity",
+            "wander_angle",
+            "x_acceleration",
+            "y_acceleration",
+            "z_acceleration",
+            "x_angular_rate",
+            "y_angular_rate",
+            "z_angular_rate",
+        ],
+        Format::Pof => &["distance"],
+        _ => &[],
+    }
+}
@@ -0,0 +1,90 @@
+use pos::compare::{self, Axis, Comparison};
+use pos::{Format, Trajectory};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The reference trajectory.
+    reference: PathBuf,
+
+    /// The trajectory to compare against the reference.
+    other: PathBuf,
+
+    /// The reference format, detected from the file extension if not provided.
+    #[arg(long)]
+    reference_format: Option<Format>,
+
+    /// The other format, detected from the file extension if not provided.
+    #[arg(long)]
+    other_format: Option<Format>,
+
+    /// Write every difference to this CSV file.
+    #[arg(long)]
+    series: Option<PathBuf>,
+
+    /// Print the differences as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut reference = crate::open(&args.reference, args.reference_format, false)?;
+    let reference = Trajectory::from_source(&mut *reference)?;
+    let mut other = crate::open(&args.other, args.other_format, false)?;
+    let comparison = if let Some(path) = &args.series {
+        let differences = compare::differences(&reference, &mut *other)?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "time,east,north,up,roll,pitch,yaw")?;
+        for d in &differences {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                d.time,
+                d.east,
+                d.north,
+                d.up,
+                d.roll.to_degrees(),
+                d.pitch.to_degrees(),
+                d.yaw.to_degrees()
+            )?;
+        }
+        writer.flush()?;
+        Comparison::from_differences(&differences)
+    } else {
+        Comparison::new(&reference, &mut *other)?
+    };
+    if comparison.count == 0 {
+        return Err("the trajectories do not overlap in time".into());
+    }
+    let value = json!({
+        "count": comparison.count,
+        "position": {
+            "east": axis(comparison.east, 1.0),
+            "north": axis(comparison.north, 1.0),
+            "up": axis(comparison.up, 1.0),
+        },
+        "attitude": {
+            "roll": axis(comparison.roll, 1f64.to_degrees()),
+            "pitch": axis(comparison.pitch, 1f64.to_degrees()),
+            "yaw": axis(comparison.yaw, 1f64.to_degrees()),
+        },
+    });
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&value)?);
+    } else if let Value::Object(map) = value {
+        crate::info::print(&map, "");
+    }
+    Ok(())
+}
+
+fn axis(axis: Axis, scale: f64) -> Value {
+    json!({
+        "rmse": axis.rmse * scale,
+        "mean": axis.mean * scale,
+        "max": axis.max * scale,
+    })
+}
@@ -0,0 +1,135 @@
+use pos::{pof, Format};
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The input file.
+    path: PathBuf,
+
+    /// The input format, detected from the file extension if not provided.
+    #[arg(short, long)]
+    format: Option<Format>,
+
+    /// Print the information as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let format = match args.format {
+        Some(format) => format,
+        None => Format::from_path(&args.path)?,
+    };
+    let mut info = Map::new();
+    let _ = info.insert("path".to_string(), json!(args.path));
+    let _ = info.insert("format".to_string(), json!(format.to_string()));
+    if format == Format::Pof {
+        let reader = pof::Reader::from_path(&args.path)?;
+        let _ = info.insert("version".to_string(), json!(reader.version.to_string()));
+        let _ = info.insert(
+            "header".to_string(),
+            json!({
+                "date": format!("{:04}-{:02}-{:02}", reader.year, reader.month, reader.day),
+                "entries": reader.entries,
+                "time_unit": format!("{:?}", reader.timeunit),
+                "time_info": format!("{:?}", reader.timeinfo),
+                "timezone": text(&reader.timezone),
+                "location": text(&reader.location),
+                "device": text(&reader.device),
+                "project": text(&reader.project),
+                "company": text(&reader.company),
+                "avgint": reader.avgint,
+                "maxint": reader.maxint,
+                "devint": reader.devint,
+            }),
+        );
+    }
+
+    let mut source = format.open(&args.path)?;
+    let mut count = 0u64;
+    let mut time = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut longitude = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut latitude = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut altitude = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut intervals = Vec::new();
+    let mut last_time = None;
+    while let Some(point) = source.source()? {
+        count += 1;
+        extend(&mut time, point.time);
+        extend(&mut longitude, point.longitude_degrees());
+        extend(&mut latitude, point.latitude_degrees());
+        extend(&mut altitude, point.altitude);
+        if let Some(last_time) = last_time {
+            intervals.push(point.time - last_time);
+        }
+        last_time = Some(point.time);
+    }
+    let _ = info.insert("count".to_string(), json!(count));
+    if count > 0 {
+        let _ = info.insert(
+            "time".to_string(),
+            json!({"start": time.0, "end": time.1, "duration": time.1 - time.0}),
+        );
+        let _ = info.insert(
+            "bounds".to_string(),
+            json!({
+                "min_longitude": longitude.0,
+                "max_longitude": longitude.1,
+                "min_latitude": latitude.0,
+                "max_latitude": latitude.1,
+                "min_altitude": altitude.0,
+                "max_altitude": altitude.1,
+            }),
+        );
+    }
+    if !intervals.is_empty() {
+        intervals.sort_by(f64::total_cmp);
+        let median = intervals[intervals.len() / 2];
+        if median > 0.0 {
+            let _ = info.insert("rate".to_string(), json!(1.0 / median));
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        print(&info, "");
+    }
+    Ok(())
+}
+
+fn extend(range: &mut (f64, f64), value: f64) {
+    range.0 = range.0.min(value);
+    range.1 = range.1.max(value);
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .trim()
+        .to_string()
+}
+
+pub fn print(map: &Map<String, Value>, indent: &str) {
+    for (key, value) in map {
+        match value {
+            Value::Object(map) => {
+                println!("{}{}:", indent, key);
+                print(map, &format!("{}  ", indent));
+            }
+            Value::Array(values) => {
+                println!("{}{}:", indent, key);
+                let map = values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| (i.to_string(), value.clone()))
+                    .collect();
+                print(&map, &format!("{}  ", indent));
+            }
+            Value::String(s) => println!("{}{}: {}", indent, key, s),
+            value => println!("{}{}: {}", indent, key, value),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use pos::{Format, Point, Trajectory};
+use serde_json::json;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The trajectory file.
+    path: PathBuf,
+
+    /// A file of query times, one per line. Reads from stdin if not provided or `-`.
+    times: Option<PathBuf>,
+
+    /// The trajectory format, detected from the file extension if not provided.
+    #[arg(short, long)]
+    format: Option<Format>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t = Output::Csv)]
+    output: Output,
+
+    /// Skip times outside of the trajectory instead of failing.
+    #[arg(long)]
+    skip_out_of_range: bool,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum Output {
+    /// Comma-separated values, with a header row.
+    Csv,
+    /// Newline-delimited JSON objects.
+    Json,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let format = match args.format {
+        Some(format) => format,
+        None => Format::from_path(&args.path)?,
+    };
+    let trajectory = Trajectory::from_source(&mut *format.open(&args.path)?)?;
+    let times: Box<dyn BufRead> = match args.times {
+        Some(path) if path.as_os_str() != "-" => Box::new(BufReader::new(File::open(path)?)),
+        _ => Box::new(BufReader::new(std::io::stdin())),
+    };
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    if let Output::Csv = args.output {
+        writeln!(stdout, "time,latitude,longitude,altitude,roll,pitch,yaw")?;
+    }
+    for line in times.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let time: f64 = line.parse()?;
+        let point = match trajectory.interpolate(time) {
+            Ok(point) => point,
+            Err(err) if err.kind() == pos::ErrorKind::Range && args.skip_out_of_range => continue,
+            Err(err) => return Err(err.into()),
+        };
+        match args.output {
+            Output::Csv => write_csv(&mut stdout, &point)?,
+            Output::Json => writeln!(stdout, "{}", to_json(&point))?,
+        }
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+fn write_csv<W: Write>(mut write: W, point: &Point) -> Result<(), std::io::Error> {
+    writeln!(
+        write,
+        "{:.6},{:.9},{:.9},{:.3},{:.6},{:.6},{:.6}",
+        point.time,
+        point.latitude_degrees(),
+        point.longitude_degrees(),
+        point.altitude,
+        point.roll_degrees(),
+        point.pitch_degrees(),
+        point.yaw_degrees()
+    )
+}
+
+fn to_json(point: &Point) -> serde_json::Value {
+    json!({
+        "time": point.time,
+        "latitude": point.latitude_degrees(),
+        "longitude": point.longitude_degrees(),
+        "altitude": point.altitude,
+        "roll": point.roll_degrees(),
+        "pitch": point.pitch_degrees(),
+        "yaw": point.yaw_degrees(),
+    })
+}
@@ -0,0 +1,87 @@
+//! Command-line interface for reading, inspecting, and converting position files.
+
+mod clip;
+mod convert;
+mod diff;
+mod info;
+mod interpolate;
+mod print;
+mod stats;
+
+use clap::{Parser, Subcommand};
+use pos::{poq, CombinedSource, FileAccuracySource, Format, Source};
+use std::path::Path;
+use std::process::ExitCode;
+
+/// Work with GNSS/IMU position and accuracy files.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Extracts a time window or bounding box into a new file.
+    Clip(clip::Args),
+
+    /// Converts a file to another format.
+    Convert(convert::Args),
+
+    /// Compares a trajectory against a reference, in meters and degrees.
+    Diff(diff::Args),
+
+    /// Prints information about a file.
+    Info(info::Args),
+
+    /// Interpolates points at query times.
+    #[command(alias = "interp")]
+    Interpolate(interpolate::Args),
+
+    /// Prints points as whitespace-delimited text.
+    Print(print::Args),
+
+    /// Prints trajectory statistics, with attitude rates in degrees per second.
+    Stats(stats::Args),
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let result = match args.command {
+        Command::Clip(args) => clip::run(args),
+        Command::Convert(args) => convert::run(args),
+        Command::Diff(args) => diff::run(args),
+        Command::Info(args) => info::run(args),
+        Command::Interpolate(args) => interpolate::run(args),
+        Command::Print(args) => print::run(args),
+        Command::Stats(args) => stats::run(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Opens a path as a source, combining a pof file with its .poq sidecar if requested and present.
+fn open(
+    path: &Path,
+    format: Option<Format>,
+    sidecar: bool,
+) -> Result<Box<dyn Source>, Box<dyn std::error::Error>> {
+    let format = match format {
+        Some(format) => format,
+        None => Format::from_path(path)?,
+    };
+    let source = format.open(path)?;
+    let poq = path.with_extension("poq");
+    if format == Format::Pof && sidecar && poq.exists() {
+        let accuracy_source = poq::Reader::open_file_accuracy_source(poq)?;
+        Ok(Box::new(CombinedSource::new(source, accuracy_source)?))
+    } else {
+        Ok(source)
+    }
+}
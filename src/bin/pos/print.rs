@@ -0,0 +1,47 @@
+use pos::Format;
+use std::error::Error;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The input file.
+    path: PathBuf,
+
+    /// The input format, detected from the file extension if not provided.
+    #[arg(short, long)]
+    format: Option<Format>,
+
+    /// The maximum number of points to print.
+    #[arg(short = 'n', long)]
+    limit: Option<usize>,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let format = match args.format {
+        Some(format) => format,
+        None => Format::from_path(&args.path)?,
+    };
+    let mut source = format.open(&args.path)?;
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    let mut count = 0;
+    while let Some(point) = source.source()? {
+        if args.limit.is_some_and(|limit| count >= limit) {
+            break;
+        }
+        writeln!(
+            stdout,
+            "{:.6} {:.9} {:.9} {:.3} {:.6} {:.6} {:.6}",
+            point.time,
+            point.latitude.to_degrees(),
+            point.longitude.to_degrees(),
+            point.altitude,
+            point.roll.to_degrees(),
+            point.pitch.to_degrees(),
+            point.yaw.to_degrees()
+        )?;
+        count += 1;
+    }
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use pos::stats::{Distribution, Stats};
+use pos::Format;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The input file.
+    path: PathBuf,
+
+    /// The input format, detected from the file extension if not provided.
+    #[arg(short, long)]
+    format: Option<Format>,
+
+    /// The interval, in seconds, above which a gap is reported.
+    ///
+    /// Defaults to twice the median interval.
+    #[arg(long)]
+    gap_threshold: Option<f64>,
+
+    /// Don't read accuracies from a pof file's .poq sidecar.
+    #[arg(long)]
+    no_sidecar: bool,
+
+    /// Print the statistics as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut source = crate::open(&args.path, args.format, !args.no_sidecar)?;
+    let mut stats = Stats::new();
+    if let Some(gap_threshold) = args.gap_threshold {
+        stats = stats.with_gap_threshold(gap_threshold);
+    }
+    stats.add_source(&mut *source)?;
+    let summary = stats.summary().ok_or("no points")?;
+    let value = json!({
+        "count": summary.count,
+        "time": {
+            "start": summary.start,
+            "end": summary.end,
+            "duration": summary.duration(),
+        },
+        "length": summary.length,
+        "speed": distribution(summary.speed, 1.0),
+        "altitude": distribution(summary.altitude, 1.0),
+        "interval": distribution(summary.interval, 1.0),
+        "roll_rate": distribution(summary.roll_rate, 1f64.to_degrees()),
+        "pitch_rate": distribution(summary.pitch_rate, 1f64.to_degrees()),
+        "yaw_rate": distribution(summary.yaw_rate, 1f64.to_degrees()),
+        "gaps": summary.gaps.iter().map(|gap| json!({
+            "start": gap.start,
+            "end": gap.end,
+            "duration": gap.duration(),
+        })).collect::<Vec<_>>(),
+        "horizontal_accuracy": distribution(summary.horizontal_accuracy, 1.0),
+        "vertical_accuracy": distribution(summary.vertical_accuracy, 1.0),
+    });
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&value)?);
+    } else if let Value::Object(map) = value {
+        crate::info::print(&map, "");
+    }
+    Ok(())
+}
+
+fn distribution(distribution: Option<Distribution>, scale: f64) -> Value {
+    match distribution {
+        Some(d) => json!({
+            "min": d.min * scale,
+            "max": d.max * scale,
+            "mean": d.mean * scale,
+            "p50": d.p50 * scale,
+            "p95": d.p95 * scale,
+            "p99": d.p99 * scale,
+        }),
+        None => Value::Null,
+    }
+}
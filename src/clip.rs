@@ -0,0 +1,815 @@
+//! Extract a time window or region from a trajectory.
+//!
+//! [clip()] extracts a time window or bounding box from a file into a new file of the same
+//! format. Binary formats are clipped by copying their records verbatim, so no precision is
+//! lost. When a start time is given, sbet and pof files are binary-searched for the first
+//! record, which assumes their records are sorted by time.
+//!
+//! [Clip] is a source that passes through only the points inside a [Region], such as a project
+//! area's polygon, and optionally between altitude bounds. [ClipByTime], usually created with
+//! [Source::clip], passes through only the points inside a time window.
+
+use crate::format::Format;
+#[cfg(feature = "pof")]
+use crate::pof::{self, Summary};
+use crate::point::Point;
+#[cfg(feature = "pos")]
+use crate::pos::Profile;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+#[cfg(any(feature = "pof", feature = "sbet"))]
+use byteorder::{ByteOrder, LittleEndian};
+use std::fs::File;
+#[cfg(feature = "pos")]
+use std::io::BufRead;
+use std::io::BufWriter;
+#[cfg(any(feature = "pof", feature = "pos", feature = "sbet"))]
+use std::io::{BufReader, Write};
+#[cfg(any(feature = "pof", feature = "sbet"))]
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// A geographic bounding box.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct BoundingBox {
+    pub min_longitude: Radians<f64>,
+    pub min_latitude: Radians<f64>,
+    pub max_longitude: Radians<f64>,
+    pub max_latitude: Radians<f64>,
+}
+
+/// A geographic polygon without holes.
+///
+/// Vertices are longitude and latitude pairs, and the ring is closed implicitly. Edges are
+/// straight lines in longitude and latitude, which is fine for project areas but not for
+/// polygons that cross the antimeridian.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<(Radians<f64>, Radians<f64>)>,
+}
+
+/// An area to clip to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Region {
+    /// A bounding box.
+    BoundingBox(BoundingBox),
+    /// A polygon.
+    Polygon(Polygon),
+}
+
+/// A source that only passes through points inside a region and altitude bounds.
+#[derive(Debug)]
+pub struct Clip {
+    source: Box<dyn Source>,
+    region: Option<Region>,
+    min_altitude: Option<f64>,
+    max_altitude: Option<f64>,
+}
+
+/// A source that only passes through points inside a time window, created by [Source::clip].
+///
+/// The source is assumed to be sorted by time, so reading stops at the first point after the
+/// end of the window.
+#[derive(Debug)]
+pub struct ClipByTime<S> {
+    source: S,
+    start: f64,
+    end: f64,
+    done: bool,
+}
+
+/// The points to keep.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Window {
+    /// Points before this time are dropped.
+    pub start: Option<f64>,
+    /// Points after this time are dropped.
+    pub end: Option<f64>,
+    /// Points outside of this box are dropped.
+    pub bounding_box: Option<BoundingBox>,
+}
+
+impl BoundingBox {
+    /// Returns true if this box contains the latitude and longitude.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::clip::BoundingBox;
+    /// use pos::Radians;
+    /// let bbox = BoundingBox {
+    ///     min_longitude: Radians::from_degrees(-106.0),
+    ///     min_latitude: Radians::from_degrees(39.0),
+    ///     max_longitude: Radians::from_degrees(-105.0),
+    ///     max_latitude: Radians::from_degrees(40.0),
+    /// };
+    /// assert!(bbox.contains(Radians::from_degrees(39.5), Radians::from_degrees(-105.5)));
+    /// ```
+    pub fn contains(&self, latitude: Radians<f64>, longitude: Radians<f64>) -> bool {
+        latitude.0 >= self.min_latitude.0
+            && latitude.0 <= self.max_latitude.0
+            && longitude.0 >= self.min_longitude.0
+            && longitude.0 <= self.max_longitude.0
+    }
+}
+
+impl Polygon {
+    /// Creates a new polygon from longitude and latitude vertices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::clip::Polygon;
+    /// use pos::Radians;
+    /// let polygon = Polygon::new(
+    ///     [(0.0, 0.0), (2.0, 0.0), (0.0, 2.0)]
+    ///         .into_iter()
+    ///         .map(|(x, y)| (Radians::from_degrees(x), Radians::from_degrees(y)))
+    ///         .collect(),
+    /// );
+    /// assert!(polygon.contains(Radians::from_degrees(0.5), Radians::from_degrees(0.5)));
+    /// assert!(!polygon.contains(Radians::from_degrees(1.5), Radians::from_degrees(1.5)));
+    /// ```
+    pub fn new(vertices: Vec<(Radians<f64>, Radians<f64>)>) -> Polygon {
+        Polygon { vertices }
+    }
+
+    /// Returns true if this polygon contains the latitude and longitude.
+    pub fn contains(&self, latitude: Radians<f64>, longitude: Radians<f64>) -> bool {
+        let (x, y) = (longitude.0, latitude.0);
+        let mut inside = false;
+        let mut previous = match self.vertices.last() {
+            Some(&(longitude, latitude)) => (longitude.0, latitude.0),
+            None => return false,
+        };
+        for &(longitude, latitude) in &self.vertices {
+            let vertex = (longitude.0, latitude.0);
+            if (vertex.1 > y) != (previous.1 > y)
+                && x < (previous.0 - vertex.0) * (y - vertex.1) / (previous.1 - vertex.1) + vertex.0
+            {
+                inside = !inside;
+            }
+            previous = vertex;
+        }
+        inside
+    }
+}
+
+impl Region {
+    /// Returns true if this region contains the latitude and longitude.
+    pub fn contains(&self, latitude: Radians<f64>, longitude: Radians<f64>) -> bool {
+        match self {
+            Region::BoundingBox(bbox) => bbox.contains(latitude, longitude),
+            Region::Polygon(polygon) => polygon.contains(latitude, longitude),
+        }
+    }
+}
+
+impl From<BoundingBox> for Region {
+    fn from(bbox: BoundingBox) -> Region {
+        Region::BoundingBox(bbox)
+    }
+}
+
+impl From<Polygon> for Region {
+    fn from(polygon: Polygon) -> Region {
+        Region::Polygon(polygon)
+    }
+}
+
+impl Clip {
+    /// Creates a new clip source.
+    ///
+    /// With no region, only the altitude bounds are applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::clip::{BoundingBox, Clip};
+    /// use pos::sbet::Reader;
+    /// use pos::{Radians, Source};
+    /// let bbox = BoundingBox {
+    ///     min_longitude: Radians::from_degrees(-117.0),
+    ///     min_latitude: Radians::from_degrees(32.0),
+    ///     max_longitude: Radians::from_degrees(-116.0),
+    ///     max_latitude: Radians::from_degrees(33.0),
+    /// };
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut clip = Clip::new(Box::new(reader), Some(bbox.into())).max_altitude(100.0);
+    /// assert!(clip.source().unwrap().is_none());
+    /// ```
+    pub fn new(source: Box<dyn Source>, region: Option<Region>) -> Clip {
+        Clip {
+            source,
+            region,
+            min_altitude: None,
+            max_altitude: None,
+        }
+    }
+
+    /// Drops points below this altitude.
+    pub fn min_altitude(mut self, min_altitude: f64) -> Clip {
+        self.min_altitude = Some(min_altitude);
+        self
+    }
+
+    /// Drops points above this altitude.
+    pub fn max_altitude(mut self, max_altitude: f64) -> Clip {
+        self.max_altitude = Some(max_altitude);
+        self
+    }
+
+    fn contains(&self, point: &Point) -> bool {
+        self.min_altitude.is_none_or(|min| point.altitude >= min)
+            && self.max_altitude.is_none_or(|max| point.altitude <= max)
+            && self
+                .region
+                .as_ref()
+                .is_none_or(|region| region.contains(point.latitude, point.longitude))
+    }
+}
+
+impl Source for Clip {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        while let Some(point) = self.source.source()? {
+            if self.contains(&point) {
+                return Ok(Some(point));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<S: Source> ClipByTime<S> {
+    /// Creates a new time clip, keeping points from `start` through `end`, inclusive.
+    ///
+    /// Use infinite bounds to leave either side of the window open.
+    pub fn new(source: S, start: f64, end: f64) -> ClipByTime<S> {
+        ClipByTime {
+            source,
+            start,
+            end,
+            done: false,
+        }
+    }
+}
+
+impl<S: Source> Source for ClipByTime<S> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        if self.done {
+            return Ok(None);
+        }
+        while let Some(point) = self.source.source()? {
+            if point.time > self.end {
+                self.done = true;
+                return Ok(None);
+            } else if point.time >= self.start {
+                return Ok(Some(point));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(any(feature = "pof", feature = "pos", feature = "sbet"))]
+impl Window {
+    fn is_before(&self, time: f64) -> bool {
+        self.start.is_some_and(|start| time < start)
+    }
+
+    fn is_after(&self, time: f64) -> bool {
+        self.end.is_some_and(|end| time > end)
+    }
+
+    fn contains(&self, latitude: Radians<f64>, longitude: Radians<f64>) -> bool {
+        self.bounding_box
+            .is_none_or(|bbox| bbox.contains(latitude, longitude))
+    }
+}
+
+/// Clips a file into a new file of the same format, returning the number of points written.
+///
+/// Returns [Error::DisabledFormat] if the format's cargo feature isn't enabled, and
+/// [Error::UnsupportedFormat] for formats other than sbet, pof, and pos. Pos files are read with
+/// [Profile::POS](crate::pos::Profile::POS); use [pos()] for other profiles.
+///
+/// # Examples
+///
+/// ```
+/// use pos::clip::{self, Window};
+/// use pos::Format;
+/// let path = std::env::temp_dir().join("pos-rs-clip-doctest.sbet");
+/// let window = Window { start: Some(151631.005), ..Default::default() };
+/// let count = clip::clip(Format::Sbet, "data/2-points.sbet", &path, &window).unwrap();
+/// assert_eq!(1, count);
+/// ```
+pub fn clip<P: AsRef<Path>, Q: AsRef<Path>>(
+    format: Format,
+    input: P,
+    output: Q,
+    window: &Window,
+) -> Result<u64, Error> {
+    let input = File::open(input)?;
+    let output = BufWriter::new(File::create(output)?);
+    match format {
+        #[cfg(feature = "pos")]
+        Format::Pos => pos(BufReader::new(input), output, window, Profile::POS),
+        #[cfg(feature = "sbet")]
+        Format::Sbet => sbet(input, output, window),
+        #[cfg(feature = "pof")]
+        Format::Pof => pof(input, output, window),
+        #[allow(unreachable_patterns)]
+        Format::Pos | Format::Sbet | Format::Pof => {
+            let _ = (input, output, window);
+            Err(Error::DisabledFormat(format))
+        }
+        format => {
+            let _ = (input, output, window);
+            Err(Error::UnsupportedFormat(format, "clipping"))
+        }
+    }
+}
+
+/// Clips an sbet file, starting at the reader's current position.
+///
+/// The record layout is inferred with [infer_layout](crate::sbet::infer_layout), falling back
+/// to the standard layout for files that are too short to infer.
+#[cfg(feature = "sbet")]
+pub fn sbet<R: Read + Seek, W: Write>(
+    mut read: R,
+    mut write: W,
+    window: &Window,
+) -> Result<u64, Error> {
+    let start = read.stream_position()?;
+    let record_size = crate::sbet::infer_layout(&mut read)?
+        .unwrap_or_default()
+        .record_size();
+    let count = (read.seek(SeekFrom::End(0))? - start) / record_size;
+    let first = search(&mut read, start, record_size, count, window)?;
+    let _ = read.seek(SeekFrom::Start(start + first * record_size))?;
+    let mut read = BufReader::new(read);
+    let mut record = vec![0; record_size as usize];
+    let mut written = 0;
+    for _ in first..count {
+        read.read_exact(&mut record)?;
+        let time = LittleEndian::read_f64(&record[0..]);
+        if window.is_after(time) {
+            break;
+        }
+        let latitude = Radians(LittleEndian::read_f64(&record[8..]));
+        let longitude = Radians(LittleEndian::read_f64(&record[16..]));
+        if !window.is_before(time) && window.contains(latitude, longitude) {
+            write.write_all(&record)?;
+            written += 1;
+        }
+    }
+    write.flush()?;
+    Ok(written)
+}
+
+/// Clips a pof file.
+///
+/// The header, including any extra bytes before the first record, is copied with its point
+/// count, bounds, and interval statistics updated to describe the clipped points. Any bytes
+/// after the last record are copied after the clipped records.
+///
+/// Returns [Error::PofDataOffset] if the header's data offset is inside the fixed header fields.
+#[cfg(feature = "pof")]
+pub fn pof<R: Read + Seek, W: Write + Seek>(
+    mut read: R,
+    mut write: W,
+    window: &Window,
+) -> Result<u64, Error> {
+    let mut reader = pof::Reader::new(&mut read)?;
+    let data_offset = u64::from(reader.data_offset);
+    let entries = reader.entries.max(0) as u64;
+    let record_size = reader.version.record_size();
+    let trailer = reader.read_trailer()?;
+    let mut header = vec![0; data_offset as usize];
+    let _ = read.seek(SeekFrom::Start(0))?;
+    read.read_exact(&mut header)?;
+
+    let start = write.stream_position()?;
+    write.write_all(&header)?;
+    let first = search(&mut read, data_offset, record_size, entries, window)?;
+    let _ = read.seek(SeekFrom::Start(data_offset + first * record_size))?;
+    let mut read = BufReader::new(read);
+    let mut record = vec![0; record_size as usize];
+    let mut summary = Summary::default();
+    for _ in first..entries {
+        read.read_exact(&mut record)?;
+        let time = LittleEndian::read_f64(&record[0..]);
+        if window.is_after(time) {
+            break;
+        }
+        let longitude = LittleEndian::read_f64(&record[8..]);
+        let latitude = LittleEndian::read_f64(&record[16..]);
+        let altitude = LittleEndian::read_f64(&record[24..]);
+        if !window.is_before(time)
+            && window.contains(
+                Radians::from_degrees(latitude),
+                Radians::from_degrees(longitude),
+            )
+        {
+            write.write_all(&record)?;
+            summary.add(time, longitude, latitude, altitude);
+        }
+    }
+    write.write_all(&trailer)?;
+    let end = write.stream_position()?;
+
+    let _ = write.seek(SeekFrom::Start(start + pof::ENTRIES_OFFSET))?;
+    summary.write(&mut write)?;
+    let _ = write.seek(SeekFrom::Start(end))?;
+    write.flush()?;
+    Ok(summary.entries() as u64)
+}
+
+/// Clips an ASCII pos file with the given column profile, keeping its header lines.
+///
+/// Header lines are found the same way as by [pos::Reader](crate::pos::Reader): the first line,
+/// and any lines after it that don't start with a number, up to the first point. Latitudes and
+/// longitudes are read in degrees.
+#[cfg(feature = "pos")]
+pub fn pos<R: BufRead, W: Write>(
+    mut read: R,
+    mut write: W,
+    window: &Window,
+    profile: Profile,
+) -> Result<u64, Error> {
+    let mut line = String::new();
+    let _ = read.read_line(&mut line)?;
+    write.write_all(line.as_bytes())?;
+    let mut data = false;
+    let mut written = 0;
+    loop {
+        line.clear();
+        if read.read_line(&mut line)? == 0 {
+            break;
+        }
+        let values: Vec<_> = line.split_whitespace().collect();
+        match values.first() {
+            Some(value) if data || value.parse::<f64>().is_ok() => data = true,
+            _ if !data => {
+                write.write_all(line.as_bytes())?;
+                continue;
+            }
+            _ => continue,
+        }
+        let time = profile.time(&values)?;
+        if window.is_after(time) {
+            break;
+        }
+        let column = |column: usize| values.get(column).ok_or(Error::MissingColumn(column));
+        let latitude = Radians::from_degrees(column(profile.latitude)?.parse()?);
+        let longitude = Radians::from_degrees(column(profile.longitude)?.parse()?);
+        if !window.is_before(time) && window.contains(latitude, longitude) {
+            write.write_all(line.as_bytes())?;
+            written += 1;
+        }
+    }
+    write.flush()?;
+    Ok(written)
+}
+
+/// Returns the index of the first record at or after the window's start time.
+#[cfg(any(feature = "pof", feature = "sbet"))]
+fn search<R: Read + Seek>(
+    read: &mut R,
+    offset: u64,
+    record_size: u64,
+    count: u64,
+    window: &Window,
+) -> Result<u64, Error> {
+    match window.start {
+        Some(start) => crate::source::search_time(read, offset, record_size, count, start),
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "pof", feature = "sbet"))]
+    use byteorder::WriteBytesExt;
+    #[cfg(any(feature = "pof", feature = "sbet"))]
+    use std::io::Cursor;
+
+    #[cfg(feature = "sbet")]
+    fn sbet_bytes(times: &[f64]) -> Vec<u8> {
+        sbet_bytes_with_fields(times, 17)
+    }
+
+    #[cfg(feature = "sbet")]
+    fn sbet_bytes_with_fields(times: &[f64], fields: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for &time in times {
+            bytes.write_f64::<LittleEndian>(time).unwrap();
+            for _ in 1..fields {
+                bytes.write_f64::<LittleEndian>(0.0).unwrap();
+            }
+        }
+        bytes
+    }
+
+    #[cfg(feature = "pof")]
+    fn pof_bytes(times: &[f64]) -> Vec<u8> {
+        let mut bytes = vec![0; 27];
+        bytes.write_u16::<LittleEndian>(1).unwrap();
+        bytes.write_u16::<LittleEndian>(1).unwrap();
+        bytes.write_u32::<LittleEndian>(315).unwrap();
+        bytes.resize(pof::ENTRIES_OFFSET as usize, 0);
+        bytes.write_i64::<LittleEndian>(times.len() as i64).unwrap();
+        bytes.resize(315, 0);
+        for &time in times {
+            bytes.write_f64::<LittleEndian>(time).unwrap();
+            bytes.write_f64::<LittleEndian>(-105.0).unwrap();
+            bytes.write_f64::<LittleEndian>(40.0).unwrap();
+            bytes.write_f64::<LittleEndian>(time * 10.0).unwrap();
+            for _ in 4..8 {
+                bytes.write_f64::<LittleEndian>(0.0).unwrap();
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    #[cfg(feature = "sbet")]
+    fn sbet_time() {
+        let window = Window {
+            start: Some(1.5),
+            end: Some(3.0),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let input = Cursor::new(sbet_bytes(&[0.0, 1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(2, sbet(input, &mut output, &window).unwrap());
+        assert_eq!(sbet_bytes(&[2.0, 3.0]), output);
+    }
+
+    #[cfg(feature = "sbet")]
+    #[test]
+    fn sbet_no_angular_rates() {
+        let window = Window {
+            start: Some(1.5),
+            end: Some(3.0),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let input = Cursor::new(sbet_bytes_with_fields(&[0.0, 1.0, 2.0, 3.0, 4.0], 14));
+        assert_eq!(2, sbet(input, &mut output, &window).unwrap());
+        assert_eq!(sbet_bytes_with_fields(&[2.0, 3.0], 14), output);
+    }
+
+    #[test]
+    #[cfg(feature = "sbet")]
+    fn sbet_bounding_box() {
+        let window = Window {
+            bounding_box: Some(BoundingBox {
+                min_longitude: Radians(1.0),
+                min_latitude: Radians(1.0),
+                max_longitude: Radians(2.0),
+                max_latitude: Radians(2.0),
+            }),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let input = Cursor::new(sbet_bytes(&[0.0, 1.0]));
+        assert_eq!(0, sbet(input, &mut output, &window).unwrap());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "pof")]
+    fn pof_header() {
+        let window = Window {
+            start: Some(1.0),
+            end: Some(3.0),
+            ..Default::default()
+        };
+        let mut output = Cursor::new(Vec::new());
+        let input = Cursor::new(pof_bytes(&[0.0, 1.0, 1.5, 3.0, 4.0]));
+        assert_eq!(3, pof(input, &mut output, &window).unwrap());
+        output.set_position(0);
+        let clipped = pof::Reader::new(output).unwrap();
+        assert_eq!(3, clipped.entries);
+        assert_eq!(
+            [-105.0, -105.0, 40.0, 40.0, 10.0, 30.0],
+            [
+                clipped.minlon,
+                clipped.maxlon,
+                clipped.minlat,
+                clipped.maxlat,
+                clipped.minalt,
+                clipped.maxalt
+            ]
+        );
+
+        let mut writer = pof::Writer::new(Cursor::new(Vec::new())).unwrap();
+        for time in [1.0, 1.5, 3.0] {
+            writer
+                .write_point(&Point {
+                    time,
+                    longitude: Radians::from_degrees(-105.0),
+                    latitude: Radians::from_degrees(40.0),
+                    altitude: time * 10.0,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let mut written = writer.close().unwrap();
+        written.set_position(0);
+        let written = pof::Reader::new(written).unwrap();
+        assert_eq!(
+            (written.avgint, written.maxint, written.devint),
+            (clipped.avgint, clipped.maxint, clipped.devint)
+        );
+        assert_eq!(0.5, clipped.devint);
+    }
+
+    #[test]
+    #[cfg(feature = "pof")]
+    fn pof_trailer() {
+        let mut bytes = pof_bytes(&[0.0, 1.0, 2.0]);
+        bytes.extend_from_slice(b"trailer");
+        let window = Window {
+            start: Some(1.0),
+            ..Default::default()
+        };
+        let mut output = Cursor::new(Vec::new());
+        assert_eq!(2, pof(Cursor::new(bytes), &mut output, &window).unwrap());
+        output.set_position(0);
+        let mut clipped = pof::Reader::new(output).unwrap();
+        assert_eq!(b"trailer".to_vec(), clipped.read_trailer().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "pof")]
+    fn pof_undersized_header() {
+        let mut bytes = pof_bytes(&[0.0, 1.0]);
+        bytes[31..35].copy_from_slice(&40u32.to_le_bytes());
+        let mut output = Cursor::new(Vec::new());
+        let result = pof(Cursor::new(bytes), &mut output, &Window::default());
+        assert!(matches!(result, Err(Error::PofDataOffset(40))));
+    }
+
+    #[test]
+    #[cfg(feature = "pos")]
+    fn pos_time() {
+        let input = "header\n1.0 40.0 -105.0 0 0 0 0\n2.0 40.0 -105.0 0 0 0 0\n";
+        let window = Window {
+            end: Some(1.5),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        assert_eq!(
+            1,
+            pos(input.as_bytes(), &mut output, &window, Profile::POS).unwrap()
+        );
+        assert_eq!(
+            "header\n1.0 40.0 -105.0 0 0 0 0\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[cfg(feature = "pos")]
+    #[test]
+    fn pos_inertial_explorer() {
+        let header = "Version: Inertial Explorer
+Week GPSTime Latitude Longitude H-Ell SDNorth SDEast SDHeight Roll Pitch Heading RollSD PitchSD HdngSD
+
+";
+        let first =
+            "1840 151631.0 32.5 -116.9 107.7 0.02 0.01 0.05 -1.6 -1.4 174.5 0.01 0.01 0.03\n";
+        let second =
+            "1840 151632.0 32.5 -116.9 107.7 0.02 0.01 0.05 -1.6 -1.4 174.5 0.01 0.01 0.03\n";
+        let outside =
+            "1840 151633.0 40.0 -105.0 107.7 0.02 0.01 0.05 -1.6 -1.4 174.5 0.01 0.01 0.03\n";
+        let input = format!("{}{}{}{}", header, first, second, outside);
+        let window = Window {
+            start: Some(151_631.5),
+            bounding_box: Some(BoundingBox {
+                min_longitude: Radians::from_degrees(-117.0),
+                min_latitude: Radians::from_degrees(32.0),
+                max_longitude: Radians::from_degrees(-116.0),
+                max_latitude: Radians::from_degrees(33.0),
+            }),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let count = pos(
+            input.as_bytes(),
+            &mut output,
+            &window,
+            Profile::INERTIAL_EXPLORER,
+        )
+        .unwrap();
+        assert_eq!(1, count);
+        assert_eq!(
+            format!("{}{}", header, second),
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn clip_polygon() {
+        let degrees = |x: f64, y: f64| (Radians::from_degrees(x), Radians::from_degrees(y));
+        // An L shape, so the notch is inside the bounding box but outside the polygon.
+        let polygon = Polygon::new(vec![
+            degrees(0.0, 0.0),
+            degrees(2.0, 0.0),
+            degrees(2.0, 1.0),
+            degrees(1.0, 1.0),
+            degrees(1.0, 2.0),
+            degrees(0.0, 2.0),
+        ]);
+        let points: Vec<_> = [
+            (0.5, 0.5, 10.0),
+            (1.5, 1.5, 10.0),
+            (0.5, 1.5, 10.0),
+            (1.5, 0.5, 50.0),
+        ]
+        .into_iter()
+        .map(|(x, y, altitude)| {
+            let (longitude, latitude) = degrees(x, y);
+            Point {
+                longitude,
+                latitude,
+                altitude,
+                ..Default::default()
+            }
+        })
+        .collect();
+        let source = crate::source::MemorySource::new(points);
+        let mut clip = Clip::new(Box::new(source), Some(polygon.into())).max_altitude(20.0);
+        let mut kept = Vec::new();
+        while let Some(point) = clip.source().unwrap() {
+            kept.push(point.latitude.to_degrees());
+        }
+        assert_eq!(2, kept.len());
+        assert!((kept[1] - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn clip_bounding_box() {
+        let bbox = BoundingBox {
+            min_longitude: Radians::from_degrees(-106.0),
+            min_latitude: Radians::from_degrees(39.0),
+            max_longitude: Radians::from_degrees(-105.0),
+            max_latitude: Radians::from_degrees(40.0),
+        };
+        let points: Vec<_> = [
+            (-105.5, 39.5, 10.0),
+            (-104.5, 39.5, 10.0),
+            (-105.5, 40.5, 10.0),
+            (-105.5, 39.5, 0.0),
+            (-105.5, 39.5, 50.0),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (longitude, latitude, altitude))| Point {
+            time: i as f64,
+            longitude: Radians::from_degrees(longitude),
+            latitude: Radians::from_degrees(latitude),
+            altitude,
+            ..Default::default()
+        })
+        .collect();
+        let source = crate::source::MemorySource::new(points);
+        let mut clip = Clip::new(Box::new(source), Some(bbox.into()))
+            .min_altitude(5.0)
+            .max_altitude(20.0);
+        let mut kept = Vec::new();
+        while let Some(point) = clip.source().unwrap() {
+            kept.push(point.time);
+        }
+        assert_eq!(vec![0.0], kept);
+    }
+
+    #[derive(Debug)]
+    struct Counting(f64);
+
+    impl Source for Counting {
+        fn source(&mut self) -> Result<Option<Point>, Error> {
+            self.0 += 1.0;
+            Ok(Some(Point {
+                time: self.0,
+                ..Default::default()
+            }))
+        }
+    }
+
+    #[test]
+    fn clip_by_time() {
+        // The source never ends, so this only finishes if clipping stops at the end time.
+        let mut clip = Counting(0.0).clip(3.0, 5.0);
+        let mut times = Vec::new();
+        while let Some(point) = clip.source().unwrap() {
+            times.push(point.time);
+        }
+        assert_eq!(vec![3.0, 4.0, 5.0], times);
+        assert!(clip.source().unwrap().is_none());
+    }
+}
@@ -0,0 +1,281 @@
+//! Compare two trajectories.
+//!
+//! Points from one source are time-aligned to a reference [Trajectory] by interpolation, and
+//! the [Difference] at each time can either be collected as a series with [differences] or
+//! summarized with [Comparison].
+
+use crate::geodesy;
+use crate::point::Point;
+use crate::source::Source;
+use crate::trajectory::Trajectory;
+use crate::units::Radians;
+use crate::Error;
+
+/// The difference between a point and a reference point at the same time.
+///
+/// Positions are east, north, and up offsets from the reference in meters, and attitude
+/// differences are wrapped to `[-π, π)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct Difference {
+    pub time: f64,
+    pub east: f64,
+    pub north: f64,
+    pub up: f64,
+    pub roll: Radians<f64>,
+    pub pitch: Radians<f64>,
+    pub yaw: Radians<f64>,
+}
+
+/// Root-mean-square, mean, and maximum absolute differences along one axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub struct Axis {
+    pub rmse: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+/// Summary of the differences between two trajectories.
+///
+/// Positional differences are in meters, and attitude differences are in radians.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub struct Comparison {
+    /// The number of time-aligned points.
+    pub count: u64,
+    pub east: Axis,
+    pub north: Axis,
+    pub up: Axis,
+    pub roll: Axis,
+    pub pitch: Axis,
+    pub yaw: Axis,
+}
+
+impl Difference {
+    /// Computes the difference of a point from a reference point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::compare::Difference;
+    /// use pos::Point;
+    /// let reference = Point::default();
+    /// let point = Point { altitude: 1.0, ..Default::default() };
+    /// let difference = Difference::new(&reference, &point);
+    /// assert!((difference.up - 1.0).abs() < 1e-9);
+    /// ```
+    pub fn new(reference: &Point, point: &Point) -> Difference {
+        let enu = geodesy::enu(reference, point);
+        Difference {
+            time: point.time,
+            east: enu[0],
+            north: enu[1],
+            up: enu[2],
+            roll: (point.roll - reference.roll).wrapped(),
+            pitch: (point.pitch - reference.pitch).wrapped(),
+            yaw: (point.yaw - reference.yaw).wrapped(),
+        }
+    }
+}
+
+impl Comparison {
+    /// Compares every point of a source against a reference trajectory.
+    ///
+    /// Points outside of the reference's time range are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::compare::Comparison;
+    /// use pos::sbet::Reader;
+    /// use pos::Trajectory;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let reference = Trajectory::from_source(&mut reader).unwrap();
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let comparison = Comparison::new(&reference, &mut reader).unwrap();
+    /// assert_eq!(2, comparison.count);
+    /// assert_eq!(0.0, comparison.up.max);
+    /// ```
+    pub fn new(reference: &Trajectory, source: &mut dyn Source) -> Result<Comparison, Error> {
+        let mut accumulator = Accumulator::default();
+        while let Some(difference) = next(reference, source)? {
+            accumulator.add(&difference);
+        }
+        Ok(accumulator.finish())
+    }
+
+    /// Summarizes a series of differences.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::compare::{Comparison, Difference};
+    /// let differences = [
+    ///     Difference { up: 1.0, ..Default::default() },
+    ///     Difference { up: -3.0, ..Default::default() },
+    /// ];
+    /// let comparison = Comparison::from_differences(&differences);
+    /// assert_eq!(-1.0, comparison.up.mean);
+    /// assert_eq!(3.0, comparison.up.max);
+    /// ```
+    pub fn from_differences<'a>(
+        differences: impl IntoIterator<Item = &'a Difference>,
+    ) -> Comparison {
+        let mut accumulator = Accumulator::default();
+        for difference in differences {
+            accumulator.add(difference);
+        }
+        accumulator.finish()
+    }
+
+    fn axes_mut(&mut self) -> [&mut Axis; 6] {
+        [
+            &mut self.east,
+            &mut self.north,
+            &mut self.up,
+            &mut self.roll,
+            &mut self.pitch,
+            &mut self.yaw,
+        ]
+    }
+}
+
+/// Returns the difference of every point of a source from a reference trajectory.
+///
+/// Points outside of the reference's time range are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use pos::compare;
+/// use pos::sbet::Reader;
+/// use pos::Trajectory;
+/// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// let reference = Trajectory::from_source(&mut reader).unwrap();
+/// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// let differences = compare::differences(&reference, &mut reader).unwrap();
+/// assert_eq!(2, differences.len());
+/// ```
+pub fn differences(
+    reference: &Trajectory,
+    source: &mut dyn Source,
+) -> Result<Vec<Difference>, Error> {
+    let mut differences = Vec::new();
+    while let Some(difference) = next(reference, source)? {
+        differences.push(difference);
+    }
+    Ok(differences)
+}
+
+fn next(reference: &Trajectory, source: &mut dyn Source) -> Result<Option<Difference>, Error> {
+    while let Some(point) = source.source()? {
+        match reference.interpolate(point.time) {
+            Ok(interpolated) => return Ok(Some(Difference::new(&interpolated, &point))),
+            Err(Error::TimeBelowMinimum(_) | Error::TimeAboveMaximum(_)) => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(None)
+}
+
+#[derive(Debug, Default)]
+struct Accumulator {
+    comparison: Comparison,
+    sums: [f64; 6],
+    squares: [f64; 6],
+}
+
+impl Accumulator {
+    fn add(&mut self, difference: &Difference) {
+        let values = [
+            difference.east,
+            difference.north,
+            difference.up,
+            difference.roll.0,
+            difference.pitch.0,
+            difference.yaw.0,
+        ];
+        for (i, (axis, value)) in self
+            .comparison
+            .axes_mut()
+            .into_iter()
+            .zip(values)
+            .enumerate()
+        {
+            self.sums[i] += value;
+            self.squares[i] += value * value;
+            axis.max = axis.max.max(value.abs());
+        }
+        self.comparison.count += 1;
+    }
+
+    fn finish(mut self) -> Comparison {
+        if self.comparison.count > 0 {
+            let count = self.comparison.count as f64;
+            for (i, axis) in self.comparison.axes_mut().into_iter().enumerate() {
+                axis.mean = self.sums[i] / count;
+                axis.rmse = (self.squares[i] / count).sqrt();
+            }
+        }
+        self.comparison
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    #[test]
+    fn wrap_yaw() {
+        let reference = Point {
+            yaw: Radians::from_degrees(359.0),
+            ..Default::default()
+        };
+        let point = Point {
+            yaw: Radians::from_degrees(1.0),
+            ..Default::default()
+        };
+        let difference = Difference::new(&reference, &point);
+        assert!((difference.yaw.to_degrees() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn offset() {
+        let reference = Trajectory::new(vec![
+            Point {
+                time: 0.0,
+                ..Default::default()
+            },
+            Point {
+                time: 2.0,
+                ..Default::default()
+            },
+        ]);
+        let points = vec![
+            Point {
+                time: -1.0,
+                ..Default::default()
+            },
+            Point {
+                time: 1.0,
+                altitude: 3.0,
+                ..Default::default()
+            },
+            Point {
+                time: 2.0,
+                altitude: -4.0,
+                ..Default::default()
+            },
+        ];
+        let mut source = MemorySource::new(points);
+        let comparison = Comparison::new(&reference, &mut source).unwrap();
+        assert_eq!(2, comparison.count);
+        assert!((comparison.up.max - 4.0).abs() < 1e-6);
+        assert!((comparison.up.rmse - 12.5f64.sqrt()).abs() < 1e-6);
+        assert!((comparison.up.mean + 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,230 @@
+//! Convert between formats.
+//!
+//! Readers and writers already agree on units, since every [Point] holds radians and meters, so
+//! piping any [Source] into any [Sink] converts between pos, sbet, and pof files. [convert] adds
+//! the adjustments that conversions usually need on top of that: shifting and rebasing times,
+//! writing heights in another unit, and filling in fields that the target format stores but the
+//! source doesn't have, such as an sbet's velocities or a pof's distance. Its [Summary] reports
+//! the fields that were still missing, which fixed-layout writers store as zeros.
+
+use crate::along_track::AlongTrack;
+use crate::dynamics::Derive;
+use crate::point::Point;
+use crate::sink::Sink;
+use crate::source::Source;
+use crate::units::LengthUnit;
+use crate::Error;
+
+/// The number of seconds in a day.
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// The number of seconds in a week.
+const SECONDS_PER_WEEK: f64 = 604_800.0;
+
+/// How times are rebased during a conversion.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeBase {
+    /// Times are written as they are.
+    #[default]
+    Unchanged,
+    /// Times are relative to the first point, like a pof file's normalized time.
+    Start,
+    /// Times are seconds of the day, e.g. to convert GPS week seconds to a pof file's day time.
+    Day,
+    /// Times are seconds of the week, e.g. to convert GPS seconds to an sbet's week seconds.
+    Week,
+}
+
+/// Options for [convert].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConvertOptions {
+    time_offset: f64,
+    time_base: TimeBase,
+    height_unit: LengthUnit,
+    derive: bool,
+    distance: bool,
+}
+
+/// What happened during a conversion.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    /// The number of points written.
+    pub points: u64,
+    /// The optional fields, in the order of [Point::FIELDS], that at least one written point
+    /// didn't have.
+    pub missing: Vec<&'static str>,
+}
+
+impl ConvertOptions {
+    /// Creates new options that don't change any points.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::convert::{ConvertOptions, TimeBase};
+    /// let options = ConvertOptions::new().with_time_base(TimeBase::Day).with_derive(true);
+    /// ```
+    pub fn new() -> ConvertOptions {
+        ConvertOptions::default()
+    }
+
+    /// Sets the number of seconds added to every time, which is applied before rebasing.
+    pub fn with_time_offset(mut self, time_offset: f64) -> ConvertOptions {
+        self.time_offset = time_offset;
+        self
+    }
+
+    /// Sets how times are rebased.
+    pub fn with_time_base(mut self, time_base: TimeBase) -> ConvertOptions {
+        self.time_base = time_base;
+        self
+    }
+
+    /// Sets the unit that altitudes are written in, which defaults to meters.
+    ///
+    /// Only use this with writers that don't assume meters, such as pos writers.
+    pub fn with_height_unit(mut self, height_unit: LengthUnit) -> ConvertOptions {
+        self.height_unit = height_unit;
+        self
+    }
+
+    /// Sets whether missing velocities, accelerations, and angular rates are derived from the
+    /// points with [Derive].
+    pub fn with_derive(mut self, derive: bool) -> ConvertOptions {
+        self.derive = derive;
+        self
+    }
+
+    /// Sets whether each point's distance is replaced by the distance along the track, with
+    /// [AlongTrack].
+    pub fn with_distance(mut self, distance: bool) -> ConvertOptions {
+        self.distance = distance;
+        self
+    }
+}
+
+/// Writes every point from a source to a sink, adjusting the points along the way, then finishes
+/// the sink.
+///
+/// # Examples
+///
+/// ```
+/// use pos::convert::{self, ConvertOptions};
+/// use pos::sbet::Reader;
+/// use pos::pof::Writer;
+/// use std::io::Cursor;
+/// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// let mut writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+/// let options = ConvertOptions::new().with_distance(true);
+/// let summary = convert::convert(reader, &mut writer, options).unwrap();
+/// assert_eq!(2, summary.points);
+/// assert!(!summary.missing.contains(&"distance"));
+/// ```
+pub fn convert<S: Source + 'static, K: Sink>(
+    source: S,
+    mut sink: K,
+    options: ConvertOptions,
+) -> Result<Summary, Error> {
+    let mut source: Box<dyn Source> = Box::new(source);
+    if options.derive {
+        source = Box::new(Derive::new(source));
+    }
+    if options.distance {
+        source = Box::new(AlongTrack::new(source));
+    }
+    let mut seen = [true; Point::FIELDS.len()];
+    let mut start = None;
+    let mut points = 0;
+    while let Some(mut point) = source.source()? {
+        let fields = point.fields();
+        for (seen, (name, _)) in seen.iter_mut().zip(Point::FIELDS) {
+            *seen = *seen && fields.iter().any(|field| field.0 == name);
+        }
+        point.time += options.time_offset;
+        let start = *start.get_or_insert(point.time);
+        point.time = match options.time_base {
+            TimeBase::Unchanged => point.time,
+            TimeBase::Start => point.time - start,
+            TimeBase::Day => point.time.rem_euclid(SECONDS_PER_DAY),
+            TimeBase::Week => point.time.rem_euclid(SECONDS_PER_WEEK),
+        };
+        point.altitude /= options.height_unit.to_meters(1.0);
+        sink.sink(&point)?;
+        points += 1;
+    }
+    sink.finish()?;
+    let missing = if points > 0 {
+        Point::FIELDS
+            .iter()
+            .zip(seen)
+            .filter(|(_, seen)| !seen)
+            .map(|((name, _), _)| *name)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    #[cfg(feature = "tracing")]
+    tracing::debug!(points, ?missing, "converted points");
+    Ok(Summary { points, missing })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    fn source() -> MemorySource {
+        let points: Vec<_> = (0..4)
+            .map(|i| Point {
+                time: 604_800.0 + 86_400.0 + i as f64,
+                altitude: 3.048,
+                x_velocity: Some(1.0),
+                ..Default::default()
+            })
+            .collect();
+        MemorySource::new(points)
+    }
+
+    #[test]
+    fn times() {
+        for (time_base, first) in [
+            (TimeBase::Unchanged, 691_210.0),
+            (TimeBase::Start, 0.0),
+            (TimeBase::Day, 10.0),
+            (TimeBase::Week, 86_410.0),
+        ] {
+            let mut points = Vec::new();
+            let options = ConvertOptions::new()
+                .with_time_offset(10.0)
+                .with_time_base(time_base);
+            let _ = convert(source(), &mut points, options).unwrap();
+            assert_eq!(first, points[0].time, "{:?}", time_base);
+            assert_eq!(first + 3.0, points[3].time, "{:?}", time_base);
+        }
+    }
+
+    #[test]
+    fn height_unit() {
+        let mut points = Vec::new();
+        let options = ConvertOptions::new().with_height_unit(LengthUnit::Feet);
+        let _ = convert(source(), &mut points, options).unwrap();
+        assert!((points[0].altitude - 10.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn missing() {
+        let mut points = Vec::new();
+        let summary = convert(source(), &mut points, ConvertOptions::new()).unwrap();
+        assert_eq!(4, summary.points);
+        assert!(!summary.missing.contains(&"x_velocity"));
+        assert!(summary.missing.contains(&"y_velocity"));
+        assert!(summary.missing.contains(&"distance"));
+
+        let options = ConvertOptions::new().with_derive(true).with_distance(true);
+        let summary = convert(source(), &mut points, options).unwrap();
+        for name in ["distance", "y_velocity", "x_acceleration", "z_angular_rate"] {
+            assert!(!summary.missing.contains(&name), "{}", name);
+        }
+        assert!(summary.missing.contains(&"wander_angle"));
+    }
+}
@@ -0,0 +1,153 @@
+//! Crab angle analysis.
+//!
+//! The crab (or drift) angle is the difference between where the platform points (yaw) and
+//! where it's actually going (course over ground). In an aircraft it's mostly caused by
+//! crosswind, and large or varying crab angles on flight lines are a standard QC flag.
+
+use crate::geodesy;
+use crate::point::Point;
+use crate::segment::{Kind, Segment};
+use crate::units::Radians;
+
+/// Crab angle statistics over one flight line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Line {
+    /// The time of the first point in the line.
+    pub start: f64,
+    /// The time of the last point in the line.
+    pub end: f64,
+    /// The number of points with a crab angle.
+    pub count: usize,
+    /// The mean crab angle.
+    pub mean: Radians<f64>,
+    /// The minimum crab angle.
+    pub min: Radians<f64>,
+    /// The maximum crab angle.
+    pub max: Radians<f64>,
+}
+
+/// Returns the crab angle of every point, yaw minus course over ground, wrapped to `[-π, π)`.
+///
+/// The course is computed from the positions of each point's neighbors. Points moving
+/// horizontally slower than `min_speed`, in meters per second, don't have a meaningful course,
+/// so their crab angle is `None`, as is the crab angle of a lone point.
+///
+/// # Examples
+///
+/// ```
+/// use pos::{crab, Point, Radians};
+/// // Flying north while pointing five degrees east.
+/// let points: Vec<_> = (0..3)
+///     .map(|i| Point {
+///         time: i as f64,
+///         latitude: Radians::from_degrees(0.001 * i as f64),
+///         yaw: Radians::from_degrees(5.0),
+///         ..Default::default()
+///     })
+///     .collect();
+/// let angles = crab::angles(&points, 5.0);
+/// assert!((angles[1].unwrap().to_degrees() - 5.0).abs() < 1e-6);
+/// ```
+pub fn angles(points: &[Point], min_speed: f64) -> Vec<Option<Radians<f64>>> {
+    (0..points.len())
+        .map(|i| {
+            let before = &points[i.saturating_sub(1)];
+            let after = &points[(i + 1).min(points.len() - 1)];
+            let interval = after.time - before.time;
+            if interval <= 0.0 {
+                return None;
+            }
+            let [east, north, _] = geodesy::enu(before, after);
+            if east.hypot(north) / interval < min_speed {
+                return None;
+            }
+            let course = Radians(east.atan2(north));
+            Some((points[i].yaw - course).wrapped())
+        })
+        .collect()
+}
+
+/// Summarizes crab angles over each flight line.
+///
+/// Segments that aren't [lines](Kind::Line) are skipped, as are lines without any crab angles.
+/// Points are matched to segments by time, so both should come from the same trajectory.
+///
+/// # Examples
+///
+/// ```
+/// use pos::crab;
+/// use pos::sbet::Reader;
+/// use pos::segment::{Segmenter, DEFAULT_MIN_SPEED};
+/// use pos::Trajectory;
+/// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// let trajectory = Trajectory::from_source(&mut reader).unwrap();
+/// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// let segments = Segmenter::new().segment(&mut reader).unwrap();
+/// let angles = crab::angles(trajectory.points(), DEFAULT_MIN_SPEED);
+/// let lines = crab::lines(trajectory.points(), &angles, &segments);
+/// ```
+pub fn lines(points: &[Point], angles: &[Option<Radians<f64>>], segments: &[Segment]) -> Vec<Line> {
+    segments
+        .iter()
+        .filter(|segment| segment.kind == Kind::Line)
+        .filter_map(|segment| {
+            let start = points.partition_point(|point| point.time < segment.start);
+            let end = points.partition_point(|point| point.time <= segment.end);
+            let values: Vec<_> = angles[start..end].iter().flatten().map(|a| a.0).collect();
+            if values.is_empty() {
+                return None;
+            }
+            Some(Line {
+                start: segment.start,
+                end: segment.end,
+                count: values.len(),
+                mean: Radians(values.iter().sum::<f64>() / values.len() as f64),
+                min: Radians(values.iter().copied().fold(f64::INFINITY, f64::min)),
+                max: Radians(values.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segment::Segmenter;
+    use crate::source::MemorySource;
+
+    /// Flies north at about 50 m/s, pointing three degrees west, then stops.
+    fn points() -> Vec<Point> {
+        (0..40)
+            .map(|i| Point {
+                time: i as f64,
+                latitude: Radians::from_degrees(50.0 / 111_000.0 * i.min(30) as f64),
+                yaw: Radians::from_degrees(-3.0),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stationary() {
+        let crab = angles(&points(), 5.0);
+        assert!(crab[..30].iter().all(|angle| angle.is_some()));
+        assert!(crab[31..].iter().all(|angle| angle.is_none()));
+        assert_eq!(vec![None], angles(&points()[..1], 5.0));
+    }
+
+    #[test]
+    fn line() {
+        let points = points();
+        let segments = Segmenter::new()
+            .segment(&mut MemorySource::new(points.clone()))
+            .unwrap();
+        let crab = angles(&points, 5.0);
+        let lines = lines(&points, &crab, &segments);
+        assert_eq!(1, lines.len());
+        assert_eq!(0.0, lines[0].start);
+        assert_eq!(30.0, lines[0].end);
+        assert_eq!(31, lines[0].count);
+        assert!((lines[0].mean.to_degrees() + 3.0).abs() < 1e-6);
+        assert!((lines[0].max.to_degrees() + 3.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,121 @@
+//! Coordinate reference system transformations, using [proj].
+//!
+//! Points are always read as WGS84 geographic coordinates. A [Transformer] moves them into some
+//! other horizontal CRS, e.g. a state plane or UTM zone, for export.
+//! `csv::Writer::with_transformer` and `wkt::write_transformed` use one to export projected
+//! coordinates.
+//!
+//! Only the horizontal coordinates are transformed. Heights are passed through unchanged, so a
+//! transformation that includes a datum shift doesn't change them; use [datum](crate::datum) to
+//! convert between vertical datums.
+
+use crate::point::Point;
+use crate::Error;
+use proj::Proj;
+
+/// The CRS of the points produced by this crate's readers.
+pub const WGS84: &str = "EPSG:4326";
+
+/// Transforms points from one CRS to another.
+#[derive(Debug)]
+pub struct Transformer {
+    proj: Proj,
+}
+
+/// A point that has been transformed into a (possibly) projected CRS.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Transformed {
+    /// The time of the original point.
+    pub time: f64,
+    /// The easting, or longitude in degrees for geographic CRSs.
+    pub x: f64,
+    /// The northing, or latitude in degrees for geographic CRSs.
+    pub y: f64,
+    /// The height, which is passed through unchanged.
+    pub z: f64,
+}
+
+impl Transformer {
+    /// Creates a transformer from WGS84 to the given CRS.
+    ///
+    /// The CRS can be anything that PROJ understands, e.g. `"EPSG:6430"` for NAD83(2011) /
+    /// Colorado Central (ftUS).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::crs::Transformer;
+    /// let transformer = Transformer::new("EPSG:32613").unwrap();
+    /// ```
+    pub fn new(to: &str) -> Result<Transformer, Error> {
+        Transformer::from_crs(WGS84, to)
+    }
+
+    /// Creates a transformer between two arbitrary CRSs.
+    ///
+    /// Axis order is normalized so that geographic coordinates are always longitude, latitude.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::crs::Transformer;
+    /// let transformer = Transformer::from_crs("EPSG:4326", "EPSG:6342").unwrap();
+    /// ```
+    pub fn from_crs(from: &str, to: &str) -> Result<Transformer, Error> {
+        let proj = Proj::new_known_crs(from, to, None)?;
+        Ok(Transformer { proj })
+    }
+
+    /// Transforms a point.
+    ///
+    /// Only the horizontal coordinates are transformed; the altitude is passed through as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::crs::Transformer;
+    /// use pos::{Point, Radians};
+    /// let transformer = Transformer::new("EPSG:32613").unwrap();
+    /// let point = Point {
+    ///     longitude: Radians::from_degrees(-105.0),
+    ///     latitude: Radians::from_degrees(40.0),
+    ///     ..Default::default()
+    /// };
+    /// let transformed = transformer.transform(&point).unwrap();
+    /// assert!((transformed.x - 500000.0).abs() < 1e-3);
+    /// ```
+    pub fn transform(&self, point: &Point) -> Result<Transformed, Error> {
+        let (x, y) = self
+            .proj
+            .convert((point.longitude.to_degrees(), point.latitude.to_degrees()))?;
+        Ok(Transformed {
+            time: point.time,
+            x,
+            y,
+            z: point.altitude,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Radians;
+
+    #[test]
+    fn utm() {
+        let transformer = Transformer::new("EPSG:32613").unwrap();
+        let point = Point {
+            time: 42.0,
+            longitude: Radians::from_degrees(-105.0),
+            latitude: Radians::from_degrees(40.0),
+            altitude: 1600.0,
+            ..Default::default()
+        };
+        let transformed = transformer.transform(&point).unwrap();
+        assert_eq!(42.0, transformed.time);
+        assert!((transformed.x - 500000.0).abs() < 1e-3);
+        assert!((transformed.y - 4427757.219).abs() < 1e-3);
+        assert_eq!(1600.0, transformed.z);
+    }
+}
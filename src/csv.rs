@@ -0,0 +1,709 @@
+//! Read and write points as comma-separated values.
+//!
+//! Columns are chosen by name: any name in [Point::FIELDS], or one of the accuracy columns in
+//! [ACCURACY_COLUMNS], such as `sd_x` or `pdop`. Angles and angular rates are written in degrees
+//! by default, and every value is written with a fixed number of decimal places.
+//!
+//! Any delimited text trajectory can be read by describing its columns with a [ColumnMap].
+//!
+//! With the `proj` feature, [Writer::with_transformer] writes projected coordinates, such as
+//! eastings and northings, in the longitude and latitude columns.
+
+#[cfg(feature = "proj")]
+use crate::crs::Transformer;
+use crate::point::{Accuracy, Point, SatelliteCount};
+use crate::sink::Sink;
+use crate::source::Source;
+use crate::units::{AngleUnit, LengthUnit, Radians, Unit};
+use crate::{Error, Location};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The names of the columns taken from a point's accuracy, with their units.
+pub const ACCURACY_COLUMNS: [(&str, Option<Unit>); 10] = [
+    ("sd_x", Some(Unit::Meters)),
+    ("sd_y", Some(Unit::Meters)),
+    ("sd_z", Some(Unit::Meters)),
+    ("sd_roll", Some(Unit::Radians)),
+    ("sd_pitch", Some(Unit::Radians)),
+    ("sd_yaw", Some(Unit::Radians)),
+    ("pdop", None),
+    ("hdop", None),
+    ("vdop", None),
+    ("satellites", None),
+];
+
+/// The default number of decimal places.
+pub const DEFAULT_PRECISION: usize = 6;
+
+/// A column of a csv file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Column {
+    name: &'static str,
+    unit: Option<Unit>,
+}
+
+/// A csv writer.
+///
+/// Writes a header row of column names before the first point. Values that a point doesn't have
+/// are left empty.
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    writer: W,
+    columns: Vec<Column>,
+    precision: usize,
+    angle_unit: AngleUnit,
+    header: bool,
+    #[cfg(feature = "proj")]
+    transformer: Option<Transformer>,
+}
+
+impl Column {
+    /// Returns this column's name.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns this column's value for a point, in the column's unit, if the point has one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::Column;
+    /// use pos::Point;
+    /// let column: Column = "altitude".parse().unwrap();
+    /// let point = Point { altitude: 42.0, ..Default::default() };
+    /// assert_eq!(Some(42.0), column.value(&point));
+    /// ```
+    pub fn value(&self, point: &Point) -> Option<f64> {
+        let accuracy = point.accuracy;
+        match self.name {
+            "sd_x" => accuracy.map(|a| a.x),
+            "sd_y" => accuracy.map(|a| a.y),
+            "sd_z" => accuracy.map(|a| a.z),
+            "sd_roll" => accuracy.map(|a| a.roll.0),
+            "sd_pitch" => accuracy.map(|a| a.pitch.0),
+            "sd_yaw" => accuracy.map(|a| a.yaw.0),
+            "pdop" => accuracy.map(|a| a.pdop),
+            "hdop" => accuracy.and_then(|a| a.hdop),
+            "vdop" => accuracy.and_then(|a| a.vdop),
+            "satellites" => accuracy
+                .and_then(|a| a.satellite_count)
+                .map(|count| count.total().into()),
+            name => point
+                .fields()
+                .into_iter()
+                .find(|field| field.0 == name)
+                .map(|field| field.1),
+        }
+    }
+
+    /// Sets this column's value on a point, in the column's unit.
+    ///
+    /// Setting an accuracy column gives the point an accuracy if it doesn't have one, with a zero
+    /// time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::Column;
+    /// use pos::Point;
+    /// let mut point = Point::default();
+    /// "sd_z".parse::<Column>().unwrap().set(&mut point, 0.1);
+    /// assert_eq!(0.1, point.accuracy.unwrap().z);
+    /// ```
+    pub fn set(&self, point: &mut Point, value: f64) {
+        fn accuracy(point: &mut Point) -> &mut Accuracy {
+            point.accuracy.get_or_insert_with(Accuracy::default)
+        }
+        match self.name {
+            "sd_x" => accuracy(point).x = value,
+            "sd_y" => accuracy(point).y = value,
+            "sd_z" => accuracy(point).z = value,
+            "sd_roll" => accuracy(point).roll = Radians(value),
+            "sd_pitch" => accuracy(point).pitch = Radians(value),
+            "sd_yaw" => accuracy(point).yaw = Radians(value),
+            "pdop" => accuracy(point).pdop = value,
+            "hdop" => accuracy(point).hdop = Some(value),
+            "vdop" => accuracy(point).vdop = Some(value),
+            "satellites" => {
+                accuracy(point).satellite_count = Some(SatelliteCount::Unspecified(value as u16))
+            }
+            name => point
+                .set_field(name, value)
+                .expect("columns are parsed from known names"),
+        }
+    }
+
+    fn is_angle(&self) -> bool {
+        matches!(self.unit, Some(Unit::Radians | Unit::RadiansPerSecond))
+    }
+
+    fn is_height(&self) -> bool {
+        matches!(self.name, "altitude" | "heave" | "height_above_ground")
+    }
+}
+
+impl FromStr for Column {
+    type Err = Error;
+
+    /// Parses a name from [Point::FIELDS] or [ACCURACY_COLUMNS].
+    fn from_str(s: &str) -> Result<Column, Error> {
+        let s = s.trim();
+        Point::FIELDS
+            .iter()
+            .map(|&(name, unit)| (name, Some(unit)))
+            .chain(ACCURACY_COLUMNS)
+            .find(|(name, _)| *name == s)
+            .map(|(name, unit)| Column { name, unit })
+            .ok_or_else(|| Error::UnknownField(s.to_string()))
+    }
+}
+
+/// Parses a comma-separated list of column names.
+///
+/// # Examples
+///
+/// ```
+/// use pos::csv;
+/// let columns = csv::columns("time, latitude, longitude, sd_x").unwrap();
+/// assert_eq!(4, columns.len());
+/// assert!(csv::columns("time,speed").is_err());
+/// ```
+pub fn columns(s: &str) -> Result<Vec<Column>, Error> {
+    s.split(',').map(str::parse).collect()
+}
+
+/// Returns a column for every field in [Point::FIELDS], in order.
+///
+/// # Examples
+///
+/// ```
+/// use pos::csv;
+/// assert_eq!("time", csv::point_columns()[0].name());
+/// ```
+pub fn point_columns() -> Vec<Column> {
+    Point::FIELDS
+        .iter()
+        .map(|&(name, unit)| Column {
+            name,
+            unit: Some(unit),
+        })
+        .collect()
+}
+
+/// Which column of a delimited text file holds which value, and the units of the values.
+///
+/// By default, fields are separated by commas and whitespace, with runs of separators counting
+/// as one, angles and angular rates are in degrees, and heights are in meters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnMap {
+    columns: Vec<Option<Column>>,
+    delimiter: Option<char>,
+    angle_unit: AngleUnit,
+    height_unit: LengthUnit,
+}
+
+impl ColumnMap {
+    /// Creates a map from the column, if any, of each field of a line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::ColumnMap;
+    /// let map = ColumnMap::new(vec![Some("time".parse().unwrap()), None]);
+    /// ```
+    pub fn new(columns: Vec<Option<Column>>) -> ColumnMap {
+        ColumnMap {
+            columns,
+            delimiter: None,
+            angle_unit: AngleUnit::Degrees,
+            height_unit: LengthUnit::Meters,
+        }
+    }
+
+    /// Separates fields by exactly this delimiter, so empty fields are kept as missing values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::ColumnMap;
+    /// let map: ColumnMap = "time,latitude,longitude".parse().unwrap();
+    /// let map = map.with_delimiter(';');
+    /// ```
+    pub fn with_delimiter(mut self, delimiter: char) -> ColumnMap {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Sets the unit of angles, angular rates, and their standard deviations, which defaults to
+    /// degrees.
+    pub fn with_angle_unit(mut self, angle_unit: AngleUnit) -> ColumnMap {
+        self.angle_unit = angle_unit;
+        self
+    }
+
+    /// Sets the unit of altitudes, heave, and heights above ground, which defaults to meters.
+    pub fn with_height_unit(mut self, height_unit: LengthUnit) -> ColumnMap {
+        self.height_unit = height_unit;
+        self
+    }
+
+    /// Reads a point from a line's fields.
+    fn point(&self, line: &str) -> Result<Point, Error> {
+        let fields: Vec<&str> = match self.delimiter {
+            Some(delimiter) => line.split(delimiter).map(str::trim).collect(),
+            None => line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|field| !field.is_empty())
+                .collect(),
+        };
+        let mut point = Point::default();
+        for (i, column) in self.columns.iter().enumerate() {
+            let column = match column {
+                Some(column) => column,
+                None => continue,
+            };
+            let field = fields.get(i).ok_or(Error::MissingColumn(i))?;
+            if field.is_empty() {
+                continue;
+            }
+            let mut value: f64 = field.parse()?;
+            if column.is_angle() {
+                value = self.angle_unit.to_radians(value).0;
+            } else if column.is_height() {
+                value = self.height_unit.to_meters(value);
+            }
+            column.set(&mut point, value);
+        }
+        if let Some(accuracy) = point.accuracy.as_mut() {
+            accuracy.time = point.time;
+        }
+        Ok(point)
+    }
+}
+
+impl FromStr for ColumnMap {
+    type Err = Error;
+
+    /// Parses a comma-separated list of column names, where an empty name or `_` skips a field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::ColumnMap;
+    /// let map: ColumnMap = "time,_,latitude,longitude,altitude".parse().unwrap();
+    /// assert!("time,speed".parse::<ColumnMap>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<ColumnMap, Error> {
+        s.split(',')
+            .map(|name| match name.trim() {
+                "" | "_" => Ok(None),
+                name => name.parse().map(Some),
+            })
+            .collect::<Result<_, _>>()
+            .map(ColumnMap::new)
+    }
+}
+
+/// A delimited text reader.
+///
+/// Blank lines and lines starting with `#` are skipped, as are header lines, whose fields aren't
+/// all numbers, before the first point. Fields without a column are ignored, and point values
+/// without a field are left at their defaults.
+#[derive(Debug)]
+pub struct Reader<R: BufRead> {
+    reader: R,
+    map: ColumnMap,
+    data: bool,
+    record: u64,
+    path: Option<PathBuf>,
+}
+
+impl Reader<BufReader<File>> {
+    /// Creates a new reader for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::csv::Reader;
+    /// let map = "time,latitude,longitude,altitude".parse().unwrap();
+    /// let reader = Reader::from_path("trajectory.txt", map).unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        map: ColumnMap,
+    ) -> Result<Reader<BufReader<File>>, std::io::Error> {
+        let path = path.as_ref();
+        let mut reader = Reader::new(BufReader::new(File::open(path)?), map);
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a new reader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::Reader;
+    /// let reader = Reader::new(std::io::empty(), "time".parse().unwrap());
+    /// ```
+    pub fn new(reader: R, map: ColumnMap) -> Reader<R> {
+        Reader {
+            reader,
+            map,
+            data: false,
+            record: 0,
+            path: None,
+        }
+    }
+
+    /// Creates a new reader whose columns are named by the first line, such as the header row
+    /// written by [Writer].
+    ///
+    /// Fields are separated by exactly one comma, so empty fields are missing values. Names that
+    /// aren't columns are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::Reader;
+    /// let bytes = b"time,notes,altitude\n1.5,,107.0\n";
+    /// let mut reader = Reader::from_header(&bytes[..]).unwrap();
+    /// let point = reader.read_point().unwrap().unwrap();
+    /// assert_eq!((1.5, 107.0), (point.time, point.altitude));
+    /// ```
+    pub fn from_header(mut reader: R) -> Result<Reader<R>, std::io::Error> {
+        let mut header = String::new();
+        let _ = reader.read_line(&mut header)?;
+        let map = ColumnMap::new(header.split(',').map(|name| name.parse().ok()).collect())
+            .with_delimiter(',');
+        Ok(Reader::new(reader, map))
+    }
+
+    /// Sets the path that read errors are located in.
+    pub(crate) fn with_path(mut self, path: &Path) -> Reader<R> {
+        self.path = Some(path.to_path_buf());
+        self
+    }
+
+    /// Reads the next point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::Reader;
+    /// let bytes = b"gps_time,lat,lon,h\n1.5,32.5,-117.0,107.0\n";
+    /// let map = "time,latitude,longitude,altitude".parse().unwrap();
+    /// let mut reader = Reader::new(&bytes[..], map);
+    /// let point = reader.read_point().unwrap().unwrap();
+    /// assert_eq!(1.5, point.time);
+    /// assert!((point.latitude_degrees() - 32.5).abs() < 1e-12);
+    /// ```
+    pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match self.map.point(line) {
+                Ok(point) => {
+                    self.data = true;
+                    self.record += 1;
+                    return Ok(Some(point));
+                }
+                Err(Error::ParseFloat(_)) if !self.data => continue,
+                Err(err) => {
+                    self.record += 1;
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> IntoIterator for Reader<R> {
+    type Item = Point;
+    type IntoIter = ReaderIterator<R>;
+    fn into_iter(self) -> Self::IntoIter {
+        ReaderIterator { reader: self }
+    }
+}
+
+/// An iterator over a delimited text reader.
+#[derive(Debug)]
+pub struct ReaderIterator<R: BufRead> {
+    reader: Reader<R>,
+}
+
+impl<R: BufRead> Iterator for ReaderIterator<R> {
+    type Item = Point;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_point().unwrap()
+    }
+}
+
+impl<R: Debug + BufRead> Source for Reader<R> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let record = self.record;
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(record),
+            })
+        });
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read delimited text line");
+        }
+        result
+    }
+}
+
+impl Writer<BufWriter<File>> {
+    /// Creates a new writer for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::csv::{self, Writer};
+    /// let writer = Writer::from_path("out.csv", csv::columns("time,altitude").unwrap()).unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        columns: Vec<Column>,
+    ) -> Result<Writer<BufWriter<File>>, std::io::Error> {
+        Ok(Writer::new(BufWriter::new(File::create(path)?), columns))
+    }
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new writer for some columns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::{self, Writer};
+    /// let writer = Writer::new(Vec::new(), csv::columns("time,altitude").unwrap());
+    /// ```
+    pub fn new(writer: W, columns: Vec<Column>) -> Writer<W> {
+        Writer {
+            writer,
+            columns,
+            precision: DEFAULT_PRECISION,
+            angle_unit: AngleUnit::Degrees,
+            header: false,
+            #[cfg(feature = "proj")]
+            transformer: None,
+        }
+    }
+
+    /// Sets the number of decimal places, which defaults to [DEFAULT_PRECISION].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::{self, Writer};
+    /// let writer = Writer::new(Vec::new(), csv::columns("time").unwrap()).with_precision(3);
+    /// ```
+    pub fn with_precision(mut self, precision: usize) -> Writer<W> {
+        self.precision = precision;
+        self
+    }
+
+    /// Sets the unit of angles and angular rates, which defaults to degrees.
+    pub fn with_angle_unit(mut self, angle_unit: AngleUnit) -> Writer<W> {
+        self.angle_unit = angle_unit;
+        self
+    }
+
+    /// Transforms positions into another CRS as they're written.
+    ///
+    /// The longitude and latitude columns hold the transformed x and y, e.g. easting and
+    /// northing, in the CRS's units rather than the angle unit. Altitudes are written unchanged,
+    /// so a datum shift doesn't change heights.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::crs::Transformer;
+    /// use pos::csv::{self, Writer};
+    /// use pos::{Point, Radians};
+    /// let mut writer = Writer::new(Vec::new(), csv::columns("longitude,latitude").unwrap())
+    ///     .with_precision(0)
+    ///     .with_transformer(Transformer::new("EPSG:32613").unwrap());
+    /// let point = Point {
+    ///     longitude: Radians::from_degrees(-105.0),
+    ///     latitude: Radians::from_degrees(40.0),
+    ///     ..Default::default()
+    /// };
+    /// writer.write_point(&point).unwrap();
+    /// let text = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    /// assert_eq!("longitude,latitude\n500000,4427757\n", text);
+    /// ```
+    #[cfg(feature = "proj")]
+    pub fn with_transformer(mut self, transformer: Transformer) -> Writer<W> {
+        self.transformer = Some(transformer);
+        self
+    }
+
+    /// Writes a point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::csv::{self, Writer};
+    /// use pos::Point;
+    /// let mut writer = Writer::new(Vec::new(), csv::columns("time,distance").unwrap())
+    ///     .with_precision(2);
+    /// writer.write_point(&Point { time: 1.0, ..Default::default() }).unwrap();
+    /// let text = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    /// assert_eq!("time,distance\n1.00,\n", text);
+    /// ```
+    pub fn write_point(&mut self, point: &Point) -> Result<(), std::io::Error> {
+        self.write_header()?;
+        #[cfg(feature = "proj")]
+        let transformed = self
+            .transformer
+            .as_ref()
+            .map(|transformer| transformer.transform(point))
+            .transpose()
+            .map_err(std::io::Error::other)?;
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                self.writer.write_all(b",")?;
+            }
+            #[cfg(feature = "proj")]
+            if let Some(transformed) = &transformed {
+                let value = match column.name {
+                    "longitude" => Some(transformed.x),
+                    "latitude" => Some(transformed.y),
+                    _ => None,
+                };
+                if let Some(value) = value {
+                    write!(self.writer, "{:.*}", self.precision, value)?;
+                    continue;
+                }
+            }
+            if let Some(mut value) = column.value(point) {
+                if column.is_angle() {
+                    value = self.angle_unit.convert(Radians(value));
+                }
+                write!(self.writer, "{:.*}", self.precision, value)?;
+            }
+        }
+        writeln!(self.writer)
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, std::io::Error> {
+        self.write_header()?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_header(&mut self) -> Result<(), std::io::Error> {
+        if self.header {
+            return Ok(());
+        }
+        self.header = true;
+        let names: Vec<_> = self.columns.iter().map(|column| column.name).collect();
+        writeln!(self.writer, "{}", names.join(","))
+    }
+}
+
+impl<W: Write + Debug> Sink for Writer<W> {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        self.write_point(point).map_err(Error::from)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.write_header()?;
+        self.writer.flush().map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::{Accuracy, SatelliteCount};
+
+    #[test]
+    fn write() {
+        let point = Point {
+            time: 10.0,
+            latitude: Radians::from_degrees(45.0),
+            roll: Radians::from_degrees(-1.5),
+            accuracy: Some(Accuracy {
+                x: 0.02,
+                yaw: Radians::from_degrees(0.25),
+                satellite_count: Some(SatelliteCount::Specified { gps: 8, glonass: 4 }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let columns = columns("time,latitude,roll,sd_x,sd_yaw,satellites,hdop").unwrap();
+        let mut writer = Writer::new(Vec::new(), columns).with_precision(3);
+        writer.write_point(&point).unwrap();
+        writer.write_point(&Point::default()).unwrap();
+        let text = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            "time,latitude,roll,sd_x,sd_yaw,satellites,hdop\n\
+             10.000,45.000,-1.500,0.020,0.250,12.000,\n\
+             0.000,0.000,0.000,,,,\n",
+            text
+        );
+    }
+
+    #[test]
+    fn read() {
+        let text = "# exported trajectory\n\
+                    week;time;lat;lon;height;heading;sd_h\n\
+                    2000;10.0;0.7;-1.8;300;1.5;\n\
+                    \n\
+                    2000;10.5;0.7;-1.8;301;1.6;0.25\n";
+        let map: ColumnMap = ",time,latitude,longitude,altitude,yaw,sd_z"
+            .parse()
+            .unwrap();
+        let map = map
+            .with_delimiter(';')
+            .with_angle_unit(AngleUnit::Radians)
+            .with_height_unit(LengthUnit::Feet);
+        let points: Vec<_> = Reader::new(text.as_bytes(), map).into_iter().collect();
+        assert_eq!(2, points.len());
+        assert_eq!(10.0, points[0].time);
+        assert_eq!(Radians(-1.8), points[0].longitude);
+        assert!((points[0].altitude - 91.44).abs() < 1e-9);
+        assert_eq!(Radians(1.5), points[0].yaw);
+        assert_eq!(None, points[0].accuracy);
+        let accuracy = points[1].accuracy.unwrap();
+        assert_eq!((10.5, 0.25), (accuracy.time, accuracy.z));
+
+        let map: ColumnMap = "time,latitude,longitude".parse().unwrap();
+        let mut reader = Reader::new(&b"1 2 3\n4 5\n"[..], map);
+        assert!(reader.read_point().unwrap().is_some());
+        assert!(matches!(reader.read_point(), Err(Error::MissingColumn(2))));
+    }
+
+    #[test]
+    fn unknown_column() {
+        assert!(matches!(
+            "speed".parse::<Column>(),
+            Err(Error::UnknownField(_))
+        ));
+    }
+}
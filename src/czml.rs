@@ -0,0 +1,296 @@
+//! Export to [CZML](https://github.com/AnalyticalGraphicsInc/czml-writer/wiki/CZML-Guide), for
+//! animating trajectories in CesiumJS.
+//!
+//! Point times are interpreted as seconds since an [Epoch], e.g. the start of the GPS week for
+//! sbet files. Leap seconds are not applied.
+
+pub use crate::epoch::Epoch;
+use crate::point::Point;
+use crate::units::Radians;
+use crate::Error;
+use std::io::Write;
+
+/// Options for writing CZML.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// The date that point times are relative to.
+    pub epoch: Epoch,
+
+    /// The name of the trajectory.
+    pub name: String,
+
+    /// If set, points are colored by their horizontal accuracy, from green at the first value
+    /// to red at the second value, in meters.
+    pub accuracy_range: Option<(f64, f64)>,
+}
+
+impl Options {
+    /// Creates new options with the given epoch, a default name, and no accuracy styling.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::czml::{Epoch, Options};
+    /// let options = Options::new(Epoch::new(2015, 4, 26));
+    /// ```
+    pub fn new(epoch: Epoch) -> Options {
+        Options {
+            epoch,
+            name: "trajectory".to_string(),
+            accuracy_range: None,
+        }
+    }
+}
+
+/// Writes points as a CZML document.
+///
+/// The document contains a clock spanning the trajectory and a single packet with time-dynamic
+/// position and orientation, plus a path.
+///
+/// # Examples
+///
+/// ```
+/// use pos::czml::{self, Epoch, Options};
+/// use pos::sbet::Reader;
+/// use pos::Trajectory;
+/// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// let trajectory = Trajectory::from_source(&mut reader).unwrap();
+/// let mut buffer = Vec::new();
+/// czml::write(&mut buffer, trajectory.points(), &Options::new(Epoch::new(2015, 4, 26))).unwrap();
+/// ```
+pub fn write<W: Write>(mut write: W, points: &[Point], options: &Options) -> Result<(), Error> {
+    let epoch = options.epoch.iso8601(0.0);
+    let interval = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => format!(
+            "{}/{}",
+            options.epoch.iso8601(first.time),
+            options.epoch.iso8601(last.time)
+        ),
+        _ => format!("{}/{}", epoch, epoch),
+    };
+    let name = escape(&options.name);
+    write!(
+        write,
+        "[{{\"id\":\"document\",\"name\":\"{}\",\"version\":\"1.0\",\"clock\":{{\"interval\":\"{}\",\"currentTime\":\"{}\",\"multiplier\":1}}}}",
+        name,
+        interval,
+        interval.split('/').next().unwrap_or(&epoch)
+    )?;
+    write!(
+        write,
+        ",{{\"id\":\"{}\",\"name\":\"{}\",\"availability\":\"{}\"",
+        name, name, interval
+    )?;
+
+    write!(
+        write,
+        ",\"position\":{{\"epoch\":\"{}\",\"cartographicDegrees\":[",
+        epoch
+    )?;
+    for (i, point) in points.iter().enumerate() {
+        write!(
+            write,
+            "{}{},{},{},{}",
+            if i == 0 { "" } else { "," },
+            point.time,
+            point.longitude_degrees(),
+            point.latitude_degrees(),
+            point.altitude
+        )?;
+    }
+    write!(write, "]}}")?;
+
+    write!(
+        write,
+        ",\"orientation\":{{\"epoch\":\"{}\",\"unitQuaternion\":[",
+        epoch
+    )?;
+    for (i, point) in points.iter().enumerate() {
+        let q = orientation(point);
+        write!(
+            write,
+            "{}{},{},{},{},{}",
+            if i == 0 { "" } else { "," },
+            point.time,
+            q[0],
+            q[1],
+            q[2],
+            q[3]
+        )?;
+    }
+    write!(write, "]}}")?;
+
+    if let Some((good, bad)) = options.accuracy_range {
+        write!(
+            write,
+            ",\"point\":{{\"pixelSize\":6,\"color\":{{\"epoch\":\"{}\",\"rgba\":[",
+            epoch
+        )?;
+        let mut first = true;
+        for point in points {
+            if let Some(accuracy) = point.accuracy {
+                let horizontal = accuracy.x.hypot(accuracy.y);
+                let f = ((horizontal - good) / (bad - good)).clamp(0.0, 1.0);
+                write!(
+                    write,
+                    "{}{},{},{},0,255",
+                    if first { "" } else { "," },
+                    point.time,
+                    (255.0 * f).round(),
+                    (255.0 * (1.0 - f)).round()
+                )?;
+                first = false;
+            }
+        }
+        write!(write, "]}}}}")?;
+    }
+
+    write!(
+        write,
+        ",\"path\":{{\"width\":2,\"leadTime\":0,\"material\":{{\"solidColor\":{{\"color\":{{\"rgba\":[255,255,0,255]}}}}}}}}}}]"
+    )?;
+    Ok(())
+}
+
+/// Returns the earth-fixed orientation of a point as a unit quaternion, `[x, y, z, w]`.
+///
+/// Cesium's body frame is forward-left-up with heading measured from east, so the point's
+/// yaw is rotated by a quarter turn.
+fn orientation(point: &Point) -> [f64; 4] {
+    let heading = point.yaw - Radians(std::f64::consts::FRAC_PI_2);
+    let body = matmul(
+        matmul(rotation_z(-heading.0), rotation_y(-point.pitch.0)),
+        rotation_x(point.roll.0),
+    );
+    let (sin_lat, cos_lat) = point.latitude.0.sin_cos();
+    let (sin_lon, cos_lon) = point.longitude.0.sin_cos();
+    let enu = [
+        [-sin_lon, -sin_lat * cos_lon, cos_lat * cos_lon],
+        [cos_lon, -sin_lat * sin_lon, cos_lat * sin_lon],
+        [0.0, cos_lat, sin_lat],
+    ];
+    quaternion(matmul(enu, body))
+}
+
+fn rotation_x(angle: f64) -> [[f64; 3]; 3] {
+    let (s, c) = angle.sin_cos();
+    [[1.0, 0.0, 0.0], [0.0, c, -s], [0.0, s, c]]
+}
+
+fn rotation_y(angle: f64) -> [[f64; 3]; 3] {
+    let (s, c) = angle.sin_cos();
+    [[c, 0.0, s], [0.0, 1.0, 0.0], [-s, 0.0, c]]
+}
+
+fn rotation_z(angle: f64) -> [[f64; 3]; 3] {
+    let (s, c) = angle.sin_cos();
+    [[c, -s, 0.0], [s, c, 0.0], [0.0, 0.0, 1.0]]
+}
+
+fn matmul(a: [[f64; 3]; 3], b: [[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut m = [[0.0; 3]; 3];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+fn quaternion(m: [[f64; 3]; 3]) -> [f64; 4] {
+    let trace = m[0][0] + m[1][1] + m[2][2];
+    if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [
+            (m[2][1] - m[1][2]) / s,
+            (m[0][2] - m[2][0]) / s,
+            (m[1][0] - m[0][1]) / s,
+            s / 4.0,
+        ]
+    } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+        let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+        [
+            s / 4.0,
+            (m[0][1] + m[1][0]) / s,
+            (m[0][2] + m[2][0]) / s,
+            (m[2][1] - m[1][2]) / s,
+        ]
+    } else if m[1][1] > m[2][2] {
+        let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+        [
+            (m[0][1] + m[1][0]) / s,
+            s / 4.0,
+            (m[1][2] + m[2][1]) / s,
+            (m[0][2] - m[2][0]) / s,
+        ]
+    } else {
+        let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+        [
+            (m[0][2] + m[2][0]) / s,
+            (m[1][2] + m[2][1]) / s,
+            s / 4.0,
+            (m[1][0] - m[0][1]) / s,
+        ]
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Accuracy;
+
+    #[test]
+    fn orientation_level_north() {
+        // Level and facing north at the equator and prime meridian, the body's forward axis
+        // points along ECEF +Z.
+        let q = orientation(&Point::default());
+        let (x, y, z, w) = (q[0], q[1], q[2], q[3]);
+        let forward = [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y + w * z),
+            2.0 * (x * z - w * y),
+        ];
+        assert!(forward[0].abs() < 1e-12);
+        assert!(forward[1].abs() < 1e-12);
+        assert!((forward[2] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn document() {
+        let points = [
+            Point {
+                time: 10.0,
+                accuracy: Some(Accuracy::default()),
+                ..Default::default()
+            },
+            Point {
+                time: 11.0,
+                ..Default::default()
+            },
+        ];
+        let mut options = Options::new(Epoch::new(2015, 4, 26));
+        options.name = "a \"name\"".to_string();
+        options.accuracy_range = Some((0.0, 1.0));
+        let mut buffer = Vec::new();
+        write(&mut buffer, &points, &options).unwrap();
+        let czml = String::from_utf8(buffer).unwrap();
+        assert!(czml.starts_with("[{\"id\":\"document\",\"name\":\"a \\\"name\\\"\""));
+        assert!(czml.contains("\"interval\":\"2015-04-26T00:00:10.000Z/2015-04-26T00:00:11.000Z\""));
+        assert!(czml.contains("\"cartographicDegrees\":[10,0,0,0,11,0,0,0]"));
+        assert!(czml.contains("\"rgba\":[10,0,255,0,255]"));
+        assert!(czml.ends_with("}]"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Conversion of points into [polars] data frames.
+//!
+//! Each field of [Point] becomes one column, named after the field. Angular values are stored in
+//! radians, the optional [Accuracy](crate::point::Accuracy) is flattened into `accuracy_*`
+//! columns, and the upper triangle of the optional [Covariance] into `covariance_*` columns,
+//! such as `covariance_x_roll`.
+
+use crate::point::{Covariance, Point};
+use crate::source::Source;
+use crate::Error;
+use polars::prelude::{Column, DataFrame};
+
+/// Creates a data frame from a slice of points.
+///
+/// # Examples
+///
+/// ```
+/// use pos::dataframe;
+/// use pos::Point;
+/// let points = vec![Point::default(); 2];
+/// let dataframe = dataframe::to_dataframe(&points).unwrap();
+/// assert_eq!(2, dataframe.height());
+/// ```
+pub fn to_dataframe(points: &[Point]) -> Result<DataFrame, Error> {
+    macro_rules! column {
+        ($name:expr, $value:expr) => {{
+            let values: Vec<_> = points.iter().map($value).collect();
+            Column::new($name.into(), values)
+        }};
+    }
+
+    let mut columns = vec![
+        column!("time", |p| p.time),
+        column!("longitude", |p| p.longitude.0),
+        column!("latitude", |p| p.latitude.0),
+        column!("altitude", |p| p.altitude),
+        column!("roll", |p| p.roll.0),
+        column!("pitch", |p| p.pitch.0),
+        column!("yaw", |p| p.yaw.0),
+        column!("distance", |p| p.distance),
+        column!("x_velocity", |p| p.x_velocity),
+        column!("y_velocity", |p| p.y_velocity),
+        column!("z_velocity", |p| p.z_velocity),
+        column!("wander_angle", |p| p.wander_angle.map(|r| r.0)),
+        column!("x_acceleration", |p| p.x_acceleration),
+        column!("y_acceleration", |p| p.y_acceleration),
+        column!("z_acceleration", |p| p.z_acceleration),
+        column!("x_angular_rate", |p| p.x_angular_rate.map(|r| r.0)),
+        column!("y_angular_rate", |p| p.y_angular_rate.map(|r| r.0)),
+        column!("z_angular_rate", |p| p.z_angular_rate.map(|r| r.0)),
+        column!("height_above_ground", |p| p.height_above_ground),
+        column!("heave", |p| p.heave),
+        column!("accuracy_time", |p| p.accuracy.map(|a| a.time)),
+        column!("accuracy_x", |p| p.accuracy.map(|a| a.x)),
+        column!("accuracy_y", |p| p.accuracy.map(|a| a.y)),
+        column!("accuracy_z", |p| p.accuracy.map(|a| a.z)),
+        column!("accuracy_roll", |p| p.accuracy.map(|a| a.roll.0)),
+        column!("accuracy_pitch", |p| p.accuracy.map(|a| a.pitch.0)),
+        column!("accuracy_yaw", |p| p.accuracy.map(|a| a.yaw.0)),
+        column!("accuracy_pdop", |p| p.accuracy.map(|a| a.pdop)),
+        column!("accuracy_hdop", |p| p.accuracy.and_then(|a| a.hdop)),
+        column!("accuracy_vdop", |p| p.accuracy.and_then(|a| a.vdop)),
+        column!("accuracy_satellite_count", |p| p
+            .accuracy
+            .and_then(|a| a.satellite_count)
+            .map(|s| u32::from(s.total()))),
+    ];
+    for (i, row) in Covariance::AXES.iter().enumerate() {
+        for (j, column) in Covariance::AXES.iter().enumerate().skip(i) {
+            columns.push(column!(format!("covariance_{}_{}", row, column), |p| p
+                .covariance
+                .map(|c| c.get(i, j))));
+        }
+    }
+    DataFrame::new(columns).map_err(Error::from)
+}
+
+/// Reads every point from a source into a data frame.
+///
+/// Unlike collecting the source's iterator, errors are returned instead of panicking.
+///
+/// # Examples
+///
+/// ```
+/// use pos::dataframe;
+/// use pos::sbet::Reader;
+/// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// let dataframe = dataframe::read_dataframe(&mut reader).unwrap();
+/// assert_eq!(2, dataframe.height());
+/// ```
+pub fn read_dataframe(source: &mut dyn Source) -> Result<DataFrame, Error> {
+    let mut points = Vec::new();
+    while let Some(point) = source.source()? {
+        points.push(point);
+    }
+    to_dataframe(&points)
+}
+
+#[cfg(all(test, feature = "sbet"))]
+mod tests {
+    use super::*;
+    use crate::sbet;
+
+    #[test]
+    fn columns() {
+        let mut reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+        let dataframe = read_dataframe(&mut reader).unwrap();
+        assert_eq!(52, dataframe.width());
+        let time = dataframe.column("time").unwrap().f64().unwrap();
+        assert!((1.5163100e5 - time.get(0).unwrap()).abs() < 1e-2);
+        assert_eq!(
+            2,
+            dataframe.column("accuracy_x").unwrap().null_count(),
+            "sbet files don't carry accuracy information"
+        );
+    }
+}
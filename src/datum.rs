@@ -0,0 +1,238 @@
+//! Vertical datums.
+//!
+//! Formats disagree on what altitude means: sbet and pof files store heights above the WGS84
+//! ellipsoid, while ASCII pos files are often exported with heights above a geoid. Mixing the
+//! two silently shifts a trajectory by tens of meters. A [VerticalDatum] tags heights with their
+//! reference, so mix-ups can be detected, and [Convert] moves a source's heights from one datum
+//! to another through a pluggable [Converter], such as a [Geoid].
+
+use crate::geoid::Geoid;
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The reference surface of a trajectory's heights.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VerticalDatum {
+    /// Heights above the WGS84 ellipsoid.
+    #[default]
+    Ellipsoidal,
+    /// Heights above a geoid, with the name of the geoid model if it's known, e.g. `EGM2008`.
+    Orthometric(Option<String>),
+}
+
+/// A hook that provides geoid undulations, to convert between vertical datums.
+pub trait Converter {
+    /// Returns the height of the geoid above the ellipsoid at a point, in meters.
+    fn undulation(&self, point: &Point) -> Result<f64, Error>;
+}
+
+impl Converter for Geoid {
+    fn undulation(&self, point: &Point) -> Result<f64, Error> {
+        Geoid::undulation(self, point.latitude, point.longitude).ok_or_else(|| {
+            Error::OutsideGeoid(point.latitude.to_degrees(), point.longitude.to_degrees())
+        })
+    }
+}
+
+impl VerticalDatum {
+    /// Returns a point's altitude converted from this datum to another.
+    ///
+    /// Converting between two orthometric datums with different geoid models isn't supported,
+    /// since one converter can only describe one geoid, and returns
+    /// [Error::VerticalDatumConversion].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::datum::VerticalDatum;
+    /// use pos::geoid::Geoid;
+    /// use pos::Point;
+    /// let geoid = Geoid::new("-90 90 0 360 90 180\n3 3 3\n3 3 3\n3 3 3\n".as_bytes()).unwrap();
+    /// let point = Point { altitude: 10.0, ..Default::default() };
+    /// let egm = VerticalDatum::Orthometric(Some("EGM2008".to_string()));
+    /// assert_eq!(7.0, VerticalDatum::Ellipsoidal.convert(&point, &egm, &geoid).unwrap());
+    /// ```
+    pub fn convert(
+        &self,
+        point: &Point,
+        to: &VerticalDatum,
+        converter: &dyn Converter,
+    ) -> Result<f64, Error> {
+        match (self, to) {
+            (from, to) if from == to => Ok(point.altitude),
+            (VerticalDatum::Ellipsoidal, VerticalDatum::Orthometric(_)) => {
+                Ok(point.altitude - converter.undulation(point)?)
+            }
+            (VerticalDatum::Orthometric(_), VerticalDatum::Ellipsoidal) => {
+                Ok(point.altitude + converter.undulation(point)?)
+            }
+            (from, to) => Err(Error::VerticalDatumConversion(
+                from.to_string(),
+                to.to_string(),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for VerticalDatum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerticalDatum::Ellipsoidal => f.write_str("ellipsoidal"),
+            VerticalDatum::Orthometric(None) => f.write_str("orthometric"),
+            VerticalDatum::Orthometric(Some(geoid)) => write!(f, "orthometric:{}", geoid),
+        }
+    }
+}
+
+impl FromStr for VerticalDatum {
+    type Err = Error;
+
+    /// Parses `ellipsoidal`, `orthometric`, or `orthometric:<geoid>`.
+    fn from_str(s: &str) -> Result<VerticalDatum, Error> {
+        let (kind, geoid) = match s.split_once(':') {
+            Some((kind, geoid)) => (kind, Some(geoid.to_string())),
+            None => (s, None),
+        };
+        match (kind.to_lowercase().as_str(), geoid) {
+            ("ellipsoidal", None) => Ok(VerticalDatum::Ellipsoidal),
+            ("orthometric", geoid) => Ok(VerticalDatum::Orthometric(geoid)),
+            _ => Err(Error::UnknownVerticalDatum(s.to_string())),
+        }
+    }
+}
+
+/// A source that converts the heights of another source from one vertical datum to another.
+pub struct Convert {
+    source: Box<dyn Source>,
+    from: VerticalDatum,
+    to: VerticalDatum,
+    converter: Box<dyn Converter>,
+}
+
+impl Convert {
+    /// Creates a new converting source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::datum::{Convert, VerticalDatum};
+    /// use pos::geoid::Geoid;
+    /// use pos::sbet;
+    /// use pos::Source;
+    /// let geoid = Geoid::new("-90 90 0 360 90 180\n1 1 1\n2 2 2\n3 3 3\n".as_bytes()).unwrap();
+    /// let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut source = Convert::new(
+    ///     Box::new(reader),
+    ///     VerticalDatum::Ellipsoidal,
+    ///     VerticalDatum::Orthometric(None),
+    ///     Box::new(geoid),
+    /// );
+    /// let point = source.source().unwrap().unwrap();
+    /// ```
+    pub fn new(
+        source: Box<dyn Source>,
+        from: VerticalDatum,
+        to: VerticalDatum,
+        converter: Box<dyn Converter>,
+    ) -> Convert {
+        Convert {
+            source,
+            from,
+            to,
+            converter,
+        }
+    }
+}
+
+impl Source for Convert {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        match self.source.source()? {
+            Some(mut point) => {
+                point.altitude = self.from.convert(&point, &self.to, &*self.converter)?;
+                Ok(Some(point))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl fmt::Debug for Convert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Convert")
+            .field("source", &self.source)
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    struct Constant(f64);
+
+    impl Converter for Constant {
+        fn undulation(&self, _: &Point) -> Result<f64, Error> {
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn parse() {
+        for datum in [
+            VerticalDatum::Ellipsoidal,
+            VerticalDatum::Orthometric(None),
+            VerticalDatum::Orthometric(Some("EGM96".to_string())),
+        ] {
+            assert_eq!(datum, datum.to_string().parse().unwrap());
+        }
+        assert!("ellipsoidal:EGM96".parse::<VerticalDatum>().is_err());
+        assert!("geoidal".parse::<VerticalDatum>().is_err());
+    }
+
+    #[test]
+    fn convert() {
+        let point = Point {
+            altitude: 100.0,
+            ..Default::default()
+        };
+        let egm96 = VerticalDatum::Orthometric(Some("EGM96".to_string()));
+        let egm2008 = VerticalDatum::Orthometric(Some("EGM2008".to_string()));
+        let converter = Constant(-30.0);
+        assert_eq!(
+            130.0,
+            VerticalDatum::Ellipsoidal
+                .convert(&point, &egm96, &converter)
+                .unwrap()
+        );
+        assert_eq!(
+            70.0,
+            egm96
+                .convert(&point, &VerticalDatum::Ellipsoidal, &converter)
+                .unwrap()
+        );
+        assert_eq!(100.0, egm96.convert(&point, &egm96, &converter).unwrap());
+        assert!(matches!(
+            egm96.convert(&point, &egm2008, &converter),
+            Err(Error::VerticalDatumConversion(..))
+        ));
+    }
+
+    #[test]
+    fn source() {
+        let points = vec![Point::default(); 2];
+        let mut source = Convert::new(
+            Box::new(MemorySource::new(points)),
+            VerticalDatum::Orthometric(None),
+            VerticalDatum::Ellipsoidal,
+            Box::new(Constant(5.0)),
+        );
+        assert_eq!(5.0, source.source().unwrap().unwrap().altitude);
+    }
+}
@@ -0,0 +1,153 @@
+//! Thin out dense trajectories while they're read.
+//!
+//! A 200 Hz trajectory has far more points than a coverage map or a quick plot needs.
+//! [Decimate] keeps every nth point and [DecimateByInterval] keeps at most one point per time
+//! interval, so the dropped points are never collected into memory.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+
+/// Times within this many seconds of the next interval count as reaching it, so that sampling
+/// jitter in the last digits doesn't skip a point.
+const INTERVAL_TOLERANCE: f64 = 1e-6;
+
+/// A source that passes through every nth point.
+#[derive(Debug)]
+pub struct Decimate<S> {
+    source: S,
+    every_nth: usize,
+    index: usize,
+}
+
+/// A source that passes through a point, then skips points until `dt` seconds have elapsed.
+///
+/// The interval is measured from the last point that was kept, so gaps in the source don't
+/// shift later points onto a fixed grid.
+#[derive(Debug)]
+pub struct DecimateByInterval<S> {
+    source: S,
+    dt: f64,
+    last: Option<f64>,
+}
+
+impl<S: Source> Decimate<S> {
+    /// Creates a new decimation that keeps the first point and every `every_nth` point after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every_nth` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::decimate::Decimate;
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let decimate = Decimate::new(reader, 2);
+    /// assert_eq!(1, decimate.try_iter().count());
+    /// ```
+    pub fn new(source: S, every_nth: usize) -> Decimate<S> {
+        assert!(every_nth > 0, "every_nth must be at least one");
+        Decimate {
+            source,
+            every_nth,
+            index: 0,
+        }
+    }
+}
+
+impl<S: Source> Source for Decimate<S> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        while let Some(point) = self.source.source()? {
+            let index = self.index;
+            self.index += 1;
+            if index.is_multiple_of(self.every_nth) {
+                return Ok(Some(point));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<S: Source> DecimateByInterval<S> {
+    /// Creates a new decimation that keeps points at least `dt` seconds apart.
+    ///
+    /// A non-positive `dt` passes through every point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::decimate::DecimateByInterval;
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let decimate = DecimateByInterval::new(reader, 1.0);
+    /// assert_eq!(1, decimate.try_iter().count());
+    /// ```
+    pub fn new(source: S, dt: f64) -> DecimateByInterval<S> {
+        DecimateByInterval {
+            source,
+            dt,
+            last: None,
+        }
+    }
+}
+
+impl<S: Source> Source for DecimateByInterval<S> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        while let Some(point) = self.source.source()? {
+            if self
+                .last
+                .is_none_or(|last| point.time - last >= self.dt - INTERVAL_TOLERANCE)
+            {
+                self.last = Some(point.time);
+                return Ok(Some(point));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    fn source(times: &[f64]) -> MemorySource {
+        MemorySource::new(
+            times
+                .iter()
+                .map(|&time| Point {
+                    time,
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    }
+
+    fn times<S: Source>(source: S) -> Vec<f64> {
+        source.try_iter().map(|point| point.unwrap().time).collect()
+    }
+
+    #[test]
+    fn every_nth() {
+        let times = times(Decimate::new(source(&[0.0, 1.0, 2.0, 3.0, 4.0]), 2));
+        assert_eq!(vec![0.0, 2.0, 4.0], times);
+    }
+
+    #[test]
+    fn by_interval() {
+        // 200 Hz, with a gap, decimated to 100 Hz.
+        let input: Vec<f64> = (0..6)
+            .chain(9..12)
+            .map(|i| 100000.0 + f64::from(i) * 0.005)
+            .collect();
+        let times = times(DecimateByInterval::new(source(&input), 0.01));
+        assert_eq!(
+            vec![input[0], input[2], input[4], input[6], input[8]],
+            times
+        );
+    }
+}
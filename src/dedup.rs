@@ -0,0 +1,127 @@
+//! Resolve repeated timestamps.
+//!
+//! Consecutive points with the same time make interpolation divide by zero, so sources that
+//! repeat timestamps, e.g. because of a logging glitch or a concatenated file, should be
+//! deduplicated before they're interpolated.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+
+/// How to resolve a run of points with the same time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Policy {
+    /// Keep the first point.
+    #[default]
+    First,
+    /// Keep the last point.
+    Last,
+    /// Average every field of the points.
+    ///
+    /// Angles are averaged the same way they're interpolated, without wrapping, and optional
+    /// fields are only kept if every point has them.
+    Average,
+}
+
+/// A source that yields one point for each run of consecutive points with the same time.
+#[derive(Debug)]
+pub struct Dedup {
+    source: Box<dyn Source>,
+    policy: Policy,
+    next: Option<Point>,
+}
+
+impl Dedup {
+    /// Creates a new dedup source with the given policy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::dedup::{Dedup, Policy};
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut dedup = Dedup::new(Box::new(reader), Policy::Average);
+    /// let point = dedup.source().unwrap().unwrap();
+    /// ```
+    pub fn new(source: Box<dyn Source>, policy: Policy) -> Dedup {
+        Dedup {
+            source,
+            policy,
+            next: None,
+        }
+    }
+}
+
+impl Source for Dedup {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let mut point = match self.next.take() {
+            Some(point) => point,
+            None => match self.source.source()? {
+                Some(point) => point,
+                None => return Ok(None),
+            },
+        };
+        let mut count = 1;
+        while let Some(next) = self.source.source()? {
+            if next.time != point.time {
+                self.next = Some(next);
+                break;
+            }
+            count += 1;
+            match self.policy {
+                Policy::First => {}
+                Policy::Last => point = next,
+                Policy::Average => point = point.lerp(&next, 1.0 / count as f64),
+            }
+        }
+        Ok(Some(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    fn dedup(policy: Policy) -> Vec<Point> {
+        let points = [(0.0, 1.0), (1.0, 2.0), (1.0, 3.0), (1.0, 7.0), (2.0, 4.0)]
+            .into_iter()
+            .map(|(time, altitude)| Point {
+                time,
+                altitude,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let mut dedup = Dedup::new(Box::new(MemorySource::new(points)), policy);
+        let mut points = Vec::new();
+        while let Some(point) = dedup.source().unwrap() {
+            points.push(point);
+        }
+        points
+    }
+
+    fn altitudes(points: &[Point]) -> Vec<f64> {
+        points.iter().map(|point| point.altitude).collect()
+    }
+
+    #[test]
+    fn first() {
+        let points = dedup(Policy::First);
+        let times: Vec<_> = points.iter().map(|point| point.time).collect();
+        assert_eq!(vec![0.0, 1.0, 2.0], times);
+        assert_eq!(vec![1.0, 2.0, 4.0], altitudes(&points));
+    }
+
+    #[test]
+    fn last() {
+        assert_eq!(vec![1.0, 7.0, 4.0], altitudes(&dedup(Policy::Last)));
+    }
+
+    #[test]
+    fn average() {
+        let points = dedup(Policy::Average);
+        assert_eq!(1.0, points[1].time);
+        assert!((points[1].altitude - 4.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,276 @@
+//! Derive missing dynamics by finite differencing.
+//!
+//! pos and pof files don't record velocities, accelerations, or angular rates. [Derive] fills
+//! them in from neighboring points by fitting a quadratic through each point and its neighbors
+//! (or the nearest three points at the ends of the source), using their actual times.
+
+use crate::geodesy;
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+use std::collections::VecDeque;
+
+/// A source that derives missing velocities, accelerations, and angular rates.
+///
+/// Following [SavitzkyGolay](crate::smooth::SavitzkyGolay), `x_velocity`, `y_velocity`, and
+/// `z_velocity` are east, north, and up velocities in meters per second, the accelerations are
+/// in the same frame in meters per second squared, and `x_angular_rate`, `y_angular_rate`, and
+/// `z_angular_rate` are roll, pitch, and yaw (Euler angle) rates.
+///
+/// Values recorded by the source are never overwritten. Points with derived values are marked
+/// as [derived](Point::derived). A source with a single point, or with repeated timestamps
+/// around a point, passes that point through unchanged; use [Dedup](crate::dedup::Dedup)
+/// first if the source might repeat timestamps.
+#[derive(Debug)]
+pub struct Derive {
+    source: Box<dyn Source>,
+    buffer: VecDeque<Point>,
+    index: usize,
+    accelerations: bool,
+    angular_rates: bool,
+}
+
+impl Derive {
+    /// Creates a new source that derives dynamics from another.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::dynamics::Derive;
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut derive = Derive::new(Box::new(reader));
+    /// let point = derive.source().unwrap().unwrap();
+    /// ```
+    pub fn new(source: Box<dyn Source>) -> Derive {
+        Derive {
+            source,
+            buffer: VecDeque::new(),
+            index: 0,
+            accelerations: true,
+            angular_rates: true,
+        }
+    }
+
+    /// Sets whether missing accelerations are derived, which defaults to true.
+    pub fn with_accelerations(mut self, accelerations: bool) -> Derive {
+        self.accelerations = accelerations;
+        self
+    }
+
+    /// Sets whether missing angular rates are derived, which defaults to true.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::dynamics::Derive;
+    /// use pos::sbet::Reader;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let derive = Derive::new(Box::new(reader))
+    ///     .with_accelerations(false)
+    ///     .with_angular_rates(false);
+    /// ```
+    pub fn with_angular_rates(mut self, angular_rates: bool) -> Derive {
+        self.angular_rates = angular_rates;
+        self
+    }
+}
+
+impl Source for Derive {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        while self.buffer.len() < (self.index + 2).max(3) {
+            match self.source.source()? {
+                Some(point) => self.buffer.push_back(point),
+                None => break,
+            }
+        }
+        if self.index >= self.buffer.len() {
+            return Ok(None);
+        }
+        let point = derive(
+            self.buffer.make_contiguous(),
+            self.index,
+            self.accelerations,
+            self.angular_rates,
+        );
+        self.index += 1;
+        if self.index > 2 {
+            let _ = self.buffer.pop_front();
+            self.index -= 1;
+        }
+        Ok(Some(point))
+    }
+}
+
+fn derive(points: &[Point], index: usize, accelerations: bool, angular_rates: bool) -> Point {
+    let mut point = points[index];
+    let stencil = match points.len() {
+        0 | 1 => return point,
+        2 => &points[0..2],
+        n => {
+            let start = index.saturating_sub(1).min(n - 3);
+            &points[start..start + 3]
+        }
+    };
+    if stencil.windows(2).any(|w| w[1].time <= w[0].time) {
+        return point;
+    }
+    let origin = stencil[stencil.len() / 2];
+    let times: Vec<_> = stencil.iter().map(|p| p.time).collect();
+    let enu: Vec<_> = stencil.iter().map(|p| geodesy::enu(&origin, p)).collect();
+    let angle = |f: fn(&Point) -> Radians<f64>| -> Vec<f64> {
+        stencil
+            .iter()
+            .map(|p| (f(p) - f(&origin)).wrapped().0)
+            .collect()
+    };
+    let derivatives = |values: &[f64]| differentiate(&times, values, point.time);
+    let [east, north, up] = [0, 1, 2].map(|i| {
+        let values: Vec<_> = enu.iter().map(|enu| enu[i]).collect();
+        derivatives(&values)
+    });
+    let roll = derivatives(&angle(|p| p.roll));
+    let pitch = derivatives(&angle(|p| p.pitch));
+    let yaw = derivatives(&angle(|p| p.yaw));
+    let mut derived = false;
+    let mut set = |field: &mut Option<f64>, value: Option<f64>| {
+        if field.is_none() && value.is_some() {
+            *field = value;
+            derived = true;
+        }
+    };
+    set(&mut point.x_velocity, Some(east.0));
+    set(&mut point.y_velocity, Some(north.0));
+    set(&mut point.z_velocity, Some(up.0));
+    if accelerations {
+        set(&mut point.x_acceleration, east.1);
+        set(&mut point.y_acceleration, north.1);
+        set(&mut point.z_acceleration, up.1);
+    }
+    let mut set_angle = |field: &mut Option<Radians<f64>>, value: f64| {
+        if field.is_none() {
+            *field = Some(Radians(value));
+            derived = true;
+        }
+    };
+    if angular_rates {
+        set_angle(&mut point.x_angular_rate, roll.0);
+        set_angle(&mut point.y_angular_rate, pitch.0);
+        set_angle(&mut point.z_angular_rate, yaw.0);
+    }
+    point.derived |= derived;
+    point
+}
+
+/// Returns the first and (with three points) second derivatives at `time` of the line or
+/// quadratic through the values.
+fn differentiate(times: &[f64], values: &[f64], time: f64) -> (f64, Option<f64>) {
+    if let [t0, t1] = *times {
+        return ((values[1] - values[0]) / (t1 - t0), None);
+    }
+    let mut first = 0.0;
+    let mut second = 0.0;
+    for i in 0..3 {
+        let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+        let denominator = (times[i] - times[j]) * (times[i] - times[k]);
+        first += values[i] * ((time - times[j]) + (time - times[k])) / denominator;
+        second += values[i] * 2.0 / denominator;
+    }
+    (first, Some(second))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    fn derive_all(points: Vec<Point>) -> Vec<Point> {
+        let mut derive = Derive::new(Box::new(MemorySource::new(points)));
+        let mut points = Vec::new();
+        while let Some(point) = derive.source().unwrap() {
+            points.push(point);
+        }
+        points
+    }
+
+    #[test]
+    fn quadratic() {
+        // Irregularly sampled, with altitude 2t² + 3t and yaw wrapping through ±180°.
+        let points = [0.0, 0.5, 1.5, 2.0, 3.5]
+            .into_iter()
+            .map(|time: f64| Point {
+                time,
+                altitude: 2.0 * time * time + 3.0 * time,
+                yaw: Radians::from_degrees(170.0 + 10.0 * time).wrapped(),
+                ..Default::default()
+            })
+            .collect();
+        let points = derive_all(points);
+        assert_eq!(5, points.len());
+        for point in points {
+            assert!(point.derived);
+            let expected = 4.0 * point.time + 3.0;
+            assert!((point.z_velocity.unwrap() - expected).abs() < 1e-3);
+            assert!((point.z_acceleration.unwrap() - 4.0).abs() < 1e-3);
+            assert!(point.x_velocity.unwrap().abs() < 1e-6);
+            assert!((point.z_angular_rate.unwrap().to_degrees() - 10.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn recorded() {
+        let points = (0..3)
+            .map(|i| Point {
+                time: i as f64,
+                altitude: i as f64,
+                x_velocity: Some(1.0),
+                y_velocity: Some(2.0),
+                z_velocity: Some(3.0),
+                ..Default::default()
+            })
+            .collect();
+        let point = derive_all(points)[1];
+        assert_eq!(Some(3.0), point.z_velocity);
+        assert!(point.z_acceleration.unwrap().abs() < 1e-6);
+    }
+
+    #[test]
+    fn velocities_only() {
+        let points: Vec<_> = (0..3)
+            .map(|i| Point {
+                time: i as f64,
+                altitude: i as f64,
+                yaw: Radians(0.1 * i as f64),
+                ..Default::default()
+            })
+            .collect();
+        let mut derive = Derive::new(Box::new(MemorySource::new(points)))
+            .with_accelerations(false)
+            .with_angular_rates(false);
+        let point = derive.source().unwrap().unwrap();
+        assert!(point.derived);
+        assert!((point.z_velocity.unwrap() - 1.0).abs() < 1e-6);
+        assert_eq!(None, point.z_acceleration);
+        assert_eq!(None, point.z_angular_rate);
+    }
+
+    #[test]
+    fn short() {
+        let point = Point::default();
+        let points = derive_all(vec![point]);
+        assert_eq!(vec![point], points);
+        let points = derive_all(vec![
+            point,
+            Point {
+                time: 2.0,
+                altitude: 4.0,
+                ..point
+            },
+        ]);
+        assert_eq!(2, points.len());
+        assert!((points[1].z_velocity.unwrap() - 2.0).abs() < 1e-6);
+        assert_eq!(None, points[1].z_acceleration);
+    }
+}
@@ -0,0 +1,394 @@
+//! Read photogrammetric exterior orientation (EO) files.
+//!
+//! An EO file has one line per image: the image id, the perspective center's `x`, `y`, and `z`,
+//! and the camera's omega, phi, and kappa. Fields are separated by commas or whitespace.
+//!
+//! Omega, phi, and kappa are the angles of the camera-to-map rotation `Rx(ω) Ry(φ) Rz(κ)`, where
+//! the map frame is east, north, and up, and the camera frame is x right and y up in the image,
+//! with the camera looking down its negative z axis. They're converted to a roll, pitch, and yaw
+//! by assuming the camera points straight down with the top of the image toward the nose, so a
+//! level platform heading north has zero omega, phi, and kappa. No boresight or meridian
+//! convergence is applied, so angles from projected EO files are relative to grid north.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::{AngleUnit, Radians};
+use crate::{Error, Location};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// One image's exterior orientation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Exposure {
+    /// The image id.
+    pub image: String,
+    /// The exposure time, if the file has a time column.
+    pub time: Option<f64>,
+    /// The perspective center's x, or easting.
+    pub x: f64,
+    /// The perspective center's y, or northing.
+    pub y: f64,
+    /// The perspective center's z, or height.
+    pub z: f64,
+    /// The rotation about the x axis.
+    pub omega: Radians<f64>,
+    /// The rotation about the y axis.
+    pub phi: Radians<f64>,
+    /// The rotation about the z axis.
+    pub kappa: Radians<f64>,
+}
+
+/// An EO reader.
+#[derive(Debug)]
+pub struct Reader<R: BufRead> {
+    reader: R,
+    angle_unit: AngleUnit,
+    time_column: Option<usize>,
+    data: bool,
+    count: u32,
+    path: Option<PathBuf>,
+}
+
+impl Reader<BufReader<File>> {
+    /// Creates a new reader from a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::eo::Reader;
+    /// let reader = Reader::from_path("exposures.txt").unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, std::io::Error> {
+        let path = path.as_ref();
+        let mut reader = Reader::new(BufReader::new(File::open(path)?));
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a new reader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::eo::Reader;
+    /// let reader = Reader::new(std::io::empty());
+    /// ```
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            angle_unit: AngleUnit::Degrees,
+            time_column: None,
+            data: false,
+            count: 0,
+            path: None,
+        }
+    }
+
+    /// Sets the unit of omega, phi, and kappa in the file, which defaults to degrees.
+    pub fn with_angle_unit(mut self, angle_unit: AngleUnit) -> Reader<R> {
+        self.angle_unit = angle_unit;
+        self
+    }
+
+    /// Reads exposure times from this zero-based column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::eo::Reader;
+    /// let bytes = b"IMG_0001 -105 40 1600 0 0 0 151200.5\n";
+    /// let mut reader = Reader::new(&bytes[..]).with_time_column(7);
+    /// let exposure = reader.read_exposure().unwrap().unwrap();
+    /// assert_eq!(Some(151200.5), exposure.time);
+    /// ```
+    pub fn with_time_column(mut self, column: usize) -> Reader<R> {
+        self.time_column = Some(column);
+        self
+    }
+
+    /// Reads the next exposure.
+    ///
+    /// Blank lines, lines starting with `#`, and header lines before the first exposure are
+    /// skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::eo::Reader;
+    /// let bytes = b"image,x,y,z,omega,phi,kappa\nIMG_0001,-105,40,1600,1.5,-0.5,-90\n";
+    /// let mut reader = Reader::new(&bytes[..]);
+    /// let exposure = reader.read_exposure().unwrap().unwrap();
+    /// assert_eq!("IMG_0001", exposure.image);
+    /// assert_eq!(1600.0, exposure.z);
+    /// ```
+    pub fn read_exposure(&mut self) -> Result<Option<Exposure>, Error> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match self.exposure(line) {
+                Ok(exposure) => {
+                    self.data = true;
+                    return Ok(Some(exposure));
+                }
+                Err(Error::ParseFloat(_)) if !self.data => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Reads the next exposure as a point, with [Exposure::to_point].
+    ///
+    /// Without a time column, point times are the exposures' zero-based indices in the file.
+    /// Each point's event is its index.
+    pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
+        let exposure = match self.read_exposure()? {
+            Some(exposure) => exposure,
+            None => return Ok(None),
+        };
+        let index = self.count;
+        self.count += 1;
+        let mut point = exposure.to_point();
+        if exposure.time.is_none() {
+            point.time = f64::from(index);
+        }
+        point.event = Some(index);
+        Ok(Some(point))
+    }
+
+    fn exposure(&self, line: &str) -> Result<Exposure, Error> {
+        let fields: Vec<_> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .collect();
+        let field = |column: usize| fields.get(column).ok_or(Error::MissingColumn(column));
+        let number = |column: usize| -> Result<f64, Error> { Ok(field(column)?.parse()?) };
+        let angle =
+            |column: usize| -> Result<_, Error> { Ok(self.angle_unit.to_radians(number(column)?)) };
+        Ok(Exposure {
+            x: number(1)?,
+            y: number(2)?,
+            z: number(3)?,
+            omega: angle(4)?,
+            phi: angle(5)?,
+            kappa: angle(6)?,
+            time: self.time_column.map(number).transpose()?,
+            image: field(0)?.to_string(),
+        })
+    }
+}
+
+impl Exposure {
+    /// Converts this exposure to a point, treating `x` and `y` as longitude and latitude in
+    /// degrees.
+    ///
+    /// Without a time, the point's time is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::eo::Exposure;
+    /// use pos::Radians;
+    /// let exposure = Exposure { x: -105.0, y: 40.0, kappa: Radians::from_degrees(-90.0), ..Default::default() };
+    /// let point = exposure.to_point();
+    /// assert!((point.latitude_degrees() - 40.0).abs() < 1e-12);
+    /// assert!((point.yaw_degrees() - 90.0).abs() < 1e-9);
+    /// ```
+    pub fn to_point(&self) -> Point {
+        self.to_point_with(|x, y| (x, y))
+    }
+
+    /// Converts this exposure to a point, using a function from `x` and `y` to longitude and
+    /// latitude in degrees.
+    pub fn to_point_with<F: Fn(f64, f64) -> (f64, f64)>(&self, unproject: F) -> Point {
+        let (longitude, latitude) = unproject(self.x, self.y);
+        let (roll, pitch, yaw) = opk_to_rpy(self.omega, self.phi, self.kappa);
+        Point {
+            time: self.time.unwrap_or_default(),
+            longitude: Radians::from_degrees(longitude),
+            latitude: Radians::from_degrees(latitude),
+            altitude: self.z,
+            roll,
+            pitch,
+            yaw,
+            ..Default::default()
+        }
+    }
+}
+
+/// Converts omega, phi, and kappa to a roll, pitch, and yaw, with the conventions in the
+/// [module documentation](self).
+///
+/// The yaw is in `[0, 2π)`.
+///
+/// # Examples
+///
+/// ```
+/// use pos::eo;
+/// use pos::Radians;
+/// let (roll, pitch, yaw) = eo::opk_to_rpy(Radians(0.0), Radians(0.0), Radians::from_degrees(-90.0));
+/// assert!(roll.0.abs() < 1e-12 && pitch.0.abs() < 1e-12);
+/// assert!((yaw.to_degrees() - 90.0).abs() < 1e-9);
+/// ```
+pub fn opk_to_rpy(
+    omega: Radians<f64>,
+    phi: Radians<f64>,
+    kappa: Radians<f64>,
+) -> (Radians<f64>, Radians<f64>, Radians<f64>) {
+    let (so, co) = omega.0.sin_cos();
+    let (sp, cp) = phi.0.sin_cos();
+    let (sk, ck) = kappa.0.sin_cos();
+    let camera_to_map = [
+        [cp * ck, -cp * sk, sp],
+        [co * sk + so * sp * ck, co * ck - so * sp * sk, -so * cp],
+        [so * sk - co * sp * ck, so * ck + co * sp * sk, co * cp],
+    ];
+    let r = swap(camera_to_map);
+    (
+        Radians(r[2][1].atan2(r[2][2])),
+        Radians(-r[2][0].clamp(-1.0, 1.0).asin()),
+        Radians(r[1][0].atan2(r[0][0]).rem_euclid(std::f64::consts::TAU)),
+    )
+}
+
+/// Converts a roll, pitch, and yaw to omega, phi, and kappa, the inverse of [opk_to_rpy].
+///
+/// # Examples
+///
+/// ```
+/// use pos::eo;
+/// use pos::Radians;
+/// let (omega, phi, kappa) = eo::rpy_to_opk(Radians(0.0), Radians(0.0), Radians::from_degrees(90.0));
+/// assert!((kappa.to_degrees() + 90.0).abs() < 1e-9);
+/// ```
+pub fn rpy_to_opk(
+    roll: Radians<f64>,
+    pitch: Radians<f64>,
+    yaw: Radians<f64>,
+) -> (Radians<f64>, Radians<f64>, Radians<f64>) {
+    let (sr, cr) = roll.0.sin_cos();
+    let (sp, cp) = pitch.0.sin_cos();
+    let (sy, cy) = yaw.0.sin_cos();
+    let body_to_ned = [
+        [cp * cy, sr * sp * cy - cr * sy, cr * sp * cy + sr * sy],
+        [cp * sy, sr * sp * sy + cr * cy, cr * sp * sy - sr * cy],
+        [-sp, sr * cp, cr * cp],
+    ];
+    let r = swap(body_to_ned);
+    (
+        Radians((-r[1][2]).atan2(r[2][2])),
+        Radians(r[0][2].clamp(-1.0, 1.0).asin()),
+        Radians((-r[0][1]).atan2(r[0][0])),
+    )
+}
+
+/// Swaps the first two rows and columns of a rotation and negates the third.
+///
+/// Both NED to ENU and the body frame to the camera frame swap the first two axes and flip the
+/// third, so this converts body-to-NED rotations to camera-to-map ones, and back.
+fn swap(r: [[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    [
+        [r[1][1], r[1][0], -r[1][2]],
+        [r[0][1], r[0][0], -r[0][2]],
+        [-r[2][1], -r[2][0], r[2][2]],
+    ]
+}
+
+impl<R: BufRead> IntoIterator for Reader<R> {
+    type Item = Point;
+    type IntoIter = ReaderIterator<R>;
+    fn into_iter(self) -> Self::IntoIter {
+        ReaderIterator { reader: self }
+    }
+}
+
+/// An iterator over an EO reader.
+#[derive(Debug)]
+pub struct ReaderIterator<R: BufRead> {
+    reader: Reader<R>,
+}
+
+impl<R: BufRead> Iterator for ReaderIterator<R> {
+    type Item = Point;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_point().unwrap()
+    }
+}
+
+impl<R: Debug + BufRead> Source for Reader<R> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let record = u64::from(self.count);
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(record),
+            })
+        });
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read eo line");
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for (roll, pitch, yaw) in [(2.0, -3.0, 45.0), (-10.0, 5.0, 200.0), (0.5, 0.5, 359.0)] {
+            let (omega, phi, kappa) = rpy_to_opk(
+                Radians::from_degrees(roll),
+                Radians::from_degrees(pitch),
+                Radians::from_degrees(yaw),
+            );
+            let (r, p, y) = opk_to_rpy(omega, phi, kappa);
+            assert!((r.to_degrees() - roll).abs() < 1e-9);
+            assert!((p.to_degrees() - pitch).abs() < 1e-9);
+            assert!((y.to_degrees() - yaw).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn nose_up() {
+        // Pitching the nose up tilts the top of the image, the camera's y axis, up, which is a
+        // positive rotation about the camera's x axis.
+        let (omega, phi, _) = rpy_to_opk(Radians(0.0), Radians::from_degrees(5.0), Radians(0.0));
+        assert!((omega.to_degrees() - 5.0).abs() < 1e-9);
+        assert!(phi.0.abs() < 1e-12);
+    }
+
+    #[test]
+    fn read() {
+        let text = "# EO export\n\
+                    ImageID X Y Z Omega Phi Kappa\n\
+                    IMG_0001 -105.0 40.0 1600.0 0 0 -90\n\
+                    \n\
+                    IMG_0002 -105.1 40.1 1601.0 0 0 0\n\
+                    IMG_0003 -105.2 40.2\n";
+        let mut reader = Reader::new(text.as_bytes());
+        let point = reader.read_point().unwrap().unwrap();
+        assert_eq!((0.0, Some(0)), (point.time, point.event));
+        assert!((point.yaw_degrees() - 90.0).abs() < 1e-9);
+        let point = reader.read_point().unwrap().unwrap();
+        assert_eq!((1.0, Some(1)), (point.time, point.event));
+        assert_eq!(1601.0, point.altitude);
+        assert!(matches!(reader.read_point(), Err(Error::MissingColumn(3))));
+    }
+}
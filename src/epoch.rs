@@ -0,0 +1,92 @@
+//! Calendar dates that point times are relative to.
+
+/// A calendar date, at midnight UTC, that point times are relative to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[allow(missing_docs)]
+pub struct Epoch {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Epoch {
+    /// Creates a new epoch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::epoch::Epoch;
+    /// let epoch = Epoch::new(2015, 4, 26);
+    /// ```
+    pub fn new(year: i32, month: u32, day: u32) -> Epoch {
+        Epoch { year, month, day }
+    }
+
+    /// Formats a time, in seconds since this epoch, as an ISO 8601 string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::epoch::Epoch;
+    /// let epoch = Epoch::new(2015, 4, 26);
+    /// assert_eq!("2015-04-27T01:00:00.500Z", epoch.iso8601(90000.5));
+    /// ```
+    pub fn iso8601(&self, seconds: f64) -> String {
+        let millis = (seconds * 1000.0).round() as i64;
+        let days = self.days() + millis.div_euclid(86_400_000);
+        let millis = millis.rem_euclid(86_400_000);
+        let (year, month, day) = civil_from_days(days);
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year,
+            month,
+            day,
+            millis / 3_600_000,
+            millis / 60_000 % 60,
+            millis / 1000 % 60,
+            millis % 1000
+        )
+    }
+
+    pub(crate) fn days(&self) -> i64 {
+        // Howard Hinnant's days_from_civil.
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year - era * 400;
+        let month = i64::from(self.month);
+        let doy =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    // Howard Hinnant's civil_from_days.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch() {
+        let epoch = Epoch::new(2016, 2, 28);
+        assert_eq!("2016-02-28T00:00:00.000Z", epoch.iso8601(0.0));
+        assert_eq!("2016-02-29T00:00:01.000Z", epoch.iso8601(86401.0));
+        assert_eq!("2016-03-01T00:00:00.000Z", epoch.iso8601(2.0 * 86400.0));
+        assert_eq!(
+            "1969-12-31T23:59:59.000Z",
+            Epoch::new(1970, 1, 1).iso8601(-1.0)
+        );
+    }
+}
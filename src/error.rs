@@ -1,7 +1,14 @@
+use crate::format::Format;
+use std::fmt;
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// Crate-specific error enum.
+///
+/// New variants may be added in any release, so match on [Error::kind] to handle classes of
+/// errors.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     /// [std::io::Error]
     #[error(transparent)]
@@ -11,6 +18,80 @@ pub enum Error {
     #[error(transparent)]
     ParseFloat(#[from] std::num::ParseFloatError),
 
+    /// [gdal::errors::GdalError]
+    #[cfg(feature = "gdal")]
+    #[error(transparent)]
+    Gdal(#[from] gdal::errors::GdalError),
+
+    /// [arrow_schema::ArrowError]
+    #[cfg(feature = "arrow")]
+    #[error(transparent)]
+    Arrow(#[from] arrow_schema::ArrowError),
+
+    /// [::parquet::errors::ParquetError]
+    #[cfg(feature = "parquet")]
+    #[error(transparent)]
+    Parquet(#[from] ::parquet::errors::ParquetError),
+
+    /// [polars::error::PolarsError]
+    #[cfg(feature = "polars")]
+    #[error(transparent)]
+    Polars(#[from] polars::error::PolarsError),
+
+    /// [proj::ProjCreateError]
+    #[cfg(feature = "proj")]
+    #[error(transparent)]
+    ProjCreate(#[from] proj::ProjCreateError),
+
+    /// [proj::ProjError]
+    #[cfg(feature = "proj")]
+    #[error(transparent)]
+    Proj(#[from] proj::ProjError),
+
+    /// The geoid grid is malformed.
+    #[error("Invalid geoid grid: {0}")]
+    InvalidGeoid(String),
+
+    /// The location is outside of the geoid grid.
+    #[error("Location is outside of the geoid grid: latitude {0}, longitude {1}")]
+    OutsideGeoid(f64, f64),
+
+    /// The file format could not be determined.
+    #[error("Unknown format: {0}")]
+    UnknownFormat(String),
+
+    /// Support for the file format wasn't enabled when this crate was compiled.
+    #[error("Support for the {0} format is not enabled")]
+    DisabledFormat(Format),
+
+    /// The file format can't be used in this direction, such as reading a KML file.
+    #[error("The {0} format doesn't support {1}")]
+    UnsupportedFormat(Format, &'static str),
+
+    /// The unit name is not recognized.
+    #[error("Unknown unit: {0}")]
+    UnknownUnit(String),
+
+    /// The name is not one of [Point::FIELDS](crate::Point::FIELDS).
+    #[error("Unknown field: {0}")]
+    UnknownField(String),
+
+    /// The pos column profile name is not recognized.
+    #[error("Unknown pos profile: {0}")]
+    UnknownProfile(String),
+
+    /// A line of a text file doesn't have a column that's being read.
+    #[error("The line has no column {0}")]
+    MissingColumn(usize),
+
+    /// The vertical datum name is not recognized.
+    #[error("Unknown vertical datum: {0}")]
+    UnknownVerticalDatum(String),
+
+    /// Heights can't be converted between these vertical datums.
+    #[error("Cannot convert heights from the {0} vertical datum to {1}")]
+    VerticalDatumConversion(String, String),
+
     /// The time unit code is invalid.
     #[error("The pof time unit code is invalid: {0}")]
     PofTimeUnit(u8),
@@ -19,6 +100,26 @@ pub enum Error {
     #[error("The pof time info code is invalid: {0}")]
     PofTimeInfo(u8),
 
+    /// The pof data offset is inside the fixed header fields.
+    #[error("The pof data offset is inside the 315-byte header: {0}")]
+    PofDataOffset(u32),
+
+    /// A KITTI OXTS packet doesn't have the right number of values.
+    #[error("The KITTI OXTS packet has {0} values instead of 30")]
+    KittiPacket(usize),
+
+    /// A KITTI timestamp is malformed.
+    #[error("Invalid KITTI timestamp: {0}")]
+    KittiTimestamp(String),
+
+    /// The file doesn't start with the TerraScan trajectory signature.
+    #[error("Not a TerraScan trajectory, the signature is {0:?}")]
+    TrjSignature(String),
+
+    /// No known sbet record layout fits the file.
+    #[error("Could not infer the sbet record layout")]
+    SbetLayout,
+
     /// Error returned when trying to extrapolate with only one point in the source.
     #[error("Cannot interpolate in a source with only one point")]
     OnePoint,
@@ -30,4 +131,166 @@ pub enum Error {
     /// The time value is above the maximum time of the source.
     #[error("Time value is above the maximum of the source: {0}")]
     TimeAboveMaximum(f64),
+
+    /// A chained source's first point isn't after the previous source's last point.
+    #[error("The next source starts at {1}, which isn't after the previous source's end at {0}")]
+    ChainTime(f64, f64),
+
+    /// Two trajectories don't overlap for long enough to compare them.
+    #[error("The trajectories overlap for {0} seconds, which is too short")]
+    InsufficientOverlap(f64),
+
+    /// An error at a location in an input.
+    #[error("{location}: {source}")]
+    Located {
+        /// Where the error happened.
+        location: Location,
+        /// The underlying error.
+        source: Box<Error>,
+    },
+}
+
+/// A stable category of [Error].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Reading or writing failed, including errors from GDAL.
+    Io,
+    /// A value, header, or grid could not be parsed.
+    Parse,
+    /// The file format is unknown or not enabled.
+    Format,
+    /// A time or location is outside of the data's range.
+    Range,
+    /// The input or configuration is otherwise invalid, including errors from PROJ, Polars,
+    /// and Arrow.
+    Validation,
+}
+
+/// Where in an input an error happened.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Location {
+    /// The path of the file, if known.
+    pub path: Option<PathBuf>,
+    /// The zero-based index of the record, if known.
+    ///
+    /// Readers count the points they've read, or the data lines of text files, so header lines
+    /// and skipped messages aren't counted.
+    pub record: Option<u64>,
+}
+
+impl Error {
+    /// Returns the category of this error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::{Error, ErrorKind};
+    /// assert_eq!(ErrorKind::Range, Error::TimeAboveMaximum(42.0).kind());
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::Io(_) => ErrorKind::Io,
+            #[cfg(feature = "gdal")]
+            Error::Gdal(_) => ErrorKind::Io,
+            Error::ParseFloat(_)
+            | Error::InvalidGeoid(_)
+            | Error::UnknownUnit(_)
+            | Error::UnknownVerticalDatum(_)
+            | Error::UnknownProfile(_)
+            | Error::MissingColumn(_)
+            | Error::PofTimeUnit(_)
+            | Error::PofTimeInfo(_)
+            | Error::PofDataOffset(_)
+            | Error::KittiPacket(_)
+            | Error::KittiTimestamp(_) => ErrorKind::Parse,
+            Error::UnknownFormat(_)
+            | Error::DisabledFormat(_)
+            | Error::UnsupportedFormat(..)
+            | Error::SbetLayout
+            | Error::TrjSignature(_) => ErrorKind::Format,
+            Error::OutsideGeoid(..)
+            | Error::TimeBelowMinimum(_)
+            | Error::TimeAboveMaximum(_)
+            | Error::InsufficientOverlap(_)
+            | Error::ChainTime(..) => ErrorKind::Range,
+            #[cfg(feature = "parquet")]
+            Error::Parquet(_) => ErrorKind::Io,
+            #[cfg(feature = "arrow")]
+            Error::Arrow(_) => ErrorKind::Validation,
+            #[cfg(feature = "polars")]
+            Error::Polars(_) => ErrorKind::Validation,
+            #[cfg(feature = "proj")]
+            Error::ProjCreate(_) | Error::Proj(_) => ErrorKind::Validation,
+            Error::OnePoint | Error::UnknownField(_) | Error::VerticalDatumConversion(..) => {
+                ErrorKind::Validation
+            }
+            Error::Located { ref source, .. } => source.kind(),
+        }
+    }
+
+    /// Attaches a location to this error.
+    ///
+    /// If this error already has a location, any unknown parts of it are filled in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::{Error, Location};
+    /// let location = Location { record: Some(3), ..Default::default() };
+    /// let error = Error::OnePoint.at(location.clone());
+    /// assert_eq!(Some(&location), error.location());
+    /// ```
+    pub fn at(self, location: Location) -> Error {
+        match self {
+            Error::Located {
+                location: inner,
+                source,
+            } => Error::Located {
+                location: Location {
+                    path: inner.path.or(location.path),
+                    record: inner.record.or(location.record),
+                },
+                source,
+            },
+            error => Error::Located {
+                location,
+                source: Box::new(error),
+            },
+        }
+    }
+
+    /// Returns where this error happened, if known.
+    pub fn location(&self) -> Option<&Location> {
+        match *self {
+            Error::Located { ref location, .. } => Some(location),
+            _ => None,
+        }
+    }
+}
+
+impl Location {
+    /// Creates a location for a path.
+    pub fn from_path<P: Into<PathBuf>>(path: P) -> Location {
+        Location {
+            path: Some(path.into()),
+            record: None,
+        }
+    }
+
+    /// Sets the record index of this location.
+    pub fn with_record(mut self, record: u64) -> Location {
+        self.record = Some(record);
+        self
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.path, self.record) {
+            (Some(path), Some(record)) => write!(f, "{}, record {}", path.display(), record),
+            (Some(path), None) => write!(f, "{}", path.display()),
+            (None, Some(record)) => write!(f, "record {}", record),
+            (None, None) => f.write_str("unknown location"),
+        }
+    }
 }
@@ -0,0 +1,154 @@
+//! Associate external events with a trajectory.
+//!
+//! Sensors such as cameras and scanners record the times of their triggers or marks separately
+//! from the trajectory. [associate] looks up each event time in a [Trajectory] and returns the
+//! pose of the platform at that event, either from the nearest recorded point or interpolated
+//! between the two points around it.
+
+use crate::point::Point;
+use crate::trajectory::Trajectory;
+use crate::Error;
+
+/// How an event is matched to the trajectory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Method {
+    /// Use the point closest in time to the event.
+    Nearest,
+    /// Linearly interpolate a point at the event's time.
+    #[default]
+    Interpolate,
+}
+
+/// An event and the trajectory point associated with it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EventPose {
+    /// The zero-based index of the event in the input times.
+    pub index: usize,
+    /// The time of the event.
+    pub time: f64,
+    /// The associated point.
+    ///
+    /// For [Method::Interpolate] this point's time is the event's time, and for
+    /// [Method::Nearest] it's the time of the nearest recorded point.
+    pub point: Point,
+}
+
+impl EventPose {
+    /// Returns the time from the event to its point, in seconds.
+    ///
+    /// This is always zero for interpolated points.
+    pub fn offset(&self) -> f64 {
+        self.point.time - self.time
+    }
+}
+
+/// Associates every event time with a point in the trajectory.
+///
+/// Event times don't have to be sorted. Returns an error if any event is outside of the
+/// trajectory's time range, or if the trajectory has fewer than two points.
+///
+/// # Examples
+///
+/// ```
+/// use pos::events::{self, Method};
+/// use pos::{Point, Trajectory};
+/// let trajectory = Trajectory::new(vec![
+///     Point { time: 0.0, altitude: 0.0, ..Default::default() },
+///     Point { time: 1.0, altitude: 10.0, ..Default::default() },
+/// ]);
+/// let poses = events::associate(&trajectory, &[0.25, 0.75], Method::Interpolate).unwrap();
+/// assert_eq!(2.5, poses[0].point.altitude);
+/// let poses = events::associate(&trajectory, &[0.25, 0.75], Method::Nearest).unwrap();
+/// assert_eq!(10.0, poses[1].point.altitude);
+/// assert_eq!(0.25, poses[1].offset());
+/// ```
+pub fn associate(
+    trajectory: &Trajectory,
+    times: &[f64],
+    method: Method,
+) -> Result<Vec<EventPose>, Error> {
+    times
+        .iter()
+        .enumerate()
+        .map(|(index, &time)| {
+            let point = match method {
+                Method::Nearest => nearest(trajectory, time)?,
+                Method::Interpolate => trajectory.interpolate(time)?,
+            };
+            Ok(EventPose { index, time, point })
+        })
+        .collect()
+}
+
+fn nearest(trajectory: &Trajectory, time: f64) -> Result<Point, Error> {
+    let points = trajectory.points();
+    if points.len() < 2 {
+        return Err(Error::OnePoint);
+    }
+    if time < points[0].time {
+        return Err(Error::TimeBelowMinimum(time));
+    }
+    if time > points[points.len() - 1].time {
+        return Err(Error::TimeAboveMaximum(time));
+    }
+    let index = points
+        .partition_point(|p| p.time < time)
+        .clamp(1, points.len() - 1);
+    let (before, after) = (points[index - 1], points[index]);
+    if time - before.time <= after.time - time {
+        Ok(before)
+    } else {
+        Ok(after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trajectory() -> Trajectory {
+        Trajectory::new(
+            (0..3)
+                .map(|i| Point {
+                    time: i as f64,
+                    altitude: 10.0 * i as f64,
+                    event: if i == 1 { Some(7) } else { None },
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn nearest() {
+        let poses = associate(&trajectory(), &[1.4, 0.5, 2.0], Method::Nearest).unwrap();
+        assert_eq!(
+            vec![(0, 1.0), (1, 0.0), (2, 2.0)],
+            poses
+                .iter()
+                .map(|pose| (pose.index, pose.point.time))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(7), poses[0].point.event);
+        assert!((poses[0].offset() + 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn interpolate() {
+        let poses = associate(&trajectory(), &[1.5], Method::Interpolate).unwrap();
+        assert_eq!(15.0, poses[0].point.altitude);
+        assert_eq!(0.0, poses[0].offset());
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert!(matches!(
+            associate(&trajectory(), &[1.0, 3.0], Method::Nearest),
+            Err(Error::TimeAboveMaximum(_))
+        ));
+        assert!(matches!(
+            associate(&trajectory(), &[-1.0], Method::Interpolate),
+            Err(Error::TimeBelowMinimum(_))
+        ));
+    }
+}
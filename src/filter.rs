@@ -0,0 +1,402 @@
+//! Kalman filtering and Rauch-Tung-Striebel smoothing.
+//!
+//! Each channel — east, north, and up position relative to the first point, and roll, pitch,
+//! and yaw — is filtered independently with a constant-velocity model. On yaw this is a
+//! constant-turn model. Channels are decoupled to keep the filter small and dependency-free,
+//! which is a reasonable approximation for cleaning up noisy low-cost GNSS/IMU trajectories,
+//! but it is not a replacement for a full inertial navigation filter.
+
+use crate::geodesy;
+use crate::point::Point;
+use crate::units::Radians;
+
+/// The default process noise on position, as an acceleration spectral density in m²/s³.
+pub const DEFAULT_POSITION_PROCESS_NOISE: f64 = 1.0;
+
+/// The default process noise on attitude, in rad²/s³.
+pub const DEFAULT_ATTITUDE_PROCESS_NOISE: f64 = 1e-4;
+
+/// The default position measurement standard deviation, in meters.
+pub const DEFAULT_POSITION_MEASUREMENT_NOISE: f64 = 1.0;
+
+/// The default attitude measurement standard deviation, in radians.
+pub const DEFAULT_ATTITUDE_MEASUREMENT_NOISE: f64 = 0.01;
+
+/// A Kalman filter and RTS smoother over trajectories.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KalmanFilter {
+    position_process_noise: f64,
+    attitude_process_noise: f64,
+    position_measurement_noise: f64,
+    attitude_measurement_noise: Radians<f64>,
+    use_accuracy: bool,
+}
+
+/// The state of one channel: a value and its rate, with their covariance.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct State {
+    x: [f64; 2],
+    p: [[f64; 2]; 2],
+}
+
+impl Default for KalmanFilter {
+    fn default() -> KalmanFilter {
+        KalmanFilter {
+            position_process_noise: DEFAULT_POSITION_PROCESS_NOISE,
+            attitude_process_noise: DEFAULT_ATTITUDE_PROCESS_NOISE,
+            position_measurement_noise: DEFAULT_POSITION_MEASUREMENT_NOISE,
+            attitude_measurement_noise: Radians(DEFAULT_ATTITUDE_MEASUREMENT_NOISE),
+            use_accuracy: true,
+        }
+    }
+}
+
+impl KalmanFilter {
+    /// Creates a new filter with the default noise parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::filter::KalmanFilter;
+    /// let filter = KalmanFilter::new().position_measurement_noise(3.0);
+    /// ```
+    pub fn new() -> KalmanFilter {
+        KalmanFilter::default()
+    }
+
+    /// Sets the position process noise, as an acceleration spectral density in m²/s³.
+    ///
+    /// Larger values let the filtered trajectory follow the measurements more closely.
+    pub fn position_process_noise(mut self, position_process_noise: f64) -> KalmanFilter {
+        self.position_process_noise = position_process_noise;
+        self
+    }
+
+    /// Sets the attitude process noise, in rad²/s³.
+    pub fn attitude_process_noise(mut self, attitude_process_noise: f64) -> KalmanFilter {
+        self.attitude_process_noise = attitude_process_noise;
+        self
+    }
+
+    /// Sets the position measurement standard deviation, in meters.
+    pub fn position_measurement_noise(mut self, position_measurement_noise: f64) -> KalmanFilter {
+        self.position_measurement_noise = position_measurement_noise;
+        self
+    }
+
+    /// Sets the attitude measurement standard deviation.
+    pub fn attitude_measurement_noise(
+        mut self,
+        attitude_measurement_noise: Radians<f64>,
+    ) -> KalmanFilter {
+        self.attitude_measurement_noise = attitude_measurement_noise;
+        self
+    }
+
+    /// Sets whether to use each point's accuracy, when present, as its measurement noise.
+    ///
+    /// Accuracy `x`, `y`, and `z` are used for east, north, and up. Defaults to true.
+    pub fn use_accuracy(mut self, use_accuracy: bool) -> KalmanFilter {
+        self.use_accuracy = use_accuracy;
+        self
+    }
+
+    /// Runs the filter forward over time-ordered points, returning the filtered points.
+    ///
+    /// Velocities are written to `x_velocity`, `y_velocity`, and `z_velocity` as east, north,
+    /// and up in meters per second, and roll, pitch, and yaw rates to `x_angular_rate`,
+    /// `y_angular_rate`, and `z_angular_rate`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::filter::KalmanFilter;
+    /// use pos::Point;
+    /// let points = vec![Point::default(), Point { time: 1.0, ..Default::default() }];
+    /// let filtered = KalmanFilter::new().filter(&points);
+    /// assert_eq!(2, filtered.len());
+    /// ```
+    pub fn filter(&self, points: &[Point]) -> Vec<Point> {
+        let (origin, filtered, _) = self.forward(points);
+        points
+            .iter()
+            .zip(filtered)
+            .map(|(point, states)| to_point(&origin, point, &states))
+            .collect()
+    }
+
+    /// Runs the filter forward and then an RTS smoother backward over time-ordered points,
+    /// returning the smoothed points.
+    ///
+    /// Velocities and rates are written as with [KalmanFilter::filter].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::filter::KalmanFilter;
+    /// use pos::Point;
+    /// let points = vec![Point::default(), Point { time: 1.0, ..Default::default() }];
+    /// let smoothed = KalmanFilter::new().smooth(&points);
+    /// assert_eq!(2, smoothed.len());
+    /// ```
+    pub fn smooth(&self, points: &[Point]) -> Vec<Point> {
+        let (origin, mut smoothed, predicted) = self.forward(points);
+        for i in (0..smoothed.len().saturating_sub(1)).rev() {
+            let dt = points[i + 1].time - points[i].time;
+            let next = smoothed[i + 1];
+            for ((state, predicted), next) in
+                smoothed[i].iter_mut().zip(&predicted[i + 1]).zip(&next)
+            {
+                *state = state.smooth(predicted, next, dt);
+            }
+        }
+        points
+            .iter()
+            .zip(smoothed)
+            .map(|(point, states)| to_point(&origin, point, &states))
+            .collect()
+    }
+
+    /// Runs the forward pass, returning the origin, the filtered states, and the predicted
+    /// states before each update.
+    #[allow(clippy::type_complexity)]
+    fn forward(&self, points: &[Point]) -> (Point, Vec<[State; 6]>, Vec<[State; 6]>) {
+        let origin = points.first().copied().unwrap_or_default();
+        let mut filtered: Vec<[State; 6]> = Vec::with_capacity(points.len());
+        let mut predicted = Vec::with_capacity(points.len());
+        let mut last = origin;
+        for point in points {
+            let measurements = self.measurements(&origin, point);
+            let states = match filtered.last() {
+                Some(previous) => {
+                    let dt = point.time - last.time;
+                    let prediction: [State; 6] = std::array::from_fn(|channel| {
+                        let q = if channel < 3 {
+                            self.position_process_noise
+                        } else {
+                            self.attitude_process_noise
+                        };
+                        previous[channel].predict(dt, q)
+                    });
+                    predicted.push(prediction);
+                    let mut states = prediction;
+                    for (channel, (state, (value, variance))) in
+                        states.iter_mut().zip(measurements).enumerate()
+                    {
+                        // Unwrap angles against the prediction so they're continuous.
+                        let value = if channel < 3 {
+                            value
+                        } else {
+                            state.x[0] + Radians(value - state.x[0]).wrapped().0
+                        };
+                        *state = state.update(value, variance);
+                    }
+                    states
+                }
+                None => {
+                    let states = measurements.map(|(value, variance)| State {
+                        x: [value, 0.0],
+                        p: [[variance, 0.0], [0.0, 1e6]],
+                    });
+                    predicted.push(states);
+                    states
+                }
+            };
+            filtered.push(states);
+            last = *point;
+        }
+        (origin, filtered, predicted)
+    }
+
+    /// Returns each channel's measurement and variance.
+    fn measurements(&self, origin: &Point, point: &Point) -> [(f64, f64); 6] {
+        let enu = geodesy::enu(origin, point);
+        let accuracy = point.accuracy.filter(|_| self.use_accuracy);
+        let position = self.position_measurement_noise;
+        let attitude = self.attitude_measurement_noise;
+        let sigmas = match accuracy {
+            Some(a) => [a.x, a.y, a.z, a.roll.0, a.pitch.0, a.yaw.0],
+            None => [
+                position, position, position, attitude.0, attitude.0, attitude.0,
+            ],
+        };
+        let values = [
+            enu[0],
+            enu[1],
+            enu[2],
+            point.roll.0,
+            point.pitch.0,
+            point.yaw.0,
+        ];
+        let mut measurements = [(0.0, 0.0); 6];
+        for ((measurement, value), sigma) in measurements.iter_mut().zip(values).zip(sigmas) {
+            *measurement = (value, sigma.max(f64::EPSILON).powi(2));
+        }
+        measurements
+    }
+}
+
+impl State {
+    fn predict(&self, dt: f64, q: f64) -> State {
+        let [x, v] = self.x;
+        let p = self.p;
+        let p00 = p[0][0] + dt * (p[1][0] + p[0][1]) + dt * dt * p[1][1] + q * dt.powi(3) / 3.0;
+        let p01 = p[0][1] + dt * p[1][1] + q * dt * dt / 2.0;
+        let p11 = p[1][1] + q * dt;
+        State {
+            x: [x + dt * v, v],
+            p: [[p00, p01], [p01, p11]],
+        }
+    }
+
+    fn update(&self, value: f64, variance: f64) -> State {
+        let p = self.p;
+        let s = p[0][0] + variance;
+        let k = [p[0][0] / s, p[1][0] / s];
+        let residual = value - self.x[0];
+        State {
+            x: [self.x[0] + k[0] * residual, self.x[1] + k[1] * residual],
+            p: [
+                [(1.0 - k[0]) * p[0][0], (1.0 - k[0]) * p[0][1]],
+                [p[1][0] - k[1] * p[0][0], p[1][1] - k[1] * p[0][1]],
+            ],
+        }
+    }
+
+    /// Returns this filtered state smoothed with the next predicted and smoothed states.
+    fn smooth(&self, predicted: &State, next: &State, dt: f64) -> State {
+        let p = self.p;
+        // C = P F^T inv(P_predicted)
+        let pf = [
+            [p[0][0] + dt * p[0][1], p[0][1]],
+            [p[1][0] + dt * p[1][1], p[1][1]],
+        ];
+        let inverse = match invert(predicted.p) {
+            Some(inverse) => inverse,
+            None => return *next,
+        };
+        let c = multiply(pf, inverse);
+        let dx = [next.x[0] - predicted.x[0], next.x[1] - predicted.x[1]];
+        let dp = [
+            [
+                next.p[0][0] - predicted.p[0][0],
+                next.p[0][1] - predicted.p[0][1],
+            ],
+            [
+                next.p[1][0] - predicted.p[1][0],
+                next.p[1][1] - predicted.p[1][1],
+            ],
+        ];
+        let cdp = multiply(c, dp);
+        let correction = multiply(cdp, [[c[0][0], c[1][0]], [c[0][1], c[1][1]]]);
+        State {
+            x: [
+                self.x[0] + c[0][0] * dx[0] + c[0][1] * dx[1],
+                self.x[1] + c[1][0] * dx[0] + c[1][1] * dx[1],
+            ],
+            p: [
+                [p[0][0] + correction[0][0], p[0][1] + correction[0][1]],
+                [p[1][0] + correction[1][0], p[1][1] + correction[1][1]],
+            ],
+        }
+    }
+}
+
+fn invert(m: [[f64; 2]; 2]) -> Option<[[f64; 2]; 2]> {
+    let determinant = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+    if determinant.abs() < f64::MIN_POSITIVE {
+        return None;
+    }
+    Some([
+        [m[1][1] / determinant, -m[0][1] / determinant],
+        [-m[1][0] / determinant, m[0][0] / determinant],
+    ])
+}
+
+fn multiply(a: [[f64; 2]; 2], b: [[f64; 2]; 2]) -> [[f64; 2]; 2] {
+    [
+        [
+            a[0][0] * b[0][0] + a[0][1] * b[1][0],
+            a[0][0] * b[0][1] + a[0][1] * b[1][1],
+        ],
+        [
+            a[1][0] * b[0][0] + a[1][1] * b[1][0],
+            a[1][0] * b[0][1] + a[1][1] * b[1][1],
+        ],
+    ]
+}
+
+fn to_point(origin: &Point, point: &Point, states: &[State; 6]) -> Point {
+    let (latitude, longitude, altitude) =
+        geodesy::from_enu(origin, [states[0].x[0], states[1].x[0], states[2].x[0]]);
+    Point {
+        latitude,
+        longitude,
+        altitude,
+        roll: Radians(states[3].x[0]).wrapped(),
+        pitch: Radians(states[4].x[0]).wrapped(),
+        yaw: Radians(states[5].x[0]).wrapped(),
+        x_velocity: Some(states[0].x[1]),
+        y_velocity: Some(states[1].x[1]),
+        z_velocity: Some(states[2].x[1]),
+        x_angular_rate: Some(Radians(states[3].x[1])),
+        y_angular_rate: Some(Radians(states[4].x[1])),
+        z_angular_rate: Some(Radians(states[5].x[1])),
+        ..*point
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A noisy climb at 2 m/s with a steady 1°/s turn.
+    fn points() -> Vec<Point> {
+        let noise = [0.3, -0.5, 0.1, 0.4, -0.2, -0.4, 0.5, 0.0, -0.3, 0.2];
+        (0..100)
+            .map(|i| {
+                let time = i as f64;
+                let e = noise[i % noise.len()];
+                Point {
+                    time,
+                    latitude: Radians::from_degrees(40.0),
+                    longitude: Radians::from_degrees(-105.0),
+                    altitude: 2.0 * time + e,
+                    yaw: Radians::from_degrees(170.0 + time + e).wrapped(),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    fn rmse(points: &[Point]) -> f64 {
+        let sum: f64 = points
+            .iter()
+            .map(|p| (p.altitude - 2.0 * p.time).powi(2))
+            .sum();
+        (sum / points.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn filter_and_smooth() {
+        let points = points();
+        let filter = KalmanFilter::new()
+            .position_process_noise(1e-4)
+            .attitude_process_noise(1e-8)
+            .position_measurement_noise(0.4)
+            .attitude_measurement_noise(Radians::from_degrees(0.4));
+        let filtered = filter.filter(&points);
+        let smoothed = filter.smooth(&points);
+        assert!(rmse(&filtered[20..]) < rmse(&points[20..]));
+        assert!(rmse(&smoothed) < rmse(&filtered));
+        let middle = smoothed[50];
+        assert!((middle.z_velocity.unwrap() - 2.0).abs() < 0.05);
+        assert!((middle.z_angular_rate.unwrap().to_degrees() - 1.0).abs() < 0.05);
+        assert!((middle.yaw.to_degrees() + 140.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(KalmanFilter::new().smooth(&[]).is_empty());
+    }
+}
@@ -0,0 +1,709 @@
+//! Position file formats, and detecting them from paths.
+
+use crate::datum::VerticalDatum;
+use crate::point::Point;
+#[cfg(any(feature = "pof", feature = "pos", feature = "sbet"))]
+use crate::sink::FileSink;
+use crate::sink::Sink;
+use crate::source::Source;
+use crate::{Error, Location};
+use std::fmt;
+#[cfg(any(
+    feature = "pof",
+    feature = "pos",
+    feature = "sbet",
+    feature = "csv",
+    feature = "nmea",
+    feature = "ubx",
+    feature = "sbf",
+    feature = "tlog",
+    feature = "trj",
+    feature = "eo",
+    feature = "kml",
+    feature = "wkt"
+))]
+use std::fs::File;
+#[cfg(any(
+    feature = "pof",
+    feature = "pos",
+    feature = "sbet",
+    feature = "csv",
+    feature = "nmea",
+    feature = "ubx",
+    feature = "sbf",
+    feature = "tlog",
+    feature = "trj",
+    feature = "eo"
+))]
+use std::io::BufReader;
+#[cfg(any(
+    feature = "pof",
+    feature = "pos",
+    feature = "sbet",
+    feature = "kml",
+    feature = "wkt"
+))]
+use std::io::BufWriter;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A position file format.
+///
+/// Every format can be named and detected, whether or not its cargo feature is enabled. Some
+/// formats can only be read or only be written; see [Format::can_read] and [Format::can_write].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Format {
+    /// ASCII pos files.
+    Pos,
+    /// Binary sbet files.
+    Sbet,
+    /// Riegl's binary pof files.
+    Pof,
+    /// Comma-separated values with a header row of column names, behind the `csv` feature.
+    Csv,
+    /// JSON Lines, which can only be written, behind the `jsonl` feature.
+    Jsonl,
+    /// Apache Parquet files, behind the `parquet` feature.
+    Parquet,
+    /// KML documents, which can only be written, behind the `kml` feature.
+    Kml,
+    /// Well-known text, which can only be written, behind the `wkt` feature.
+    Wkt,
+    /// NMEA 0183 logs, which can only be read, behind the `nmea` feature.
+    Nmea,
+    /// u-blox UBX logs, which can only be read, behind the `ubx` feature.
+    Ubx,
+    /// Septentrio SBF logs, which can only be read, behind the `sbf` feature.
+    Sbf,
+    /// MAVLink telemetry logs, which can only be read, behind the `tlog` feature.
+    Tlog,
+    /// TerraScan binary trajectories, which can only be read, behind the `trj` feature.
+    Trj,
+    /// Exterior orientation files, which can only be read, behind the `eo` feature.
+    Eo,
+    /// KITTI OXTS directories, which can only be read, behind the `kitti` feature.
+    ///
+    /// Recordings are directories, so they're never detected from a path.
+    Kitti,
+}
+
+impl Format {
+    /// Returns every supported format.
+    pub fn all() -> &'static [Format] {
+        &[
+            Format::Pos,
+            Format::Sbet,
+            Format::Pof,
+            Format::Csv,
+            Format::Jsonl,
+            Format::Parquet,
+            Format::Kml,
+            Format::Wkt,
+            Format::Nmea,
+            Format::Ubx,
+            Format::Sbf,
+            Format::Tlog,
+            Format::Trj,
+            Format::Eo,
+            Format::Kitti,
+        ]
+    }
+
+    /// Detects a file's format from its extension.
+    ///
+    /// Applanix's `.out` extension is treated as sbet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::format::Format;
+    /// assert_eq!(Format::Sbet, Format::from_path("data/2-points.sbet").unwrap());
+    /// assert_eq!(Format::Csv, Format::from_path("trajectory.csv").unwrap());
+    /// assert!(Format::from_path("data/2-points.txt").is_err());
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Format, Error> {
+        let path = path.as_ref();
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| e.parse().ok())
+            .filter(|&format| format != Format::Kitti)
+            .ok_or_else(|| Error::UnknownFormat(path.display().to_string()))
+    }
+
+    /// Returns the canonical file extension for this format.
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            Format::Pos => "pos",
+            Format::Sbet => "sbet",
+            Format::Pof => "pof",
+            Format::Csv => "csv",
+            Format::Jsonl => "jsonl",
+            Format::Parquet => "parquet",
+            Format::Kml => "kml",
+            Format::Wkt => "wkt",
+            Format::Nmea => "nmea",
+            Format::Ubx => "ubx",
+            Format::Sbf => "sbf",
+            Format::Tlog => "tlog",
+            Format::Trj => "trj",
+            Format::Eo => "eo",
+            Format::Kitti => "kitti",
+        }
+    }
+
+    /// Returns true if this crate can read this format, when its feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::format::Format;
+    /// assert!(Format::Nmea.can_read());
+    /// assert!(!Format::Kml.can_read());
+    /// ```
+    pub fn can_read(&self) -> bool {
+        !matches!(*self, Format::Jsonl | Format::Kml | Format::Wkt)
+    }
+
+    /// Returns true if this crate can write this format, when its feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::format::Format;
+    /// assert!(Format::Kml.can_write());
+    /// assert!(!Format::Nmea.can_write());
+    /// ```
+    pub fn can_write(&self) -> bool {
+        matches!(
+            *self,
+            Format::Pos
+                | Format::Sbet
+                | Format::Pof
+                | Format::Csv
+                | Format::Jsonl
+                | Format::Parquet
+                | Format::Kml
+                | Format::Wkt
+        )
+    }
+
+    /// Returns the vertical datum that this format's heights are defined in, if the format
+    /// defines one.
+    ///
+    /// sbet and pof heights are ellipsoidal, as are the heights read from NMEA, UBX, and SBF
+    /// logs. pos files are exported with either ellipsoidal or orthometric heights, so their
+    /// datum is unknown unless the file is tagged; see
+    /// [pos::Reader::vertical_datum](crate::pos::Reader::vertical_datum).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::datum::VerticalDatum;
+    /// use pos::format::Format;
+    /// assert_eq!(Some(VerticalDatum::Ellipsoidal), Format::Sbet.vertical_datum());
+    /// assert_eq!(None, Format::Pos.vertical_datum());
+    /// ```
+    pub fn vertical_datum(&self) -> Option<VerticalDatum> {
+        match *self {
+            Format::Sbet | Format::Pof | Format::Nmea | Format::Ubx | Format::Sbf => {
+                Some(VerticalDatum::Ellipsoidal)
+            }
+            _ => None,
+        }
+    }
+
+    /// Opens a path as a source of this format.
+    ///
+    /// csv files are read with [csv::Reader::from_header](crate::csv::Reader::from_header).
+    /// Returns [Error::DisabledFormat] if this format's cargo feature isn't enabled, and
+    /// [Error::UnsupportedFormat] if this format can't be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::format::Format;
+    /// let source = Format::Sbet.open("data/2-points.sbet").unwrap();
+    /// ```
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<Box<dyn Source>, Error> {
+        Ok(Box::new(AnyReader::open(*self, path)?))
+    }
+
+    /// Creates a sink that writes this format to a path.
+    ///
+    /// csv files get a column for every field in [Point::FIELDS], with angles in degrees. KML
+    /// and well-known text files hold a single line, so their points are kept in memory and
+    /// written when the sink is finished; well-known text is written with an SRID of 4326.
+    /// Returns [Error::DisabledFormat] if this format's cargo feature isn't enabled, and
+    /// [Error::UnsupportedFormat] if this format can't be written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::format::Format;
+    /// let sink = Format::Pof.create("out.pof").unwrap();
+    /// ```
+    pub fn create<P: AsRef<Path>>(&self, path: P) -> Result<Box<dyn Sink>, Error> {
+        if !self.can_write() {
+            return Err(Error::UnsupportedFormat(*self, "writing"));
+        }
+        match *self {
+            #[cfg(feature = "pos")]
+            Format::Pos => crate::pos::Writer::<BufWriter<File>>::create_file_sink(path),
+            #[cfg(feature = "sbet")]
+            Format::Sbet => crate::sbet::Writer::<BufWriter<File>>::create_file_sink(path),
+            #[cfg(feature = "pof")]
+            Format::Pof => crate::pof::Writer::<BufWriter<File>>::create_file_sink(path),
+            #[cfg(feature = "csv")]
+            Format::Csv => Ok(Box::new(crate::csv::Writer::from_path(
+                path,
+                crate::csv::point_columns(),
+            )?)),
+            #[cfg(feature = "jsonl")]
+            Format::Jsonl => Ok(Box::new(crate::jsonl::Writer::from_path(path)?)),
+            #[cfg(feature = "parquet")]
+            Format::Parquet => Ok(Box::new(crate::parquet::Writer::from_path(path)?)),
+            #[cfg(feature = "kml")]
+            Format::Kml => Buffered::create(path, |write, points| {
+                crate::kml::write(write, points, &crate::kml::Options::new())
+            }),
+            #[cfg(feature = "wkt")]
+            Format::Wkt => Buffered::create(path, |write, points| {
+                crate::wkt::write(write, points, Some(4326))
+            }),
+            #[allow(unreachable_patterns)]
+            format => {
+                let _ = path;
+                Err(Error::DisabledFormat(format))
+            }
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Format, Error> {
+        let lowercase = s.to_lowercase();
+        match lowercase.as_str() {
+            "sbet" | "out" => Ok(Format::Sbet),
+            extension => Format::all()
+                .iter()
+                .copied()
+                .find(|format| format.extension() == extension)
+                .ok_or_else(|| Error::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// A sink that keeps its points in memory, then writes them all to a file when it's finished.
+#[cfg(any(feature = "kml", feature = "wkt"))]
+struct Buffered {
+    write: Option<BufWriter<File>>,
+    points: Vec<Point>,
+    finish: fn(&mut BufWriter<File>, &[Point]) -> Result<(), Error>,
+}
+
+#[cfg(any(feature = "kml", feature = "wkt"))]
+impl Buffered {
+    fn create<P: AsRef<Path>>(
+        path: P,
+        finish: fn(&mut BufWriter<File>, &[Point]) -> Result<(), Error>,
+    ) -> Result<Box<dyn Sink>, Error> {
+        Ok(Box::new(Buffered {
+            write: Some(BufWriter::new(File::create(path)?)),
+            points: Vec::new(),
+            finish,
+        }))
+    }
+}
+
+#[cfg(any(feature = "kml", feature = "wkt"))]
+impl fmt::Debug for Buffered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Buffered")
+            .field("points", &self.points.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(any(feature = "kml", feature = "wkt"))]
+impl Sink for Buffered {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        self.points.push(*point);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if let Some(mut write) = self.write.take() {
+            (self.finish)(&mut write, &self.points)?;
+            std::io::Write::flush(&mut write)?;
+        }
+        Ok(())
+    }
+}
+
+/// A reader for any enabled format, dispatched statically.
+///
+/// This is an alternative to the boxed sources returned by [open] for applications that need a
+/// concrete, `Send + 'static` type.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+#[cfg_attr(
+    not(any(
+        feature = "pof",
+        feature = "pos",
+        feature = "sbet",
+        feature = "csv",
+        feature = "parquet",
+        feature = "nmea",
+        feature = "ubx",
+        feature = "sbf",
+        feature = "tlog",
+        feature = "trj",
+        feature = "eo",
+        feature = "kitti"
+    )),
+    allow(missing_copy_implementations)
+)]
+#[non_exhaustive]
+pub enum AnyReader {
+    /// A pos reader.
+    #[cfg(feature = "pos")]
+    Pos(crate::pos::Reader<BufReader<File>>),
+    /// An sbet reader.
+    #[cfg(feature = "sbet")]
+    Sbet(crate::sbet::Reader<BufReader<File>>),
+    /// A pof reader.
+    #[cfg(feature = "pof")]
+    Pof(crate::pof::Reader<BufReader<File>>),
+    /// A csv reader.
+    #[cfg(feature = "csv")]
+    Csv(crate::csv::Reader<BufReader<File>>),
+    /// A parquet reader.
+    #[cfg(feature = "parquet")]
+    Parquet(crate::parquet::Reader),
+    /// An NMEA reader.
+    #[cfg(feature = "nmea")]
+    Nmea(crate::nmea::Reader<BufReader<File>>),
+    /// A UBX reader.
+    #[cfg(feature = "ubx")]
+    Ubx(crate::ubx::Reader<BufReader<File>>),
+    /// An SBF reader.
+    #[cfg(feature = "sbf")]
+    Sbf(crate::sbf::Reader<BufReader<File>>),
+    /// A MAVLink telemetry log reader.
+    #[cfg(feature = "tlog")]
+    Tlog(crate::tlog::Reader<BufReader<File>>),
+    /// A TerraScan trajectory reader.
+    #[cfg(feature = "trj")]
+    Trj(crate::trj::Reader<BufReader<File>>),
+    /// An exterior orientation reader.
+    #[cfg(feature = "eo")]
+    Eo(crate::eo::Reader<BufReader<File>>),
+    /// A KITTI OXTS reader.
+    #[cfg(feature = "kitti")]
+    Kitti(crate::kitti::Reader),
+}
+
+impl AnyReader {
+    /// Opens a reader for a path, detecting its format from its extension.
+    ///
+    /// Returns [Error::DisabledFormat] if the format's cargo feature isn't enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::format::{AnyReader, Format};
+    /// let reader = AnyReader::from_path("data/2-points.sbet").unwrap();
+    /// assert_eq!(Format::Sbet, reader.format());
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<AnyReader, Error> {
+        AnyReader::open(Format::from_path(&path)?, path)
+    }
+
+    /// Opens a reader for a path of the given format.
+    ///
+    /// Returns [Error::DisabledFormat] if the format's cargo feature isn't enabled, and
+    /// [Error::UnsupportedFormat] if the format can't be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::format::{AnyReader, Format};
+    /// let reader = AnyReader::open(Format::Sbet, "data/2-points.sbet").unwrap();
+    /// ```
+    pub fn open<P: AsRef<Path>>(format: Format, path: P) -> Result<AnyReader, Error> {
+        match format {
+            #[cfg(feature = "pos")]
+            Format::Pos => Ok(AnyReader::Pos(crate::pos::Reader::from_path(path)?)),
+            #[cfg(feature = "sbet")]
+            Format::Sbet => Ok(AnyReader::Sbet(crate::sbet::Reader::from_path(path)?)),
+            #[cfg(feature = "pof")]
+            Format::Pof => Ok(AnyReader::Pof(crate::pof::Reader::from_path(path)?)),
+            #[cfg(feature = "csv")]
+            Format::Csv => Ok(AnyReader::Csv(
+                crate::csv::Reader::from_header(BufReader::new(File::open(&path)?))?
+                    .with_path(path.as_ref()),
+            )),
+            #[cfg(feature = "parquet")]
+            Format::Parquet => Ok(AnyReader::Parquet(crate::parquet::Reader::from_path(path)?)),
+            #[cfg(feature = "nmea")]
+            Format::Nmea => Ok(AnyReader::Nmea(crate::nmea::Reader::from_path(path)?)),
+            #[cfg(feature = "ubx")]
+            Format::Ubx => Ok(AnyReader::Ubx(crate::ubx::Reader::from_path(path)?)),
+            #[cfg(feature = "sbf")]
+            Format::Sbf => Ok(AnyReader::Sbf(crate::sbf::Reader::from_path(path)?)),
+            #[cfg(feature = "tlog")]
+            Format::Tlog => Ok(AnyReader::Tlog(crate::tlog::Reader::from_path(path)?)),
+            #[cfg(feature = "trj")]
+            Format::Trj => Ok(AnyReader::Trj(crate::trj::Reader::from_path(path)?)),
+            #[cfg(feature = "eo")]
+            Format::Eo => Ok(AnyReader::Eo(crate::eo::Reader::from_path(path)?)),
+            #[cfg(feature = "kitti")]
+            Format::Kitti => Ok(AnyReader::Kitti(crate::kitti::Reader::from_path(path)?)),
+            format => {
+                let _ = path;
+                if format.can_read() {
+                    Err(Error::DisabledFormat(format))
+                } else {
+                    Err(Error::UnsupportedFormat(format, "reading"))
+                }
+            }
+        }
+    }
+
+    /// Returns the format of this reader.
+    pub fn format(&self) -> Format {
+        match *self {
+            #[cfg(feature = "pos")]
+            AnyReader::Pos(_) => Format::Pos,
+            #[cfg(feature = "sbet")]
+            AnyReader::Sbet(_) => Format::Sbet,
+            #[cfg(feature = "pof")]
+            AnyReader::Pof(_) => Format::Pof,
+            #[cfg(feature = "csv")]
+            AnyReader::Csv(_) => Format::Csv,
+            #[cfg(feature = "parquet")]
+            AnyReader::Parquet(_) => Format::Parquet,
+            #[cfg(feature = "nmea")]
+            AnyReader::Nmea(_) => Format::Nmea,
+            #[cfg(feature = "ubx")]
+            AnyReader::Ubx(_) => Format::Ubx,
+            #[cfg(feature = "sbf")]
+            AnyReader::Sbf(_) => Format::Sbf,
+            #[cfg(feature = "tlog")]
+            AnyReader::Tlog(_) => Format::Tlog,
+            #[cfg(feature = "trj")]
+            AnyReader::Trj(_) => Format::Trj,
+            #[cfg(feature = "eo")]
+            AnyReader::Eo(_) => Format::Eo,
+            #[cfg(feature = "kitti")]
+            AnyReader::Kitti(_) => Format::Kitti,
+        }
+    }
+
+    fn as_source(&mut self) -> &mut dyn Source {
+        match *self {
+            #[cfg(feature = "pos")]
+            AnyReader::Pos(ref mut reader) => reader,
+            #[cfg(feature = "sbet")]
+            AnyReader::Sbet(ref mut reader) => reader,
+            #[cfg(feature = "pof")]
+            AnyReader::Pof(ref mut reader) => reader,
+            #[cfg(feature = "csv")]
+            AnyReader::Csv(ref mut reader) => reader,
+            #[cfg(feature = "parquet")]
+            AnyReader::Parquet(ref mut reader) => reader,
+            #[cfg(feature = "nmea")]
+            AnyReader::Nmea(ref mut reader) => reader,
+            #[cfg(feature = "ubx")]
+            AnyReader::Ubx(ref mut reader) => reader,
+            #[cfg(feature = "sbf")]
+            AnyReader::Sbf(ref mut reader) => reader,
+            #[cfg(feature = "tlog")]
+            AnyReader::Tlog(ref mut reader) => reader,
+            #[cfg(feature = "trj")]
+            AnyReader::Trj(ref mut reader) => reader,
+            #[cfg(feature = "eo")]
+            AnyReader::Eo(ref mut reader) => reader,
+            #[cfg(feature = "kitti")]
+            AnyReader::Kitti(ref mut reader) => reader,
+        }
+    }
+}
+
+impl Source for AnyReader {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        self.as_source().source()
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        match *self {
+            #[cfg(feature = "sbet")]
+            AnyReader::Sbet(ref reader) => reader.len_hint(),
+            #[cfg(feature = "pof")]
+            AnyReader::Pof(ref reader) => reader.len_hint(),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
+
+/// Opens a path as a source, detecting its format from its extension.
+///
+/// # Examples
+///
+/// ```
+/// let source = pos::format::open("data/2-points.sbet").unwrap();
+/// ```
+pub fn open<P: AsRef<Path>>(path: P) -> Result<Box<dyn Source>, Error> {
+    Format::from_path(&path)?.open(path)
+}
+
+/// Reads every point from a path into memory, detecting its format from its extension.
+///
+/// Unlike iterating over a source, errors are returned rather than panicking, with the path
+/// added to the failing record's [Location]. When the point count is known up front, from the
+/// size of an sbet file or the header of a pof file, the points are allocated all at once.
+///
+/// # Examples
+///
+/// ```
+/// let points = pos::read("data/2-points.sbet").unwrap();
+/// assert_eq!(2, points.len());
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+)]
+pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<Point>, Error> {
+    let path = path.as_ref();
+    read_located(path).map_err(|err| err.at(Location::from_path(path)))
+}
+
+fn read_located(path: &Path) -> Result<Vec<Point>, Error> {
+    let format = Format::from_path(path)?;
+    let mut source = format.open(path)?;
+    let capacity = source.len_hint().unwrap_or(0);
+    let mut points = Vec::with_capacity(capacity);
+    while let Some(point) = source
+        .source()
+        .map_err(|err| err.at(Location::default().with_record(points.len() as u64)))?
+    {
+        points.push(point);
+    }
+    #[cfg(feature = "tracing")]
+    tracing::debug!(%format, count = points.len(), capacity, "read points");
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "sbet")]
+    #[test]
+    fn any_reader_len_hint() {
+        let mut reader = AnyReader::from_path("data/2-points.sbet").unwrap();
+        assert_eq!(Some(2), reader.len_hint());
+        let _ = reader.source().unwrap();
+        assert_eq!(Some(1), reader.len_hint());
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(Format::Pos, Format::from_path("a/b.POS").unwrap());
+        assert_eq!(
+            Format::Sbet,
+            Format::from_path("sbet_mission_1.out").unwrap()
+        );
+        assert_eq!(
+            Format::Pof,
+            Format::from_path("sbet_mission_1.pof").unwrap()
+        );
+        assert!(Format::from_path("sbet_mission_1").is_err());
+        for format in Format::all() {
+            assert_eq!(*format, format.to_string().parse().unwrap());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sbet")]
+    fn read_sbet() {
+        let points = read("data/2-points.sbet").unwrap();
+        assert_eq!(2, points.len());
+        let err = read("data/does-not-exist.sbet").unwrap_err();
+        assert_eq!(crate::ErrorKind::Io, err.kind());
+        assert_eq!(
+            Some(Path::new("data/does-not-exist.sbet")),
+            err.location().and_then(|location| location.path.as_deref())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sbet")]
+    fn any_reader() {
+        fn assert_send<T: Send + 'static>(_: &T) {}
+        let mut reader = AnyReader::from_path("data/2-points.sbet").unwrap();
+        assert_send(&reader);
+        assert_eq!(Format::Sbet, reader.format());
+        assert!(reader.source().unwrap().is_some());
+        assert!(reader.source().unwrap().is_some());
+        assert!(reader.source().unwrap().is_none());
+    }
+
+    #[test]
+    fn unsupported() {
+        assert!(matches!(
+            Format::Kml.open("data/2-points.kml").unwrap_err(),
+            Error::UnsupportedFormat(Format::Kml, _)
+        ));
+        assert!(matches!(
+            Format::Nmea.create("out.nmea").unwrap_err(),
+            Error::UnsupportedFormat(Format::Nmea, _)
+        ));
+        assert!(Format::from_path("oxts.kitti").is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "csv", feature = "sbet"))]
+    fn csv_round_trip() {
+        let points = read("data/2-points.sbet").unwrap();
+        let path = std::env::temp_dir().join("pos-rs-format-round-trip.csv");
+        let mut sink = Format::Csv.create(&path).unwrap();
+        for point in &points {
+            sink.sink(point).unwrap();
+        }
+        sink.finish().unwrap();
+        drop(sink);
+        let round_trip = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(points.len(), round_trip.len());
+        for (point, round_trip) in points.iter().zip(&round_trip) {
+            assert!((point.time - round_trip.time).abs() < 1e-6);
+            assert!((point.latitude.0 - round_trip.latitude.0).abs() < 1e-7);
+            assert!((point.longitude.0 - round_trip.longitude.0).abs() < 1e-7);
+            assert!((point.altitude - round_trip.altitude).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "kml", feature = "sbet"))]
+    fn kml() {
+        let path = std::env::temp_dir().join("pos-rs-format.kml");
+        let mut sink = Format::Kml.create(&path).unwrap();
+        for point in read("data/2-points.sbet").unwrap() {
+            sink.sink(&point).unwrap();
+        }
+        sink.finish().unwrap();
+        let kml = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(kml.contains("<kml"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Convert vectors between navigation and body frames.
+//!
+//! The navigation frames are local-level: north, east, and down (NED), or east, north, and up
+//! (ENU). The body frame is x forward, y right, and z down, and is related to NED by a point's
+//! attitude, applied as yaw, then pitch, then roll, with yaw measured clockwise from north.
+
+use crate::point::Point;
+
+/// Converts a north, east, down vector to east, north, up.
+///
+/// # Examples
+///
+/// ```
+/// use pos::frames;
+/// assert_eq!([2.0, 1.0, -3.0], frames::ned_to_enu([1.0, 2.0, 3.0]));
+/// ```
+pub fn ned_to_enu([north, east, down]: [f64; 3]) -> [f64; 3] {
+    [east, north, -down]
+}
+
+/// Converts an east, north, up vector to north, east, down.
+///
+/// # Examples
+///
+/// ```
+/// use pos::frames;
+/// assert_eq!([1.0, 2.0, 3.0], frames::enu_to_ned([2.0, 1.0, -3.0]));
+/// ```
+pub fn enu_to_ned([east, north, up]: [f64; 3]) -> [f64; 3] {
+    [north, east, -up]
+}
+
+/// Rotates a body-frame vector into north, east, down using a point's attitude.
+///
+/// # Examples
+///
+/// ```
+/// use pos::{frames, Point, Radians};
+/// let point = Point { yaw: Radians::from_degrees(90.0), ..Default::default() };
+/// let ned = frames::body_to_ned(&point, [1.0, 0.0, 0.0]);
+/// assert!((ned[1] - 1.0).abs() < 1e-9);
+/// ```
+pub fn body_to_ned(point: &Point, body: [f64; 3]) -> [f64; 3] {
+    let r = rotation(point);
+    [
+        r[0][0] * body[0] + r[0][1] * body[1] + r[0][2] * body[2],
+        r[1][0] * body[0] + r[1][1] * body[1] + r[1][2] * body[2],
+        r[2][0] * body[0] + r[2][1] * body[1] + r[2][2] * body[2],
+    ]
+}
+
+/// Rotates a north, east, down vector into the body frame using a point's attitude.
+///
+/// This is the inverse of [body_to_ned].
+///
+/// # Examples
+///
+/// ```
+/// use pos::{frames, Point, Radians};
+/// let point = Point { yaw: Radians::from_degrees(90.0), ..Default::default() };
+/// let body = frames::ned_to_body(&point, [0.0, 1.0, 0.0]);
+/// assert!((body[0] - 1.0).abs() < 1e-9);
+/// ```
+pub fn ned_to_body(point: &Point, ned: [f64; 3]) -> [f64; 3] {
+    let r = rotation(point);
+    [
+        r[0][0] * ned[0] + r[1][0] * ned[1] + r[2][0] * ned[2],
+        r[0][1] * ned[0] + r[1][1] * ned[1] + r[2][1] * ned[2],
+        r[0][2] * ned[0] + r[1][2] * ned[1] + r[2][2] * ned[2],
+    ]
+}
+
+/// Rotates a body-frame vector into east, north, up using a point's attitude.
+pub fn body_to_enu(point: &Point, body: [f64; 3]) -> [f64; 3] {
+    ned_to_enu(body_to_ned(point, body))
+}
+
+/// Rotates an east, north, up vector into the body frame using a point's attitude.
+pub fn enu_to_body(point: &Point, enu: [f64; 3]) -> [f64; 3] {
+    ned_to_body(point, enu_to_ned(enu))
+}
+
+/// Converts roll, pitch, and yaw rates into body-frame angular rates using a point's attitude.
+///
+/// Euler angle rates are only the body rates when the platform is level. Differentiating a
+/// trajectory's attitude gives Euler angle rates, while IMUs and sbet files record body rates.
+///
+/// # Examples
+///
+/// ```
+/// use pos::{frames, Point, Radians};
+/// let point = Point { roll: Radians::from_degrees(90.0), ..Default::default() };
+/// let body = frames::euler_rates_to_body(&point, [0.0, 0.0, 1.0]);
+/// assert!((body[1] - 1.0).abs() < 1e-9);
+/// ```
+pub fn euler_rates_to_body(point: &Point, [roll, pitch, yaw]: [f64; 3]) -> [f64; 3] {
+    let (sr, cr) = point.roll.0.sin_cos();
+    let (sp, cp) = point.pitch.0.sin_cos();
+    [
+        roll - yaw * sp,
+        pitch * cr + yaw * sr * cp,
+        -pitch * sr + yaw * cr * cp,
+    ]
+}
+
+/// The body-to-NED rotation matrix.
+fn rotation(point: &Point) -> [[f64; 3]; 3] {
+    let (sr, cr) = point.roll.0.sin_cos();
+    let (sp, cp) = point.pitch.0.sin_cos();
+    let (sy, cy) = point.yaw.0.sin_cos();
+    [
+        [cp * cy, sr * sp * cy - cr * sy, cr * sp * cy + sr * sy],
+        [cp * sy, sr * sp * sy + cr * cy, cr * sp * sy - sr * cy],
+        [-sp, sr * cp, cr * cp],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Radians;
+
+    fn assert_close(expected: [f64; 3], actual: [f64; 3]) {
+        for (e, a) in expected.iter().zip(actual) {
+            assert!((e - a).abs() < 1e-9, "{expected:?} != {actual:?}");
+        }
+    }
+
+    #[test]
+    fn level() {
+        let point = Point::default();
+        assert_close([1.0, 2.0, 3.0], body_to_ned(&point, [1.0, 2.0, 3.0]));
+        assert_close([2.0, 1.0, -3.0], body_to_enu(&point, [1.0, 2.0, 3.0]));
+    }
+
+    #[test]
+    fn roll_and_pitch() {
+        let point = Point {
+            roll: Radians::from_degrees(90.0),
+            ..Default::default()
+        };
+        assert_close([0.0, 0.0, 1.0], body_to_ned(&point, [0.0, 1.0, 0.0]));
+        let point = Point {
+            pitch: Radians::from_degrees(90.0),
+            ..Default::default()
+        };
+        assert_close([0.0, 0.0, -1.0], body_to_ned(&point, [1.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let point = Point {
+            roll: Radians::from_degrees(3.0),
+            pitch: Radians::from_degrees(-5.0),
+            yaw: Radians::from_degrees(217.0),
+            ..Default::default()
+        };
+        let body = [1.5, -0.2, 0.7];
+        assert_close(body, ned_to_body(&point, body_to_ned(&point, body)));
+        assert_close(body, enu_to_body(&point, body_to_enu(&point, body)));
+    }
+
+    #[test]
+    fn euler_rates() {
+        assert_close(
+            [1.0, 2.0, 3.0],
+            euler_rates_to_body(&Point::default(), [1.0, 2.0, 3.0]),
+        );
+        let point = Point {
+            pitch: Radians::from_degrees(90.0),
+            ..Default::default()
+        };
+        assert_close(
+            [-1.0, 0.0, 0.0],
+            euler_rates_to_body(&point, [0.0, 0.0, 1.0]),
+        );
+    }
+}
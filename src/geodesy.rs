@@ -0,0 +1,207 @@
+//! Geodetic calculations on the WGS84 ellipsoid.
+
+use crate::point::Point;
+use crate::units::Radians;
+
+/// The WGS84 semi-major axis, in meters.
+pub const WGS84_A: f64 = 6378137.0;
+
+/// The WGS84 flattening.
+pub const WGS84_F: f64 = 1.0 / 298.257223563;
+
+/// The WGS84 first eccentricity squared.
+pub const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+
+/// Converts geodetic coordinates to earth-centered, earth-fixed coordinates.
+///
+/// # Examples
+///
+/// ```
+/// use pos::geodesy;
+/// use pos::Radians;
+/// let ecef = geodesy::to_ecef(Radians(0.0), Radians(0.0), 0.0);
+/// assert_eq!([geodesy::WGS84_A, 0.0, 0.0], ecef);
+/// ```
+pub fn to_ecef(latitude: Radians<f64>, longitude: Radians<f64>, height: f64) -> [f64; 3] {
+    let (sin_lat, cos_lat) = latitude.0.sin_cos();
+    let (sin_lon, cos_lon) = longitude.0.sin_cos();
+    let n = WGS84_A / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+    [
+        (n + height) * cos_lat * cos_lon,
+        (n + height) * cos_lat * sin_lon,
+        (n * (1.0 - WGS84_E2) + height) * sin_lat,
+    ]
+}
+
+/// Converts earth-centered, earth-fixed coordinates to geodetic latitude, longitude, and height.
+///
+/// # Examples
+///
+/// ```
+/// use pos::geodesy;
+/// use pos::Radians;
+/// let ecef = geodesy::to_ecef(Radians::from_degrees(40.0), Radians::from_degrees(-105.0), 1600.0);
+/// let (latitude, longitude, height) = geodesy::from_ecef(ecef);
+/// assert!((latitude.to_degrees() - 40.0).abs() < 1e-9);
+/// assert!((longitude.to_degrees() + 105.0).abs() < 1e-9);
+/// assert!((height - 1600.0).abs() < 1e-6);
+/// ```
+pub fn from_ecef(ecef: [f64; 3]) -> (Radians<f64>, Radians<f64>, f64) {
+    let [x, y, z] = ecef;
+    let longitude = y.atan2(x);
+    let p = x.hypot(y);
+    let mut latitude = z.atan2(p * (1.0 - WGS84_E2));
+    let mut height = 0.0;
+    for _ in 0..10 {
+        let sin_lat = latitude.sin();
+        let n = WGS84_A / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+        height = if latitude.cos().abs() > 1e-10 {
+            p / latitude.cos() - n
+        } else {
+            z.abs() - n * (1.0 - WGS84_E2)
+        };
+        latitude = z.atan2(p * (1.0 - WGS84_E2 * n / (n + height)));
+    }
+    (Radians(latitude), Radians(longitude), height)
+}
+
+/// Returns the east, north, and up offsets of a point from an origin, in meters.
+///
+/// # Examples
+///
+/// ```
+/// use pos::geodesy;
+/// use pos::Point;
+/// let origin = Point::default();
+/// let point = Point { altitude: 10.0, ..Default::default() };
+/// let enu = geodesy::enu(&origin, &point);
+/// assert!((enu[2] - 10.0).abs() < 1e-9);
+/// ```
+pub fn enu(origin: &Point, point: &Point) -> [f64; 3] {
+    let o = to_ecef(origin.latitude, origin.longitude, origin.altitude);
+    let p = to_ecef(point.latitude, point.longitude, point.altitude);
+    let d = [p[0] - o[0], p[1] - o[1], p[2] - o[2]];
+    let (sin_lat, cos_lat) = origin.latitude.0.sin_cos();
+    let (sin_lon, cos_lon) = origin.longitude.0.sin_cos();
+    [
+        -sin_lon * d[0] + cos_lon * d[1],
+        -sin_lat * cos_lon * d[0] - sin_lat * sin_lon * d[1] + cos_lat * d[2],
+        cos_lat * cos_lon * d[0] + cos_lat * sin_lon * d[1] + sin_lat * d[2],
+    ]
+}
+
+/// Returns the geodetic latitude, longitude, and height of an east, north, and up offset from
+/// an origin.
+///
+/// This is the inverse of [enu].
+///
+/// # Examples
+///
+/// ```
+/// use pos::geodesy;
+/// use pos::Point;
+/// let origin = Point::default();
+/// let (_, _, height) = geodesy::from_enu(&origin, [0.0, 0.0, 10.0]);
+/// assert!((height - 10.0).abs() < 1e-6);
+/// ```
+pub fn from_enu(origin: &Point, enu: [f64; 3]) -> (Radians<f64>, Radians<f64>, f64) {
+    let [e, n, u] = enu;
+    let o = to_ecef(origin.latitude, origin.longitude, origin.altitude);
+    let (sin_lat, cos_lat) = origin.latitude.0.sin_cos();
+    let (sin_lon, cos_lon) = origin.longitude.0.sin_cos();
+    from_ecef([
+        o[0] - sin_lon * e - sin_lat * cos_lon * n + cos_lat * cos_lon * u,
+        o[1] + cos_lon * e - sin_lat * sin_lon * n + cos_lat * sin_lon * u,
+        o[2] + cos_lat * n + sin_lat * u,
+    ])
+}
+
+/// Returns the straight-line distance between two points, in meters.
+///
+/// # Examples
+///
+/// ```
+/// use pos::geodesy;
+/// use pos::Point;
+/// let a = Point::default();
+/// let b = Point { altitude: 10.0, ..Default::default() };
+/// assert!((geodesy::distance(&a, &b) - 10.0).abs() < 1e-9);
+/// ```
+pub fn distance(a: &Point, b: &Point) -> f64 {
+    let a = to_ecef(a.latitude, a.longitude, a.altitude);
+    let b = to_ecef(b.latitude, b.longitude, b.altitude);
+    ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2) + (b[2] - a[2]).powi(2)).sqrt()
+}
+
+/// Returns the horizontal distance between two points, in meters.
+///
+/// This is the straight-line distance with both points at the first point's altitude, so it's
+/// only meaningful for points that are close together, e.g. consecutive trajectory points.
+///
+/// # Examples
+///
+/// ```
+/// use pos::geodesy;
+/// use pos::Point;
+/// let a = Point::default();
+/// let b = Point { altitude: 10.0, ..Default::default() };
+/// assert_eq!(0.0, geodesy::horizontal_distance(&a, &b));
+/// ```
+pub fn horizontal_distance(a: &Point, b: &Point) -> f64 {
+    distance(
+        a,
+        &Point {
+            altitude: a.altitude,
+            ..*b
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ecef_pole() {
+        let ecef = to_ecef(Radians::from_degrees(90.0), Radians(0.0), 0.0);
+        assert!(ecef[0].abs() < 1e-6);
+        assert!((ecef[2] - 6356752.314245).abs() < 1e-6);
+    }
+
+    #[test]
+    fn enu_offsets() {
+        let origin = Point {
+            latitude: Radians::from_degrees(40.0),
+            longitude: Radians::from_degrees(-105.0),
+            ..Default::default()
+        };
+        let north = Point {
+            latitude: Radians::from_degrees(40.001),
+            ..origin
+        };
+        let enu = enu(&origin, &north);
+        assert!(enu[0].abs() < 1e-6);
+        assert!((enu[1] - 111.0).abs() < 0.1, "{}", enu[1]);
+    }
+
+    #[test]
+    fn enu_roundtrip() {
+        let origin = Point {
+            latitude: Radians::from_degrees(-33.0),
+            longitude: Radians::from_degrees(151.0),
+            altitude: 50.0,
+            ..Default::default()
+        };
+        let (latitude, longitude, altitude) = from_enu(&origin, [120.0, -340.0, 25.0]);
+        let point = Point {
+            latitude,
+            longitude,
+            altitude,
+            ..Default::default()
+        };
+        let enu = enu(&origin, &point);
+        assert!((enu[0] - 120.0).abs() < 1e-6);
+        assert!((enu[1] + 340.0).abs() < 1e-6);
+        assert!((enu[2] - 25.0).abs() < 1e-6);
+    }
+}
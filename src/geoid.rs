@@ -0,0 +1,257 @@
+//! Geoid models, for converting ellipsoidal heights to orthometric heights.
+//!
+//! sbet and pof files store altitudes above the WGS84 ellipsoid. Most deliverables want heights
+//! above the geoid instead, which means subtracting the geoid undulation at each point.
+//! Undulations are read from NGA's ASCII grid format, which is used for e.g. the EGM96
+//! `WW15MGH.GRD` and EGM2008 grids.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// A geoid undulation grid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Geoid {
+    /// The southernmost latitude of the grid, in degrees.
+    pub south: f64,
+
+    /// The northernmost latitude of the grid, in degrees.
+    pub north: f64,
+
+    /// The westernmost longitude of the grid, in degrees.
+    pub west: f64,
+
+    /// The easternmost longitude of the grid, in degrees.
+    pub east: f64,
+
+    /// The latitude spacing of the grid, in degrees.
+    pub dlat: f64,
+
+    /// The longitude spacing of the grid, in degrees.
+    pub dlon: f64,
+
+    rows: usize,
+    cols: usize,
+    values: Vec<f64>,
+}
+
+impl Geoid {
+    /// Reads a geoid grid from a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::geoid::Geoid;
+    /// let geoid = Geoid::from_path("WW15MGH.GRD").unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Geoid, Error> {
+        Geoid::new(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a geoid grid from a reader.
+    ///
+    /// The first six values are the south, north, west, and east bounds followed by the latitude
+    /// and longitude spacing, all in degrees. The undulations follow in rows from north to
+    /// south, with each row running from west to east.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::geoid::Geoid;
+    /// let grid = "0 1 0 1 1 1\n1 2\n3 4\n";
+    /// let geoid = Geoid::new(grid.as_bytes()).unwrap();
+    /// ```
+    pub fn new<R: BufRead>(mut reader: R) -> Result<Geoid, Error> {
+        let mut text = String::new();
+        let _ = reader.read_to_string(&mut text)?;
+        let mut values = text
+            .split_whitespace()
+            .map(|s| s.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() < 6 {
+            return Err(Error::InvalidGeoid("missing header".to_string()));
+        }
+        let header: Vec<_> = values.drain(0..6).collect();
+        let (south, north, west, east, dlat, dlon) = (
+            header[0], header[1], header[2], header[3], header[4], header[5],
+        );
+        if dlat <= 0.0 || dlon <= 0.0 || north < south || east < west {
+            return Err(Error::InvalidGeoid(format!("invalid header: {:?}", header)));
+        }
+        let rows = ((north - south) / dlat).round() as usize + 1;
+        let cols = ((east - west) / dlon).round() as usize + 1;
+        if values.len() != rows * cols {
+            return Err(Error::InvalidGeoid(format!(
+                "expected {} values, found {}",
+                rows * cols,
+                values.len()
+            )));
+        }
+        Ok(Geoid {
+            south,
+            north,
+            west,
+            east,
+            dlat,
+            dlon,
+            rows,
+            cols,
+            values,
+        })
+    }
+
+    /// Returns the geoid undulation at the given location, bilinearly interpolated.
+    ///
+    /// Returns `None` if the location is outside of the grid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::geoid::Geoid;
+    /// use pos::Radians;
+    /// let geoid = Geoid::new("0 1 0 1 1 1\n1 2\n3 4\n".as_bytes()).unwrap();
+    /// let undulation = geoid
+    ///     .undulation(Radians::from_degrees(0.5), Radians::from_degrees(0.5))
+    ///     .unwrap();
+    /// assert_eq!(2.5, undulation);
+    /// ```
+    pub fn undulation(&self, latitude: Radians<f64>, longitude: Radians<f64>) -> Option<f64> {
+        let latitude = latitude.to_degrees();
+        let mut longitude = longitude.to_degrees();
+        while longitude < self.west {
+            longitude += 360.0;
+        }
+        while longitude > self.east {
+            longitude -= 360.0;
+        }
+        if latitude < self.south || latitude > self.north || longitude < self.west {
+            return None;
+        }
+        let row = (self.north - latitude) / self.dlat;
+        let col = (longitude - self.west) / self.dlon;
+        let r0 = (row.floor() as usize).min(self.rows - 1);
+        let c0 = (col.floor() as usize).min(self.cols - 1);
+        let r1 = (r0 + 1).min(self.rows - 1);
+        let c1 = (c0 + 1).min(self.cols - 1);
+        let fr = row - r0 as f64;
+        let fc = col - c0 as f64;
+        let value = |r: usize, c: usize| self.values[r * self.cols + c];
+        let top = value(r0, c0) + fc * (value(r0, c1) - value(r0, c0));
+        let bottom = value(r1, c0) + fc * (value(r1, c1) - value(r1, c0));
+        Some(top + fr * (bottom - top))
+    }
+
+    /// Converts an ellipsoidal height to an orthometric height.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::geoid::Geoid;
+    /// use pos::Point;
+    /// let geoid = Geoid::new("0 1 0 1 1 1\n1 2\n3 4\n".as_bytes()).unwrap();
+    /// let point = Point { altitude: 10.0, ..Default::default() };
+    /// assert_eq!(7.0, geoid.orthometric_height(&point).unwrap());
+    /// ```
+    pub fn orthometric_height(&self, point: &Point) -> Result<f64, Error> {
+        self.undulation(point.latitude, point.longitude)
+            .map(|n| point.altitude - n)
+            .ok_or_else(|| {
+                Error::OutsideGeoid(point.latitude.to_degrees(), point.longitude.to_degrees())
+            })
+    }
+}
+
+/// A source that converts the altitudes of another source to orthometric heights.
+#[derive(Debug)]
+pub struct OrthometricSource {
+    source: Box<dyn Source>,
+    geoid: Geoid,
+}
+
+impl OrthometricSource {
+    /// Creates a new orthometric source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::geoid::{Geoid, OrthometricSource};
+    /// use pos::sbet;
+    /// let geoid = Geoid::new("-90 90 0 360 90 180\n1 1 1\n2 2 2\n3 3 3\n".as_bytes()).unwrap();
+    /// let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+    /// let source = OrthometricSource::new(Box::new(reader), geoid);
+    /// ```
+    pub fn new(source: Box<dyn Source>, geoid: Geoid) -> OrthometricSource {
+        OrthometricSource { source, geoid }
+    }
+}
+
+impl Source for OrthometricSource {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        match self.source.source()? {
+            Some(mut point) => {
+                point.altitude = self.geoid.orthometric_height(&point)?;
+                Ok(Some(point))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "sbet")]
+    use crate::sbet;
+
+    fn geoid() -> Geoid {
+        Geoid::new("-90 90 0 360 90 180\n1 1 1\n2 2 2\n3 3 3\n".as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn undulation() {
+        let geoid = geoid();
+        assert_eq!(
+            Some(2.0),
+            geoid.undulation(Radians::from_degrees(0.0), Radians::from_degrees(-45.0))
+        );
+        assert_eq!(
+            Some(1.5),
+            geoid.undulation(Radians::from_degrees(45.0), Radians::from_degrees(200.0))
+        );
+        assert_eq!(
+            Some(3.0),
+            geoid.undulation(Radians::from_degrees(-90.0), Radians::from_degrees(360.0))
+        );
+        assert_eq!(
+            None,
+            geoid.undulation(Radians::from_degrees(91.0), Radians::from_degrees(0.0))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Geoid::new("0 1 0 1 1 1\n1 2 3\n".as_bytes()).is_err());
+        assert!(Geoid::new("0 1 0".as_bytes()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "sbet")]
+    fn source() {
+        let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+        let ellipsoidal: Vec<_> = sbet::Reader::from_path("data/2-points.sbet")
+            .unwrap()
+            .into_iter()
+            .collect();
+        let source: Box<dyn Source> = Box::new(OrthometricSource::new(Box::new(reader), geoid()));
+        for (orthometric, ellipsoidal) in source.into_iter().zip(ellipsoidal) {
+            let n = geoid()
+                .undulation(ellipsoidal.latitude, ellipsoidal.longitude)
+                .unwrap();
+            assert_eq!(ellipsoidal.altitude - n, orthometric.altitude);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Heading conventions.
+//!
+//! Formats disagree on the range of yaw: some write it as a signed angle in `(-180°, 180°]` and
+//! others as a compass heading in `[0°, 360°)`. [Normalize] puts every point from a source into
+//! one [Convention], so points from different formats can be compared and exported
+//! consistently.
+//!
+//! For turn-rate analysis, smoothing, and plotting, [unwrap] and the [Unwrap] source instead
+//! remove the jumps at the ends of the range, so yaw becomes a continuous series.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+use std::f64::consts::PI;
+
+/// The range that yaw is expressed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Convention {
+    /// Yaw is in `(-180°, 180°]`.
+    #[default]
+    Signed,
+    /// Yaw is a compass heading in `[0°, 360°)`.
+    Heading,
+}
+
+impl Convention {
+    /// Expresses an angle in this convention's range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::heading::Convention;
+    /// use pos::Radians;
+    /// let yaw = Radians::from_degrees(-90.0);
+    /// assert!((Convention::Heading.normalize(yaw).to_degrees() - 270.0).abs() < 1e-9);
+    /// assert!((Convention::Signed.normalize(yaw).to_degrees() + 90.0).abs() < 1e-9);
+    /// ```
+    pub fn normalize(self, angle: Radians<f64>) -> Radians<f64> {
+        match self {
+            Convention::Signed => {
+                let yaw = angle.wrapped();
+                Radians(if yaw.0 > -PI { yaw.0 } else { PI })
+            }
+            Convention::Heading => {
+                // rem_euclid can round tiny negative angles up to exactly 2π.
+                let heading = angle.0.rem_euclid(2.0 * PI);
+                Radians(if heading < 2.0 * PI { heading } else { 0.0 })
+            }
+        }
+    }
+
+    /// Normalizes a point's attitude.
+    ///
+    /// Yaw is put in this convention's range, and roll and pitch are wrapped into
+    /// `[-180°, 180°)`.
+    pub fn apply(self, point: &Point) -> Point {
+        Point {
+            roll: point.roll.wrapped(),
+            pitch: point.pitch.wrapped(),
+            yaw: self.normalize(point.yaw),
+            ..*point
+        }
+    }
+}
+
+/// A source that normalizes the attitude of every point from another source.
+#[derive(Debug)]
+pub struct Normalize {
+    source: Box<dyn Source>,
+    convention: Convention,
+}
+
+impl Normalize {
+    /// Creates a new normalizing source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::heading::{Convention, Normalize};
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut source = Normalize::new(Box::new(reader), Convention::Heading);
+    /// let point = source.source().unwrap().unwrap();
+    /// assert!(point.yaw.0 >= 0.0);
+    /// ```
+    pub fn new(source: Box<dyn Source>, convention: Convention) -> Normalize {
+        Normalize { source, convention }
+    }
+}
+
+impl Source for Normalize {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        Ok(self
+            .source
+            .source()?
+            .map(|point| self.convention.apply(&point)))
+    }
+}
+
+/// Unwraps a series of angles into a continuous series.
+///
+/// Each angle is shifted by a multiple of 360° so it differs from the previous unwrapped angle
+/// by less than 180°. The first angle is unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use pos::heading;
+/// use pos::Radians;
+/// let yaws = [170.0, -170.0, -150.0].map(Radians::from_degrees);
+/// let unwrapped = heading::unwrap(yaws);
+/// assert!((unwrapped[1].to_degrees() - 190.0).abs() < 1e-9);
+/// assert!((unwrapped[2].to_degrees() - 210.0).abs() < 1e-9);
+/// ```
+pub fn unwrap<I: IntoIterator<Item = Radians<f64>>>(angles: I) -> Vec<Radians<f64>> {
+    let mut previous = None;
+    angles
+        .into_iter()
+        .map(|angle| {
+            let angle = unwrap_from(previous, angle);
+            previous = Some(angle);
+            angle
+        })
+        .collect()
+}
+
+fn unwrap_from(previous: Option<Radians<f64>>, angle: Radians<f64>) -> Radians<f64> {
+    match previous {
+        Some(previous) => previous + (angle - previous).wrapped(),
+        None => angle,
+    }
+}
+
+/// A source that unwraps the yaw of every point from another source into a continuous series.
+///
+/// Yaw can grow past ±180° as the platform keeps turning, so points from this source shouldn't
+/// be written to formats that expect a bounded heading without normalizing them again.
+#[derive(Debug)]
+pub struct Unwrap {
+    source: Box<dyn Source>,
+    previous: Option<Radians<f64>>,
+}
+
+impl Unwrap {
+    /// Creates a new unwrapping source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::heading::Unwrap;
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut source = Unwrap::new(Box::new(reader));
+    /// let point = source.source().unwrap().unwrap();
+    /// ```
+    pub fn new(source: Box<dyn Source>) -> Unwrap {
+        Unwrap {
+            source,
+            previous: None,
+        }
+    }
+}
+
+impl Source for Unwrap {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        Ok(self.source.source()?.map(|mut point| {
+            point.yaw = unwrap_from(self.previous, point.yaw);
+            self.previous = Some(point.yaw);
+            point
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    #[test]
+    fn normalize() {
+        let points = [-190.0, -180.0, 0.0, 180.0, 359.0, 720.0]
+            .into_iter()
+            .map(|yaw| Point {
+                yaw: Radians::from_degrees(yaw),
+                roll: Radians::from_degrees(yaw),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        for (convention, expected) in [
+            (Convention::Heading, [170.0, 180.0, 0.0, 180.0, 359.0, 0.0]),
+            (Convention::Signed, [170.0, 180.0, 0.0, 180.0, -1.0, 0.0]),
+        ] {
+            let mut source =
+                Normalize::new(Box::new(MemorySource::new(points.clone())), convention);
+            for expected in expected {
+                let point = source.source().unwrap().unwrap();
+                assert!(
+                    (point.yaw.to_degrees() - expected).abs() < 1e-9,
+                    "{:?} {} != {}",
+                    convention,
+                    point.yaw.to_degrees(),
+                    expected
+                );
+                assert!(point.roll.0 >= -PI && point.roll.0 < PI);
+            }
+        }
+    }
+
+    #[test]
+    fn unwrap_turns() {
+        // Two full turns to the right, then back a quarter turn.
+        let degrees: Vec<_> = (0..=72)
+            .map(|i| 10.0 * i as f64)
+            .chain((1..=9).map(|i| 720.0 - 10.0 * i as f64))
+            .collect();
+        let points: Vec<_> = degrees
+            .iter()
+            .map(|&yaw| Point {
+                yaw: Convention::Signed.normalize(Radians::from_degrees(yaw)),
+                ..Default::default()
+            })
+            .collect();
+        let unwrapped = unwrap(points.iter().map(|point| point.yaw));
+        let mut source = Unwrap::new(Box::new(MemorySource::new(points)));
+        for (expected, angle) in degrees.iter().zip(unwrapped) {
+            let point = source.source().unwrap().unwrap();
+            assert_eq!(angle, point.yaw);
+            assert!((angle.to_degrees() - expected).abs() < 1e-9);
+        }
+        assert!(source.source().unwrap().is_none());
+    }
+}
@@ -1,15 +1,25 @@
 //! Interpolate between two position points.
 
 use crate::point::Point;
-use crate::source::Source;
+use crate::source::{SeekableSource, Source};
+use crate::spill::Spill;
 use crate::Error;
 
 /// Structure that handles the interpolation.
+///
+/// Every point read from the source is kept so that earlier times can be queried again. By
+/// default they're kept in memory, but [Interpolator::with_spill] keeps only the most recent
+/// points in memory and spills older ones to a temporary file, and
+/// [Interpolator::with_seekable] keeps a bounded window of points and seeks the source to go
+/// back before it.
 #[derive(Debug)]
 pub struct Interpolator {
     index: usize,
-    source: Box<dyn Source>,
+    source: Input,
     points: Vec<Point>,
+    offset: usize,
+    start_time: f64,
+    spill: Option<Spill>,
 }
 
 impl Interpolator {
@@ -23,7 +33,12 @@ impl Interpolator {
     /// let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
     /// let interpolator = Interpolator::new(Box::new(reader)).unwrap();
     /// ```
-    pub fn new(mut source: Box<dyn Source>) -> Result<Interpolator, Error> {
+    pub fn new(source: Box<dyn Source>) -> Result<Interpolator, Error> {
+        Interpolator::from_input(Input::Source(source))
+    }
+
+    #[allow(clippy::useless_conversion)]
+    fn from_input(mut source: Input) -> Result<Interpolator, Error> {
         let mut points = Vec::with_capacity(2);
         for _ in 0..2 {
             points.push(match source.source()? {
@@ -34,12 +49,72 @@ impl Interpolator {
             });
         }
         Ok(Interpolator {
+            start_time: points[0].time,
             points,
             source,
             index: 1,
+            offset: 0,
+            spill: None,
         })
     }
 
+    /// Creates a new interpolator that spills older points to a temporary file.
+    ///
+    /// Points are spilled in segments of `segment_size` points, and at most two segments are
+    /// held in memory: the most recent points, and the last segment read back from the file.
+    /// Queries that go backwards jump straight to the right segment, so nearly-sorted queries
+    /// stay fast. The file is deleted when the interpolator is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::interpolate::Interpolator;
+    /// use pos::sbet;
+    /// let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+    /// let interpolator = Interpolator::with_spill(Box::new(reader), 100_000).unwrap();
+    /// ```
+    pub fn with_spill(source: Box<dyn Source>, segment_size: usize) -> Result<Interpolator, Error> {
+        let mut interpolator = Interpolator::new(source)?;
+        interpolator.spill = Some(Spill::new(segment_size.max(1))?);
+        Ok(interpolator)
+    }
+
+    /// Creates a new interpolator that keeps at most `capacity` points in memory, and seeks the
+    /// source when a query goes back before them.
+    ///
+    /// Going back costs a seek and a re-read, so this suits sources that can seek cheaply, such
+    /// as sbet and pof readers, and queries that are mostly sorted. The capacity is at least two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::interpolate::Interpolator;
+    /// use pos::sbet;
+    /// let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+    /// let interpolator = Interpolator::with_seekable(Box::new(reader), 100_000).unwrap();
+    /// ```
+    pub fn with_seekable(
+        source: Box<dyn SeekableSource>,
+        capacity: usize,
+    ) -> Result<Interpolator, Error> {
+        Interpolator::from_input(Input::Seekable(source, capacity.max(2)))
+    }
+
+    /// Returns the time of the first point from the source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::interpolate::Interpolator;
+    /// use pos::sbet;
+    /// let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+    /// let interpolator = Interpolator::new(Box::new(reader)).unwrap();
+    /// assert_eq!(151631.00283607095, interpolator.start_time());
+    /// ```
+    pub fn start_time(&self) -> f64 {
+        self.start_time
+    }
+
     /// Interpolate a new point for the given time.
     ///
     /// # Examples
@@ -51,25 +126,43 @@ impl Interpolator {
     /// let mut interpolator = Interpolator::new(Box::new(reader)).unwrap();
     /// let point = interpolator.interpolate(1.516310048360710e5).unwrap();
     /// ```
+    #[allow(clippy::useless_conversion)]
     pub fn interpolate(&mut self, time: f64) -> Result<Point, Error> {
         loop {
-            assert!(self.index != 0 && self.index != self.points.len());
-            if time < self.points[self.index - 1].time {
-                if self.index == 1 {
+            assert!(self.index != 0 && self.index != self.len());
+            if time < self.get(self.index - 1)?.time {
+                if time < self.start_time {
                     return Err(Error::TimeBelowMinimum(time).into());
-                } else {
-                    self.index -= 1;
                 }
-            } else if time > self.points[self.index].time {
-                if self.index < self.points.len() - 1 {
+                if self.index == 1 {
+                    self.reload(time)?;
+                    continue;
+                }
+                self.index = match &self.spill {
+                    Some(spill) => spill
+                        .seek(time)
+                        .map_or(1, |start| start + 1)
+                        .min(self.index - 1),
+                    None => self.index - 1,
+                };
+            } else if time > self.get(self.index)?.time {
+                if self.index < self.len() - 1 {
                     self.index += 1;
                 } else {
                     match self.source.source()? {
                         Some(point) => {
-                            self.points.push(point);
+                            self.push(point)?;
                             self.index += 1;
+                            #[cfg(feature = "tracing")]
+                            tracing::trace!(
+                                time = point.time,
+                                buffered = self.points.len(),
+                                "buffered interpolation point"
+                            );
                         }
                         None => {
+                            #[cfg(feature = "tracing")]
+                            tracing::debug!(time, "time is past the end of the source");
                             return Err(Error::TimeAboveMaximum(time).into());
                         }
                     }
@@ -78,17 +171,102 @@ impl Interpolator {
                 break;
             }
         }
-        Ok(self.points[self.index - 1].interpolate(&self.points[self.index], time))
+        let before = self.get(self.index - 1)?;
+        Ok(before.interpolate(&self.get(self.index)?, time))
+    }
+
+    fn len(&self) -> usize {
+        self.offset + self.points.len()
+    }
+
+    fn get(&mut self, index: usize) -> Result<Point, Error> {
+        match self.spill.as_mut() {
+            Some(spill) if index < self.offset => spill.get(index),
+            _ => Ok(self.points[index - self.offset]),
+        }
+    }
+
+    /// Refills the window of points from a seekable source so that it starts at or before a
+    /// time.
+    ///
+    /// The source is sought back by twice the window's mean interval, doubling until a point at
+    /// or before the time is found.
+    fn reload(&mut self, time: f64) -> Result<(), Error> {
+        let source = match &mut self.source {
+            Input::Seekable(source, _) => source,
+            Input::Source(_) => return Err(Error::TimeBelowMinimum(time)),
+        };
+        let span = self.points[self.points.len() - 1].time - self.points[0].time;
+        let mut lookback = match 2.0 * span / (self.points.len() - 1) as f64 {
+            lookback if lookback > 0.0 => lookback,
+            _ => 1.0,
+        };
+        let first = loop {
+            let start = time - lookback;
+            source.seek_to_time(start)?;
+            match source.source()? {
+                Some(point) if point.time <= time => break point,
+                _ if start < self.start_time => return Err(Error::TimeBelowMinimum(time)),
+                _ => lookback *= 2.0,
+            }
+        };
+        let second = source.source()?.ok_or(Error::TimeAboveMaximum(time))?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(time, start = first.time, "reloaded interpolation points");
+        self.points.clear();
+        self.points.extend([first, second]);
+        self.index = 1;
+        Ok(())
+    }
+
+    fn push(&mut self, point: Point) -> Result<(), Error> {
+        self.points.push(point);
+        if let Input::Seekable(_, capacity) = self.source {
+            if self.points.len() > capacity {
+                let excess = self.points.len() - capacity;
+                let _ = self.points.drain(..excess);
+                self.index -= excess;
+            }
+        }
+        if let Some(spill) = self.spill.as_mut() {
+            let segment_size = spill.segment_size();
+            if self.points.len() > segment_size {
+                spill.push(&self.points[..segment_size])?;
+                let _ = self.points.drain(..segment_size);
+                self.offset = spill.len();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where an interpolator reads its points from.
+#[derive(Debug)]
+enum Input {
+    Source(Box<dyn Source>),
+    Seekable(Box<dyn SeekableSource>, usize),
+}
+
+impl Input {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        match self {
+            Input::Source(source) => source.source(),
+            Input::Seekable(source, _) => source.source(),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sbet;
+    use crate::source::MemorySource;
 
     #[test]
+    #[cfg(feature = "sbet")]
+    #[allow(clippy::excessive_precision)]
     fn interp_sbet() {
+        use crate::sbet;
+
         let mut interpolator = Interpolator::new(Box::new(
             sbet::Reader::from_path(
                 "data/2-point\
@@ -102,4 +280,57 @@ mod tests {
         assert_eq!(time, point.time);
         assert!(interpolator.interpolate(0.0).is_err());
     }
+
+    #[test]
+    fn spill() {
+        let points: Vec<_> = (0..100)
+            .map(|i| Point {
+                time: i as f64,
+                altitude: 10.0 * i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let mut interpolator =
+            Interpolator::with_spill(Box::new(MemorySource::new(points)), 8).unwrap();
+        for time in [5.5, 50.5, 48.25, 3.5, 98.5, 0.0, 99.0, 60.5] {
+            let point = interpolator.interpolate(time).unwrap();
+            assert!((10.0 * time - point.altitude).abs() < 1e-9, "{}", time);
+        }
+        assert!(interpolator.points.len() <= 8);
+        assert_eq!(0.0, interpolator.start_time());
+        assert!(matches!(
+            interpolator.interpolate(-1.0),
+            Err(Error::TimeBelowMinimum(_))
+        ));
+        assert!(matches!(
+            interpolator.interpolate(100.0),
+            Err(Error::TimeAboveMaximum(_))
+        ));
+    }
+
+    #[test]
+    fn seekable() {
+        let points: Vec<_> = (0..100)
+            .map(|i| Point {
+                time: i as f64,
+                altitude: 10.0 * i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let source = MemorySource::new(points);
+        let mut interpolator = Interpolator::with_seekable(Box::new(source), 8).unwrap();
+        for time in [5.5, 50.5, 48.25, 3.5, 98.5, 0.0, 99.0, 60.5, 0.25] {
+            let point = interpolator.interpolate(time).unwrap();
+            assert!((10.0 * time - point.altitude).abs() < 1e-9, "{}", time);
+            assert!(interpolator.points.len() <= 8);
+        }
+        assert!(matches!(
+            interpolator.interpolate(-1.0),
+            Err(Error::TimeBelowMinimum(_))
+        ));
+        assert!(matches!(
+            interpolator.interpolate(100.0),
+            Err(Error::TimeAboveMaximum(_))
+        ));
+    }
 }
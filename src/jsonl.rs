@@ -0,0 +1,111 @@
+//! Export points as [JSON Lines](https://jsonlines.org/).
+//!
+//! Each point is written as one JSON object on its own line, with the same fields as its
+//! [serde](https://serde.rs/) serialization: angles are in radians, and missing optional fields
+//! are `null`. Points are written as they arrive, so any [Source](crate::Source) can be streamed
+//! through a [Writer] with [sink::pipe](crate::sink::pipe).
+
+use crate::point::Point;
+use crate::sink::Sink;
+use crate::Error;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// A JSON Lines writer.
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    writer: W,
+}
+
+impl Writer<BufWriter<File>> {
+    /// Creates a new writer for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::jsonl::Writer;
+    /// let writer = Writer::from_path("trajectory.jsonl").unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Writer<BufWriter<File>>, std::io::Error> {
+        Ok(Writer::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new writer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::jsonl::Writer;
+    /// let writer = Writer::new(Vec::new());
+    /// ```
+    pub fn new(writer: W) -> Writer<W> {
+        Writer { writer }
+    }
+
+    /// Writes a point as one line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::jsonl::Writer;
+    /// use pos::Point;
+    /// let mut writer = Writer::new(Vec::new());
+    /// writer.write_point(&Point { time: 42.0, ..Default::default() }).unwrap();
+    /// let text = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    /// assert!(text.starts_with("{\"time\":42.0,"));
+    /// assert!(text.ends_with("}\n"));
+    /// ```
+    pub fn write_point(&mut self, point: &Point) -> Result<(), std::io::Error> {
+        serde_json::to_writer(&mut self.writer, point)?;
+        writeln!(self.writer)
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, std::io::Error> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write + Debug> Sink for Writer<W> {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        self.write_point(point).map_err(Error::from)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink;
+    use crate::source::MemorySource;
+    use crate::units::Radians;
+
+    #[test]
+    fn lines() {
+        let points: Vec<_> = (0..3)
+            .map(|i| Point {
+                time: i as f64,
+                latitude: Radians::from_degrees(45.0),
+                distance: if i == 1 { Some(10.0) } else { None },
+                ..Default::default()
+            })
+            .collect();
+        let mut writer = Writer::new(Vec::new());
+        let count = sink::pipe(&mut MemorySource::new(points.clone()), &mut writer).unwrap();
+        assert_eq!(3, count);
+        let text = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        let read: Vec<Point> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(points, read);
+    }
+}
@@ -0,0 +1,236 @@
+//! Read [KITTI](https://www.cvlibs.net/datasets/kitti/raw_data.php) OXTS packets.
+//!
+//! A KITTI recording's `oxts` directory holds a `timestamps.txt` file, with one
+//! `YYYY-MM-DD hh:mm:ss.fffffffff` timestamp per line, and a `data` directory with one text file
+//! per timestamp, each holding a single packet of 30 space-separated values.
+//!
+//! KITTI uses a forward-left-up vehicle frame and an east-referenced, counterclockwise yaw, so
+//! packets are converted to this crate's conventions: the pitch is positive nose up, the yaw is
+//! a clockwise heading from north, velocities and accelerations are east, north, and up, as from
+//! [Derive](crate::dynamics::Derive), and angular rates are roll, pitch, and yaw rates,
+//! approximated by the body rates.
+
+use crate::epoch::Epoch;
+use crate::point::{Accuracy, Point};
+use crate::source::Source;
+use crate::units::Radians;
+use crate::{Error, Location};
+use std::f64::consts::FRAC_PI_2;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::{Path, PathBuf};
+
+/// The number of values in an OXTS packet.
+pub const PACKET_LENGTH: usize = 30;
+
+/// A KITTI OXTS reader.
+#[derive(Debug)]
+pub struct Reader {
+    timestamps: Lines<BufReader<File>>,
+    packets: std::vec::IntoIter<PathBuf>,
+    epoch: Option<Epoch>,
+    record: u64,
+}
+
+impl Reader {
+    /// Opens a recording's `oxts` directory.
+    ///
+    /// Packet files are read in the order of their names, which KITTI zero-pads.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::kitti::Reader;
+    /// let reader = Reader::from_path("2011_09_26_drive_0001_sync/oxts").unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader, Error> {
+        let path = path.as_ref();
+        let timestamps = BufReader::new(File::open(path.join("timestamps.txt"))?).lines();
+        let mut packets = Vec::new();
+        for entry in std::fs::read_dir(path.join("data"))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "txt") {
+                packets.push(path);
+            }
+        }
+        packets.sort();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(packets = packets.len(), "opened kitti oxts directory");
+        Ok(Reader {
+            timestamps,
+            packets: packets.into_iter(),
+            epoch: None,
+            record: 0,
+        })
+    }
+
+    /// Returns the date of the first timestamp, which point times are relative to.
+    ///
+    /// This is `None` until the first point has been read.
+    pub fn epoch(&self) -> Option<Epoch> {
+        self.epoch
+    }
+
+    /// Reads the next packet.
+    ///
+    /// Point times are seconds since midnight UTC on the [epoch](Reader::epoch). Reading stops
+    /// at the end of the timestamps or of the packets, whichever comes first.
+    pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
+        let (timestamp, path) = match (self.timestamps.next(), self.packets.next()) {
+            (Some(timestamp), Some(path)) => (timestamp?, path),
+            _ => return Ok(None),
+        };
+        self.record += 1;
+        let located = |err: Error| err.at(Location::from_path(&path));
+        let (epoch, seconds) = parse_timestamp(&timestamp).map_err(located)?;
+        let first = *self.epoch.get_or_insert(epoch);
+        let time = (epoch.days() - first.days()) as f64 * 86_400.0 + seconds;
+        let packet = std::fs::read_to_string(&path)?;
+        parse_packet(&packet, time).map(Some).map_err(located)
+    }
+}
+
+impl Iterator for Reader {
+    type Item = Point;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_point().unwrap()
+    }
+}
+
+impl Source for Reader {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let record = self.record;
+        let result = self
+            .read_point()
+            .map_err(|err| err.at(Location::default().with_record(record)));
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read kitti oxts packet");
+        }
+        result
+    }
+}
+
+/// Parses one OXTS packet into a point at a time.
+///
+/// The position accuracy becomes the horizontal accuracy. The other accuracies and the satellite
+/// count aren't kept.
+///
+/// # Examples
+///
+/// ```
+/// use pos::kitti;
+/// let packet = "49.015 8.434 116.4 0.03 -0.01 1.5708 0 1 0 0 0 0 0 9.8 0 0 9.8 0 0 0 0 0 0 0.5 0.02 4 10 4 4 6";
+/// let point = kitti::parse_packet(packet, 0.0).unwrap();
+/// assert!((point.latitude_degrees() - 49.015).abs() < 1e-12);
+/// assert!(point.yaw.0.abs() < 1e-4);
+/// ```
+pub fn parse_packet(packet: &str, time: f64) -> Result<Point, Error> {
+    let values = packet
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<f64>, _>>()?;
+    if values.len() != PACKET_LENGTH {
+        return Err(Error::KittiPacket(values.len()));
+    }
+    let yaw = values[5];
+    let (sin, cos) = yaw.sin_cos();
+    let (forward, left) = (values[14], values[15]);
+    Ok(Point {
+        time,
+        latitude: Radians::from_degrees(values[0]),
+        longitude: Radians::from_degrees(values[1]),
+        altitude: values[2],
+        roll: Radians(values[3]),
+        pitch: Radians(-values[4]),
+        yaw: Radians(FRAC_PI_2 - yaw),
+        x_velocity: Some(values[7]),
+        y_velocity: Some(values[6]),
+        z_velocity: Some(values[10]),
+        x_acceleration: Some(forward * cos - left * sin),
+        y_acceleration: Some(forward * sin + left * cos),
+        z_acceleration: Some(values[16]),
+        x_angular_rate: Some(Radians(values[17])),
+        y_angular_rate: Some(Radians(-values[18])),
+        z_angular_rate: Some(Radians(-values[19])),
+        accuracy: Some(Accuracy {
+            time,
+            x: values[23],
+            y: values[23],
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+/// Parses a `YYYY-MM-DD hh:mm:ss.fffffffff` timestamp into a date and seconds of the day.
+fn parse_timestamp(timestamp: &str) -> Result<(Epoch, f64), Error> {
+    let invalid = || Error::KittiTimestamp(timestamp.to_string());
+    let (date, time) = timestamp.trim().split_once(' ').ok_or_else(invalid)?;
+    let date: Vec<&str> = date.split('-').collect();
+    let time: Vec<&str> = time.split(':').collect();
+    if date.len() != 3 || time.len() != 3 {
+        return Err(invalid());
+    }
+    let epoch = Epoch::new(
+        date[0].parse().map_err(|_| invalid())?,
+        date[1].parse().map_err(|_| invalid())?,
+        date[2].parse().map_err(|_| invalid())?,
+    );
+    let hours: f64 = time[0].parse()?;
+    let minutes: f64 = time[1].parse()?;
+    let seconds: f64 = time[2].parse()?;
+    Ok((epoch, hours * 3600.0 + minutes * 60.0 + seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKET: &str = "49.015 8.434 116.4 0.03 -0.01 0 1 2 0 0 0.5 0 0 0 1 0 9.8 0.1 0.2 0.3 0 0 0 0.5 0.02 4 10 4 4 6";
+
+    #[test]
+    fn packet() {
+        let point = parse_packet(PACKET, 1.0).unwrap();
+        assert!((point.yaw.to_degrees() - 90.0).abs() < 1e-12);
+        assert_eq!(0.01, point.pitch.0);
+        assert_eq!((Some(2.0), Some(1.0)), (point.x_velocity, point.y_velocity));
+        assert_eq!(Some(0.5), point.z_velocity);
+        assert_eq!(Some(1.0), point.x_acceleration);
+        assert_eq!(Some(Radians(-0.3)), point.z_angular_rate);
+        assert_eq!(0.5, point.accuracy.unwrap().x);
+        assert!(matches!(
+            parse_packet("1 2 3", 0.0),
+            Err(Error::KittiPacket(3))
+        ));
+    }
+
+    #[test]
+    fn directory() {
+        let path = std::env::temp_dir().join("pos-rs-kitti-oxts");
+        let data = path.join("data");
+        std::fs::create_dir_all(&data).unwrap();
+        std::fs::write(
+            path.join("timestamps.txt"),
+            "2011-09-26 23:59:59.500000000\n2011-09-27 00:00:00.500000000\n",
+        )
+        .unwrap();
+        for i in 0..2 {
+            std::fs::write(data.join(format!("{:010}.txt", i)), PACKET).unwrap();
+        }
+        let mut reader = Reader::from_path(&path).unwrap();
+        let times: Vec<_> = reader.by_ref().map(|point| point.time).collect();
+        assert_eq!(vec![86_399.5, 86_400.5], times);
+        assert_eq!(Some(Epoch::new(2011, 9, 26)), reader.epoch());
+        std::fs::remove_dir_all(&path).unwrap();
+
+        assert!(matches!(
+            parse_timestamp("2011-09-26T00:00:00"),
+            Err(Error::KittiTimestamp(_))
+        ));
+    }
+}
@@ -0,0 +1,311 @@
+//! Export flightlines to [KML](https://developers.google.com/kml), for viewing in Google Earth.
+//!
+//! A trajectory is written as a single placemark, either as a `gx:Track`, which Google Earth can
+//! animate with its time slider, or as a plain `LineString`. KMZ files are the same document,
+//! zipped.
+
+use crate::epoch::Epoch;
+use crate::point::Point;
+use crate::Error;
+use std::io::Write;
+
+const LOCAL_FILE_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+/// How a flightline's altitudes are interpreted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AltitudeMode {
+    /// Altitudes are heights above sea level.
+    #[default]
+    Absolute,
+    /// Altitudes are ignored and the line is draped on the terrain.
+    ClampToGround,
+}
+
+/// The geometry of a flightline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Geometry {
+    /// A `gx:Track`, with a timestamp and attitude for every point. Point times are seconds
+    /// since the epoch.
+    Track(Epoch),
+    /// A `LineString`, with only coordinates.
+    LineString,
+}
+
+/// Options for writing KML.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// The name of the flightline.
+    pub name: String,
+
+    /// The geometry of the flightline.
+    pub geometry: Geometry,
+
+    /// How altitudes are interpreted.
+    pub altitude_mode: AltitudeMode,
+}
+
+impl Options {
+    /// Creates new options for a line string with absolute altitudes and a default name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::kml::Options;
+    /// let options = Options::new();
+    /// ```
+    pub fn new() -> Options {
+        Options {
+            name: "trajectory".to_string(),
+            geometry: Geometry::LineString,
+            altitude_mode: AltitudeMode::Absolute,
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options::new()
+    }
+}
+
+impl AltitudeMode {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            AltitudeMode::Absolute => "absolute",
+            AltitudeMode::ClampToGround => "clampToGround",
+        }
+    }
+}
+
+/// Writes points as a KML document.
+///
+/// # Examples
+///
+/// ```
+/// use pos::epoch::Epoch;
+/// use pos::kml::{self, Geometry, Options};
+/// use pos::sbet::Reader;
+/// use pos::Trajectory;
+/// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// let trajectory = Trajectory::from_source(&mut reader).unwrap();
+/// let mut options = Options::new();
+/// options.geometry = Geometry::Track(Epoch::new(2015, 4, 26));
+/// let mut buffer = Vec::new();
+/// kml::write(&mut buffer, trajectory.points(), &options).unwrap();
+/// ```
+pub fn write<W: Write>(mut write: W, points: &[Point], options: &Options) -> Result<(), Error> {
+    let name = escape(&options.name);
+    writeln!(write, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        write,
+        "<kml xmlns=\"http://www.opengis.net/kml/2.2\" xmlns:gx=\"http://www.google.com/kml/ext/2.2\">"
+    )?;
+    writeln!(write, "<Document><name>{}</name>", name)?;
+    writeln!(write, "<Placemark><name>{}</name>", name)?;
+    let altitude_mode = options.altitude_mode.as_str();
+    match options.geometry {
+        Geometry::Track(epoch) => {
+            writeln!(
+                write,
+                "<gx:Track><altitudeMode>{}</altitudeMode>",
+                altitude_mode
+            )?;
+            for point in points {
+                writeln!(write, "<when>{}</when>", epoch.iso8601(point.time))?;
+            }
+            for point in points {
+                writeln!(
+                    write,
+                    "<gx:coord>{} {} {}</gx:coord>",
+                    point.longitude_degrees(),
+                    point.latitude_degrees(),
+                    point.altitude
+                )?;
+            }
+            for point in points {
+                writeln!(
+                    write,
+                    "<gx:angles>{} {} {}</gx:angles>",
+                    point.yaw.to_degrees(),
+                    point.pitch.to_degrees(),
+                    point.roll.to_degrees()
+                )?;
+            }
+            writeln!(write, "</gx:Track>")?;
+        }
+        Geometry::LineString => {
+            write!(
+                write,
+                "<LineString><tessellate>{}</tessellate><altitudeMode>{}</altitudeMode><coordinates>",
+                u8::from(options.altitude_mode == AltitudeMode::ClampToGround),
+                altitude_mode
+            )?;
+            for (i, point) in points.iter().enumerate() {
+                write!(
+                    write,
+                    "{}{},{},{}",
+                    if i == 0 { "" } else { " " },
+                    point.longitude_degrees(),
+                    point.latitude_degrees(),
+                    point.altitude
+                )?;
+            }
+            writeln!(write, "</coordinates></LineString>")?;
+        }
+    }
+    writeln!(write, "</Placemark></Document></kml>")?;
+    Ok(())
+}
+
+/// Writes points as a KMZ archive, which holds the KML document as `doc.kml`.
+///
+/// The document is stored without compression.
+///
+/// # Examples
+///
+/// ```
+/// use pos::kml::{self, Options};
+/// use pos::Point;
+/// let mut buffer = Vec::new();
+/// kml::write_kmz(&mut buffer, &[Point::default()], &Options::new()).unwrap();
+/// assert_eq!(b"PK", &buffer[..2]);
+/// ```
+pub fn write_kmz<W: Write>(mut write: W, points: &[Point], options: &Options) -> Result<(), Error> {
+    const NAME: &[u8] = b"doc.kml";
+    let mut document = Vec::new();
+    self::write(&mut document, points, options)?;
+    let crc = crc32(&document);
+    let size = document.len() as u32;
+    // Stored entries, dated 1980-01-01, as written by most zip tools for a missing timestamp.
+    let entry = |signature: u32| {
+        let mut bytes = signature.to_le_bytes().to_vec();
+        if signature == CENTRAL_DIRECTORY_SIGNATURE {
+            bytes.extend_from_slice(&20u16.to_le_bytes());
+        }
+        for value in [20u16, 0, 0, 0, 0x21] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [crc, size, size] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(NAME.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        bytes
+    };
+    let mut local = entry(LOCAL_FILE_SIGNATURE);
+    local.extend_from_slice(NAME);
+    let mut central = entry(CENTRAL_DIRECTORY_SIGNATURE);
+    for value in [0u16, 0, 0] {
+        central.extend_from_slice(&value.to_le_bytes());
+    }
+    for value in [0u32, 0] {
+        central.extend_from_slice(&value.to_le_bytes());
+    }
+    central.extend_from_slice(NAME);
+    let mut end = END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes().to_vec();
+    for value in [0u16, 0, 1, 1] {
+        end.extend_from_slice(&value.to_le_bytes());
+    }
+    end.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    end.extend_from_slice(&((local.len() + document.len()) as u32).to_le_bytes());
+    end.extend_from_slice(&0u16.to_le_bytes());
+    write.write_all(&local)?;
+    write.write_all(&document)?;
+    write.write_all(&central)?;
+    write.write_all(&end)?;
+    Ok(())
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Radians;
+
+    fn points() -> Vec<Point> {
+        (0..2)
+            .map(|i| Point {
+                time: 10.0 + i as f64,
+                longitude: Radians::from_degrees(-105.0),
+                latitude: Radians::from_degrees(40.0),
+                altitude: 1000.0,
+                yaw: Radians::from_degrees(90.0),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn line_string() {
+        let mut options = Options::new();
+        options.name = "a <b>".to_string();
+        options.altitude_mode = AltitudeMode::ClampToGround;
+        let mut buffer = Vec::new();
+        write(&mut buffer, &points(), &options).unwrap();
+        let kml = String::from_utf8(buffer).unwrap();
+        assert!(kml.contains("<name>a &lt;b&gt;</name>"));
+        assert!(kml.contains(
+            "<tessellate>1</tessellate><altitudeMode>clampToGround</altitudeMode><coordinates>-105,40,1000 -105,40,1000</coordinates>"
+        ));
+    }
+
+    #[test]
+    fn track() {
+        let mut options = Options::new();
+        options.geometry = Geometry::Track(Epoch::new(2015, 4, 26));
+        let mut buffer = Vec::new();
+        write(&mut buffer, &points(), &options).unwrap();
+        let kml = String::from_utf8(buffer).unwrap();
+        assert!(kml.contains("<altitudeMode>absolute</altitudeMode>"));
+        assert!(kml.contains("<when>2015-04-26T00:00:11.000Z</when>"));
+        assert!(kml.contains("<gx:coord>-105 40 1000</gx:coord>"));
+        assert_eq!(2, kml.matches("<gx:angles>").count());
+    }
+
+    #[test]
+    fn kmz() {
+        let mut document = Vec::new();
+        write(&mut document, &points(), &Options::new()).unwrap();
+        let mut buffer = Vec::new();
+        write_kmz(&mut buffer, &points(), &Options::new()).unwrap();
+        assert_eq!(LOCAL_FILE_SIGNATURE.to_le_bytes(), buffer[..4]);
+        assert_eq!(document[..], buffer[37..37 + document.len()]);
+        assert_eq!(crc32(&document).to_le_bytes(), buffer[14..18]);
+        let end = buffer.len() - 22;
+        assert_eq!(
+            END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes(),
+            buffer[end..end + 4]
+        );
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+    }
+}
@@ -0,0 +1,111 @@
+//! Apply body-frame lever arms to positions.
+//!
+//! A lever arm is the offset from the navigation reference point (usually the IMU) to some other
+//! point on the platform, such as a GNSS antenna or a sensor, measured in the body frame. Body
+//! coordinates are x forward, y right, and z down, as described in [frames](crate::frames).
+
+use crate::frames;
+use crate::geodesy;
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+
+/// Offsets a point's position by a body-frame lever arm, in meters.
+///
+/// Only the position changes; attitude, velocities, and accuracies are copied unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use pos::lever_arm;
+/// use pos::Point;
+/// // A sensor one meter below the IMU of a level platform.
+/// let point = lever_arm::apply(&Point::default(), [0.0, 0.0, 1.0]);
+/// assert!((point.altitude + 1.0).abs() < 1e-6);
+/// ```
+pub fn apply(point: &Point, lever_arm: [f64; 3]) -> Point {
+    let enu = frames::body_to_enu(point, lever_arm);
+    let (latitude, longitude, altitude) = geodesy::from_enu(point, enu);
+    Point {
+        latitude,
+        longitude,
+        altitude,
+        ..*point
+    }
+}
+
+/// A source that applies a lever arm to every point from another source.
+#[derive(Debug)]
+pub struct LeverArm {
+    source: Box<dyn Source>,
+    lever_arm: [f64; 3],
+}
+
+impl LeverArm {
+    /// Creates a new lever arm source from a body-frame offset, in meters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::lever_arm::LeverArm;
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut antenna = LeverArm::new(Box::new(reader), [0.1, 0.0, -1.2]);
+    /// let point = antenna.source().unwrap().unwrap();
+    /// ```
+    pub fn new(source: Box<dyn Source>, lever_arm: [f64; 3]) -> LeverArm {
+        LeverArm { source, lever_arm }
+    }
+}
+
+impl Source for LeverArm {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        Ok(self
+            .source
+            .source()?
+            .map(|point| apply(&point, self.lever_arm)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Radians;
+
+    fn assert_enu(point: &Point, lever_arm: [f64; 3], expected: [f64; 3]) {
+        let enu = geodesy::enu(point, &apply(point, lever_arm));
+        for (actual, expected) in enu.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-6, "{enu:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn level() {
+        assert_enu(&Point::default(), [1.0, 2.0, 3.0], [2.0, 1.0, -3.0]);
+    }
+
+    #[test]
+    fn yaw() {
+        let point = Point {
+            yaw: Radians::from_degrees(90.0),
+            ..Default::default()
+        };
+        assert_enu(&point, [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        assert_enu(&point, [0.0, 1.0, 0.0], [0.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn roll_and_pitch() {
+        let point = Point {
+            roll: Radians::from_degrees(90.0),
+            ..Default::default()
+        };
+        assert_enu(&point, [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]);
+        let point = Point {
+            pitch: Radians::from_degrees(90.0),
+            ..Default::default()
+        };
+        assert_enu(&point, [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+    }
+}
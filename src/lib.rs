@@ -5,6 +5,18 @@
 //! - `pos`: ASCII format
 //! - `sbet`: binary format, with optional associated `rmsmsg` accuracy file
 //! - `pof`: Riegl's binary format, with optional associated `poq` accuracy file
+//!
+//! Each format is behind a cargo feature of the same name (`pof` includes `poq`), and all of
+//! them are enabled by default. Consumers that only need one format can disable default
+//! features to skip compiling the others. Opening a file of a disabled format returns
+//! [Error::DisabledFormat].
+//!
+//! Readers and writers for other formats, such as `csv`, `nmea`, `kml`, `czml`, and `wkt`, are
+//! behind features of the same name, as is the multithreaded `batch` module. None of them are
+//! enabled by default.
+//!
+//! The `tracing` feature emits [tracing](https://docs.rs/tracing) spans and events when files
+//! are opened, headers are parsed, records fail to read, and interpolation buffers points.
 
 #![deny(
     missing_copy_implementations,
@@ -20,18 +32,108 @@
     variant_size_differences
 )]
 
+pub mod along_track;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "batch")]
+pub mod batch;
+pub mod clip;
+pub mod compare;
+pub mod convert;
+pub mod crab;
+#[cfg(feature = "proj")]
+pub mod crs;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "czml")]
+pub mod czml;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+pub mod datum;
+pub mod decimate;
+pub mod dedup;
+pub mod dynamics;
+#[cfg(feature = "eo")]
+pub mod eo;
+pub mod epoch;
 mod error;
+pub mod events;
+#[cfg(feature = "filter")]
+pub mod filter;
+pub mod format;
+pub mod frames;
+pub mod geodesy;
+pub mod geoid;
+pub mod heading;
 pub mod interpolate;
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
+#[cfg(feature = "kitti")]
+pub mod kitti;
+#[cfg(feature = "kml")]
+pub mod kml;
+pub mod lever_arm;
+pub mod merge;
+#[cfg(feature = "nmea")]
+pub mod nmea;
+pub mod offset;
+#[cfg(feature = "gdal")]
+pub mod ogr;
+pub mod outage;
+pub mod outlier;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "pof")]
 pub mod pof;
 pub mod point;
+#[cfg(feature = "pof")]
 pub mod poq;
+#[cfg(feature = "pos")]
 pub mod pos;
+pub mod pose;
+pub mod qc;
+pub mod resample;
+#[cfg(feature = "sbet")]
+pub mod rmsmsg;
+#[cfg(feature = "ros")]
+pub mod ros;
+pub mod rotate;
+#[cfg(feature = "sbet")]
 pub mod sbet;
+#[cfg(feature = "sbf")]
+pub mod sbf;
+pub mod segment;
+pub mod simplify;
+pub mod sink;
+pub mod smooth;
+#[cfg(feature = "sbet")]
+pub mod smrmsg;
 pub mod source;
+mod spill;
+pub mod stationary;
+pub mod stats;
+pub mod terrain;
+#[cfg(any(test, feature = "testutil"))]
+pub mod testutil;
+pub mod threaded;
+#[cfg(feature = "tlog")]
+pub mod tlog;
+pub mod trajectory;
+#[cfg(feature = "trj")]
+pub mod trj;
+#[cfg(feature = "ubx")]
+pub mod ubx;
 pub mod units;
+pub mod verify;
+#[cfg(feature = "wkt")]
+pub mod wkt;
 
-pub use error::Error;
+pub use error::{Error, ErrorKind, Location};
+pub use format::{read, AnyReader, Format};
 pub use interpolate::Interpolator;
 pub use point::{Accuracy, Point};
+pub use pose::{Pose, PoseProvider};
+pub use sink::{FileSink, Sink};
 pub use source::{AccuracySource, CombinedSource, FileAccuracySource, FileSource, Source};
+pub use trajectory::Trajectory;
 pub use units::Radians;
@@ -0,0 +1,196 @@
+//! Merge overlapping trajectories.
+//!
+//! Where two solutions overlap in time, such as forward and reverse processing of the same
+//! flight, each point is blended with the other solution's interpolated point using
+//! inverse-variance weights from their accuracies. Outside of the overlap, points from either
+//! trajectory are used as-is.
+
+use crate::geodesy;
+use crate::point::{Accuracy, Point};
+use crate::trajectory::Trajectory;
+use crate::units::Radians;
+
+/// Merges two trajectories into one.
+///
+/// Points of the first trajectory inside the overlap are blended with the second trajectory,
+/// and points of the second trajectory inside the overlap are dropped, so the merged
+/// trajectory has the first trajectory's sampling wherever they overlap.
+///
+/// # Examples
+///
+/// ```
+/// use pos::merge;
+/// use pos::{Point, Trajectory};
+/// let forward = Trajectory::new(vec![
+///     Point { time: 0.0, ..Default::default() },
+///     Point { time: 1.0, ..Default::default() },
+/// ]);
+/// let reverse = Trajectory::new(vec![
+///     Point { time: 0.5, ..Default::default() },
+///     Point { time: 2.0, ..Default::default() },
+/// ]);
+/// let merged = merge::merge(&forward, &reverse);
+/// let times: Vec<_> = merged.points().iter().map(|p| p.time).collect();
+/// assert_eq!(vec![0.0, 1.0, 2.0], times);
+/// ```
+pub fn merge(a: &Trajectory, b: &Trajectory) -> Trajectory {
+    let mut points = Vec::with_capacity(a.len() + b.len());
+    for point in a.points() {
+        match b.interpolate(point.time) {
+            Ok(other) => points.push(blend(point, &other)),
+            Err(_) => points.push(*point),
+        }
+    }
+    let overlaps = |time: f64| match (a.points().first(), a.points().last()) {
+        (Some(first), Some(last)) => a.len() > 1 && time >= first.time && time <= last.time,
+        _ => false,
+    };
+    points.extend(b.points().iter().filter(|point| !overlaps(point.time)));
+    Trajectory::new(points)
+}
+
+/// Blends two points at the same time, weighting each axis by the inverse of its variance.
+///
+/// Positions are weighted by the east, north, and vertical accuracies, and attitudes by the
+/// roll, pitch, and yaw accuracies. If either point has no accuracy, or an accuracy isn't
+/// positive, that axis is weighted equally. The blended accuracy is the combined standard
+/// deviation, or `None` unless both points have an accuracy. Every other field comes from the
+/// first point.
+///
+/// # Examples
+///
+/// ```
+/// use pos::merge;
+/// use pos::point::{Accuracy, Point};
+/// let accuracy = Accuracy { x: 1.0, y: 1.0, z: 1.0, ..Default::default() };
+/// let a = Point { altitude: 0.0, accuracy: Some(accuracy), ..Default::default() };
+/// let b = Point { altitude: 3.0, accuracy: Some(Accuracy { z: 0.5, ..accuracy }), ..a };
+/// let blended = merge::blend(&a, &b);
+/// assert!((blended.altitude - 2.4).abs() < 1e-6);
+/// ```
+pub fn blend(a: &Point, b: &Point) -> Point {
+    let sigmas = |accuracy: Option<Accuracy>| {
+        accuracy.map(|accuracy| {
+            [
+                accuracy.x,
+                accuracy.y,
+                accuracy.z,
+                accuracy.roll.0,
+                accuracy.pitch.0,
+                accuracy.yaw.0,
+            ]
+        })
+    };
+    let (sigmas_a, sigmas_b) = (sigmas(a.accuracy), sigmas(b.accuracy));
+    let mut weights = [0.5; 6];
+    let mut combined = [0.0; 6];
+    if let (Some(sigmas_a), Some(sigmas_b)) = (sigmas_a, sigmas_b) {
+        for i in 0..6 {
+            let (sa, sb) = (sigmas_a[i], sigmas_b[i]);
+            if sa > 0.0 && sb > 0.0 {
+                let (wa, wb) = (sa.powi(-2), sb.powi(-2));
+                weights[i] = wb / (wa + wb);
+                combined[i] = (wa + wb).powf(-0.5);
+            } else {
+                combined[i] = sa.max(sb);
+            }
+        }
+    }
+    let enu = geodesy::enu(a, b);
+    let (latitude, longitude, altitude) = geodesy::from_enu(
+        a,
+        [
+            enu[0] * weights[0],
+            enu[1] * weights[1],
+            enu[2] * weights[2],
+        ],
+    );
+    let angle =
+        |a: Radians<f64>, b: Radians<f64>, weight: f64| a + Radians((b - a).wrapped().0 * weight);
+    Point {
+        latitude,
+        longitude,
+        altitude,
+        roll: angle(a.roll, b.roll, weights[3]),
+        pitch: angle(a.pitch, b.pitch, weights[4]),
+        yaw: angle(a.yaw, b.yaw, weights[5]),
+        accuracy: a.accuracy.zip(b.accuracy).map(|(accuracy, _)| Accuracy {
+            x: combined[0],
+            y: combined[1],
+            z: combined[2],
+            roll: Radians(combined[3]),
+            pitch: Radians(combined[4]),
+            yaw: Radians(combined[5]),
+            ..accuracy
+        }),
+        ..*a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(time: f64, altitude: f64, sigma: Option<f64>) -> Point {
+        Point {
+            time,
+            altitude,
+            accuracy: sigma.map(|sigma| Accuracy {
+                time,
+                x: sigma,
+                y: sigma,
+                z: sigma,
+                roll: Radians(sigma),
+                pitch: Radians(sigma),
+                yaw: Radians(sigma),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn weighted() {
+        let blended = blend(&point(0.0, 0.0, Some(2.0)), &point(0.0, 5.0, Some(1.0)));
+        assert!((blended.altitude - 4.0).abs() < 1e-6);
+        let accuracy = blended.accuracy.unwrap();
+        assert!((accuracy.z - 0.8f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unweighted() {
+        let blended = blend(&point(0.0, 0.0, None), &point(0.0, 4.0, Some(1.0)));
+        assert!((blended.altitude - 2.0).abs() < 1e-6);
+        assert!(blended.accuracy.is_none());
+    }
+
+    #[test]
+    fn wrapped_yaw() {
+        let a = Point {
+            yaw: Radians::from_degrees(179.0),
+            ..Default::default()
+        };
+        let b = Point {
+            yaw: Radians::from_degrees(-179.0),
+            ..Default::default()
+        };
+        let yaw = blend(&a, &b).yaw.wrapped().to_degrees().abs();
+        assert!((yaw - 180.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn overlap() {
+        let a = Trajectory::new(vec![point(0.0, 0.0, None), point(2.0, 0.0, None)]);
+        let b = Trajectory::new(vec![
+            point(1.0, 2.0, None),
+            point(2.0, 2.0, None),
+            point(3.0, 2.0, None),
+        ]);
+        let merged = merge(&a, &b);
+        let times: Vec<_> = merged.points().iter().map(|p| p.time).collect();
+        assert_eq!(vec![0.0, 2.0, 3.0], times);
+        assert!(merged.points()[0].altitude.abs() < 1e-6);
+        assert!((merged.points()[1].altitude - 1.0).abs() < 1e-6);
+        assert!((merged.points()[2].altitude - 2.0).abs() < 1e-6);
+    }
+}
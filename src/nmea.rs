@@ -0,0 +1,379 @@
+//! Read [NMEA 0183](https://en.wikipedia.org/wiki/NMEA_0183) logs.
+//!
+//! GGA, RMC, and HDT sentences are combined into points, one per epoch: GGA sentences give the
+//! position, height, and fix quality, RMC sentences give the position and the velocity over
+//! ground, and HDT sentences give the heading. Sentences from any talker are accepted, and other
+//! sentences, sentences without a fix, and sentences with a bad checksum are skipped.
+//!
+//! NMEA sentences only record the time of day, so point times are seconds since the midnight
+//! before the first sentence, and keep increasing past later midnights.
+
+use crate::point::{FixQuality, Point};
+use crate::source::Source;
+use crate::units::Radians;
+use crate::{Error, Location};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// The number of seconds in a day.
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Meters per second in one knot.
+const METERS_PER_SECOND_PER_KNOT: f64 = 1852.0 / 3600.0;
+
+/// An NMEA reader.
+#[derive(Debug)]
+pub struct Reader<R: BufRead> {
+    reader: R,
+    pending: Option<Point>,
+    gga: bool,
+    heading: Option<Radians<f64>>,
+    day: f64,
+    last_time: Option<f64>,
+    record: u64,
+    path: Option<PathBuf>,
+}
+
+impl Reader<BufReader<File>> {
+    /// Creates a new reader from a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::nmea::Reader;
+    /// let reader = Reader::from_path("gnss.nmea").unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, std::io::Error> {
+        let path = path.as_ref();
+        let mut reader = Reader::new(BufReader::new(File::open(path)?));
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a new reader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::nmea::Reader;
+    /// let reader = Reader::new(std::io::empty());
+    /// ```
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            pending: None,
+            gga: false,
+            heading: None,
+            day: 0.0,
+            last_time: None,
+            record: 0,
+            path: None,
+        }
+    }
+
+    /// Reads the next epoch's point.
+    ///
+    /// Altitudes are ellipsoidal heights, the GGA altitude plus the geoid separation. Roll and
+    /// pitch are zero, and so is the yaw if there's no HDT sentence for the epoch. A heading
+    /// that comes before any fix is used for the next one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::nmea::Reader;
+    /// let bytes = b"$GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,*76
+    /// $HEHDT,274.07,T*19
+    /// ";
+    /// let mut reader = Reader::new(&bytes[..]);
+    /// let point = reader.read_point().unwrap().unwrap();
+    /// assert_eq!(9.0 * 3600.0 + 27.0 * 60.0 + 50.0, point.time);
+    /// assert!((point.latitude_degrees() - 53.36134).abs() < 1e-5);
+    /// assert!((point.altitude - 116.9).abs() < 1e-9);
+    /// assert!((point.yaw_degrees() - 274.07).abs() < 1e-9);
+    /// assert!(reader.read_point().unwrap().is_none());
+    /// ```
+    pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(self.pending.take());
+            }
+            let fields = match fields(line.trim()) {
+                Some(fields) => fields,
+                None => continue,
+            };
+            let kind = fields[0]
+                .get(fields[0].len().saturating_sub(3)..)
+                .unwrap_or_default();
+            let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+            let point = match kind {
+                "GGA" => {
+                    let time = match time_of_day(field(1))? {
+                        Some(time_of_day) => self.time(time_of_day),
+                        None => continue,
+                    };
+                    let done = self.advance(time);
+                    let quality = field(6).parse().ok().and_then(FixQuality::from_nmea);
+                    if let (Some(latitude), Some(longitude), Some(altitude), Some(_)) = (
+                        angle(field(2), field(3))?,
+                        angle(field(4), field(5))?,
+                        number(field(9))?,
+                        quality,
+                    ) {
+                        let separation = number(field(11))?.unwrap_or_default();
+                        self.gga = true;
+                        let pending = self.pending(time);
+                        pending.latitude = latitude;
+                        pending.longitude = longitude;
+                        pending.altitude = altitude + separation;
+                        pending.fix_quality = quality;
+                    }
+                    done
+                }
+                "RMC" => {
+                    let time = match time_of_day(field(1))? {
+                        Some(time_of_day) => self.time(time_of_day),
+                        None => continue,
+                    };
+                    let done = self.advance(time);
+                    if let (Some(latitude), Some(longitude), "A") = (
+                        angle(field(3), field(4))?,
+                        angle(field(5), field(6))?,
+                        field(2),
+                    ) {
+                        let speed = number(field(7))?;
+                        let course = number(field(8))?;
+                        let gga = self.gga;
+                        let pending = self.pending(time);
+                        if !gga {
+                            pending.latitude = latitude;
+                            pending.longitude = longitude;
+                        }
+                        if let (Some(speed), Some(course)) = (speed, course) {
+                            let speed = speed * METERS_PER_SECOND_PER_KNOT;
+                            let course = course.to_radians();
+                            pending.x_velocity = Some(speed * course.sin());
+                            pending.y_velocity = Some(speed * course.cos());
+                        }
+                    }
+                    done
+                }
+                "HDT" => {
+                    if let Some(heading) = number(field(1))? {
+                        let heading = Radians::from_degrees(heading);
+                        match self.pending.as_mut() {
+                            Some(pending) => pending.yaw = heading,
+                            None => self.heading = Some(heading),
+                        }
+                    }
+                    None
+                }
+                _ => None,
+            };
+            if point.is_some() {
+                return Ok(point);
+            }
+        }
+    }
+
+    /// Converts a time of day into a time, counting the midnights that have passed.
+    fn time(&mut self, time_of_day: f64) -> f64 {
+        let mut time = time_of_day + self.day;
+        if let Some(last_time) = self.last_time {
+            if time < last_time - SECONDS_PER_DAY / 2.0 {
+                self.day += SECONDS_PER_DAY;
+                time += SECONDS_PER_DAY;
+            }
+        }
+        self.last_time = Some(time);
+        time
+    }
+
+    /// Returns the previous epoch's point, if a sentence at this time starts a new epoch.
+    fn advance(&mut self, time: f64) -> Option<Point> {
+        if self.pending.is_some_and(|pending| pending.time != time) {
+            self.gga = false;
+            self.pending.take()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the point of the current epoch, starting it if needed.
+    fn pending(&mut self, time: f64) -> &mut Point {
+        let heading = &mut self.heading;
+        self.pending.get_or_insert_with(|| Point {
+            time,
+            yaw: heading.take().unwrap_or_default(),
+            ..Default::default()
+        })
+    }
+}
+
+impl<R: BufRead> IntoIterator for Reader<R> {
+    type Item = Point;
+    type IntoIter = ReaderIterator<R>;
+    fn into_iter(self) -> Self::IntoIter {
+        ReaderIterator { reader: self }
+    }
+}
+
+/// An iterator over an NMEA reader.
+#[derive(Debug)]
+pub struct ReaderIterator<R: BufRead> {
+    reader: Reader<R>,
+}
+
+impl<R: BufRead> Iterator for ReaderIterator<R> {
+    type Item = Point;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_point().unwrap()
+    }
+}
+
+impl<R: Debug + BufRead> Source for Reader<R> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(self.record),
+            })
+        });
+        if let Ok(Some(_)) = result {
+            self.record += 1;
+        }
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read nmea sentence");
+        }
+        result
+    }
+}
+
+/// Splits a sentence into its fields, starting with the address, if its checksum is valid.
+fn fields(line: &str) -> Option<Vec<&str>> {
+    let body = line.strip_prefix('$')?;
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum, 16).ok()?;
+            if body.bytes().fold(0, |checksum, byte| checksum ^ byte) != expected {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(line, "skipping nmea sentence with a bad checksum");
+                return None;
+            }
+            body
+        }
+        None => body,
+    };
+    Some(body.split(',').collect())
+}
+
+fn number(field: &str) -> Result<Option<f64>, Error> {
+    if field.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(field.parse()?))
+    }
+}
+
+/// Parses `hhmmss.ss` into seconds of the day.
+fn time_of_day(field: &str) -> Result<Option<f64>, Error> {
+    if field.len() < 6 || !field.is_ascii() {
+        return Ok(None);
+    }
+    let hours: f64 = field[..2].parse()?;
+    let minutes: f64 = field[2..4].parse()?;
+    let seconds: f64 = field[4..].parse()?;
+    Ok(Some(hours * 3600.0 + minutes * 60.0 + seconds))
+}
+
+/// Parses a `dddmm.mmmm` angle and its hemisphere.
+fn angle(field: &str, hemisphere: &str) -> Result<Option<Radians<f64>>, Error> {
+    let value = match number(field)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let degrees = (value / 100.0).trunc();
+    let degrees = degrees + (value - degrees * 100.0) / 60.0;
+    Ok(match hemisphere {
+        "N" | "E" => Some(Radians::from_degrees(degrees)),
+        "S" | "W" => Some(Radians::from_degrees(-degrees)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentence(body: &str) -> String {
+        let checksum = body.bytes().fold(0, |checksum, byte| checksum ^ byte);
+        format!("${}*{:02X}\n", body, checksum)
+    }
+
+    fn gga(time: &str) -> String {
+        sentence(&format!(
+            "GNGGA,{},5321.6802,N,00630.3372,W,4,8,1.03,61.7,M,55.2,M,,",
+            time
+        ))
+    }
+
+    #[test]
+    fn epochs() {
+        let mut log = String::new();
+        log.push_str(&gga("092750.000"));
+        log.push_str(&sentence(
+            "GPRMC,092750.000,A,5321.6802,N,00630.3372,W,10.0,90.0,280511,,,A",
+        ));
+        log.push_str(&sentence("HEHDT,274.07,T"));
+        log.push_str("garbage\n");
+        log.push_str(&sentence("GPGGA,092751.000,,,,,0,0,,,M,,M,,"));
+        log.push_str(&sentence("HEHDT,10.0,T"));
+        log.push_str(&gga("092752.000").replace("*", "0*"));
+        log.push_str(&sentence(
+            "GPRMC,092752.000,A,5321.6802,S,00630.3372,E,,,280511,,,A",
+        ));
+        let points: Vec<_> = Reader::new(log.as_bytes()).into_iter().collect();
+        assert_eq!(2, points.len());
+
+        let point = points[0];
+        assert_eq!(Some(FixQuality::RtkFixed), point.fix_quality);
+        assert!((point.longitude_degrees() + 6.50562).abs() < 1e-5);
+        assert!((point.x_velocity.unwrap() - 5.144_444).abs() < 1e-6);
+        assert!(point.y_velocity.unwrap().abs() < 1e-9);
+        assert!((point.yaw_degrees() - 274.07).abs() < 1e-9);
+
+        let point = points[1];
+        assert_eq!(34_072.0, point.time);
+        assert!((point.latitude_degrees() + 53.36134).abs() < 1e-5);
+        assert!((point.yaw_degrees() - 10.0).abs() < 1e-9);
+        assert_eq!(None, point.fix_quality);
+        assert_eq!(0.0, point.altitude);
+    }
+
+    #[test]
+    fn midnight() {
+        let log = gga("235959.000") + &gga("000000.000");
+        let points: Vec<_> = Reader::new(log.as_bytes()).into_iter().collect();
+        assert_eq!(86_399.0, points[0].time);
+        assert_eq!(86_400.0, points[1].time);
+    }
+
+    #[test]
+    fn non_ascii() {
+        let log = "$aéé,1\n".to_string()
+            + "$GPGGA,aébcde,5321.6802,N,00630.3372,W,4,8,1.03,61.7,M,55.2,M,,\n"
+            + &gga("092750.000");
+        let mut reader = Reader::new(log.as_bytes());
+        assert_eq!(34_070.0, reader.read_point().unwrap().unwrap().time);
+        assert!(reader.read_point().unwrap().is_none());
+    }
+}
@@ -0,0 +1,282 @@
+//! Estimate the time offset between two trajectories.
+//!
+//! Two solutions of the same platform, such as a GNSS-only trajectory and an integrated
+//! GNSS/INS solution, can disagree on time by a constant offset if one sensor's clock isn't
+//! synchronized. [Estimator] finds that offset by cross-correlating the east, north, and up
+//! velocities of the two trajectories, which are derived from their positions so that the
+//! estimate doesn't depend on either trajectory recording velocities.
+
+use crate::geodesy;
+use crate::point::Point;
+use crate::trajectory::Trajectory;
+use crate::Error;
+
+/// The default largest offset to search for, in seconds.
+pub const DEFAULT_MAX_OFFSET: f64 = 1.0;
+
+/// The default spacing of the correlation grid, in seconds.
+pub const DEFAULT_STEP: f64 = 0.01;
+
+/// An estimated time offset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Estimate {
+    /// The offset, in seconds.
+    ///
+    /// A point in the other trajectory at time `t + offset` lines up with the reference at time
+    /// `t`, so subtract the offset from the other trajectory's times to synchronize it.
+    pub offset: f64,
+    /// The correlation of the two velocity series at the offset, from -1 to 1.
+    pub correlation: f64,
+    /// The root-mean-square distance between the two trajectories after removing the offset, in
+    /// meters.
+    pub residual: f64,
+}
+
+/// Estimates the time offset between two trajectories.
+#[derive(Clone, Copy, Debug)]
+pub struct Estimator {
+    max_offset: f64,
+    step: f64,
+}
+
+impl Estimator {
+    /// Creates a new estimator with the default maximum offset and step.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::offset::Estimator;
+    /// let estimator = Estimator::new();
+    /// ```
+    pub fn new() -> Estimator {
+        Estimator {
+            max_offset: DEFAULT_MAX_OFFSET,
+            step: DEFAULT_STEP,
+        }
+    }
+
+    /// Sets the largest offset to search for, in seconds.
+    pub fn with_max_offset(mut self, max_offset: f64) -> Estimator {
+        self.max_offset = max_offset.abs();
+        self
+    }
+
+    /// Sets the spacing of the correlation grid, in seconds.
+    ///
+    /// The offset is refined to a fraction of a step by fitting a parabola through the
+    /// correlation peak.
+    pub fn with_step(mut self, step: f64) -> Estimator {
+        self.step = step.abs();
+        self
+    }
+
+    /// Estimates the offset of `other` from `reference`.
+    ///
+    /// Returns [Error::InsufficientOverlap] if, after leaving room for the largest offset, the
+    /// trajectories don't overlap for at least three steps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::offset::Estimator;
+    /// use pos::{Point, Radians, Trajectory};
+    /// let points = |shift: f64| {
+    ///     (0..1000)
+    ///         .map(|i| {
+    ///             let time = i as f64 * 0.01;
+    ///             Point {
+    ///                 time: time + shift,
+    ///                 latitude: Radians::from_degrees(45.0 + 1e-4 * time.sin()),
+    ///                 ..Default::default()
+    ///             }
+    ///         })
+    ///         .collect::<Vec<_>>()
+    /// };
+    /// let reference = Trajectory::new(points(0.0));
+    /// let other = Trajectory::new(points(0.2));
+    /// let estimate = Estimator::new().estimate(&reference, &other).unwrap();
+    /// assert!((estimate.offset - 0.2).abs() < 0.01);
+    /// ```
+    pub fn estimate(&self, reference: &Trajectory, other: &Trajectory) -> Result<Estimate, Error> {
+        let (reference_points, other_points) = (reference.points(), other.points());
+        if reference_points.is_empty() || other_points.is_empty() {
+            return Err(Error::InsufficientOverlap(0.0));
+        }
+        let start = reference_points[0].time.max(other_points[0].time);
+        let end = reference_points[reference_points.len() - 1]
+            .time
+            .min(other_points[other_points.len() - 1].time);
+        // Leave room for the largest lag, and for the half step used to difference velocities.
+        let margin = self.max_offset + self.step;
+        let lags = (self.max_offset / self.step).round() as usize;
+        let count = if end - start > 2.0 * margin {
+            ((end - start - 2.0 * margin) / self.step) as usize + 1
+        } else {
+            0
+        };
+        if self.step <= 0.0 || count < 3 {
+            return Err(Error::InsufficientOverlap((end - start).max(0.0)));
+        }
+        let origin = reference_points[0];
+        let first = start + margin;
+        let reference_velocities = self.velocities(
+            reference,
+            &origin,
+            (0..count).map(|i| first + i as f64 * self.step),
+        )?;
+        let other_velocities = self.velocities(
+            other,
+            &origin,
+            (0..count + 2 * lags).map(|i| first + (i as f64 - lags as f64) * self.step),
+        )?;
+        let correlations: Vec<_> = (0..=2 * lags)
+            .map(|shift| {
+                correlate(
+                    &reference_velocities,
+                    &other_velocities[shift..shift + count],
+                )
+            })
+            .collect();
+        let peak = correlations
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i)
+            .unwrap_or(lags);
+        let refinement = if peak > 0 && peak < 2 * lags {
+            let (a, b, c) = (
+                correlations[peak - 1],
+                correlations[peak],
+                correlations[peak + 1],
+            );
+            let denominator = a - 2.0 * b + c;
+            if denominator < 0.0 {
+                (0.5 * (a - c) / denominator).clamp(-0.5, 0.5)
+            } else {
+                0.0
+            }
+        } else {
+            0.0
+        };
+        let offset = (peak as f64 - lags as f64 + refinement) * self.step;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            offset,
+            correlation = correlations[peak],
+            "estimated time offset"
+        );
+        let mut sum_squares = 0.0;
+        for i in 0..count {
+            let time = first + i as f64 * self.step;
+            let a = reference.interpolate(time)?;
+            let b = other.interpolate(time + offset)?;
+            sum_squares += geodesy::distance(&a, &b).powi(2);
+        }
+        Ok(Estimate {
+            offset,
+            correlation: correlations[peak],
+            residual: (sum_squares / count as f64).sqrt(),
+        })
+    }
+
+    fn velocities(
+        &self,
+        trajectory: &Trajectory,
+        origin: &Point,
+        times: impl Iterator<Item = f64>,
+    ) -> Result<Vec<[f64; 3]>, Error> {
+        let half = self.step / 2.0;
+        times
+            .map(|time| {
+                let before = geodesy::enu(origin, &trajectory.interpolate(time - half)?);
+                let after = geodesy::enu(origin, &trajectory.interpolate(time + half)?);
+                Ok([0, 1, 2].map(|i| (after[i] - before[i]) / self.step))
+            })
+            .collect()
+    }
+}
+
+impl Default for Estimator {
+    fn default() -> Estimator {
+        Estimator::new()
+    }
+}
+
+/// Returns the correlation of two multi-channel series, pooling the covariances and variances of
+/// each channel.
+fn correlate(a: &[[f64; 3]], b: &[[f64; 3]]) -> f64 {
+    let n = a.len() as f64;
+    let mut covariance = 0.0;
+    let mut variance_a = 0.0;
+    let mut variance_b = 0.0;
+    for channel in 0..3 {
+        let mean_a = a.iter().map(|v| v[channel]).sum::<f64>() / n;
+        let mean_b = b.iter().map(|v| v[channel]).sum::<f64>() / n;
+        for (a, b) in a.iter().zip(b) {
+            let (da, db) = (a[channel] - mean_a, b[channel] - mean_b);
+            covariance += da * db;
+            variance_a += da * da;
+            variance_b += db * db;
+        }
+    }
+    if variance_a > 0.0 && variance_b > 0.0 {
+        covariance / (variance_a * variance_b).sqrt()
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Radians;
+
+    fn trajectory(shift: f64) -> Trajectory {
+        Trajectory::new(
+            (0..2000)
+                .map(|i| {
+                    let time = i as f64 * 0.01;
+                    Point {
+                        time: time + shift,
+                        latitude: Radians::from_degrees(45.0 + 1e-4 * (0.7 * time).sin()),
+                        longitude: Radians::from_degrees(-105.0 + 1e-4 * (1.3 * time).cos()),
+                        altitude: 100.0 + (0.5 * time).sin(),
+                        ..Default::default()
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn shifted() {
+        let reference = trajectory(0.0);
+        for shift in [-0.333, 0.0, 0.125, 0.9] {
+            let estimate = Estimator::new()
+                .estimate(&reference, &trajectory(shift))
+                .unwrap();
+            assert!(
+                (estimate.offset - shift).abs() < 2e-3,
+                "{} != {}",
+                estimate.offset,
+                shift
+            );
+            assert!(estimate.correlation > 0.99);
+            assert!(estimate.residual < 0.05);
+        }
+    }
+
+    #[test]
+    fn insufficient_overlap() {
+        let reference = trajectory(0.0);
+        assert!(matches!(
+            Estimator::new().estimate(&reference, &trajectory(19.5)),
+            Err(Error::InsufficientOverlap(_))
+        ));
+        assert!(matches!(
+            Estimator::new().estimate(&reference, &Trajectory::new(Vec::new())),
+            Err(Error::InsufficientOverlap(_))
+        ));
+    }
+}
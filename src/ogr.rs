@@ -0,0 +1,132 @@
+//! Export to any OGR vector format, using [gdal].
+//!
+//! Each point becomes one 3D point feature in WGS84, with its time, attitude, and any
+//! available dynamics and accuracy as attributes. Angles are written in degrees.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+use gdal::spatial_ref::{AxisMappingStrategy, SpatialRef};
+use gdal::vector::{
+    Feature, Geometry, LayerAccess, LayerOptions, OGRFieldType, OGRwkbGeometryType,
+};
+use gdal::DriverManager;
+use std::path::Path;
+
+const FIELDS: [&str; 17] = [
+    "time",
+    "roll",
+    "pitch",
+    "yaw",
+    "distance",
+    "x_velocity",
+    "y_velocity",
+    "z_velocity",
+    "wander_angle",
+    "height_above_ground",
+    "heave",
+    "accuracy_x",
+    "accuracy_y",
+    "accuracy_z",
+    "accuracy_roll",
+    "accuracy_pitch",
+    "accuracy_yaw",
+];
+
+/// Writes every point from a source to a new OGR dataset, returning the number of points written.
+///
+/// The driver is the GDAL short name, e.g. `"GPKG"`, `"OpenFileGDB"`, or `"ESRI Shapefile"`.
+/// The layer is named `layer_name`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pos::ogr;
+/// use pos::sbet::Reader;
+/// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// ogr::write("trajectory.gpkg", "GPKG", "trajectory", &mut reader).unwrap();
+/// ```
+pub fn write<P: AsRef<Path>>(
+    path: P,
+    driver: &str,
+    layer_name: &str,
+    source: &mut dyn Source,
+) -> Result<u64, Error> {
+    let driver = DriverManager::get_driver_by_name(driver)?;
+    let mut dataset = driver.create_vector_only(path)?;
+    let mut srs = SpatialRef::from_epsg(4326)?;
+    srs.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    let layer = dataset.create_layer(LayerOptions {
+        name: layer_name,
+        srs: Some(&srs),
+        ty: OGRwkbGeometryType::wkbPoint25D,
+        options: None,
+    })?;
+    let fields: Vec<_> = FIELDS
+        .iter()
+        .map(|&name| (name, OGRFieldType::OFTReal))
+        .collect();
+    layer.create_defn_fields(&fields)?;
+
+    let mut count = 0;
+    while let Some(point) = source.source()? {
+        let mut geometry = Geometry::empty(OGRwkbGeometryType::wkbPoint25D)?;
+        geometry.add_point((
+            point.longitude.to_degrees(),
+            point.latitude.to_degrees(),
+            point.altitude,
+        ));
+        let mut feature = Feature::new(layer.defn())?;
+        feature.set_geometry(geometry)?;
+        for (i, value) in attributes(&point).iter().enumerate() {
+            if let Some(value) = value {
+                feature.set_field_double(i, *value)?;
+            }
+        }
+        feature.create(&layer)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn attributes(point: &Point) -> [Option<f64>; 17] {
+    let accuracy = point.accuracy;
+    [
+        Some(point.time),
+        Some(point.roll.to_degrees()),
+        Some(point.pitch.to_degrees()),
+        Some(point.yaw.to_degrees()),
+        point.distance,
+        point.x_velocity,
+        point.y_velocity,
+        point.z_velocity,
+        point.wander_angle.map(|r| r.to_degrees()),
+        point.height_above_ground,
+        point.heave,
+        accuracy.map(|a| a.x),
+        accuracy.map(|a| a.y),
+        accuracy.map(|a| a.z),
+        accuracy.map(|a| a.roll.to_degrees()),
+        accuracy.map(|a| a.pitch.to_degrees()),
+        accuracy.map(|a| a.yaw.to_degrees()),
+    ]
+}
+
+#[cfg(all(test, feature = "sbet"))]
+mod tests {
+    use super::*;
+    use crate::sbet;
+    use gdal::Dataset;
+
+    #[test]
+    fn geopackage() {
+        let path = std::env::temp_dir().join("pos-rs-ogr-test.gpkg");
+        let _ = std::fs::remove_file(&path);
+        let mut reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+        assert_eq!(2, write(&path, "GPKG", "trajectory", &mut reader).unwrap());
+        let dataset = Dataset::open(&path).unwrap();
+        let layer = dataset.layer_by_name("trajectory").unwrap();
+        assert_eq!(2, layer.feature_count());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
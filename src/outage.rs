@@ -0,0 +1,271 @@
+//! Detect GNSS outages.
+//!
+//! Outages are runs of accuracy records where the GNSS solution is degraded: the PDOP is high,
+//! too few satellites are tracked, or the position RMS spikes. They can be detected from a
+//! stream of [Accuracy] records, such as a poq file, or from the accuracies attached to the
+//! points of a source. Each [Outage] can then be used to flag or exclude the affected part of
+//! the trajectory.
+
+use crate::point::Accuracy;
+use crate::source::Source;
+use crate::Error;
+
+/// The default maximum PDOP outside of an outage.
+pub const DEFAULT_MAX_PDOP: f64 = 6.0;
+
+/// The default minimum number of satellites outside of an outage.
+pub const DEFAULT_MIN_SATELLITES: u16 = 4;
+
+/// The default maximum position RMS outside of an outage, in meters.
+pub const DEFAULT_MAX_RMS: f64 = 1.0;
+
+/// Detects GNSS outages.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Detector {
+    max_pdop: f64,
+    min_satellites: u16,
+    max_rms: f64,
+    min_duration: f64,
+}
+
+/// A GNSS outage.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outage {
+    /// The time of the first degraded record.
+    pub start: f64,
+    /// The time of the last degraded record.
+    pub end: f64,
+    /// The largest PDOP during the outage.
+    pub max_pdop: f64,
+    /// The fewest satellites during the outage, if any record counted them.
+    pub min_satellites: Option<u16>,
+    /// The largest position RMS during the outage, in meters.
+    pub max_rms: f64,
+}
+
+impl Default for Detector {
+    fn default() -> Detector {
+        Detector {
+            max_pdop: DEFAULT_MAX_PDOP,
+            min_satellites: DEFAULT_MIN_SATELLITES,
+            max_rms: DEFAULT_MAX_RMS,
+            min_duration: 0.0,
+        }
+    }
+}
+
+impl Detector {
+    /// Creates a new detector with the default thresholds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::outage::Detector;
+    /// let detector = Detector::new().max_pdop(4.0).min_satellites(6);
+    /// ```
+    pub fn new() -> Detector {
+        Detector::default()
+    }
+
+    /// Sets the largest PDOP outside of an outage.
+    pub fn max_pdop(mut self, max_pdop: f64) -> Detector {
+        self.max_pdop = max_pdop;
+        self
+    }
+
+    /// Sets the fewest satellites outside of an outage.
+    ///
+    /// Records that don't count their satellites never fail this check.
+    pub fn min_satellites(mut self, min_satellites: u16) -> Detector {
+        self.min_satellites = min_satellites;
+        self
+    }
+
+    /// Sets the largest position RMS outside of an outage, in meters.
+    ///
+    /// The position RMS is the root-sum-square of the x, y, and z accuracies.
+    pub fn max_rms(mut self, max_rms: f64) -> Detector {
+        self.max_rms = max_rms;
+        self
+    }
+
+    /// Sets the minimum duration of a reported outage, in seconds, which defaults to zero.
+    pub fn min_duration(mut self, min_duration: f64) -> Detector {
+        self.min_duration = min_duration;
+        self
+    }
+
+    /// Returns every outage in a sequence of accuracies, in time order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::outage::Detector;
+    /// use pos::point::Accuracy;
+    /// let accuracies = (0..10).map(|i| Accuracy {
+    ///     time: i as f64,
+    ///     pdop: if (3..6).contains(&i) { 10.0 } else { 1.5 },
+    ///     ..Default::default()
+    /// });
+    /// let outages = Detector::new().detect(accuracies);
+    /// assert_eq!(1, outages.len());
+    /// assert_eq!((3.0, 5.0), (outages[0].start, outages[0].end));
+    /// ```
+    pub fn detect<I: IntoIterator<Item = Accuracy>>(&self, accuracies: I) -> Vec<Outage> {
+        let mut outages = Vec::new();
+        let mut current = None;
+        for accuracy in accuracies {
+            self.add(&mut outages, &mut current, &accuracy);
+        }
+        self.finish(&mut outages, current);
+        outages
+    }
+
+    /// Returns every outage in the accuracies of a source's points, in time order.
+    ///
+    /// Points without an accuracy are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::outage::Detector;
+    /// use pos::sbet::Reader;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let outages = Detector::new().detect_source(&mut reader).unwrap();
+    /// assert!(outages.is_empty());
+    /// ```
+    pub fn detect_source(&self, source: &mut dyn Source) -> Result<Vec<Outage>, Error> {
+        let mut outages = Vec::new();
+        let mut current = None;
+        while let Some(point) = source.source()? {
+            if let Some(accuracy) = point.accuracy {
+                self.add(&mut outages, &mut current, &accuracy);
+            }
+        }
+        self.finish(&mut outages, current);
+        Ok(outages)
+    }
+
+    fn add(&self, outages: &mut Vec<Outage>, current: &mut Option<Outage>, accuracy: &Accuracy) {
+        let satellites = accuracy.satellite_count.map(|count| count.total());
+        let rms = (accuracy.x.powi(2) + accuracy.y.powi(2) + accuracy.z.powi(2)).sqrt();
+        let degraded = accuracy.pdop > self.max_pdop
+            || satellites.is_some_and(|satellites| satellites < self.min_satellites)
+            || rms > self.max_rms;
+        if degraded {
+            let outage = current.get_or_insert(Outage {
+                start: accuracy.time,
+                end: accuracy.time,
+                max_pdop: accuracy.pdop,
+                min_satellites: satellites,
+                max_rms: rms,
+            });
+            outage.end = accuracy.time;
+            outage.max_pdop = outage.max_pdop.max(accuracy.pdop);
+            outage.min_satellites = match (outage.min_satellites, satellites) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            outage.max_rms = outage.max_rms.max(rms);
+        } else if let Some(outage) = current.take() {
+            self.finish(outages, Some(outage));
+        }
+    }
+
+    fn finish(&self, outages: &mut Vec<Outage>, outage: Option<Outage>) {
+        if let Some(outage) = outage {
+            if outage.duration() >= self.min_duration {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    start = outage.start,
+                    end = outage.end,
+                    "detected GNSS outage"
+                );
+                outages.push(outage);
+            }
+        }
+    }
+}
+
+impl Outage {
+    /// Returns the duration of this outage, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+
+    /// Returns true if a time is within this outage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::outage::Outage;
+    /// let outage = Outage { start: 1.0, end: 2.0, max_pdop: 8.0, min_satellites: None, max_rms: 0.1 };
+    /// assert!(outage.contains(1.5));
+    /// assert!(!outage.contains(2.5));
+    /// ```
+    pub fn contains(&self, time: f64) -> bool {
+        time >= self.start && time <= self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::{Point, SatelliteCount};
+    use crate::source::MemorySource;
+
+    fn accuracies() -> Vec<Accuracy> {
+        (0..20)
+            .map(|i| Accuracy {
+                time: i as f64,
+                x: if i == 15 { 3.0 } else { 0.1 },
+                pdop: 1.5,
+                satellite_count: Some(SatelliteCount::Unspecified(match i {
+                    4..=7 => 3,
+                    _ => 9,
+                })),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn outages() {
+        let outages = Detector::new().detect(accuracies());
+        assert_eq!(2, outages.len());
+        assert_eq!((4.0, 7.0), (outages[0].start, outages[0].end));
+        assert_eq!(Some(3), outages[0].min_satellites);
+        assert_eq!((15.0, 15.0), (outages[1].start, outages[1].end));
+        assert!((outages[1].max_rms - 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn min_duration() {
+        let outages = Detector::new().min_duration(1.0).detect(accuracies());
+        assert_eq!(1, outages.len());
+        assert_eq!(3.0, outages[0].duration());
+    }
+
+    #[test]
+    fn source() {
+        let points: Vec<_> = accuracies()
+            .into_iter()
+            .map(|accuracy| Point {
+                time: accuracy.time,
+                accuracy: Some(accuracy),
+                ..Default::default()
+            })
+            .chain(std::iter::once(Point {
+                time: 20.0,
+                ..Default::default()
+            }))
+            .collect();
+        let outages = Detector::new()
+            .min_satellites(2)
+            .detect_source(&mut MemorySource::new(points))
+            .unwrap();
+        assert_eq!(1, outages.len());
+        assert_eq!(15.0, outages[0].start);
+    }
+}
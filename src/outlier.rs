@@ -0,0 +1,398 @@
+//! Detect and remove outliers.
+//!
+//! A single bad epoch can poison interpolation for everything around it. A [Detector] flags
+//! points that jump implausibly far from the points before them, by speed, acceleration, or
+//! attitude rate, and can also flag points whose position is far from the median of their
+//! neighbors, measured in median absolute deviations (MAD). An [OutlierFilter] applies the
+//! jump checks to a source, dropping or repairing flagged points as they stream through.
+
+use crate::geodesy;
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+use std::collections::VecDeque;
+
+/// The default number of consecutive flagged points after which the detector assumes the
+/// reference point was the outlier, and starts over from the current point.
+pub const DEFAULT_MAX_CONSECUTIVE: usize = 10;
+
+/// The factor that scales a median absolute deviation to a standard deviation, for normally
+/// distributed values.
+const MAD_SCALE: f64 = 1.4826;
+
+/// Flags implausible points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Detector {
+    max_speed: Option<f64>,
+    max_acceleration: Option<f64>,
+    max_attitude_rate: Option<Radians<f64>>,
+    mad: Option<(usize, f64)>,
+    max_consecutive: usize,
+}
+
+/// What to do with outliers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Action {
+    /// Drop outliers.
+    #[default]
+    Drop,
+    /// Replace outliers' positions and attitudes by interpolating between the good points on
+    /// either side. Outliers at the end of a source, with no good point after them, are dropped.
+    Repair,
+}
+
+/// A source that drops or repairs the outliers of another source.
+///
+/// Only the detector's jump checks are applied, since the MAD check needs the whole trajectory.
+#[derive(Debug)]
+pub struct OutlierFilter {
+    source: Box<dyn Source>,
+    detector: Detector,
+    action: Action,
+    reference: VecDeque<Point>,
+    pending: Vec<Point>,
+    ready: VecDeque<Point>,
+    consecutive: usize,
+}
+
+impl Default for Detector {
+    fn default() -> Detector {
+        Detector {
+            max_speed: None,
+            max_acceleration: None,
+            max_attitude_rate: None,
+            mad: None,
+            max_consecutive: DEFAULT_MAX_CONSECUTIVE,
+        }
+    }
+}
+
+impl Detector {
+    /// Creates a new detector that doesn't flag anything.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::outlier::Detector;
+    /// use pos::Radians;
+    /// let detector = Detector::new()
+    ///     .max_speed(100.0)
+    ///     .max_attitude_rate(Radians::from_degrees(90.0));
+    /// ```
+    pub fn new() -> Detector {
+        Detector::default()
+    }
+
+    /// Flags points that moved faster than this from the point before, in meters per second.
+    pub fn max_speed(mut self, max_speed: f64) -> Detector {
+        self.max_speed = Some(max_speed);
+        self
+    }
+
+    /// Flags points that accelerated faster than this, in meters per second squared.
+    pub fn max_acceleration(mut self, max_acceleration: f64) -> Detector {
+        self.max_acceleration = Some(max_acceleration);
+        self
+    }
+
+    /// Flags points whose roll, pitch, or yaw changed faster than this, per second.
+    pub fn max_attitude_rate(mut self, max_attitude_rate: Radians<f64>) -> Detector {
+        self.max_attitude_rate = Some(max_attitude_rate);
+        self
+    }
+
+    /// Flags points whose east, north, or up position differs from the median of the
+    /// `half_width` points on either side by more than `threshold` scaled MADs.
+    ///
+    /// This check is only applied by [Detector::detect].
+    pub fn mad(mut self, half_width: usize, threshold: f64) -> Detector {
+        self.mad = Some((half_width, threshold));
+        self
+    }
+
+    /// Sets the number of consecutive flagged points after which the detector starts over.
+    pub fn max_consecutive(mut self, max_consecutive: usize) -> Detector {
+        self.max_consecutive = max_consecutive;
+        self
+    }
+
+    /// Returns the indices of the outliers in time-ordered points.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::outlier::Detector;
+    /// use pos::Point;
+    /// let points: Vec<_> = [0.0, 1.0, 500.0, 3.0]
+    ///     .iter()
+    ///     .enumerate()
+    ///     .map(|(i, &altitude)| Point { time: i as f64, altitude, ..Default::default() })
+    ///     .collect();
+    /// assert_eq!(vec![2], Detector::new().max_speed(10.0).detect(&points));
+    /// ```
+    pub fn detect(&self, points: &[Point]) -> Vec<usize> {
+        let mut flagged = vec![false; points.len()];
+        let mut reference = VecDeque::with_capacity(2);
+        let mut consecutive = 0;
+        for (i, point) in points.iter().enumerate() {
+            if self.is_jump(&reference, point) && consecutive < self.max_consecutive {
+                flagged[i] = true;
+                consecutive += 1;
+            } else {
+                push(&mut reference, *point);
+                consecutive = 0;
+            }
+        }
+        if let Some((half_width, threshold)) = self.mad {
+            for i in mad(points, half_width, threshold) {
+                flagged[i] = true;
+            }
+        }
+        flagged
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &flagged)| flagged.then_some(i))
+            .collect()
+    }
+
+    /// Returns true if a point jumps implausibly from the reference points before it.
+    fn is_jump(&self, reference: &VecDeque<Point>, point: &Point) -> bool {
+        let last = match reference.back() {
+            Some(last) => last,
+            None => return false,
+        };
+        let interval = point.time - last.time;
+        if interval <= 0.0 {
+            return false;
+        }
+        let enu = geodesy::enu(last, point);
+        let velocity = enu.map(|d| d / interval);
+        if self.max_speed.is_some_and(|max| norm(velocity) > max) {
+            return true;
+        }
+        if let Some(max) = self.max_attitude_rate {
+            let rates = [
+                point.roll - last.roll,
+                point.pitch - last.pitch,
+                point.yaw - last.yaw,
+            ]
+            .map(|d| d.wrapped().0.abs() / interval);
+            if rates.iter().any(|&rate| rate > max.0) {
+                return true;
+            }
+        }
+        if let (Some(max), Some(first)) = (self.max_acceleration, reference.front()) {
+            let previous_interval = last.time - first.time;
+            if reference.len() == 2 && previous_interval > 0.0 {
+                let previous = geodesy::enu(first, last).map(|d| d / previous_interval);
+                let change = [
+                    velocity[0] - previous[0],
+                    velocity[1] - previous[1],
+                    velocity[2] - previous[2],
+                ];
+                if norm(change) / ((interval + previous_interval) / 2.0) > max {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl OutlierFilter {
+    /// Creates a new outlier filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::outlier::{Action, Detector, OutlierFilter};
+    /// use pos::sbet::Reader;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let detector = Detector::new().max_speed(100.0);
+    /// let source = OutlierFilter::new(Box::new(reader), detector, Action::Repair);
+    /// ```
+    pub fn new(source: Box<dyn Source>, detector: Detector, action: Action) -> OutlierFilter {
+        OutlierFilter {
+            source,
+            detector,
+            action,
+            reference: VecDeque::with_capacity(2),
+            pending: Vec::new(),
+            ready: VecDeque::new(),
+            consecutive: 0,
+        }
+    }
+}
+
+impl Source for OutlierFilter {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        while self.ready.is_empty() {
+            let point = match self.source.source()? {
+                Some(point) => point,
+                None => return Ok(None),
+            };
+            if self.detector.is_jump(&self.reference, &point)
+                && self.consecutive < self.detector.max_consecutive
+            {
+                self.consecutive += 1;
+                if self.action == Action::Repair {
+                    self.pending.push(point);
+                }
+                continue;
+            }
+            if let Some(last) = self.reference.back() {
+                for outlier in self.pending.drain(..) {
+                    let repaired = last.interpolate(&point, outlier.time);
+                    self.ready.push_back(Point {
+                        latitude: repaired.latitude,
+                        longitude: repaired.longitude,
+                        altitude: repaired.altitude,
+                        roll: repaired.roll,
+                        pitch: repaired.pitch,
+                        yaw: repaired.yaw,
+                        ..outlier
+                    });
+                }
+            }
+            self.pending.clear();
+            self.consecutive = 0;
+            push(&mut self.reference, point);
+            self.ready.push_back(point);
+        }
+        Ok(self.ready.pop_front())
+    }
+}
+
+/// Returns the indices of points whose position is more than `threshold` scaled MADs from the
+/// median of their neighbors.
+fn mad(points: &[Point], half_width: usize, threshold: f64) -> Vec<usize> {
+    let origin = match points.first() {
+        Some(origin) => origin,
+        None => return Vec::new(),
+    };
+    let positions: Vec<_> = points.iter().map(|p| geodesy::enu(origin, p)).collect();
+    let mut outliers = Vec::new();
+    for i in 0..points.len() {
+        let start = i.saturating_sub(half_width);
+        let end = (i + half_width).min(points.len() - 1);
+        for axis in 0..3 {
+            let mut values: Vec<_> = positions[start..=end].iter().map(|p| p[axis]).collect();
+            let median = median(&mut values);
+            let mut deviations: Vec<_> = values.iter().map(|v| (v - median).abs()).collect();
+            let mad = MAD_SCALE * self::median(&mut deviations);
+            let residual = (positions[i][axis] - median).abs();
+            if mad > 0.0 && residual > threshold * mad {
+                outliers.push(i);
+                break;
+            }
+        }
+    }
+    outliers
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
+
+fn norm(v: [f64; 3]) -> f64 {
+    (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+}
+
+fn push(reference: &mut VecDeque<Point>, point: Point) {
+    if reference.len() == 2 {
+        let _ = reference.pop_front();
+    }
+    reference.push_back(point);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    fn points(altitudes: &[f64]) -> Vec<Point> {
+        altitudes
+            .iter()
+            .enumerate()
+            .map(|(i, &altitude)| Point {
+                time: i as f64,
+                altitude,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn filter(points: Vec<Point>, detector: Detector, action: Action) -> Vec<Point> {
+        let mut source = OutlierFilter::new(Box::new(MemorySource::new(points)), detector, action);
+        let mut points = Vec::new();
+        while let Some(point) = source.source().unwrap() {
+            points.push(point);
+        }
+        points
+    }
+
+    #[test]
+    fn speed() {
+        let detector = Detector::new().max_speed(10.0);
+        let points = points(&[0.0, 1.0, 500.0, 501.0, 3.0, 4.0]);
+        assert_eq!(vec![2, 3], detector.detect(&points));
+    }
+
+    #[test]
+    fn acceleration() {
+        let detector = Detector::new().max_acceleration(5.0);
+        let points = points(&[0.0, 1.0, 2.0, 20.0, 21.0]);
+        assert_eq!(vec![3, 4], detector.detect(&points));
+    }
+
+    #[test]
+    fn attitude_rate() {
+        let detector = Detector::new().max_attitude_rate(Radians::from_degrees(10.0));
+        let mut points = points(&[0.0, 0.0, 0.0]);
+        points[1].yaw = Radians::from_degrees(45.0);
+        assert_eq!(vec![1], detector.detect(&points));
+    }
+
+    #[test]
+    fn max_consecutive() {
+        let detector = Detector::new().max_speed(10.0).max_consecutive(2);
+        let points = points(&[500.0, 0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(vec![1, 2], detector.detect(&points));
+    }
+
+    #[test]
+    fn mad() {
+        let detector = Detector::new().mad(3, 5.0);
+        let points = points(&[0.0, 0.1, -0.1, 0.0, 8.0, 0.1, -0.1, 0.0]);
+        assert_eq!(vec![4], detector.detect(&points));
+    }
+
+    #[test]
+    fn drop() {
+        let detector = Detector::new().max_speed(10.0);
+        let filtered = filter(points(&[0.0, 1.0, 500.0, 3.0]), detector, Action::Drop);
+        let times: Vec<_> = filtered.iter().map(|p| p.time).collect();
+        assert_eq!(vec![0.0, 1.0, 3.0], times);
+    }
+
+    #[test]
+    fn repair() {
+        let detector = Detector::new().max_speed(10.0);
+        let filtered = filter(
+            points(&[0.0, 1.0, 500.0, 3.0, 600.0]),
+            detector,
+            Action::Repair,
+        );
+        let altitudes: Vec<_> = filtered.iter().map(|p| p.altitude).collect();
+        assert_eq!(4, altitudes.len());
+        assert!((altitudes[2] - 2.0).abs() < 1e-6);
+        assert!((altitudes[3] - 3.0).abs() < 1e-6);
+    }
+}
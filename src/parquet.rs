@@ -0,0 +1,297 @@
+//! Read and write [Apache Parquet](https://parquet.apache.org/) files.
+//!
+//! Files have the columns of [Point::arrow_schema], as described in [arrow](crate::arrow).
+//!
+//! Points are written in batches, one row group per batch, so memory use doesn't grow with the
+//! length of the trajectory.
+
+use crate::arrow::{self, DEFAULT_BATCH_SIZE};
+use crate::point::Point;
+use crate::sink::Sink;
+use crate::source::Source;
+use crate::{Error, Location};
+use ::parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::file::reader::ChunkReader;
+use arrow_schema::SchemaRef;
+use std::fmt::{self, Debug};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A parquet writer.
+pub struct Writer<W: Write + Send> {
+    writer: ArrowWriter<W>,
+    schema: SchemaRef,
+    points: Vec<Point>,
+    batch_size: usize,
+}
+
+/// A parquet reader.
+pub struct Reader {
+    batches: ParquetRecordBatchReader,
+    points: std::vec::IntoIter<Point>,
+    record: u64,
+    path: Option<PathBuf>,
+}
+
+impl Writer<File> {
+    /// Creates a new writer for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::parquet::Writer;
+    /// let writer = Writer::from_path("trajectory.parquet").unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Writer<File>, Error> {
+        Writer::new(File::create(path)?)
+    }
+}
+
+impl<W: Write + Send> Writer<W> {
+    /// Creates a new writer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::parquet::Writer;
+    /// let writer = Writer::new(Vec::new()).unwrap();
+    /// ```
+    pub fn new(writer: W) -> Result<Writer<W>, Error> {
+        let schema = Arc::new(Point::arrow_schema());
+        Ok(Writer {
+            writer: ArrowWriter::try_new(writer, schema.clone(), None)?,
+            schema,
+            points: Vec::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Sets the number of points in each row group, which defaults to [DEFAULT_BATCH_SIZE].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::parquet::Writer;
+    /// let writer = Writer::new(Vec::new()).unwrap().with_batch_size(1024);
+    /// ```
+    pub fn with_batch_size(mut self, batch_size: usize) -> Writer<W> {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Writes a point.
+    ///
+    /// Points are buffered until a full batch has been written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::parquet::Writer;
+    /// use pos::Point;
+    /// let mut writer = Writer::new(Vec::new()).unwrap();
+    /// writer.write_point(&Point::default()).unwrap();
+    /// ```
+    pub fn write_point(&mut self, point: &Point) -> Result<(), Error> {
+        self.points.push(*point);
+        if self.points.len() >= self.batch_size {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    /// Writes any buffered points and the file footer, and returns the underlying writer.
+    ///
+    /// Don't close a writer that has already been finished as a [Sink], since that writes the
+    /// footer too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::parquet::Writer;
+    /// use pos::Point;
+    /// let mut writer = Writer::new(Vec::new()).unwrap();
+    /// writer.write_point(&Point::default()).unwrap();
+    /// let bytes = writer.close().unwrap();
+    /// assert_eq!(b"PAR1", &bytes[..4]);
+    /// ```
+    pub fn close(mut self) -> Result<W, Error> {
+        self.write_batch()?;
+        self.writer.into_inner().map_err(Error::from)
+    }
+
+    fn write_batch(&mut self) -> Result<(), Error> {
+        if self.points.is_empty() {
+            return Ok(());
+        }
+        let batch = arrow::record_batch(self.schema.clone(), &self.points)?;
+        self.writer.write(&batch)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(rows = self.points.len(), "wrote parquet row group");
+        self.points.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> Debug for Writer<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Writer")
+            .field("points", &self.points.len())
+            .field("batch_size", &self.batch_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write + Send> Sink for Writer<W> {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        self.write_point(point)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.write_batch()?;
+        let _ = self.writer.finish()?;
+        Ok(())
+    }
+}
+
+impl Reader {
+    /// Opens a reader for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::parquet::Reader;
+    /// let reader = Reader::from_path("trajectory.parquet").unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader, Error> {
+        let path = path.as_ref();
+        let mut reader =
+            Reader::new(File::open(path)?).map_err(|err| err.at(Location::from_path(path)))?;
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
+    }
+
+    /// Creates a new reader.
+    ///
+    /// Only the time, position, and attitude columns are required. Any other column of
+    /// [Point::arrow_schema] can be missing, in which case that field of every point is `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::parquet::Reader;
+    /// use std::fs::File;
+    /// let mut reader = Reader::new(File::open("trajectory.parquet").unwrap()).unwrap();
+    /// let point = reader.read_point().unwrap();
+    /// ```
+    pub fn new<R: ChunkReader + 'static>(reader: R) -> Result<Reader, Error> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(reader)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            rows = builder.metadata().file_metadata().num_rows(),
+            "opened parquet file"
+        );
+        Ok(Reader {
+            batches: builder.build()?,
+            points: Vec::new().into_iter(),
+            record: 0,
+            path: None,
+        })
+    }
+
+    /// Reads a point.
+    pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
+        loop {
+            if let Some(point) = self.points.next() {
+                return Ok(Some(point));
+            }
+            match self.batches.next() {
+                Some(batch) => self.points = arrow::to_points(&batch?)?.into_iter(),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl Debug for Reader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reader")
+            .field("points", &self.points.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Source for Reader {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(self.record),
+            })
+        });
+        if let Ok(Some(_)) = result {
+            self.record += 1;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::{Accuracy, Covariance, SatelliteCount};
+    use crate::units::Radians;
+
+    fn points() -> Vec<Point> {
+        (0..5)
+            .map(|i| Point {
+                time: i as f64,
+                latitude: Radians::from_degrees(40.0),
+                altitude: 1000.0 + i as f64,
+                distance: if i % 2 == 0 { Some(i as f64) } else { None },
+                accuracy: if i == 1 {
+                    Some(Accuracy {
+                        time: 1.0,
+                        x: 0.02,
+                        hdop: Some(0.9),
+                        satellite_count: Some(SatelliteCount::Unspecified(12)),
+                        ..Default::default()
+                    })
+                } else {
+                    None
+                },
+                covariance: if i == 2 {
+                    Some(Covariance::from_upper([0.5; 21]))
+                } else {
+                    None
+                },
+                event: Some(i),
+                derived: i == 3,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = std::env::temp_dir().join("pos-rs-parquet-round-trip.parquet");
+        let mut writer = Writer::from_path(&path).unwrap().with_batch_size(2);
+        for point in points() {
+            writer.write_point(&point).unwrap();
+        }
+        let _ = writer.close().unwrap();
+        let mut reader = Reader::from_path(&path).unwrap();
+        let mut read = Vec::new();
+        while let Some(point) = reader.source().unwrap() {
+            read.push(point);
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(points(), read);
+    }
+}
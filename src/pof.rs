@@ -3,15 +3,24 @@
 //! These are Riegl-specific GNSS/IMU data files.
 
 use crate::point::Point;
-use crate::source::Source;
+use crate::sink::Sink;
+use crate::source::{SeekableSource, Source};
 use crate::units::Radians;
-use crate::Error;
-use byteorder::{LittleEndian, ReadBytesExt};
-use std::fmt::Debug;
+use crate::{Error, Location};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::fmt::{self, Debug};
 use std::fs::File;
-use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::iter::IntoIterator;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// The size of the header fields that this crate knows about, in bytes.
+///
+/// Files can have more header bytes, up to the data offset.
+pub(crate) const HEADER_SIZE: u32 = 315;
+
+/// The byte offset of the entry count in the header.
+pub(crate) const ENTRIES_OFFSET: u64 = 41;
 
 /// A pos file reader.
 #[derive(Debug)]
@@ -22,6 +31,9 @@ pub struct Reader<R: Read + Seek> {
     /// The name of the company that produced this file.
     pub company: [u8; 32],
 
+    /// The byte offset of the first point record, from the start of the file.
+    pub data_offset: u32,
+
     /// The day this file was written.
     pub day: u16,
 
@@ -84,6 +96,7 @@ pub struct Reader<R: Read + Seek> {
 
     reader: R,
     position: i64,
+    path: Option<PathBuf>,
 }
 
 impl Reader<BufReader<File>> {
@@ -95,14 +108,31 @@ impl Reader<BufReader<File>> {
     /// use pos::pof::Reader;
     /// let reader = Reader::from_path("data/sbet_mission_1.pof").unwrap();
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, Error> {
+        let path = path.as_ref();
         let reader = BufReader::new(File::open(path)?);
-        Reader::new(reader)
+        let mut reader = Reader::new(reader).map_err(|err| err.at(Location::from_path(path)))?;
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
     }
 }
 
 impl<R: Read + Seek> Reader<R> {
-    fn new(mut reader: R) -> Result<Reader<R>, Error> {
+    /// Creates a new reader, reading the header.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::pof::Reader;
+    /// use std::fs::File;
+    /// let file = File::open("data/sbet_mission_1.pof").unwrap();
+    /// let reader = Reader::new(file).unwrap();
+    /// ```
+    pub fn new(mut reader: R) -> Result<Reader<R>, Error> {
         let mut preamble = [0; 27];
         reader.read_exact(&mut preamble)?;
 
@@ -111,6 +141,9 @@ impl<R: Read + Seek> Reader<R> {
         let version = Version::new(major, minor);
 
         let data_offset = reader.read_u32::<LittleEndian>()?;
+        if data_offset < HEADER_SIZE {
+            return Err(Error::PofDataOffset(data_offset));
+        }
         let year = reader.read_u16::<LittleEndian>()?;
         let month = reader.read_u16::<LittleEndian>()?;
         let day = reader.read_u16::<LittleEndian>()?;
@@ -143,10 +176,20 @@ impl<R: Read + Seek> Reader<R> {
         reader.read_exact(&mut reserved2)?;
 
         let _ = reader.seek(SeekFrom::Start(data_offset as u64))?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            %version,
+            entries,
+            data_offset,
+            ?timeunit,
+            ?timeinfo,
+            "read pof header"
+        );
 
         Ok(Reader {
             avgint,
             company,
+            data_offset,
             day,
             device,
             devint,
@@ -168,6 +211,7 @@ impl<R: Read + Seek> Reader<R> {
             timezone,
             version,
             year,
+            path: None,
         })
     }
 
@@ -212,6 +256,57 @@ impl<R: Read + Seek> Reader<R> {
             ..Default::default()
         }))
     }
+
+    /// Reads any bytes between the end of the known header fields and the start of the points.
+    ///
+    /// Some tools store auxiliary metadata here. The reader's position is restored afterwards,
+    /// so this can be called at any time.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::pof::Reader;
+    /// let mut reader = Reader::from_path("data/sbet_mission_1.pof").unwrap();
+    /// let extra = reader.read_extra_header().unwrap();
+    /// ```
+    pub fn read_extra_header(&mut self) -> Result<Vec<u8>, Error> {
+        let length = self.data_offset.saturating_sub(HEADER_SIZE);
+        self.read_section(u64::from(HEADER_SIZE), Some(u64::from(length)))
+    }
+
+    /// Reads any bytes after the last point record.
+    ///
+    /// The reader's position is restored afterwards, so this can be called at any time.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::pof::Reader;
+    /// let mut reader = Reader::from_path("data/sbet_mission_1.pof").unwrap();
+    /// let trailer = reader.read_trailer().unwrap();
+    /// ```
+    pub fn read_trailer(&mut self) -> Result<Vec<u8>, Error> {
+        let end =
+            u64::from(self.data_offset) + self.entries.max(0) as u64 * self.version.record_size();
+        self.read_section(end, None)
+    }
+
+    fn remaining(&self) -> usize {
+        usize::try_from(self.entries - self.position).unwrap_or(0)
+    }
+
+    fn read_section(&mut self, start: u64, length: Option<u64>) -> Result<Vec<u8>, Error> {
+        let position = self.reader.stream_position()?;
+        let _ = self.reader.seek(SeekFrom::Start(start))?;
+        let mut bytes = Vec::new();
+        let result = match length {
+            Some(length) => (&mut self.reader).take(length).read_to_end(&mut bytes),
+            None => self.reader.read_to_end(&mut bytes),
+        };
+        let _ = self.reader.seek(SeekFrom::Start(position))?;
+        let _ = result?;
+        Ok(bytes)
+    }
 }
 
 impl<R: Read + Seek> IntoIterator for Reader<R> {
@@ -223,6 +318,9 @@ impl<R: Read + Seek> IntoIterator for Reader<R> {
 }
 
 /// An iterator over a pof reader.
+///
+/// Panics if a point can't be read. Use [Source::try_iter](crate::Source::try_iter) to handle
+/// errors.
 #[derive(Debug)]
 pub struct ReaderIterator<R: Read + Seek> {
     reader: Reader<R>,
@@ -233,10 +331,15 @@ impl<R: Read + Seek> Iterator for ReaderIterator<R> {
     fn next(&mut self) -> Option<Self::Item> {
         self.reader.read_point().unwrap()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        crate::source::size_hint(Some(self.reader.remaining()))
+    }
 }
 
 /// pof file version.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Version {
     major: u16,
     minor: u16,
@@ -258,6 +361,21 @@ impl Version {
     fn has_distance(&self) -> bool {
         self.minor >= 1
     }
+
+    /// Returns the size of one point record, in bytes.
+    pub(crate) fn record_size(&self) -> u64 {
+        if self.has_distance() {
+            64
+        } else {
+            56
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
 }
 
 /// Seconds format.
@@ -280,6 +398,14 @@ impl TimeUnit {
             _ => Err(Error::PofTimeUnit(n)),
         }
     }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            TimeUnit::Normalized => 0,
+            TimeUnit::Day => 1,
+            TimeUnit::Week => 2,
+        }
+    }
 }
 
 /// Time format.
@@ -302,11 +428,337 @@ impl TimeInfo {
             _ => Err(Error::PofTimeInfo(n)),
         }
     }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            TimeInfo::Gps => 0,
+            TimeInfo::Utc => 1,
+            TimeInfo::Unknown => 2,
+        }
+    }
 }
 
 impl<R: Debug + Seek + Read> Source for Reader<R> {
     fn source(&mut self) -> Result<Option<Point>, Error> {
-        self.read_point()
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(self.position as u64),
+            })
+        });
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, position = self.position, "could not read pof record");
+        }
+        result
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        Some(self.remaining())
+    }
+}
+
+impl<R: Debug + Seek + Read> SeekableSource for Reader<R> {
+    fn rewind(&mut self) -> Result<(), Error> {
+        let _ = self
+            .reader
+            .seek(SeekFrom::Start(u64::from(self.data_offset)))?;
+        self.position = 0;
+        Ok(())
+    }
+
+    fn seek_to_time(&mut self, time: f64) -> Result<(), Error> {
+        let offset = u64::from(self.data_offset);
+        let record_size = self.version.record_size();
+        let index = crate::source::search_time(
+            &mut self.reader,
+            offset,
+            record_size,
+            self.entries.max(0) as u64,
+            time,
+        )?;
+        let _ = self
+            .reader
+            .seek(SeekFrom::Start(offset + index * record_size))?;
+        self.position = index as i64;
+        Ok(())
+    }
+}
+
+/// A version 1.1 pof writer.
+///
+/// The header's entry count, bounds, and interval statistics depend on every point, so a
+/// placeholder header is written on creation and patched with the real values on
+/// [close](Writer::close). The preamble and the free-text header fields are written as zeros.
+#[derive(Debug)]
+pub struct Writer<W: Write + Seek> {
+    writer: W,
+    start: u64,
+    date: (u16, u16, u16),
+    timeunit: TimeUnit,
+    timeinfo: TimeInfo,
+    summary: Summary,
+}
+
+/// The entry count, bounds, and interval statistics of a pof file's header.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Summary {
+    entries: i64,
+    lon: (f64, f64),
+    lat: (f64, f64),
+    alt: (f64, f64),
+    last_time: Option<f64>,
+    intervals: Intervals,
+}
+
+/// Running statistics of the intervals between consecutive times.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Intervals {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    max: f64,
+}
+
+impl Writer<BufWriter<File>> {
+    /// Creates a new writer for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::pof::Writer;
+    /// let writer = Writer::from_path("out.pof").unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Writer<BufWriter<File>>, std::io::Error> {
+        Writer::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek> Writer<W> {
+    /// Creates a new writer, writing a placeholder header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pof::Writer;
+    /// use std::io::Cursor;
+    /// let writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+    /// ```
+    pub fn new(mut writer: W) -> Result<Writer<W>, std::io::Error> {
+        let start = writer.stream_position()?;
+        writer.write_all(&[0; HEADER_SIZE as usize])?;
+        Ok(Writer {
+            writer,
+            start,
+            date: (1970, 1, 1),
+            timeunit: TimeUnit::Week,
+            timeinfo: TimeInfo::Gps,
+            summary: Summary::default(),
+        })
+    }
+
+    /// Sets the date of the data, which defaults to 1970-01-01.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pof::Writer;
+    /// use std::io::Cursor;
+    /// let writer = Writer::new(Cursor::new(Vec::new())).unwrap().with_date(2015, 4, 29);
+    /// ```
+    pub fn with_date(mut self, year: u16, month: u16, day: u16) -> Writer<W> {
+        self.date = (year, month, day);
+        self
+    }
+
+    /// Sets the unit of the points' times, which defaults to GPS week seconds.
+    pub fn with_timeunit(mut self, timeunit: TimeUnit) -> Writer<W> {
+        self.timeunit = timeunit;
+        self
+    }
+
+    /// Sets the time system of the points' times, which defaults to GPS time.
+    pub fn with_timeinfo(mut self, timeinfo: TimeInfo) -> Writer<W> {
+        self.timeinfo = timeinfo;
+        self
+    }
+
+    /// Writes a point.
+    ///
+    /// Angles are written in degrees, and a missing distance is written as zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pof::Writer;
+    /// use pos::Point;
+    /// use std::io::Cursor;
+    /// let mut writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+    /// writer.write_point(&Point::default()).unwrap();
+    /// ```
+    pub fn write_point(&mut self, point: &Point) -> Result<(), std::io::Error> {
+        let longitude = point.longitude.to_degrees();
+        let latitude = point.latitude.to_degrees();
+        for value in [
+            point.time,
+            longitude,
+            latitude,
+            point.altitude,
+            point.roll.to_degrees(),
+            point.pitch.to_degrees(),
+            point.yaw.to_degrees(),
+            point.distance.unwrap_or_default(),
+        ] {
+            self.writer.write_f64::<LittleEndian>(value)?;
+        }
+        self.summary
+            .add(point.time, longitude, latitude, point.altitude);
+        Ok(())
+    }
+
+    /// Patches the header, flushes, and returns the underlying writer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pof::{Reader, Writer};
+    /// use pos::Point;
+    /// use std::io::Cursor;
+    /// let mut writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+    /// writer.write_point(&Point { time: 1.0, ..Default::default() }).unwrap();
+    /// writer.write_point(&Point { time: 1.5, ..Default::default() }).unwrap();
+    /// let mut cursor = writer.close().unwrap();
+    /// cursor.set_position(0);
+    /// let reader = Reader::new(cursor).unwrap();
+    /// assert_eq!(2, reader.entries);
+    /// assert_eq!(0.5, reader.maxint);
+    /// ```
+    pub fn close(mut self) -> Result<W, std::io::Error> {
+        self.patch_header()?;
+        Ok(self.writer)
+    }
+
+    fn patch_header(&mut self) -> Result<(), std::io::Error> {
+        let end = self.writer.stream_position()?;
+        let _ = self.writer.seek(SeekFrom::Start(self.start))?;
+        self.write_header()?;
+        let _ = self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(entries = self.summary.entries, "wrote pof header");
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> Result<(), std::io::Error> {
+        self.writer.write_all(&[0; 27])?;
+        for value in [1, 1] {
+            self.writer.write_u16::<LittleEndian>(value)?;
+        }
+        self.writer.write_u32::<LittleEndian>(HEADER_SIZE)?;
+        for value in [self.date.0, self.date.1, self.date.2] {
+            self.writer.write_u16::<LittleEndian>(value)?;
+        }
+        self.summary.write(&mut self.writer)?;
+        self.writer.write_u8(self.timeunit.to_u8())?;
+        self.writer.write_u8(self.timeinfo.to_u8())?;
+        self.writer
+            .write_all(&[0; 16 + 16 + 32 + 32 + 32 + 32 + 32])
+    }
+}
+
+impl Summary {
+    /// Adds a point, with its longitude and latitude in degrees.
+    pub(crate) fn add(&mut self, time: f64, longitude: f64, latitude: f64, altitude: f64) {
+        self.entries += 1;
+        for (range, value) in [
+            (&mut self.lon, longitude),
+            (&mut self.lat, latitude),
+            (&mut self.alt, altitude),
+        ] {
+            *range = (range.0.min(value), range.1.max(value));
+        }
+        if let Some(last_time) = self.last_time {
+            self.intervals.add(time - last_time);
+        }
+        self.last_time = Some(time);
+    }
+
+    /// Returns the number of points that have been added.
+    pub(crate) fn entries(&self) -> i64 {
+        self.entries
+    }
+
+    /// Writes the header fields from the entry count through devint, in file order.
+    pub(crate) fn write<W: Write>(&self, mut writer: W) -> Result<(), std::io::Error> {
+        let bound = |value: f64| if value.is_finite() { value } else { 0.0 };
+        writer.write_i64::<LittleEndian>(self.entries)?;
+        for value in [
+            bound(self.lon.0),
+            bound(self.lon.1),
+            bound(self.lat.0),
+            bound(self.lat.1),
+            bound(self.alt.0),
+            bound(self.alt.1),
+            self.intervals.mean,
+            self.intervals.max,
+            self.intervals.deviation(),
+        ] {
+            writer.write_f64::<LittleEndian>(value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Summary {
+    fn default() -> Summary {
+        Summary {
+            entries: 0,
+            lon: (f64::INFINITY, f64::NEG_INFINITY),
+            lat: (f64::INFINITY, f64::NEG_INFINITY),
+            alt: (f64::INFINITY, f64::NEG_INFINITY),
+            last_time: None,
+            intervals: Intervals::default(),
+        }
+    }
+}
+
+impl Intervals {
+    pub(crate) fn add(&mut self, interval: f64) {
+        self.count += 1;
+        let delta = interval - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (interval - self.mean);
+        self.max = if self.count == 1 {
+            interval
+        } else {
+            self.max.max(interval)
+        };
+    }
+
+    pub(crate) fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub(crate) fn max(&self) -> f64 {
+        self.max
+    }
+
+    pub(crate) fn deviation(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.m2 / self.count as f64).sqrt()
+        }
+    }
+}
+
+impl<W: Write + Seek + Debug> Sink for Writer<W> {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        self.write_point(point).map_err(Error::from)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.patch_header().map_err(Error::from)
     }
 }
 
@@ -338,10 +790,96 @@ mod tests {
         assert_eq!(0.0, point.distance.unwrap());
     }
 
+    #[test]
+    fn sections() {
+        use crate::testutil;
+        use std::io::Cursor;
+
+        let points = [Point::default(), Point::default()];
+        let mut bytes = testutil::pof(&points);
+        let extra = b"extra";
+        let data_offset = HEADER_SIZE + extra.len() as u32;
+        bytes[31..35].copy_from_slice(&data_offset.to_le_bytes());
+        let _ = bytes.splice(
+            HEADER_SIZE as usize..HEADER_SIZE as usize,
+            extra.iter().copied(),
+        );
+        bytes.extend_from_slice(b"trailer");
+
+        let mut reader = Reader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(data_offset, reader.data_offset);
+        assert!(reader.read_point().unwrap().is_some());
+        assert_eq!(extra.to_vec(), reader.read_extra_header().unwrap());
+        assert_eq!(b"trailer".to_vec(), reader.read_trailer().unwrap());
+        assert!(reader.read_point().unwrap().is_some());
+        assert!(reader.read_point().unwrap().is_none());
+    }
+
     #[test]
     fn iter() {
         let reader = Reader::from_path("data/sbet_mission_1.pof").unwrap();
         let points: Vec<_> = reader.into_iter().collect();
         assert_eq!(1114521, points.len());
     }
+
+    #[test]
+    fn write() {
+        use crate::testutil;
+        use std::io::Cursor;
+
+        let points: Vec<_> = [0.0, 0.5, 1.5]
+            .iter()
+            .enumerate()
+            .map(|(i, &time)| Point {
+                time,
+                latitude: Radians::from_degrees(45.0 + i as f64),
+                longitude: Radians::from_degrees(-105.0 - i as f64),
+                altitude: 100.0 * i as f64,
+                yaw: Radians::from_degrees(90.0),
+                distance: Some(i as f64),
+                ..Default::default()
+            })
+            .collect();
+        let mut writer = Writer::new(Cursor::new(Vec::new()))
+            .unwrap()
+            .with_date(2000, 1, 1)
+            .with_timeunit(TimeUnit::Normalized);
+        for point in &points {
+            writer.write_point(point).unwrap();
+        }
+        let bytes = writer.close().unwrap().into_inner();
+        assert_eq!(testutil::pof(&points), bytes);
+
+        let mut reader = Reader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(3, reader.entries);
+        assert_eq!((-107.0, -105.0), (reader.minlon, reader.maxlon));
+        assert_eq!((45.0, 47.0), (reader.minlat, reader.maxlat));
+        assert_eq!((0.0, 200.0), (reader.minalt, reader.maxalt));
+        assert_eq!(
+            (0.75, 1.0, 0.25),
+            (reader.avgint, reader.maxint, reader.devint)
+        );
+        let point = reader.read_point().unwrap().unwrap();
+        assert!((point.yaw.to_degrees() - 90.0).abs() < 1e-12);
+        assert_eq!(Some(2), reader.len_hint());
+    }
+
+    #[test]
+    fn seek() {
+        let points: Vec<_> = [1.0, 2.0, 3.0]
+            .into_iter()
+            .map(|time| Point {
+                time,
+                ..Default::default()
+            })
+            .collect();
+        let mut reader = Reader::new(std::io::Cursor::new(crate::testutil::pof(&points))).unwrap();
+        reader.seek_to_time(2.0).unwrap();
+        assert_eq!(Some(2), reader.len_hint());
+        assert_eq!(2.0, reader.source().unwrap().unwrap().time);
+        reader.rewind().unwrap();
+        assert_eq!(1.0, reader.source().unwrap().unwrap().time);
+        reader.seek_to_time(4.0).unwrap();
+        assert!(reader.source().unwrap().is_none());
+    }
 }
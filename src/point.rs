@@ -1,6 +1,7 @@
 //! Points.
 
-use crate::units::Radians;
+use crate::units::{Radians, Unit};
+use crate::Error;
 
 macro_rules! interpolate {
     ($lhs:ident, $rhs:ident, $factor:ident, $var:ident) => {{
@@ -26,6 +27,7 @@ macro_rules! interpolate_optional {
 ///
 /// This must contain position and attidue information, and may contain error information.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Point {
     pub time: f64,
@@ -46,10 +48,198 @@ pub struct Point {
     pub x_angular_rate: Option<Radians<f64>>,
     pub y_angular_rate: Option<Radians<f64>>,
     pub z_angular_rate: Option<Radians<f64>>,
+    pub height_above_ground: Option<f64>,
+    /// The vertical displacement from wave motion, in meters, positive up.
+    pub heave: Option<f64>,
     pub accuracy: Option<Accuracy>,
+    /// The full covariance of the position and attitude, if the format records it.
+    pub covariance: Option<Covariance>,
+    /// The quality of the GNSS solution, if the format records it.
+    pub fix_quality: Option<FixQuality>,
+    /// An event or status flag recorded with this point, such as a trigger mark.
+    pub event: Option<u32>,
+    /// True if any velocities, accelerations, or angular rates were derived from neighboring
+    /// points, e.g. by [Derive](crate::dynamics::Derive), rather than recorded by the source.
+    pub derived: bool,
 }
 
 impl Point {
+    /// The name and unit of every field that [Point::fields] can return, in order.
+    ///
+    /// Exporters can use this to write a header before they've seen any points.
+    pub const FIELDS: [(&'static str, Unit); 20] = [
+        ("time", Unit::Seconds),
+        ("longitude", Unit::Radians),
+        ("latitude", Unit::Radians),
+        ("altitude", Unit::Meters),
+        ("roll", Unit::Radians),
+        ("pitch", Unit::Radians),
+        ("yaw", Unit::Radians),
+        ("distance", Unit::Meters),
+        ("x_velocity", Unit::MetersPerSecond),
+        ("y_velocity", Unit::MetersPerSecond),
+        ("z_velocity", Unit::MetersPerSecond),
+        ("wander_angle", Unit::Radians),
+        ("x_acceleration", Unit::MetersPerSecondSquared),
+        ("y_acceleration", Unit::MetersPerSecondSquared),
+        ("z_acceleration", Unit::MetersPerSecondSquared),
+        ("x_angular_rate", Unit::RadiansPerSecond),
+        ("y_angular_rate", Unit::RadiansPerSecond),
+        ("z_angular_rate", Unit::RadiansPerSecond),
+        ("height_above_ground", Unit::Meters),
+        ("heave", Unit::Meters),
+    ];
+
+    /// Returns the name, value, and unit of every populated field of this point.
+    ///
+    /// Fields are returned in the order of [Point::FIELDS], skipping optional fields that are
+    /// `None`. Angles are in radians. The accuracy, fix quality, event, and derived flag aren't
+    /// included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::units::Unit;
+    /// use pos::Point;
+    /// let point = Point {
+    ///     altitude: 42.0,
+    ///     x_velocity: Some(1.0),
+    ///     ..Default::default()
+    /// };
+    /// let fields = point.fields();
+    /// assert_eq!(8, fields.len());
+    /// assert_eq!(("altitude", 42.0, Unit::Meters), fields[3]);
+    /// assert_eq!(("x_velocity", 1.0, Unit::MetersPerSecond), fields[7]);
+    /// ```
+    pub fn fields(&self) -> Vec<(&'static str, f64, Unit)> {
+        let values = [
+            Some(self.time),
+            Some(self.longitude.0),
+            Some(self.latitude.0),
+            Some(self.altitude),
+            Some(self.roll.0),
+            Some(self.pitch.0),
+            Some(self.yaw.0),
+            self.distance,
+            self.x_velocity,
+            self.y_velocity,
+            self.z_velocity,
+            self.wander_angle.map(|r| r.0),
+            self.x_acceleration,
+            self.y_acceleration,
+            self.z_acceleration,
+            self.x_angular_rate.map(|r| r.0),
+            self.y_angular_rate.map(|r| r.0),
+            self.z_angular_rate.map(|r| r.0),
+            self.height_above_ground,
+            self.heave,
+        ];
+        Point::FIELDS
+            .iter()
+            .zip(values)
+            .filter_map(|(&(name, unit), value)| value.map(|value| (name, value, unit)))
+            .collect()
+    }
+
+    /// Sets a field by its name in [Point::FIELDS], the inverse of [Point::fields].
+    ///
+    /// Angles are in radians. Returns [Error::UnknownField] if the name isn't a field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::Point;
+    /// let mut point = Point::default();
+    /// point.set_field("x_velocity", 1.0).unwrap();
+    /// assert_eq!(Some(1.0), point.x_velocity);
+    /// assert!(point.set_field("speed", 1.0).is_err());
+    /// ```
+    pub fn set_field(&mut self, name: &str, value: f64) -> Result<(), Error> {
+        match name {
+            "time" => self.time = value,
+            "longitude" => self.longitude = Radians(value),
+            "latitude" => self.latitude = Radians(value),
+            "altitude" => self.altitude = value,
+            "roll" => self.roll = Radians(value),
+            "pitch" => self.pitch = Radians(value),
+            "yaw" => self.yaw = Radians(value),
+            "distance" => self.distance = Some(value),
+            "x_velocity" => self.x_velocity = Some(value),
+            "y_velocity" => self.y_velocity = Some(value),
+            "z_velocity" => self.z_velocity = Some(value),
+            "wander_angle" => self.wander_angle = Some(Radians(value)),
+            "x_acceleration" => self.x_acceleration = Some(value),
+            "y_acceleration" => self.y_acceleration = Some(value),
+            "z_acceleration" => self.z_acceleration = Some(value),
+            "x_angular_rate" => self.x_angular_rate = Some(Radians(value)),
+            "y_angular_rate" => self.y_angular_rate = Some(Radians(value)),
+            "z_angular_rate" => self.z_angular_rate = Some(Radians(value)),
+            "height_above_ground" => self.height_above_ground = Some(value),
+            "heave" => self.heave = Some(value),
+            _ => return Err(Error::UnknownField(name.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Returns the latitude in degrees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::{Point, Radians};
+    /// let point = Point {
+    ///     latitude: Radians::from_degrees(45.0),
+    ///     x_angular_rate: Some(Radians::from_degrees(2.0)),
+    ///     ..Default::default()
+    /// };
+    /// assert!((point.latitude_degrees() - 45.0).abs() < 1e-12);
+    /// assert!((point.x_angular_rate_degrees().unwrap() - 2.0).abs() < 1e-12);
+    /// assert_eq!(None, point.wander_angle_degrees());
+    /// ```
+    pub fn latitude_degrees(&self) -> f64 {
+        self.latitude.to_degrees()
+    }
+
+    /// Returns the longitude in degrees.
+    pub fn longitude_degrees(&self) -> f64 {
+        self.longitude.to_degrees()
+    }
+
+    /// Returns the roll in degrees.
+    pub fn roll_degrees(&self) -> f64 {
+        self.roll.to_degrees()
+    }
+
+    /// Returns the pitch in degrees.
+    pub fn pitch_degrees(&self) -> f64 {
+        self.pitch.to_degrees()
+    }
+
+    /// Returns the yaw in degrees.
+    pub fn yaw_degrees(&self) -> f64 {
+        self.yaw.to_degrees()
+    }
+
+    /// Returns the wander angle in degrees.
+    pub fn wander_angle_degrees(&self) -> Option<f64> {
+        self.wander_angle.map(Radians::to_degrees)
+    }
+
+    /// Returns the angular rate about the x axis in degrees per second.
+    pub fn x_angular_rate_degrees(&self) -> Option<f64> {
+        self.x_angular_rate.map(Radians::to_degrees)
+    }
+
+    /// Returns the angular rate about the y axis in degrees per second.
+    pub fn y_angular_rate_degrees(&self) -> Option<f64> {
+        self.y_angular_rate.map(Radians::to_degrees)
+    }
+
+    /// Returns the angular rate about the z axis in degrees per second.
+    pub fn z_angular_rate_degrees(&self) -> Option<f64> {
+        self.z_angular_rate.map(Radians::to_degrees)
+    }
+
     /// Linearly interpolate a new point between these two.
     ///
     /// # Examples
@@ -64,6 +254,19 @@ impl Point {
     /// ```
     pub fn interpolate(&self, other: &Point, time: f64) -> Point {
         let factor = (time - self.time) / (other.time - self.time);
+        Point {
+            accuracy: if let Some(a1) = self.accuracy {
+                other.accuracy.map(|a2| a1.interpolate(&a2, time))
+            } else {
+                None
+            },
+            ..self.lerp(other, factor)
+        }
+    }
+
+    /// Linearly interpolates every field by a factor, where zero is this point and one is the
+    /// other.
+    pub(crate) fn lerp(&self, other: &Point, factor: f64) -> Point {
         Point {
             time: interpolate!(self, other, factor, time),
             longitude: interpolate!(self, other, factor, longitude),
@@ -83,17 +286,112 @@ impl Point {
             x_angular_rate: interpolate_optional!(self, other, factor, x_angular_rate),
             y_angular_rate: interpolate_optional!(self, other, factor, y_angular_rate),
             z_angular_rate: interpolate_optional!(self, other, factor, z_angular_rate),
+            height_above_ground: interpolate_optional!(self, other, factor, height_above_ground),
+            heave: interpolate_optional!(self, other, factor, heave),
             accuracy: if let Some(a1) = self.accuracy {
-                other.accuracy.map(|a2| a1.interpolate(&a2, time))
+                other.accuracy.map(|a2| a1.lerp(&a2, factor))
             } else {
                 None
             },
+            covariance: match (self.covariance, other.covariance) {
+                (Some(a), Some(b)) => Some(a.lerp(&b, factor)),
+                _ => None,
+            },
+            fix_quality: match (self.fix_quality, other.fix_quality) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                _ => None,
+            },
+            event: self.event.filter(|&event| other.event == Some(event)),
+            derived: self.derived || other.derived,
+        }
+    }
+}
+
+/// The quality of a GNSS solution.
+///
+/// Qualities are ordered from worst to best, so `quality >= FixQuality::RtkFloat` keeps RTK
+/// solutions. Interpolating between two points keeps the worse of their qualities.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FixQuality {
+    /// No GNSS, position propagated by the IMU or odometry.
+    DeadReckoning,
+    /// Standalone GNSS positioning.
+    Sps,
+    /// Differential GNSS, including SBAS and PPP.
+    Dgps,
+    /// RTK with float ambiguities.
+    RtkFloat,
+    /// RTK with fixed ambiguities.
+    RtkFixed,
+}
+
+impl FixQuality {
+    /// Returns the quality for an RTKLIB `Q` code, or `None` if the code is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::point::FixQuality;
+    /// assert_eq!(Some(FixQuality::RtkFixed), FixQuality::from_rtklib(1));
+    /// assert_eq!(None, FixQuality::from_rtklib(0));
+    /// ```
+    pub fn from_rtklib(q: u8) -> Option<FixQuality> {
+        match q {
+            1 => Some(FixQuality::RtkFixed),
+            2 => Some(FixQuality::RtkFloat),
+            3 | 4 | 6 => Some(FixQuality::Dgps),
+            5 => Some(FixQuality::Sps),
+            _ => None,
+        }
+    }
+
+    /// Returns the quality for an NMEA GGA fix quality indicator, or `None` if there's no fix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::point::FixQuality;
+    /// assert_eq!(Some(FixQuality::RtkFloat), FixQuality::from_nmea(5));
+    /// assert_eq!(None, FixQuality::from_nmea(0));
+    /// ```
+    pub fn from_nmea(quality: u8) -> Option<FixQuality> {
+        match quality {
+            1 => Some(FixQuality::Sps),
+            2 | 3 => Some(FixQuality::Dgps),
+            4 => Some(FixQuality::RtkFixed),
+            5 => Some(FixQuality::RtkFloat),
+            6 => Some(FixQuality::DeadReckoning),
+            _ => None,
+        }
+    }
+
+    /// Returns the quality for a Septentrio SBF PVT mode, or `None` if there's no GNSS solution.
+    ///
+    /// The 2D flag in the mode's high bit is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::point::FixQuality;
+    /// assert_eq!(Some(FixQuality::RtkFixed), FixQuality::from_sbf(4));
+    /// assert_eq!(Some(FixQuality::Dgps), FixQuality::from_sbf(0x8a));
+    /// assert_eq!(None, FixQuality::from_sbf(0));
+    /// ```
+    pub fn from_sbf(mode: u8) -> Option<FixQuality> {
+        match mode & 0x0f {
+            1 => Some(FixQuality::Sps),
+            2 | 6 | 10 => Some(FixQuality::Dgps),
+            4 | 7 => Some(FixQuality::RtkFixed),
+            5 | 8 => Some(FixQuality::RtkFloat),
+            _ => None,
         }
     }
 }
 
 /// The accuracy of a position.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Accuracy {
     pub time: f64,
@@ -104,6 +402,10 @@ pub struct Accuracy {
     pub pitch: Radians<f64>,
     pub yaw: Radians<f64>,
     pub pdop: f64,
+    /// Horizontal dilution of precision, if the format records it.
+    pub hdop: Option<f64>,
+    /// Vertical dilution of precision, if the format records it.
+    pub vdop: Option<f64>,
     pub satellite_count: Option<SatelliteCount>,
 }
 
@@ -116,12 +418,20 @@ impl Accuracy {
     /// use pos::point::Accuracy;
     /// let mut accuracy1: Accuracy = Default::default();
     /// accuracy1.time = 10.0;
+    /// accuracy1.hdop = Some(1.0);
     /// let mut accuracy2: Accuracy = Default::default();
     /// accuracy2.time = 20.0;
+    /// accuracy2.hdop = Some(2.0);
     /// let accuracy3 = accuracy1.interpolate(&accuracy2, 15.0);
+    /// assert_eq!(Some(1.5), accuracy3.hdop);
+    /// assert_eq!(None, accuracy3.vdop);
     /// ```
     pub fn interpolate(&self, other: &Accuracy, time: f64) -> Accuracy {
         let factor = (time - self.time) / (other.time - self.time);
+        self.lerp(other, factor)
+    }
+
+    fn lerp(&self, other: &Accuracy, factor: f64) -> Accuracy {
         Accuracy {
             time: interpolate!(self, other, factor, time),
             x: interpolate!(self, other, factor, x),
@@ -131,13 +441,118 @@ impl Accuracy {
             pitch: interpolate!(self, other, factor, pitch),
             yaw: interpolate!(self, other, factor, yaw),
             pdop: interpolate!(self, other, factor, pdop),
+            hdop: interpolate_optional!(self, other, factor, hdop),
+            vdop: interpolate_optional!(self, other, factor, vdop),
             satellite_count: None,
         }
     }
 }
 
+/// The covariance of a point's position and attitude.
+///
+/// Rows and columns are in the order of [Covariance::AXES]: east, north, and up position in
+/// meters, then roll, pitch, and yaw in radians. The matrix is symmetric, so only its upper
+/// triangle is stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Covariance([f64; 21]);
+
+impl Covariance {
+    /// The names of the rows and columns, in order.
+    pub const AXES: [&'static str; 6] = ["x", "y", "z", "roll", "pitch", "yaw"];
+
+    /// Creates a covariance from the 21 values of its upper triangle, row by row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::point::Covariance;
+    /// let mut upper = [0.0; 21];
+    /// upper[0] = 0.01;
+    /// upper[1] = 0.002;
+    /// let covariance = Covariance::from_upper(upper);
+    /// assert_eq!(0.002, covariance.get(1, 0));
+    /// ```
+    pub fn from_upper(upper: [f64; 21]) -> Covariance {
+        Covariance(upper)
+    }
+
+    /// Creates a covariance from a full matrix, using its upper triangle.
+    pub fn from_matrix(matrix: [[f64; 6]; 6]) -> Covariance {
+        let mut upper = [0.0; 21];
+        for i in 0..6 {
+            for j in i..6 {
+                upper[index(i, j)] = matrix[i][j];
+            }
+        }
+        Covariance(upper)
+    }
+
+    /// Creates a diagonal covariance from an accuracy's standard deviations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::point::{Accuracy, Covariance};
+    /// let accuracy = Accuracy { x: 0.1, ..Default::default() };
+    /// let covariance = Covariance::from_accuracy(&accuracy);
+    /// assert!((covariance.get(0, 0) - 0.01).abs() < 1e-12);
+    /// assert_eq!(0.0, covariance.get(0, 1));
+    /// ```
+    pub fn from_accuracy(accuracy: &Accuracy) -> Covariance {
+        let deviations = [
+            accuracy.x,
+            accuracy.y,
+            accuracy.z,
+            accuracy.roll.0,
+            accuracy.pitch.0,
+            accuracy.yaw.0,
+        ];
+        let mut matrix = [[0.0; 6]; 6];
+        for (i, deviation) in deviations.iter().enumerate() {
+            matrix[i][i] = deviation * deviation;
+        }
+        Covariance::from_matrix(matrix)
+    }
+
+    /// Returns the value at a row and column, which must both be less than six.
+    pub fn get(&self, row: usize, column: usize) -> f64 {
+        self.0[index(row.min(column), row.max(column))]
+    }
+
+    /// Returns the 21 values of the upper triangle, row by row.
+    pub fn upper(&self) -> &[f64; 21] {
+        &self.0
+    }
+
+    /// Returns the full matrix.
+    pub fn matrix(&self) -> [[f64; 6]; 6] {
+        let mut matrix = [[0.0; 6]; 6];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.get(i, j);
+            }
+        }
+        matrix
+    }
+
+    fn lerp(&self, other: &Covariance, factor: f64) -> Covariance {
+        let mut upper = self.0;
+        for (value, other) in upper.iter_mut().zip(other.0) {
+            *value += factor * (other - *value);
+        }
+        Covariance(upper)
+    }
+}
+
+/// Returns the index of a row and column, with `row <= column`, in the upper triangle.
+fn index(row: usize, column: usize) -> usize {
+    row * (11 - row) / 2 + column
+}
+
 /// A count of the number of satellites.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SatelliteCount {
     /// The type of the satellites being counted is unspecified.
     Unspecified(u16),
@@ -148,6 +563,41 @@ pub enum SatelliteCount {
         /// GLONASS satellites (Russia).
         glonass: u16,
     },
+    /// Counts for every constellation reported by newer files.
+    Extended {
+        /// GPS satellites (US).
+        gps: u16,
+        /// GLONASS satellites (Russia).
+        glonass: u16,
+        /// Galileo satellites (EU).
+        galileo: u16,
+        /// BeiDou satellites (China).
+        beidou: u16,
+    },
+}
+
+impl SatelliteCount {
+    /// Returns the total number of satellites, regardless of type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::point::SatelliteCount;
+    /// let count = SatelliteCount::Specified { gps: 8, glonass: 5 };
+    /// assert_eq!(13, count.total());
+    /// ```
+    pub fn total(&self) -> u16 {
+        match *self {
+            SatelliteCount::Unspecified(n) => n,
+            SatelliteCount::Specified { gps, glonass } => gps + glonass,
+            SatelliteCount::Extended {
+                gps,
+                glonass,
+                galileo,
+                beidou,
+            } => gps + glonass + galileo + beidou,
+        }
+    }
 }
 
 impl Default for SatelliteCount {
@@ -155,3 +605,27 @@ impl Default for SatelliteCount {
         SatelliteCount::Unspecified(0)
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let point = Point {
+            time: 1.0,
+            latitude: Radians::from_degrees(45.0),
+            distance: Some(2.0),
+            accuracy: Some(Accuracy {
+                x: 0.02,
+                satellite_count: Some(SatelliteCount::Specified { gps: 8, glonass: 4 }),
+                ..Default::default()
+            }),
+            covariance: Some(Covariance::from_upper([0.5; 21])),
+            fix_quality: Some(FixQuality::RtkFixed),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(point, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -1,12 +1,24 @@
 //! Position and orientation quality files.
 
+use crate::pof::Intervals;
 use crate::point::{Accuracy, SatelliteCount};
 use crate::units::Radians;
-use byteorder::{LittleEndian, ReadBytesExt};
+use crate::Location;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::fmt;
 use std::fs::File;
-use std::io::{BufReader, Read, Seek};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::iter::IntoIterator;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// The size of a version 1.2 record, the newest layout that we know about, in bytes.
+const KNOWN_RECORD_SIZE: usize = 72;
+
+/// The most unknown trailing bytes per record that we'll look for in newer versions.
+const MAX_TRAILING_SIZE: usize = 64;
+
+/// The size of the header, in bytes.
+const HEADER_SIZE: usize = 63;
 
 /// A poq file reader.
 #[derive(Debug)]
@@ -17,6 +29,9 @@ pub struct Reader<R: Read + Seek> {
     pub maxint: f64,
     pub version: Version,
     reader: R,
+    trailing: i64,
+    record: u64,
+    path: Option<PathBuf>,
 }
 
 impl Reader<BufReader<File>> {
@@ -28,14 +43,30 @@ impl Reader<BufReader<File>> {
     /// use pos::poq::Reader;
     /// let reader = Reader::from_path("data/sbet_mission_1.poq").unwrap();
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, std::io::Error> {
-        let reader = BufReader::new(File::open(path)?);
-        Reader::new(reader)
+        let path = path.as_ref();
+        let mut reader = Reader::new(BufReader::new(File::open(path)?))?;
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
     }
 }
 
 impl<R: Seek + Read> Reader<R> {
-    fn new(mut reader: R) -> Result<Reader<R>, std::io::Error> {
+    /// Creates a new reader, reading the header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::poq::Reader;
+    /// use std::fs::File;
+    /// let file = File::open("data/sbet_mission_1.poq").unwrap();
+    /// let reader = Reader::new(file).unwrap();
+    /// ```
+    pub fn new(mut reader: R) -> Result<Reader<R>, std::io::Error> {
         let mut preamble = [0; 35];
         reader.read_exact(&mut preamble)?;
 
@@ -45,16 +76,34 @@ impl<R: Seek + Read> Reader<R> {
         let avgint = reader.read_f64::<LittleEndian>()?;
         let maxint = reader.read_f64::<LittleEndian>()?;
         let devint = reader.read_f64::<LittleEndian>()?;
+        let trailing = if version.is_known() {
+            0
+        } else {
+            infer_trailing(&mut reader, maxint)?
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%version, avgint, maxint, devint, "read poq header");
 
         Ok(Reader {
             avgint,
             devint,
             maxint,
             reader,
+            trailing,
             version,
+            record: 0,
+            path: None,
         })
     }
 
+    /// Returns the path and the index of the next record, to locate read errors.
+    pub(crate) fn location(&self) -> Location {
+        Location {
+            path: self.path.clone(),
+            record: Some(self.record),
+        }
+    }
+
     /// Reads a record from this reader.
     ///
     /// # Examples
@@ -81,16 +130,23 @@ impl<R: Seek + Read> Reader<R> {
         let pitch = self.reader.read_f64::<LittleEndian>()?;
         let yaw = self.reader.read_f64::<LittleEndian>()?;
         let pdop = self.reader.read_f64::<LittleEndian>()?;
-        let satellite_count = if self.version.specifies_satellite_count() {
-            let ngps = self.reader.read_u16::<LittleEndian>()?;
-            let nglonass = self.reader.read_u16::<LittleEndian>()?;
-            SatelliteCount::Specified {
-                gps: ngps,
-                glonass: nglonass,
-            }
-        } else {
-            SatelliteCount::Unspecified(self.reader.read_u16::<LittleEndian>()?)
+        let satellite_count = match self.version.satellite_systems() {
+            1 => SatelliteCount::Unspecified(self.reader.read_u16::<LittleEndian>()?),
+            2 => SatelliteCount::Specified {
+                gps: self.reader.read_u16::<LittleEndian>()?,
+                glonass: self.reader.read_u16::<LittleEndian>()?,
+            },
+            _ => SatelliteCount::Extended {
+                gps: self.reader.read_u16::<LittleEndian>()?,
+                glonass: self.reader.read_u16::<LittleEndian>()?,
+                galileo: self.reader.read_u16::<LittleEndian>()?,
+                beidou: self.reader.read_u16::<LittleEndian>()?,
+            },
         };
+        if self.trailing > 0 {
+            let _ = self.reader.seek(SeekFrom::Current(self.trailing))?;
+        }
+        self.record += 1;
 
         Ok(Some(Accuracy {
             time,
@@ -101,6 +157,8 @@ impl<R: Seek + Read> Reader<R> {
             pitch: Radians::from_degrees(pitch),
             yaw: Radians::from_degrees(yaw),
             pdop,
+            hdop: None,
+            vdop: None,
             satellite_count: Some(satellite_count),
         }))
     }
@@ -115,6 +173,9 @@ impl<R: Seek + Read> IntoIterator for Reader<R> {
 }
 
 /// An iterator over a poq reader.
+///
+/// Panics if an accuracy can't be read. Use
+/// [AccuracySource::try_iter](crate::source::AccuracySource::try_iter) to handle errors.
 #[derive(Debug)]
 pub struct ReaderIterator<R: Read + Seek> {
     reader: Reader<R>,
@@ -127,8 +188,168 @@ impl<R: Read + Seek> Iterator for ReaderIterator<R> {
     }
 }
 
+/// A poq file writer.
+///
+/// The header's interval statistics depend on every record, so a placeholder header is written
+/// on creation and patched with the real values on [close](Writer::close).
+#[derive(Debug)]
+pub struct Writer<W: Write + Seek> {
+    writer: W,
+    start: u64,
+    version: Version,
+    last_time: Option<f64>,
+    intervals: Intervals,
+}
+
+impl Writer<BufWriter<File>> {
+    /// Creates a new writer for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::poq::Writer;
+    /// let writer = Writer::from_path("out.poq").unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Writer<BufWriter<File>>, std::io::Error> {
+        Writer::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek> Writer<W> {
+    /// Creates a new version 1.2 writer, writing a placeholder header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::poq::Writer;
+    /// use std::io::Cursor;
+    /// let writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+    /// ```
+    pub fn new(mut writer: W) -> Result<Writer<W>, std::io::Error> {
+        let start = writer.stream_position()?;
+        writer.write_all(&[0; HEADER_SIZE])?;
+        Ok(Writer {
+            writer,
+            start,
+            version: Version::new(1, 2),
+            last_time: None,
+            intervals: Intervals::default(),
+        })
+    }
+
+    /// Sets the version to write, which determines how satellite counts are encoded.
+    ///
+    /// Version 1.0 writes the total count, 1.1 writes GPS and GLONASS counts, and 1.2 and later
+    /// write GPS, GLONASS, Galileo, and BeiDou counts. Counts that a version can't hold are
+    /// dropped, and an unspecified count is written as GPS satellites. Versions newer than 1.2
+    /// are written with the version 1.2 record layout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::poq::{Version, Writer};
+    /// use std::io::Cursor;
+    /// let writer = Writer::new(Cursor::new(Vec::new())).unwrap().with_version(Version::new(1, 1));
+    /// ```
+    pub fn with_version(mut self, version: Version) -> Writer<W> {
+        self.version = version;
+        self
+    }
+
+    /// Writes an accuracy record.
+    ///
+    /// A missing satellite count is written as zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::point::Accuracy;
+    /// use pos::poq::Writer;
+    /// use std::io::Cursor;
+    /// let mut writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+    /// writer.write_accuracy(&Accuracy::default()).unwrap();
+    /// ```
+    pub fn write_accuracy(&mut self, accuracy: &Accuracy) -> Result<(), std::io::Error> {
+        for value in [
+            accuracy.time,
+            accuracy.y,
+            accuracy.x,
+            accuracy.z,
+            accuracy.roll.to_degrees(),
+            accuracy.pitch.to_degrees(),
+            accuracy.yaw.to_degrees(),
+            accuracy.pdop,
+        ] {
+            self.writer.write_f64::<LittleEndian>(value)?;
+        }
+        let count = accuracy
+            .satellite_count
+            .unwrap_or(SatelliteCount::Unspecified(0));
+        let counts = match self.version.satellite_systems() {
+            1 => vec![count.total()],
+            systems => {
+                let mut counts = match count {
+                    SatelliteCount::Unspecified(n) => vec![n, 0, 0, 0],
+                    SatelliteCount::Specified { gps, glonass } => vec![gps, glonass, 0, 0],
+                    SatelliteCount::Extended {
+                        gps,
+                        glonass,
+                        galileo,
+                        beidou,
+                    } => vec![gps, glonass, galileo, beidou],
+                };
+                counts.truncate(systems);
+                counts
+            }
+        };
+        for count in counts {
+            self.writer.write_u16::<LittleEndian>(count)?;
+        }
+        if let Some(last_time) = self.last_time {
+            self.intervals.add(accuracy.time - last_time);
+        }
+        self.last_time = Some(accuracy.time);
+        Ok(())
+    }
+
+    /// Patches the header, flushes, and returns the underlying writer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::point::Accuracy;
+    /// use pos::poq::{Reader, Writer};
+    /// use std::io::Cursor;
+    /// let mut writer = Writer::new(Cursor::new(Vec::new())).unwrap();
+    /// writer.write_accuracy(&Accuracy { time: 1.0, ..Default::default() }).unwrap();
+    /// writer.write_accuracy(&Accuracy { time: 1.2, ..Default::default() }).unwrap();
+    /// let mut cursor = writer.close().unwrap();
+    /// cursor.set_position(0);
+    /// let reader = Reader::new(cursor).unwrap();
+    /// assert!((reader.avgint - 0.2).abs() < 1e-12);
+    /// ```
+    pub fn close(mut self) -> Result<W, std::io::Error> {
+        let end = self.writer.stream_position()?;
+        let _ = self.writer.seek(SeekFrom::Start(self.start))?;
+        self.writer.write_all(&[0; 35])?;
+        self.writer.write_u16::<LittleEndian>(self.version.major)?;
+        self.writer.write_u16::<LittleEndian>(self.version.minor)?;
+        for value in [
+            self.intervals.mean(),
+            self.intervals.max(),
+            self.intervals.deviation(),
+        ] {
+            self.writer.write_f64::<LittleEndian>(value)?;
+        }
+        let _ = self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
 /// poq file version.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Version {
     major: u16,
     minor: u16,
@@ -147,8 +368,61 @@ impl Version {
         Version { major, minor }
     }
 
-    fn specifies_satellite_count(&self) -> bool {
-        self.minor >= 1
+    /// Returns the number of satellite counts in each record.
+    ///
+    /// Version 1.0 has a single, unspecified count, 1.1 adds GPS and GLONASS, and 1.2 and later
+    /// add Galileo and BeiDou.
+    fn satellite_systems(&self) -> usize {
+        match self.minor {
+            0 => 1,
+            1 => 2,
+            _ => 4,
+        }
+    }
+
+    /// Returns true if we know this version's complete record layout.
+    fn is_known(&self) -> bool {
+        self.major == 1 && self.minor <= 2
+    }
+}
+
+/// Finds the number of unknown bytes at the end of each record in a newer version.
+///
+/// Newer versions are assumed to start with the version 1.2 layout. The trailing size is the
+/// smallest one where the second record's time follows the first's by no more than the header's
+/// maximum interval. If there's only one record, or no size fits, the records are assumed to
+/// have no trailing bytes. The reader is left at the start of the first record.
+fn infer_trailing<R: Read + Seek>(reader: &mut R, maxint: f64) -> Result<i64, std::io::Error> {
+    let start = reader.stream_position()?;
+    let mut bytes = Vec::new();
+    let _ = reader
+        .by_ref()
+        .take((KNOWN_RECORD_SIZE + MAX_TRAILING_SIZE + 8) as u64)
+        .read_to_end(&mut bytes)?;
+    let _ = reader.seek(SeekFrom::Start(start))?;
+    let time = |offset: usize| {
+        bytes
+            .get(offset..offset + 8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+    };
+    let first = match time(0) {
+        Some(time) => time,
+        None => return Ok(0),
+    };
+    let maxint = if maxint > 0.0 { maxint } else { 1.0 };
+    Ok((0..=MAX_TRAILING_SIZE)
+        .step_by(2)
+        .find(|&trailing| {
+            time(KNOWN_RECORD_SIZE + trailing)
+                .map(|second| second > first && second - first <= maxint * (1.0 + 1e-9))
+                .unwrap_or(false)
+        })
+        .unwrap_or(0) as i64)
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
     }
 }
 
@@ -156,10 +430,121 @@ impl Version {
 mod tests {
     use super::*;
 
+    fn record(bytes: &mut Vec<u8>, time: f64, counts: &[u16], trailing: usize) {
+        bytes.extend_from_slice(&time.to_le_bytes());
+        for _ in 0..7 {
+            bytes.extend_from_slice(&1.0f64.to_le_bytes());
+        }
+        for count in counts {
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+        bytes.resize(bytes.len() + trailing, 0xff);
+    }
+
+    fn header(minor: u16) -> Vec<u8> {
+        let mut bytes = vec![0; 35];
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&minor.to_le_bytes());
+        for value in [0.005f64, 0.005, 0.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn extended() {
+        let mut bytes = header(2);
+        record(&mut bytes, 1.0, &[8, 5, 6, 7], 0);
+        let accuracy = Reader::new(std::io::Cursor::new(bytes))
+            .unwrap()
+            .read_accuracy()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Some(SatelliteCount::Extended {
+                gps: 8,
+                glonass: 5,
+                galileo: 6,
+                beidou: 7
+            }),
+            accuracy.satellite_count
+        );
+        assert_eq!(26, accuracy.satellite_count.unwrap().total());
+    }
+
+    #[test]
+    fn unknown_trailing_fields() {
+        let mut bytes = header(5);
+        for i in 0..3 {
+            record(&mut bytes, 1.0 + 0.005 * i as f64, &[8, 5, 6, 7], 6);
+        }
+        let accuracies: Vec<_> = Reader::new(std::io::Cursor::new(bytes))
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(3, accuracies.len());
+        assert!((1.01 - accuracies[2].time).abs() < 1e-12);
+        assert_eq!(26, accuracies[2].satellite_count.unwrap().total());
+    }
+
     #[test]
     fn iter() {
         let reader = Reader::from_path("data/sbet_mission_1.poq").unwrap();
         let records: Vec<_> = reader.into_iter().zip(0..5571).map(|(r, _)| r).collect();
         assert_eq!(5571, records.len());
     }
+
+    #[test]
+    fn write() {
+        use std::io::Cursor;
+
+        let accuracies: Vec<_> = (0..3)
+            .map(|i| Accuracy {
+                time: i as f64,
+                x: 0.1,
+                pdop: 1.5,
+                satellite_count: Some(SatelliteCount::Extended {
+                    gps: 8,
+                    glonass: 5,
+                    galileo: 6,
+                    beidou: i,
+                }),
+                ..Default::default()
+            })
+            .collect();
+        for (minor, expected) in [
+            (0, SatelliteCount::Unspecified(21)),
+            (1, SatelliteCount::Specified { gps: 8, glonass: 5 }),
+            (
+                2,
+                SatelliteCount::Extended {
+                    gps: 8,
+                    glonass: 5,
+                    galileo: 6,
+                    beidou: 2,
+                },
+            ),
+        ] {
+            let mut writer = Writer::new(Cursor::new(Vec::new()))
+                .unwrap()
+                .with_version(Version::new(1, minor));
+            for accuracy in &accuracies {
+                writer.write_accuracy(accuracy).unwrap();
+            }
+            let bytes = writer.close().unwrap().into_inner();
+            if minor == 2 {
+                assert_eq!(crate::testutil::poq(&accuracies), bytes);
+            }
+            let reader = Reader::new(Cursor::new(bytes)).unwrap();
+            assert_eq!(Version::new(1, minor), reader.version);
+            assert_eq!(
+                (1.0, 1.0, 0.0),
+                (reader.avgint, reader.maxint, reader.devint)
+            );
+            let read: Vec<_> = reader.into_iter().collect();
+            assert_eq!(3, read.len());
+            assert_eq!(0.1, read[2].x);
+            assert_eq!(Some(expected), read[2].satellite_count);
+        }
+    }
 }
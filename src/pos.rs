@@ -1,18 +1,169 @@
 //! Pos files are ASCII position files.
+//!
+//! Inertial Explorer and GrafNav exports, read with [Profile::INERTIAL_EXPLORER] and
+//! [Profile::GRAFNAV], have a GPS week column. By default their times are read as seconds of the
+//! week, like sbet files and [Profile::POS], so they line up with other formats when they're
+//! compared or merged. Set [Profile::gps_time] to [GpsTime::SinceEpoch] to add the week and
+//! read seconds since the GPS epoch instead:
+//!
+//! ```
+//! use pos::pos::{GpsTime, Profile};
+//! let profile = Profile {
+//!     gps_time: GpsTime::SinceEpoch,
+//!     ..Profile::GRAFNAV
+//! };
+//! ```
 
-use crate::point::Point;
-use crate::source::Source;
-use crate::units::Radians;
-use crate::Error;
+use crate::datum::VerticalDatum;
+use crate::point::{Accuracy, Covariance, FixQuality, Point};
+use crate::sink::Sink;
+use crate::source::{SeekableSource, Source};
+use crate::units::{AngleUnit, LengthUnit};
+use crate::{Error, Location};
 use std::fmt::Debug;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Marks the vertical datum in a header line.
+const VERTICAL_DATUM_TAG: &str = "# vertical_datum=";
+
+/// The number of seconds in a GPS week.
+const SECONDS_PER_WEEK: f64 = 604_800.0;
+
+/// The number of points between entries of a reader's seek index.
+const INDEX_INTERVAL: u64 = 1024;
+
+/// The zero-based columns that a [Reader] reads.
+///
+/// Profiles for other exports can be built by hand, or one of the named profiles can be used.
+/// Named profiles can be parsed from `pos`, `inertial-explorer` (or `ie`), and `grafnav`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Profile {
+    /// The GPS week, which is only read if [Profile::gps_time] is [GpsTime::SinceEpoch].
+    pub week: Option<usize>,
+    /// The time, in seconds of the week if there's a week column.
+    pub time: usize,
+    /// Whether point times are seconds of the week or seconds since the GPS epoch, when there's
+    /// a week column.
+    pub gps_time: GpsTime,
+    /// The latitude.
+    pub latitude: usize,
+    /// The longitude.
+    pub longitude: usize,
+    /// The altitude.
+    pub altitude: usize,
+    /// The roll, pitch, and yaw. Without them, the attitude is zero.
+    pub attitude: Option<[usize; 3]>,
+    /// The north, east, and height standard deviations, which are read into [Point::accuracy].
+    pub position_sd: Option<[usize; 3]>,
+    /// The roll, pitch, and yaw standard deviations, in the file's angle unit, which are read
+    /// into [Point::accuracy].
+    pub attitude_sd: Option<[usize; 3]>,
+}
+
+impl Profile {
+    /// The layout written by [Writer]: time, latitude, longitude, altitude, roll, pitch, and yaw.
+    pub const POS: Profile = Profile {
+        week: None,
+        time: 0,
+        gps_time: GpsTime::SecondsOfWeek,
+        latitude: 1,
+        longitude: 2,
+        altitude: 3,
+        attitude: Some([4, 5, 6]),
+        position_sd: None,
+        attitude_sd: None,
+    };
+
+    /// NovAtel Inertial Explorer's export of a combined solution: week, GPS time, latitude,
+    /// longitude, ellipsoidal height, north, east, and height standard deviations, roll, pitch,
+    /// heading, and roll, pitch, and heading standard deviations.
+    pub const INERTIAL_EXPLORER: Profile = Profile {
+        week: Some(0),
+        time: 1,
+        gps_time: GpsTime::SecondsOfWeek,
+        latitude: 2,
+        longitude: 3,
+        altitude: 4,
+        attitude: Some([8, 9, 10]),
+        position_sd: Some([5, 6, 7]),
+        attitude_sd: Some([11, 12, 13]),
+    };
+
+    /// NovAtel GrafNav's export of a GNSS solution: week, GPS time, latitude, longitude,
+    /// ellipsoidal height, and north, east, and height standard deviations.
+    pub const GRAFNAV: Profile = Profile {
+        week: Some(0),
+        time: 1,
+        gps_time: GpsTime::SecondsOfWeek,
+        latitude: 2,
+        longitude: 3,
+        altitude: 4,
+        attitude: None,
+        position_sd: Some([5, 6, 7]),
+        attitude_sd: None,
+    };
+}
+
+impl Profile {
+    /// Reads the time from a line's values, adding the week if times are since the GPS epoch.
+    pub(crate) fn time(&self, values: &[&str]) -> Result<f64, Error> {
+        let column = |column: usize| values.get(column).ok_or(Error::MissingColumn(column));
+        let mut time: f64 = column(self.time)?.parse()?;
+        if let (Some(week), GpsTime::SinceEpoch) = (self.week, self.gps_time) {
+            time += column(week)?.parse::<f64>()? * SECONDS_PER_WEEK;
+        }
+        Ok(time)
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Profile {
+        Profile::POS
+    }
+}
+
+impl FromStr for Profile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Profile, Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "pos" => Ok(Profile::POS),
+            "inertial-explorer" | "ie" => Ok(Profile::INERTIAL_EXPLORER),
+            "grafnav" => Ok(Profile::GRAFNAV),
+            _ => Err(Error::UnknownProfile(s.to_string())),
+        }
+    }
+}
+
+/// The time base of a [Profile] with a week column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GpsTime {
+    /// Seconds of the GPS week, ignoring the week column.
+    #[default]
+    SecondsOfWeek,
+    /// Seconds since the GPS epoch, adding the week column to the time.
+    SinceEpoch,
+}
 
 /// A pos reader.
 #[derive(Debug)]
 pub struct Reader<R: BufRead> {
     reader: R,
+    profile: Profile,
+    data: bool,
+    record: u64,
+    offset: u64,
+    index: Vec<(f64, u64, u64)>,
+    quality_column: Option<usize>,
+    heave_column: Option<usize>,
+    covariance_column: Option<usize>,
+    angle_unit: AngleUnit,
+    height_unit: LengthUnit,
+    vertical_datum: Option<VerticalDatum>,
+    path: Option<PathBuf>,
 }
 
 impl Reader<BufReader<File>> {
@@ -24,15 +175,178 @@ impl Reader<BufReader<File>> {
     /// use pos::pos::Reader;
     /// let reader = Reader::from_path("data/0916_2014_ie.pos").unwrap();
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, std::io::Error> {
-        let mut reader = BufReader::new(File::open(path)?);
-        let mut header = String::new();
-        let _ = reader.read_line(&mut header)?;
-        Ok(Reader { reader })
+        let path = path.as_ref();
+        let mut reader = Reader::new(BufReader::new(File::open(path)?))?;
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
     }
 }
 
 impl<R: BufRead> Reader<R> {
+    /// Creates a new reader, reading the header line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pos::Reader;
+    /// let bytes = b"time latitude longitude altitude roll pitch yaw\n";
+    /// let reader = Reader::new(&bytes[..]).unwrap();
+    /// ```
+    pub fn new(mut reader: R) -> Result<Reader<R>, std::io::Error> {
+        let mut header = String::new();
+        let offset = reader.read_line(&mut header)? as u64;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(header = header.trim_end(), "read pos header");
+        let vertical_datum = header
+            .split_once(VERTICAL_DATUM_TAG)
+            .and_then(|(_, datum)| datum.trim().parse().ok());
+        Ok(Reader {
+            reader,
+            profile: Profile::POS,
+            data: false,
+            record: 0,
+            offset,
+            index: Vec::new(),
+            quality_column: None,
+            heave_column: None,
+            covariance_column: None,
+            angle_unit: AngleUnit::Degrees,
+            height_unit: LengthUnit::Meters,
+            vertical_datum,
+            path: None,
+        })
+    }
+
+    /// Returns the vertical datum of the file's heights, if its header is tagged with one.
+    ///
+    /// [Writer::with_vertical_datum] writes the tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::datum::VerticalDatum;
+    /// use pos::pos::Reader;
+    /// let bytes = b"time latitude longitude altitude roll pitch yaw # vertical_datum=ellipsoidal\n";
+    /// let reader = Reader::new(&bytes[..]).unwrap();
+    /// assert_eq!(Some(&VerticalDatum::Ellipsoidal), reader.vertical_datum());
+    /// ```
+    pub fn vertical_datum(&self) -> Option<&VerticalDatum> {
+        self.vertical_datum.as_ref()
+    }
+
+    /// Sets the columns that are read, which default to [Profile::POS].
+    ///
+    /// Exports often start with more than one header line. Until the first point has been read,
+    /// lines that don't start with a number are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pos::{Profile, Reader};
+    /// let bytes = b"Project: mission
+    /// Week GPSTime Latitude Longitude H-Ell SDNorth SDEast SDHeight
+    /// (weeks) (sec) (deg) (deg) (m) (m) (m) (m)
+    /// 1840 151631.0 32.5 -116.9 107.7 0.02 0.01 0.05
+    /// ";
+    /// let mut reader = Reader::new(&bytes[..]).unwrap().with_profile(Profile::GRAFNAV);
+    /// let point = reader.read_point().unwrap().unwrap();
+    /// assert_eq!(151_631.0, point.time);
+    /// assert_eq!(0.01, point.accuracy.unwrap().x);
+    /// ```
+    pub fn with_profile(mut self, profile: Profile) -> Reader<R> {
+        self.profile = profile;
+        self
+    }
+
+    /// Sets the unit of the angles in the file, which defaults to degrees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pos::Reader;
+    /// use pos::units::AngleUnit;
+    /// let bytes = b"time latitude longitude altitude roll pitch yaw\n";
+    /// let reader = Reader::new(&bytes[..])
+    ///     .unwrap()
+    ///     .with_angle_unit(AngleUnit::Radians);
+    /// ```
+    pub fn with_angle_unit(mut self, angle_unit: AngleUnit) -> Reader<R> {
+        self.angle_unit = angle_unit;
+        self
+    }
+
+    /// Sets the unit of the altitudes in the file, which defaults to meters.
+    pub fn with_height_unit(mut self, height_unit: LengthUnit) -> Reader<R> {
+        self.height_unit = height_unit;
+        self
+    }
+
+    /// Reads an RTKLIB `Q` code from this zero-based column into [Point::fix_quality].
+    ///
+    /// Unknown codes leave the fix quality empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::point::FixQuality;
+    /// use pos::pos::Reader;
+    /// let bytes = b"time latitude longitude altitude roll pitch yaw q\n1 32.5 -117 107 0 0 0 1\n";
+    /// let mut reader = Reader::new(&bytes[..]).unwrap().with_quality_column(7);
+    /// let point = reader.read_point().unwrap().unwrap();
+    /// assert_eq!(Some(FixQuality::RtkFixed), point.fix_quality);
+    /// ```
+    pub fn with_quality_column(mut self, column: usize) -> Reader<R> {
+        self.quality_column = Some(column);
+        self
+    }
+
+    /// Reads heave from this zero-based column into [Point::heave].
+    ///
+    /// Heave is in the file's height unit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pos::Reader;
+    /// let bytes = b"time latitude longitude altitude roll pitch yaw heave\n1 32.5 -117 107 0 0 0 0.25\n";
+    /// let mut reader = Reader::new(&bytes[..]).unwrap().with_heave_column(7);
+    /// let point = reader.read_point().unwrap().unwrap();
+    /// assert_eq!(Some(0.25), point.heave);
+    /// ```
+    pub fn with_heave_column(mut self, column: usize) -> Reader<R> {
+        self.heave_column = Some(column);
+        self
+    }
+
+    /// Reads a [Covariance] from 21 columns, starting at this zero-based column, into
+    /// [Point::covariance].
+    ///
+    /// The columns are the upper triangle of the covariance, row by row, in meters and radians
+    /// regardless of the file's units. Lines with too few columns leave the covariance empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pos::Reader;
+    /// let mut line = "1 32.5 -117 107 0 0 0".to_string();
+    /// for i in 0..21 {
+    ///     line.push_str(&format!(" {}", i));
+    /// }
+    /// let bytes = format!("header\n{}\n", line);
+    /// let mut reader = Reader::new(bytes.as_bytes()).unwrap().with_covariance_columns(7);
+    /// let point = reader.read_point().unwrap().unwrap();
+    /// assert_eq!(20.0, point.covariance.unwrap().get(5, 5));
+    /// ```
+    pub fn with_covariance_columns(mut self, first: usize) -> Reader<R> {
+        self.covariance_column = Some(first);
+        self
+    }
+
     /// Reads a point from the file.
     ///
     /// # Examples
@@ -44,21 +358,94 @@ impl<R: BufRead> Reader<R> {
     /// ```
     pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
         let mut line = String::new();
-        let _ = self.reader.read_line(&mut line)?;
-        let values: Vec<_> = line.split_whitespace().collect();
-        if values.is_empty() {
-            return Ok(None);
-        }
-        Ok(Some(Point {
-            time: values[0].parse()?,
-            latitude: Radians::from_degrees(values[1].parse()?),
-            longitude: Radians::from_degrees(values[2].parse()?),
-            altitude: values[3].parse()?,
-            roll: Radians::from_degrees(values[4].parse()?),
-            pitch: Radians::from_degrees(values[5].parse()?),
-            yaw: Radians::from_degrees(values[6].parse()?),
+        let (values, start) = loop {
+            line.clear();
+            let start = self.offset;
+            match self.reader.read_line(&mut line)? {
+                0 => return Ok(None),
+                n => self.offset += n as u64,
+            }
+            let values: Vec<_> = line.split_whitespace().collect();
+            match values.first() {
+                Some(value) if self.data || value.parse::<f64>().is_ok() => break (values, start),
+                _ => continue,
+            }
+        };
+        self.data = true;
+        self.record += 1;
+        let profile = self.profile;
+        let column = |column: usize| values.get(column).ok_or(Error::MissingColumn(column));
+        let angle = |index: usize| -> Result<_, Error> {
+            Ok(self.angle_unit.to_radians(column(index)?.parse()?))
+        };
+        let fix_quality = match self.quality_column.and_then(|column| values.get(column)) {
+            Some(value) => value.parse().ok().and_then(FixQuality::from_rtklib),
+            None => None,
+        };
+        let heave = match self.heave_column.and_then(|column| values.get(column)) {
+            Some(value) => Some(self.height_unit.to_meters(value.parse()?)),
+            None => None,
+        };
+        let covariance = match self
+            .covariance_column
+            .and_then(|first| values.get(first..first + 21))
+        {
+            Some(columns) => {
+                let mut upper = [0.0; 21];
+                for (value, column) in upper.iter_mut().zip(columns) {
+                    *value = column.parse()?;
+                }
+                Some(Covariance::from_upper(upper))
+            }
+            None => None,
+        };
+        let time = profile.time(&values)?;
+        let (roll, pitch, yaw) = match profile.attitude {
+            Some([roll, pitch, yaw]) => (angle(roll)?, angle(pitch)?, angle(yaw)?),
+            None => Default::default(),
+        };
+        let accuracy = if profile.position_sd.is_some() || profile.attitude_sd.is_some() {
+            let mut accuracy = Accuracy {
+                time,
+                ..Default::default()
+            };
+            if let Some([north, east, height]) = profile.position_sd {
+                accuracy.x = self.height_unit.to_meters(column(east)?.parse()?);
+                accuracy.y = self.height_unit.to_meters(column(north)?.parse()?);
+                accuracy.z = self.height_unit.to_meters(column(height)?.parse()?);
+            }
+            if let Some([roll, pitch, yaw]) = profile.attitude_sd {
+                accuracy.roll = angle(roll)?;
+                accuracy.pitch = angle(pitch)?;
+                accuracy.yaw = angle(yaw)?;
+            }
+            Some(accuracy)
+        } else {
+            None
+        };
+        let point = Point {
+            time,
+            latitude: angle(profile.latitude)?,
+            longitude: angle(profile.longitude)?,
+            altitude: self
+                .height_unit
+                .to_meters(column(profile.altitude)?.parse()?),
+            roll,
+            pitch,
+            yaw,
+            heave,
+            accuracy,
+            covariance,
+            fix_quality,
             ..Default::default()
-        }))
+        };
+        let record = self.record - 1;
+        if record.is_multiple_of(INDEX_INTERVAL)
+            && self.index.last().is_none_or(|entry| entry.2 < record)
+        {
+            self.index.push((time, start, record));
+        }
+        Ok(Some(point))
     }
 }
 
@@ -71,6 +458,9 @@ impl<R: BufRead> IntoIterator for Reader<R> {
 }
 
 /// An iterator over a pos reader.
+///
+/// Panics if a point can't be read. Use [Source::try_iter](crate::Source::try_iter) to handle
+/// errors.
 #[derive(Debug)]
 pub struct ReaderIterator<R: BufRead> {
     reader: Reader<R>,
@@ -85,13 +475,207 @@ impl<R: BufRead> Iterator for ReaderIterator<R> {
 
 impl<R: Debug + BufRead> Source for Reader<R> {
     fn source(&mut self) -> Result<Option<Point>, Error> {
-        self.read_point()
+        let record = self.record;
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(record),
+            })
+        });
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read pos record");
+        }
+        result
+    }
+}
+
+/// Pos files have no fixed record size, so the reader keeps a sparse index of the byte offsets
+/// of the points it has read, every 1024 points. Seeking to a time reads forward from the
+/// nearest indexed point before it, or from the start of the file, assuming that the header
+/// line is at the start of the stream.
+impl<R: Debug + BufRead + Seek> SeekableSource for Reader<R> {
+    fn rewind(&mut self) -> Result<(), Error> {
+        let _ = self.reader.seek(SeekFrom::Start(0))?;
+        self.offset = self.reader.read_line(&mut String::new())? as u64;
+        self.data = false;
+        self.record = 0;
+        Ok(())
+    }
+
+    fn seek_to_time(&mut self, time: f64) -> Result<(), Error> {
+        match self.index.partition_point(|entry| entry.0 < time) {
+            0 => self.rewind()?,
+            n => {
+                let (_, offset, record) = self.index[n - 1];
+                let _ = self.reader.seek(SeekFrom::Start(offset))?;
+                self.offset = offset;
+                self.data = true;
+                self.record = record;
+            }
+        }
+        loop {
+            // Until the first point, the lines before it are header lines that have to be
+            // skipped again after seeking back.
+            let (offset, data, record) = (self.offset, self.data, self.record);
+            match self.read_point()? {
+                Some(point) if point.time < time => continue,
+                Some(_) => {
+                    let _ = self.reader.seek(SeekFrom::Start(offset))?;
+                    self.offset = offset;
+                    self.data = data;
+                    self.record = record;
+                    return Ok(());
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+/// A pos writer.
+///
+/// Writes one whitespace-delimited line per point, after a single header line: time, latitude,
+/// longitude, altitude, roll, pitch, and yaw. Angles are written in degrees by default, which is
+/// what [Reader] expects.
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    writer: W,
+    angle_unit: AngleUnit,
+    vertical_datum: Option<VerticalDatum>,
+    header: bool,
+}
+
+impl Writer<BufWriter<File>> {
+    /// Creates a new writer for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::pos::Writer;
+    /// let writer = Writer::from_path("out.pos").unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Writer<BufWriter<File>>, std::io::Error> {
+        Writer::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new writer.
+    ///
+    /// The header line is written before the first point, or when the writer is finished.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pos::Writer;
+    /// let writer = Writer::new(Vec::new()).unwrap();
+    /// ```
+    pub fn new(writer: W) -> Result<Writer<W>, std::io::Error> {
+        Ok(Writer {
+            writer,
+            angle_unit: AngleUnit::Degrees,
+            vertical_datum: None,
+            header: false,
+        })
+    }
+
+    /// Tags the header with the vertical datum of the written heights.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::datum::VerticalDatum;
+    /// use pos::pos::{Reader, Writer};
+    /// let datum = VerticalDatum::Orthometric(Some("EGM2008".to_string()));
+    /// let writer = Writer::new(Vec::new()).unwrap().with_vertical_datum(datum.clone());
+    /// let bytes = writer.into_inner().unwrap();
+    /// let reader = Reader::new(bytes.as_slice()).unwrap();
+    /// assert_eq!(Some(&datum), reader.vertical_datum());
+    /// ```
+    pub fn with_vertical_datum(mut self, vertical_datum: VerticalDatum) -> Writer<W> {
+        self.vertical_datum = Some(vertical_datum);
+        self
+    }
+
+    /// Sets the unit used to write angles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pos::Writer;
+    /// use pos::units::AngleUnit;
+    /// let writer = Writer::new(Vec::new()).unwrap().with_angle_unit(AngleUnit::Radians);
+    /// ```
+    pub fn with_angle_unit(mut self, angle_unit: AngleUnit) -> Writer<W> {
+        self.angle_unit = angle_unit;
+        self
+    }
+
+    /// Writes a point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::pos::Writer;
+    /// use pos::Point;
+    /// let mut writer = Writer::new(Vec::new()).unwrap();
+    /// writer.write_point(&Point::default()).unwrap();
+    /// ```
+    pub fn write_point(&mut self, point: &Point) -> Result<(), std::io::Error> {
+        self.write_header()?;
+        let unit = self.angle_unit;
+        writeln!(
+            self.writer,
+            "{} {} {} {} {} {} {}",
+            point.time,
+            unit.convert(point.latitude),
+            unit.convert(point.longitude),
+            point.altitude,
+            unit.convert(point.roll),
+            unit.convert(point.pitch),
+            unit.convert(point.yaw)
+        )
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, std::io::Error> {
+        self.write_header()?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_header(&mut self) -> Result<(), std::io::Error> {
+        if self.header {
+            return Ok(());
+        }
+        self.header = true;
+        write!(
+            self.writer,
+            "time latitude longitude altitude roll pitch yaw"
+        )?;
+        if let Some(vertical_datum) = &self.vertical_datum {
+            write!(self.writer, " {}{}", VERTICAL_DATUM_TAG, vertical_datum)?;
+        }
+        writeln!(self.writer)
+    }
+}
+
+impl<W: Write + Debug> Sink for Writer<W> {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        self.write_point(point).map_err(Error::from)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.write_header()?;
+        self.writer.flush().map_err(Error::from)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::units::Radians;
 
     #[test]
     fn point_count() {
@@ -101,4 +685,216 @@ mod tests {
             .collect();
         assert_eq!(722800, points.len());
     }
+
+    #[test]
+    fn roundtrip() {
+        let point = Point {
+            time: 151631.002836,
+            latitude: Radians::from_degrees(32.545216592),
+            longitude: Radians::from_degrees(-116.978179903),
+            altitude: 107.715,
+            roll: Radians::from_degrees(-1.611964),
+            pitch: Radians::from_degrees(-1.392233),
+            yaw: Radians::from_degrees(174.567247),
+            ..Default::default()
+        };
+        let mut writer = Writer::new(Vec::new()).unwrap();
+        writer.write_point(&point).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        let mut reader = Reader::new(bytes.as_slice()).unwrap();
+        let read = reader.read_point().unwrap().unwrap();
+        assert_eq!(point.time, read.time);
+        assert!((point.latitude.0 - read.latitude.0).abs() < 1e-15);
+        assert!((point.yaw.0 - read.yaw.0).abs() < 1e-15);
+        assert!(reader.read_point().unwrap().is_none());
+    }
+
+    #[test]
+    fn inertial_explorer() {
+        let bytes = b"Version: Inertial Explorer
+Week GPSTime Latitude Longitude H-Ell SDNorth SDEast SDHeight Roll Pitch Heading RollSD PitchSD HdngSD
+
+1840 151631.0 32.5 -116.9 107.7 0.02 0.01 0.05 -1.6 -1.4 174.5 0.01 0.01 0.03
+1840 151632.0 32.5 -116.9 107.7 0.02 0.01 0.05 -1.6 -1.4
+";
+        let profile: Profile = "ie".parse().unwrap();
+        let mut reader = Reader::new(&bytes[..]).unwrap().with_profile(profile);
+        let point = reader.read_point().unwrap().unwrap();
+        assert_eq!(151_631.0, point.time);
+        assert!((point.yaw.to_degrees() - 174.5).abs() < 1e-12);
+        let accuracy = point.accuracy.unwrap();
+        assert_eq!((0.01, 0.02, 0.05), (accuracy.x, accuracy.y, accuracy.z));
+        assert!((accuracy.yaw.to_degrees() - 0.03).abs() < 1e-12);
+        assert!(matches!(reader.read_point(), Err(Error::MissingColumn(10))));
+        assert!("novatel".parse::<Profile>().is_err());
+    }
+
+    #[test]
+    fn gps_time() {
+        let bytes = b"Week GPSTime Latitude Longitude H-Ell SDNorth SDEast SDHeight
+1840 151631.0 32.5 -116.9 107.7 0.02 0.01 0.05
+";
+        let read = |gps_time| {
+            Reader::new(&bytes[..])
+                .unwrap()
+                .with_profile(Profile {
+                    gps_time,
+                    ..Profile::GRAFNAV
+                })
+                .read_point()
+                .unwrap()
+                .unwrap()
+                .time
+        };
+        assert_eq!(151_631.0, read(GpsTime::default()));
+        assert_eq!(
+            1840.0 * SECONDS_PER_WEEK + 151_631.0,
+            read(GpsTime::SinceEpoch)
+        );
+    }
+
+    #[test]
+    fn quality_column() {
+        let bytes = b"header\n1.0 32.5 -116.9 107.7 -1.6 -1.4 174.5 1\n2.0 32.5 -116.9 107.7 -1.6 -1.4 174.5 0\n";
+        let mut reader = Reader::new(&bytes[..]).unwrap().with_quality_column(7);
+        assert_eq!(
+            Some(FixQuality::RtkFixed),
+            reader.read_point().unwrap().unwrap().fix_quality
+        );
+        assert_eq!(None, reader.read_point().unwrap().unwrap().fix_quality);
+    }
+
+    #[test]
+    fn source_error_location() {
+        let bytes = b"header\n1.0 32.5 -116.9 107.7 -1.6 -1.4 174.5\n2.0 32.5 -116.9\n3.0 32.5 -116.9 107.7 -1.6 -1.4 174.5\n";
+        let mut reader = Reader::new(&bytes[..]).unwrap();
+        assert_eq!(1.0, reader.source().unwrap().unwrap().time);
+        let err = reader.source().unwrap_err();
+        assert_eq!(Some(1), err.location().unwrap().record);
+        assert_eq!(crate::ErrorKind::Parse, err.kind());
+        assert_eq!(3.0, reader.source().unwrap().unwrap().time);
+    }
+
+    #[test]
+    fn from_path_error_location() {
+        let path = std::env::temp_dir().join("pos-rs-pos-location.pos");
+        std::fs::write(
+            &path,
+            "header\n1.0 32.5 -116.9 107.7 -1.6 -1.4 174.5\n2.0 32.5 -116.9\n",
+        )
+        .unwrap();
+        let mut reader = Reader::from_path(&path).unwrap();
+        assert!(reader.source().unwrap().is_some());
+        let err = reader.source().unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        let location = err.location().unwrap();
+        assert_eq!(Some(path.as_path()), location.path.as_deref());
+        assert_eq!(Some(1), location.record);
+    }
+
+    #[test]
+    fn heave_column() {
+        let bytes = b"header\n1.0 32.5 -116.9 107.7 -1.6 -1.4 174.5 1.0\n2.0 32.5 -116.9 107.7 -1.6 -1.4 174.5\n";
+        let mut reader = Reader::new(&bytes[..])
+            .unwrap()
+            .with_heave_column(7)
+            .with_height_unit(LengthUnit::Feet);
+        let heave = reader.read_point().unwrap().unwrap().heave.unwrap();
+        assert!((0.3048 - heave).abs() < 1e-12);
+        assert_eq!(None, reader.read_point().unwrap().unwrap().heave);
+    }
+
+    #[test]
+    fn covariance_columns() {
+        let mut upper = [0.0; 21];
+        upper[0] = 0.01;
+        upper[5] = -0.002;
+        let line = upper
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let bytes = format!("header\n1.0 32.5 -116.9 107.7 -1.6 -1.4 174.5 {}\n2.0 32.5 -116.9 107.7 -1.6 -1.4 174.5 0.1\n", line);
+        let mut reader = Reader::new(bytes.as_bytes())
+            .unwrap()
+            .with_covariance_columns(7);
+        let covariance = reader.read_point().unwrap().unwrap().covariance.unwrap();
+        assert_eq!(0.01, covariance.get(0, 0));
+        assert_eq!(-0.002, covariance.get(5, 0));
+        assert_eq!(None, reader.read_point().unwrap().unwrap().covariance);
+    }
+
+    #[test]
+    fn unit_overrides() {
+        let bytes = b"header\n1.0 0.5 -2.0 100.0 0.0 0.0 3.0\n";
+        let mut reader = Reader::new(&bytes[..])
+            .unwrap()
+            .with_angle_unit(AngleUnit::Radians)
+            .with_height_unit(LengthUnit::Feet);
+        let point = reader.read_point().unwrap().unwrap();
+        assert_eq!(Radians(0.5), point.latitude);
+        assert_eq!(Radians(3.0), point.yaw);
+        assert!((30.48 - point.altitude).abs() < 1e-12);
+    }
+
+    #[test]
+    fn seek_multi_line_header() {
+        let bytes = b"Version: Inertial Explorer
+Week GPSTime Latitude Longitude H-Ell SDNorth SDEast SDHeight
+
+1840 10.0 32.5 -116.9 107.7 0.02 0.01 0.05
+1840 11.0 32.5 -116.9 107.7 0.02 0.01 0.05
+1840 12.0 32.5 -116.9 107.7 0.02 0.01 0.05
+";
+        let week = 1840.0 * SECONDS_PER_WEEK;
+        let mut reader = Reader::new(std::io::Cursor::new(&bytes[..]))
+            .unwrap()
+            .with_profile(Profile {
+                gps_time: GpsTime::SinceEpoch,
+                ..Profile::GRAFNAV
+            });
+        reader.seek_to_time(0.0).unwrap();
+        assert_eq!(week + 10.0, reader.source().unwrap().unwrap().time);
+        reader.seek_to_time(week + 10.0).unwrap();
+        assert_eq!(week + 10.0, reader.source().unwrap().unwrap().time);
+        reader.seek_to_time(week + 11.5).unwrap();
+        assert_eq!(week + 12.0, reader.source().unwrap().unwrap().time);
+        reader.rewind().unwrap();
+        assert_eq!(week + 10.0, reader.source().unwrap().unwrap().time);
+        assert_eq!(week + 11.0, reader.source().unwrap().unwrap().time);
+    }
+
+    #[test]
+    fn seek_index() {
+        let mut bytes = String::from("header\n");
+        for i in 0..3000 {
+            bytes.push_str(&format!("{} 32.5 -117 107 0 0 0\n", i));
+        }
+        let mut reader = Reader::new(std::io::Cursor::new(bytes)).unwrap();
+        reader.seek_to_time(2500.5).unwrap();
+        assert_eq!(2501.0, reader.source().unwrap().unwrap().time);
+        assert_eq!(3, reader.index.len());
+        assert_eq!(2048.0, reader.index[2].0);
+        reader.seek_to_time(1500.0).unwrap();
+        assert_eq!(1500.0, reader.source().unwrap().unwrap().time);
+        reader.seek_to_time(2048.0).unwrap();
+        assert_eq!(2048.0, reader.source().unwrap().unwrap().time);
+        reader.seek_to_time(0.0).unwrap();
+        assert_eq!(0.0, reader.source().unwrap().unwrap().time);
+        reader.seek_to_time(2999.5).unwrap();
+        assert!(reader.source().unwrap().is_none());
+        assert_eq!(3, reader.index.len());
+    }
+
+    #[test]
+    fn seek() {
+        let bytes = "header\n1 32.5 -117 107 0 0 0\n2 32.5 -117 107 0 0 0\n3 32.5 -117 107 0 0 0\n";
+        let mut reader = Reader::new(std::io::Cursor::new(bytes)).unwrap();
+        reader.seek_to_time(1.5).unwrap();
+        assert_eq!(2.0, reader.source().unwrap().unwrap().time);
+        reader.rewind().unwrap();
+        assert_eq!(1.0, reader.source().unwrap().unwrap().time);
+        reader.seek_to_time(4.0).unwrap();
+        assert!(reader.source().unwrap().is_none());
+    }
 }
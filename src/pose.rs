@@ -0,0 +1,77 @@
+//! Poses, and a trait for things that can provide them.
+//!
+//! [PoseProvider] is intended as a small, stable integration point for point cloud processing
+//! crates, which usually only need a position and orientation for a given time.
+
+use crate::interpolate::Interpolator;
+use crate::point::Point;
+use crate::trajectory::Trajectory;
+use crate::units::Radians;
+use crate::Error;
+
+/// The position and orientation of a platform at a time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct Pose {
+    pub time: f64,
+    pub longitude: Radians<f64>,
+    pub latitude: Radians<f64>,
+    pub altitude: f64,
+    pub roll: Radians<f64>,
+    pub pitch: Radians<f64>,
+    pub yaw: Radians<f64>,
+}
+
+/// Something that can provide a pose for a given time.
+pub trait PoseProvider {
+    /// Returns the pose at the given time.
+    fn pose_at(&mut self, time: f64) -> Result<Pose, Error>;
+}
+
+impl From<Point> for Pose {
+    fn from(point: Point) -> Pose {
+        Pose {
+            time: point.time,
+            longitude: point.longitude,
+            latitude: point.latitude,
+            altitude: point.altitude,
+            roll: point.roll,
+            pitch: point.pitch,
+            yaw: point.yaw,
+        }
+    }
+}
+
+impl PoseProvider for Interpolator {
+    fn pose_at(&mut self, time: f64) -> Result<Pose, Error> {
+        self.interpolate(time).map(Pose::from)
+    }
+}
+
+impl PoseProvider for Trajectory {
+    fn pose_at(&mut self, time: f64) -> Result<Pose, Error> {
+        self.interpolate(time).map(Pose::from)
+    }
+}
+
+#[cfg(all(test, feature = "sbet"))]
+mod tests {
+    use super::*;
+    use crate::sbet;
+
+    #[test]
+    fn providers() {
+        let time = 1.51631004836071e5;
+        let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+        let mut interpolator = Interpolator::new(Box::new(reader)).unwrap();
+        let mut reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+        let mut trajectory = Trajectory::from_source(&mut reader).unwrap();
+        let providers: [&mut dyn PoseProvider; 2] = [&mut interpolator, &mut trajectory];
+        let poses: Vec<_> = providers
+            .into_iter()
+            .map(|provider| provider.pose_at(time).unwrap())
+            .collect();
+        assert_eq!(time, poses[0].time);
+        assert_eq!(poses[0], poses[1]);
+    }
+}
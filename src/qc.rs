@@ -0,0 +1,365 @@
+//! Quality-control reports for mission acceptance.
+//!
+//! A [Report] collects everything that usually gets checked before a trajectory is accepted:
+//! gaps, accuracy statistics, periods of degraded accuracy, time stamps that go backwards, and
+//! the area and time range covered. With the `serde` feature, reports can be serialized.
+
+use crate::point::{FixQuality, Point};
+use crate::source::Source;
+use crate::stats::{Gap, Stats, Summary};
+use crate::Error;
+
+/// Builds quality-control reports.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReportBuilder {
+    gap_threshold: Option<f64>,
+    max_horizontal_accuracy: Option<f64>,
+    max_vertical_accuracy: Option<f64>,
+    min_satellites: Option<u16>,
+    min_fix_quality: Option<FixQuality>,
+}
+
+/// A quality-control report.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// Trajectory statistics, including gaps and accuracy distributions.
+    pub summary: Summary,
+    /// The area and time range covered by the trajectory.
+    pub coverage: Coverage,
+    /// Periods where accuracy, satellite count, or fix quality failed the configured thresholds.
+    pub outages: Vec<Outage>,
+    /// Points whose time is not greater than the time of the point before them.
+    pub non_monotonic: Vec<TimeEvent>,
+}
+
+/// The area and time range covered by a trajectory.
+///
+/// Longitudes and latitudes are in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub struct Coverage {
+    pub start: f64,
+    pub end: f64,
+    pub min_longitude: f64,
+    pub max_longitude: f64,
+    pub min_latitude: f64,
+    pub max_latitude: f64,
+    pub min_altitude: f64,
+    pub max_altitude: f64,
+}
+
+/// A period of consecutive points that failed an accuracy, satellite, or fix quality threshold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outage {
+    /// The time of the first failing point.
+    pub start: f64,
+    /// The time of the last failing point.
+    pub end: f64,
+    /// The number of failing points.
+    pub count: u64,
+}
+
+/// A point whose time does not increase.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeEvent {
+    /// The zero-based index of the point in its source.
+    pub index: u64,
+    /// The time of the point.
+    pub time: f64,
+    /// The time of the point before it.
+    pub previous_time: f64,
+}
+
+impl ReportBuilder {
+    /// Creates a new builder with no thresholds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::qc::ReportBuilder;
+    /// let builder = ReportBuilder::new().max_horizontal_accuracy(0.1).min_satellites(6);
+    /// ```
+    pub fn new() -> ReportBuilder {
+        ReportBuilder::default()
+    }
+
+    /// Sets the interval, in seconds, above which a gap is reported.
+    ///
+    /// Defaults to twice the median interval.
+    pub fn gap_threshold(mut self, gap_threshold: f64) -> ReportBuilder {
+        self.gap_threshold = Some(gap_threshold);
+        self
+    }
+
+    /// Reports points with a horizontal accuracy above this value, in meters, as outages.
+    pub fn max_horizontal_accuracy(mut self, max_horizontal_accuracy: f64) -> ReportBuilder {
+        self.max_horizontal_accuracy = Some(max_horizontal_accuracy);
+        self
+    }
+
+    /// Reports points with a vertical accuracy above this value, in meters, as outages.
+    pub fn max_vertical_accuracy(mut self, max_vertical_accuracy: f64) -> ReportBuilder {
+        self.max_vertical_accuracy = Some(max_vertical_accuracy);
+        self
+    }
+
+    /// Reports points with fewer satellites than this as outages.
+    pub fn min_satellites(mut self, min_satellites: u16) -> ReportBuilder {
+        self.min_satellites = Some(min_satellites);
+        self
+    }
+
+    /// Reports points with a fix quality worse than this as outages.
+    ///
+    /// Points without a fix quality are not checked.
+    pub fn min_fix_quality(mut self, min_fix_quality: FixQuality) -> ReportBuilder {
+        self.min_fix_quality = Some(min_fix_quality);
+        self
+    }
+
+    /// Reads every point from a source and builds a report, or `None` if the source is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::qc::ReportBuilder;
+    /// use pos::sbet::Reader;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let report = ReportBuilder::new().build(&mut reader).unwrap().unwrap();
+    /// assert!(report.is_clean());
+    /// ```
+    pub fn build(&self, source: &mut dyn Source) -> Result<Option<Report>, Error> {
+        let mut stats = Stats::new();
+        if let Some(gap_threshold) = self.gap_threshold {
+            stats = stats.with_gap_threshold(gap_threshold);
+        }
+        let mut coverage: Option<Coverage> = None;
+        let mut outages = Vec::new();
+        let mut outage: Option<Outage> = None;
+        let mut non_monotonic = Vec::new();
+        let mut previous_time = None;
+        let mut index = 0;
+        while let Some(point) = source.source()? {
+            stats.add(&point);
+            match coverage.as_mut() {
+                Some(coverage) => coverage.add(&point),
+                None => coverage = Some(Coverage::new(&point)),
+            }
+            if let Some(previous_time) = previous_time {
+                if point.time <= previous_time {
+                    non_monotonic.push(TimeEvent {
+                        index,
+                        time: point.time,
+                        previous_time,
+                    });
+                }
+            }
+            if self.fails(&point) {
+                let outage = outage.get_or_insert(Outage {
+                    start: point.time,
+                    end: point.time,
+                    count: 0,
+                });
+                outage.end = point.time;
+                outage.count += 1;
+            } else if let Some(outage) = outage.take() {
+                outages.push(outage);
+            }
+            previous_time = Some(point.time);
+            index += 1;
+        }
+        outages.extend(outage);
+        Ok(stats
+            .summary()
+            .zip(coverage)
+            .map(|(summary, coverage)| Report {
+                summary,
+                coverage,
+                outages,
+                non_monotonic,
+            }))
+    }
+
+    fn fails(&self, point: &Point) -> bool {
+        if self
+            .min_fix_quality
+            .zip(point.fix_quality)
+            .is_some_and(|(min, quality)| quality < min)
+        {
+            return true;
+        }
+        let accuracy = match point.accuracy {
+            Some(accuracy) => accuracy,
+            None => return false,
+        };
+        let horizontal = (accuracy.x * accuracy.x + accuracy.y * accuracy.y).sqrt();
+        self.max_horizontal_accuracy
+            .is_some_and(|max| horizontal > max)
+            || self
+                .max_vertical_accuracy
+                .is_some_and(|max| accuracy.z > max)
+            || self.min_satellites.is_some_and(|min| {
+                accuracy
+                    .satellite_count
+                    .is_some_and(|count| count.total() < min)
+            })
+    }
+}
+
+impl Report {
+    /// Returns true if the report has no gaps, outages, or non-monotonic time events.
+    pub fn is_clean(&self) -> bool {
+        self.gaps().is_empty() && self.outages.is_empty() && self.non_monotonic.is_empty()
+    }
+
+    /// Returns the gaps in the trajectory.
+    pub fn gaps(&self) -> &[Gap] {
+        &self.summary.gaps
+    }
+}
+
+impl Coverage {
+    fn new(point: &Point) -> Coverage {
+        let (longitude, latitude) = (point.longitude_degrees(), point.latitude_degrees());
+        Coverage {
+            start: point.time,
+            end: point.time,
+            min_longitude: longitude,
+            max_longitude: longitude,
+            min_latitude: latitude,
+            max_latitude: latitude,
+            min_altitude: point.altitude,
+            max_altitude: point.altitude,
+        }
+    }
+
+    fn add(&mut self, point: &Point) {
+        let (longitude, latitude) = (point.longitude_degrees(), point.latitude_degrees());
+        self.start = self.start.min(point.time);
+        self.end = self.end.max(point.time);
+        self.min_longitude = self.min_longitude.min(longitude);
+        self.max_longitude = self.max_longitude.max(longitude);
+        self.min_latitude = self.min_latitude.min(latitude);
+        self.max_latitude = self.max_latitude.max(latitude);
+        self.min_altitude = self.min_altitude.min(point.altitude);
+        self.max_altitude = self.max_altitude.max(point.altitude);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Accuracy;
+    use crate::source::MemorySource;
+
+    fn point(time: f64, x: f64) -> Point {
+        Point {
+            time,
+            accuracy: Some(Accuracy {
+                time,
+                x,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn report() {
+        let points = vec![
+            point(0.0, 0.01),
+            point(1.0, 0.5),
+            point(2.0, 0.6),
+            point(3.0, 0.01),
+            point(2.5, 0.01),
+            point(4.0, 0.5),
+        ];
+        let report = ReportBuilder::new()
+            .max_horizontal_accuracy(0.1)
+            .gap_threshold(10.0)
+            .build(&mut MemorySource::new(points))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            vec![
+                Outage {
+                    start: 1.0,
+                    end: 2.0,
+                    count: 2
+                },
+                Outage {
+                    start: 4.0,
+                    end: 4.0,
+                    count: 1
+                }
+            ],
+            report.outages
+        );
+        assert_eq!(
+            vec![TimeEvent {
+                index: 4,
+                time: 2.5,
+                previous_time: 3.0
+            }],
+            report.non_monotonic
+        );
+        assert_eq!(0.0, report.coverage.start);
+        assert_eq!(4.0, report.coverage.end);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn fix_quality() {
+        let points = [
+            FixQuality::RtkFixed,
+            FixQuality::RtkFloat,
+            FixQuality::Sps,
+            FixQuality::RtkFixed,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, quality)| Point {
+            fix_quality: Some(quality),
+            ..point(i as f64, 0.01)
+        })
+        .collect::<Vec<_>>();
+        let report = ReportBuilder::new()
+            .min_fix_quality(FixQuality::RtkFloat)
+            .gap_threshold(10.0)
+            .build(&mut MemorySource::new(points))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            vec![Outage {
+                start: 2.0,
+                end: 2.0,
+                count: 1
+            }],
+            report.outages
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let report = ReportBuilder::new()
+            .build(&mut MemorySource::new(Vec::new()))
+            .unwrap();
+        assert!(report.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn json() {
+        let points = vec![point(0.0, 0.01), point(1.0, 0.01)];
+        let report = ReportBuilder::new()
+            .build(&mut MemorySource::new(points))
+            .unwrap()
+            .unwrap();
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(report, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -0,0 +1,214 @@
+//! Resample trajectories.
+
+use crate::geodesy;
+use crate::interpolate::Interpolator;
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+use std::collections::VecDeque;
+
+/// A source that resamples another source to a fixed rate.
+///
+/// Points are emitted at every multiple of the period, starting with the first multiple at or
+/// after the start of the source, e.g. on integer-millisecond boundaries at 1000 Hz. Each time
+/// is computed as `tick / rate` rather than by accumulating the period, so times don't drift.
+#[derive(Debug)]
+pub struct Resampler {
+    interpolator: Interpolator,
+    rate: f64,
+    tick: i64,
+}
+
+impl Resampler {
+    /// Creates a new resampler at the given rate, in Hz.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::resample::Resampler;
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut resampler = Resampler::new(Box::new(reader), 1000.0).unwrap();
+    /// assert_eq!(151631.003, resampler.source().unwrap().unwrap().time);
+    /// ```
+    pub fn new(source: Box<dyn Source>, rate: f64) -> Result<Resampler, Error> {
+        let interpolator = Interpolator::new(source)?;
+        let tick = (interpolator.start_time() * rate).ceil() as i64;
+        Ok(Resampler {
+            interpolator,
+            rate,
+            tick,
+        })
+    }
+}
+
+impl Source for Resampler {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let time = self.tick as f64 / self.rate;
+        match self.interpolator.interpolate(time) {
+            Ok(point) => {
+                self.tick += 1;
+                // Interpolation can round the time, so we set it exactly.
+                Ok(Some(Point { time, ..point }))
+            }
+            Err(Error::TimeAboveMaximum(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// A source that inserts interpolated points into another source so that no interval exceeds a
+/// maximum spacing.
+///
+/// Inserted points are evenly spaced in time between the original points, which are all kept.
+#[derive(Debug)]
+pub struct Densify {
+    source: Box<dyn Source>,
+    max_interval: Option<f64>,
+    max_distance: Option<f64>,
+    last: Option<Point>,
+    ready: VecDeque<Point>,
+}
+
+impl Densify {
+    /// Creates a new densifier with no maximum spacing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::resample::Densify;
+    /// use pos::sbet::Reader;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let densify = Densify::new(Box::new(reader)).max_interval(0.001).max_distance(1.0);
+    /// ```
+    pub fn new(source: Box<dyn Source>) -> Densify {
+        Densify {
+            source,
+            max_interval: None,
+            max_distance: None,
+            last: None,
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Sets the maximum time between points, in seconds.
+    pub fn max_interval(mut self, max_interval: f64) -> Densify {
+        self.max_interval = Some(max_interval);
+        self
+    }
+
+    /// Sets the maximum straight-line distance between points, in meters.
+    pub fn max_distance(mut self, max_distance: f64) -> Densify {
+        self.max_distance = Some(max_distance);
+        self
+    }
+
+    fn divisions(&self, a: &Point, b: &Point) -> usize {
+        let by_time = self
+            .max_interval
+            .map_or(1.0, |max| ((b.time - a.time) / max).ceil());
+        let by_distance = self
+            .max_distance
+            .map_or(1.0, |max| (geodesy::distance(a, b) / max).ceil());
+        by_time.max(by_distance).max(1.0) as usize
+    }
+}
+
+impl Source for Densify {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        if let Some(point) = self.ready.pop_front() {
+            return Ok(Some(point));
+        }
+        let point = match self.source.source()? {
+            Some(point) => point,
+            None => return Ok(None),
+        };
+        if let Some(last) = self.last {
+            let divisions = self.divisions(&last, &point);
+            for i in 1..divisions {
+                let time = last.time + (point.time - last.time) * i as f64 / divisions as f64;
+                self.ready.push_back(last.interpolate(&point, time));
+            }
+        }
+        self.ready.push_back(point);
+        self.last = Some(point);
+        Ok(self.ready.pop_front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    #[test]
+    fn resample() {
+        let points = vec![
+            Point {
+                time: 0.004,
+                altitude: 0.0,
+                ..Default::default()
+            },
+            Point {
+                time: 0.036,
+                altitude: 32.0,
+                ..Default::default()
+            },
+        ];
+        let mut resampler = Resampler::new(Box::new(MemorySource::new(points)), 100.0).unwrap();
+        let mut resampled = Vec::new();
+        while let Some(point) = resampler.source().unwrap() {
+            resampled.push(point);
+        }
+        let times: Vec<_> = resampled.iter().map(|p| p.time).collect();
+        assert_eq!(vec![0.01, 0.02, 0.03], times);
+        assert!((resampled[1].altitude - 16.0).abs() < 1e-9);
+    }
+
+    fn densify(densify: Densify) -> Vec<Point> {
+        let mut densify = densify;
+        let mut points = Vec::new();
+        while let Some(point) = densify.source().unwrap() {
+            points.push(point);
+        }
+        points
+    }
+
+    fn points() -> Vec<Point> {
+        vec![
+            Point {
+                time: 0.0,
+                altitude: 0.0,
+                ..Default::default()
+            },
+            Point {
+                time: 1.0,
+                altitude: 10.0,
+                ..Default::default()
+            },
+            Point {
+                time: 1.1,
+                altitude: 11.0,
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn densify_interval() {
+        let source = Box::new(MemorySource::new(points()));
+        let points = densify(Densify::new(source).max_interval(0.25));
+        let times: Vec<_> = points.iter().map(|p| p.time).collect();
+        assert_eq!(vec![0.0, 0.25, 0.5, 0.75, 1.0, 1.1], times);
+    }
+
+    #[test]
+    fn densify_distance() {
+        let source = Box::new(MemorySource::new(points()));
+        let points = densify(Densify::new(source).max_distance(4.0));
+        let altitudes: Vec<_> = points.iter().map(|p| p.altitude).collect();
+        assert_eq!(5, altitudes.len());
+        assert!((altitudes[1] - 10.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,241 @@
+//! Accuracy files that accompany sbet files.
+//!
+//! An `rmsmsg` file is a flat sequence of little-endian records, like an sbet, each holding ten
+//! doubles: the time, the north, east, and down position standard deviations in meters, the
+//! north, east, and down velocity standard deviations in meters per second, and the roll, pitch,
+//! and heading standard deviations in arc minutes.
+
+use crate::point::Accuracy;
+use crate::units::Radians;
+use crate::Location;
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::iter::IntoIterator;
+use std::path::{Path, PathBuf};
+
+/// The size of one rmsmsg record, in bytes.
+#[cfg(feature = "tracing")]
+const RECORD_SIZE: u64 = 80;
+
+/// An rmsmsg reader.
+#[derive(Debug)]
+pub struct Reader<R: Read> {
+    reader: R,
+    record: u64,
+    path: Option<PathBuf>,
+}
+
+impl Reader<BufReader<File>> {
+    /// Opens a reader for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::rmsmsg::Reader;
+    /// let reader = Reader::from_path("rmsmsg_mission.out").unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, std::io::Error> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            records = file.metadata()?.len() / RECORD_SIZE,
+            "opened rmsmsg file"
+        );
+        Ok(Reader {
+            path: Some(path.to_path_buf()),
+            ..Reader::new(BufReader::new(file))
+        })
+    }
+}
+
+impl<R: Read> Reader<R> {
+    /// Creates a new reader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::rmsmsg::Reader;
+    /// let reader = Reader::new(std::io::empty());
+    /// ```
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            record: 0,
+            path: None,
+        }
+    }
+
+    /// Returns the path and the index of the next record, to locate read errors.
+    pub(crate) fn location(&self) -> Location {
+        Location {
+            path: self.path.clone(),
+            record: Some(self.record),
+        }
+    }
+
+    /// Reads an accuracy record.
+    ///
+    /// The velocity standard deviations have no place in an [Accuracy], so they're skipped.
+    /// These files don't record dilutions of precision or satellite counts, so the PDOP is zero
+    /// and the other fields are `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::rmsmsg::Reader;
+    /// let mut reader = Reader::new(std::io::empty());
+    /// assert!(reader.read_accuracy().unwrap().is_none());
+    /// ```
+    pub fn read_accuracy(&mut self) -> Result<Option<Accuracy>, std::io::Error> {
+        use std::io::ErrorKind;
+
+        let time = match self.reader.read_f64::<LittleEndian>() {
+            Ok(time) => time,
+            Err(err) => match err.kind() {
+                ErrorKind::UnexpectedEof => return Ok(None),
+                _ => return Err(err),
+            },
+        };
+        let north = self.reader.read_f64::<LittleEndian>()?;
+        let east = self.reader.read_f64::<LittleEndian>()?;
+        let down = self.reader.read_f64::<LittleEndian>()?;
+        for _ in 0..3 {
+            let _ = self.reader.read_f64::<LittleEndian>()?;
+        }
+        let roll = self.reader.read_f64::<LittleEndian>()?;
+        let pitch = self.reader.read_f64::<LittleEndian>()?;
+        let heading = self.reader.read_f64::<LittleEndian>()?;
+        self.record += 1;
+        Ok(Some(Accuracy {
+            time,
+            x: east,
+            y: north,
+            z: down,
+            roll: Radians::from_degrees(roll / 60.0),
+            pitch: Radians::from_degrees(pitch / 60.0),
+            yaw: Radians::from_degrees(heading / 60.0),
+            pdop: 0.0,
+            hdop: None,
+            vdop: None,
+            satellite_count: None,
+        }))
+    }
+}
+
+impl<R: Read> IntoIterator for Reader<R> {
+    type Item = Accuracy;
+    type IntoIter = ReaderIterator<R>;
+    fn into_iter(self) -> Self::IntoIter {
+        ReaderIterator { reader: self }
+    }
+}
+
+/// An iterator over an rmsmsg reader.
+///
+/// Panics if an accuracy can't be read. Use
+/// [AccuracySource::try_iter](crate::source::AccuracySource::try_iter) to handle errors.
+#[derive(Debug)]
+pub struct ReaderIterator<R: Read> {
+    reader: Reader<R>,
+}
+
+impl<R: Read> Iterator for ReaderIterator<R> {
+    type Item = Accuracy;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_accuracy().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Point;
+    use crate::source::{AccuracySource, CombinedSource, MemorySource, Source};
+
+    fn bytes(times: &[f64]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for &time in times {
+            for value in [time, 0.02, 0.01, 0.05, 0.1, 0.1, 0.2, 0.6, 1.2, 3.0] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn read() {
+        let accuracies: Vec<_> = Reader::new(bytes(&[1.0, 2.0]).as_slice())
+            .into_iter()
+            .collect();
+        assert_eq!(2, accuracies.len());
+        let accuracy = accuracies[1];
+        assert_eq!(2.0, accuracy.time);
+        assert_eq!((0.01, 0.02, 0.05), (accuracy.x, accuracy.y, accuracy.z));
+        assert!((accuracy.roll.to_degrees() - 0.01).abs() < 1e-12);
+        assert!((accuracy.pitch.to_degrees() - 0.02).abs() < 1e-12);
+        assert!((accuracy.yaw.to_degrees() - 0.05).abs() < 1e-12);
+        assert_eq!(None, accuracy.satellite_count);
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = bytes(&[1.0]);
+        let mut reader = Reader::new(&bytes[..40]);
+        assert!(reader.read_accuracy().is_err());
+    }
+
+    #[test]
+    fn location() {
+        let path = std::env::temp_dir().join("pos-rs-rmsmsg-location.out");
+        let bytes = bytes(&[1.0, 2.0]);
+        std::fs::write(&path, &bytes[..120]).unwrap();
+        let mut reader = Reader::from_path(&path).unwrap();
+        assert!(AccuracySource::source(&mut reader).unwrap().is_some());
+        let err = AccuracySource::source(&mut reader).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        let location = err.location().unwrap();
+        assert_eq!(Some(path.as_path()), location.path.as_deref());
+        assert_eq!(Some(1), location.record);
+    }
+
+    #[test]
+    fn combined() {
+        let points: Vec<_> = [1.0, 1.5]
+            .iter()
+            .map(|&time| Point {
+                time,
+                ..Default::default()
+            })
+            .collect();
+        let accuracy_source: Box<dyn AccuracySource> =
+            Box::new(Reader::new(std::io::Cursor::new(bytes(&[1.0, 2.0]))));
+        let mut source =
+            CombinedSource::new(Box::new(MemorySource::new(points)), accuracy_source).unwrap();
+        let point = source.source().unwrap().unwrap();
+        assert_eq!(0.01, point.accuracy.unwrap().x);
+        assert!(source.source().unwrap().unwrap().accuracy.is_some());
+    }
+
+    #[test]
+    fn combined_static() {
+        fn assert_send<T: Send>(_: &T) {}
+        let points = vec![Point {
+            time: 1.5,
+            ..Default::default()
+        }];
+        let bytes = bytes(&[1.0, 2.0]);
+        let source =
+            CombinedSource::from_sources(MemorySource::new(points), Reader::new(bytes.as_slice()))
+                .unwrap();
+        assert_send(&source);
+        let points: Vec<_> = source.into_iter().collect();
+        assert_eq!(1, points.len());
+        assert_eq!(1.5, points[0].accuracy.unwrap().time);
+    }
+}
@@ -0,0 +1,746 @@
+//! ROS interoperability.
+//!
+//! Points are converted to `nav_msgs/Odometry` messages in a local east-north-up frame, following
+//! the [REP 103](https://www.ros.org/reps/rep-0103.html) conventions, and can be written to ROS1
+//! bag files for replay with `rosbag play`.
+
+use crate::geodesy;
+use crate::point::{Covariance, Point};
+use crate::source::Source;
+use crate::Error;
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::f64::consts::FRAC_PI_2;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const BAG_MAGIC: &[u8] = b"#ROSBAG V2.0\n";
+const BAG_HEADER_LENGTH: usize = 4096;
+const CHUNK_THRESHOLD: usize = 768 * 1024;
+const OP_MESSAGE_DATA: u8 = 0x02;
+const OP_BAG_HEADER: u8 = 0x03;
+const OP_INDEX_DATA: u8 = 0x04;
+const OP_CHUNK: u8 = 0x05;
+const OP_CHUNK_INFO: u8 = 0x06;
+const OP_CONNECTION: u8 = 0x07;
+const ODOMETRY_TYPE: &str = "nav_msgs/Odometry";
+const ODOMETRY_MD5SUM: &str = "cd5e73d190d741a2f92e81eda573aca7";
+const ODOMETRY_DEFINITION: &str = "std_msgs/Header header
+string child_frame_id
+geometry_msgs/PoseWithCovariance pose
+geometry_msgs/TwistWithCovariance twist
+
+================================================================================
+MSG: std_msgs/Header
+uint32 seq
+time stamp
+string frame_id
+
+================================================================================
+MSG: geometry_msgs/PoseWithCovariance
+geometry_msgs/Pose pose
+float64[36] covariance
+
+================================================================================
+MSG: geometry_msgs/Pose
+geometry_msgs/Point position
+geometry_msgs/Quaternion orientation
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+float64 z
+
+================================================================================
+MSG: geometry_msgs/Quaternion
+float64 x
+float64 y
+float64 z
+float64 w
+
+================================================================================
+MSG: geometry_msgs/TwistWithCovariance
+geometry_msgs/Twist twist
+float64[36] covariance
+
+================================================================================
+MSG: geometry_msgs/Twist
+geometry_msgs/Vector3 linear
+geometry_msgs/Vector3 angular
+
+================================================================================
+MSG: geometry_msgs/Vector3
+float64 x
+float64 y
+float64 z
+";
+
+/// A ROS timestamp.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Time {
+    /// Whole seconds.
+    pub sec: u32,
+    /// Nanoseconds past the second.
+    pub nsec: u32,
+}
+
+/// A `std_msgs/Header`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct Header {
+    pub seq: u32,
+    pub stamp: Time,
+    pub frame_id: String,
+}
+
+/// A `geometry_msgs/Vector3`, also used for `geometry_msgs/Point`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct Vector3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// A `geometry_msgs/Quaternion`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct Quaternion {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: f64,
+}
+
+/// A `geometry_msgs/Pose`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct Pose {
+    pub position: Vector3,
+    pub orientation: Quaternion,
+}
+
+/// A `geometry_msgs/Twist`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct Twist {
+    pub linear: Vector3,
+    pub angular: Vector3,
+}
+
+/// A `nav_msgs/Odometry`.
+#[derive(Clone, Debug, PartialEq)]
+#[allow(missing_docs)]
+pub struct Odometry {
+    pub header: Header,
+    pub child_frame_id: String,
+    pub pose: Pose,
+    pub pose_covariance: [f64; 36],
+    pub twist: Twist,
+    pub twist_covariance: [f64; 36],
+}
+
+impl Time {
+    /// Creates a ROS time from a point time, in seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::ros::Time;
+    /// let time = Time::from_seconds(1.5);
+    /// assert_eq!(1, time.sec);
+    /// assert_eq!(500_000_000, time.nsec);
+    /// ```
+    pub fn from_seconds(seconds: f64) -> Time {
+        let sec = seconds.floor();
+        Time {
+            sec: sec as u32,
+            nsec: ((seconds - sec) * 1e9).round().min(999_999_999.0) as u32,
+        }
+    }
+
+    fn write_to<W: Write>(&self, write: &mut W) -> Result<(), std::io::Error> {
+        write.write_u32::<LittleEndian>(self.sec)?;
+        write.write_u32::<LittleEndian>(self.nsec)
+    }
+
+    fn to_bytes(self) -> [u8; 8] {
+        let mut bytes = [0; 8];
+        bytes[0..4].copy_from_slice(&self.sec.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.nsec.to_le_bytes());
+        bytes
+    }
+}
+
+impl Quaternion {
+    /// Creates a quaternion from roll, pitch, and yaw angles, in radians.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::ros::Quaternion;
+    /// let quaternion = Quaternion::from_euler(0.0, 0.0, 0.0);
+    /// assert_eq!(1.0, quaternion.w);
+    /// ```
+    pub fn from_euler(roll: f64, pitch: f64, yaw: f64) -> Quaternion {
+        let (sr, cr) = (roll / 2.0).sin_cos();
+        let (sp, cp) = (pitch / 2.0).sin_cos();
+        let (sy, cy) = (yaw / 2.0).sin_cos();
+        Quaternion {
+            x: sr * cp * cy - cr * sp * sy,
+            y: cr * sp * cy + sr * cp * sy,
+            z: cr * cp * sy - sr * sp * cy,
+            w: cr * cp * cy + sr * sp * sy,
+        }
+    }
+}
+
+impl Odometry {
+    /// Serializes this message in the ROS1 wire format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::ros::Converter;
+    /// use pos::Point;
+    /// let mut converter = Converter::new(Point::default());
+    /// let odometry = converter.odometry(&Point::default());
+    /// let mut bytes = Vec::new();
+    /// odometry.write_to(&mut bytes).unwrap();
+    /// ```
+    pub fn write_to<W: Write>(&self, write: &mut W) -> Result<(), std::io::Error> {
+        write.write_u32::<LittleEndian>(self.header.seq)?;
+        self.header.stamp.write_to(write)?;
+        write_string(write, &self.header.frame_id)?;
+        write_string(write, &self.child_frame_id)?;
+        for value in [
+            self.pose.position.x,
+            self.pose.position.y,
+            self.pose.position.z,
+            self.pose.orientation.x,
+            self.pose.orientation.y,
+            self.pose.orientation.z,
+            self.pose.orientation.w,
+        ]
+        .iter()
+        .chain(self.pose_covariance.iter())
+        .chain(
+            [
+                self.twist.linear.x,
+                self.twist.linear.y,
+                self.twist.linear.z,
+                self.twist.angular.x,
+                self.twist.angular.y,
+                self.twist.angular.z,
+            ]
+            .iter(),
+        )
+        .chain(self.twist_covariance.iter())
+        {
+            write.write_f64::<LittleEndian>(*value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts points into odometry messages.
+///
+/// Positions are expressed in meters east, north, and up of an origin point. Attitude is
+/// converted from the north-east-down, forward-right-down convention used by the position
+/// formats to ROS's east-north-up, forward-left-up convention. Angular rates are rotated into
+/// the forward-left-up body frame, while linear velocities are copied as-is. If the point has a
+/// [Covariance](crate::point::Covariance), it's rotated into the same conventions to become the
+/// pose covariance; otherwise, if the point has accuracy information, its variances populate the
+/// pose covariance diagonal.
+#[derive(Clone, Debug)]
+pub struct Converter {
+    /// The origin of the local frame.
+    pub origin: Point,
+    /// The frame id of the local frame.
+    pub frame_id: String,
+    /// The frame id of the platform.
+    pub child_frame_id: String,
+    seq: u32,
+}
+
+impl Converter {
+    /// Creates a new converter with the given origin.
+    ///
+    /// The frame ids default to `map` and `base_link`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::ros::Converter;
+    /// use pos::Point;
+    /// let converter = Converter::new(Point::default());
+    /// ```
+    pub fn new(origin: Point) -> Converter {
+        Converter {
+            origin,
+            frame_id: "map".to_string(),
+            child_frame_id: "base_link".to_string(),
+            seq: 0,
+        }
+    }
+
+    /// Converts a point to an odometry message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::ros::Converter;
+    /// use pos::Point;
+    /// let mut converter = Converter::new(Point::default());
+    /// let odometry = converter.odometry(&Point { altitude: 10.0, ..Default::default() });
+    /// assert!((odometry.pose.position.z - 10.0).abs() < 1e-9);
+    /// ```
+    pub fn odometry(&mut self, point: &Point) -> Odometry {
+        let enu = geodesy::enu(&self.origin, point);
+        let mut pose_covariance = [0.0; 36];
+        let covariance = point
+            .covariance
+            .or_else(|| point.accuracy.as_ref().map(Covariance::from_accuracy));
+        if let Some(covariance) = covariance {
+            // Pitch and yaw change sign between the two attitude conventions.
+            let signs = [1.0, 1.0, 1.0, 1.0, -1.0, -1.0];
+            for (i, row) in covariance.matrix().iter().enumerate() {
+                for (j, value) in row.iter().enumerate() {
+                    pose_covariance[i * 6 + j] = signs[i] * signs[j] * value;
+                }
+            }
+        }
+        let odometry = Odometry {
+            header: Header {
+                seq: self.seq,
+                stamp: Time::from_seconds(point.time),
+                frame_id: self.frame_id.clone(),
+            },
+            child_frame_id: self.child_frame_id.clone(),
+            pose: Pose {
+                position: Vector3 {
+                    x: enu[0],
+                    y: enu[1],
+                    z: enu[2],
+                },
+                orientation: Quaternion::from_euler(
+                    point.roll.0,
+                    -point.pitch.0,
+                    FRAC_PI_2 - point.yaw.0,
+                ),
+            },
+            pose_covariance,
+            twist: Twist {
+                linear: Vector3 {
+                    x: point.x_velocity.unwrap_or(0.0),
+                    y: point.y_velocity.unwrap_or(0.0),
+                    z: point.z_velocity.unwrap_or(0.0),
+                },
+                angular: Vector3 {
+                    x: point.x_angular_rate.map_or(0.0, |r| r.0),
+                    y: point.y_angular_rate.map_or(0.0, |r| -r.0),
+                    z: point.z_angular_rate.map_or(0.0, |r| -r.0),
+                },
+            },
+            twist_covariance: [0.0; 36],
+        };
+        self.seq = self.seq.wrapping_add(1);
+        odometry
+    }
+}
+
+/// Writes odometry messages to a ROS1 bag file.
+///
+/// Messages are written uncompressed, on a single topic. The bag is only valid after
+/// [BagWriter::close] has been called.
+#[derive(Debug)]
+pub struct BagWriter<W: Write + Seek> {
+    writer: W,
+    topic: String,
+    chunk: Vec<u8>,
+    index: Vec<(Time, u32)>,
+    chunk_infos: Vec<ChunkInfo>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ChunkInfo {
+    position: u64,
+    start: Time,
+    end: Time,
+    count: u32,
+}
+
+impl BagWriter<BufWriter<File>> {
+    /// Creates a bag file at the given path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::ros::BagWriter;
+    /// let writer = BagWriter::from_path("trajectory.bag", "/odom").unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        topic: &str,
+    ) -> Result<BagWriter<BufWriter<File>>, Error> {
+        BagWriter::new(BufWriter::new(File::create(path)?), topic)
+    }
+}
+
+impl<W: Write + Seek> BagWriter<W> {
+    /// Creates a new bag writer that will publish messages on the given topic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::ros::BagWriter;
+    /// use std::io::Cursor;
+    /// let writer = BagWriter::new(Cursor::new(Vec::new()), "/odom").unwrap();
+    /// ```
+    pub fn new(mut writer: W, topic: &str) -> Result<BagWriter<W>, Error> {
+        writer.write_all(BAG_MAGIC)?;
+        write_bag_header(&mut writer, 0, 0, 0)?;
+        Ok(BagWriter {
+            writer,
+            topic: topic.to_string(),
+            chunk: Vec::new(),
+            index: Vec::new(),
+            chunk_infos: Vec::new(),
+        })
+    }
+
+    /// Writes an odometry message, stamped with its header time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::ros::{BagWriter, Converter};
+    /// use pos::Point;
+    /// use std::io::Cursor;
+    /// let mut writer = BagWriter::new(Cursor::new(Vec::new()), "/odom").unwrap();
+    /// let mut converter = Converter::new(Point::default());
+    /// writer.write(&converter.odometry(&Point::default())).unwrap();
+    /// writer.close().unwrap();
+    /// ```
+    pub fn write(&mut self, odometry: &Odometry) -> Result<(), Error> {
+        if self.chunk.is_empty() {
+            self.write_connection_to_chunk()?;
+        }
+        let time = odometry.header.stamp;
+        let mut data = Vec::new();
+        odometry.write_to(&mut data)?;
+        self.index.push((time, self.chunk.len() as u32));
+        write_record(
+            &mut self.chunk,
+            &[
+                ("op", &[OP_MESSAGE_DATA]),
+                ("conn", &0u32.to_le_bytes()),
+                ("time", &time.to_bytes()),
+            ],
+            &data,
+        )?;
+        if self.chunk.len() > CHUNK_THRESHOLD {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Writes every point from a source, converting each with the converter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::ros::{BagWriter, Converter};
+    /// use pos::sbet;
+    /// use std::io::Cursor;
+    /// let mut reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut writer = BagWriter::new(Cursor::new(Vec::new()), "/odom").unwrap();
+    /// let mut converter = None;
+    /// writer.write_source(&mut reader, &mut converter).unwrap();
+    /// writer.close().unwrap();
+    /// ```
+    pub fn write_source(
+        &mut self,
+        source: &mut dyn Source,
+        converter: &mut Option<Converter>,
+    ) -> Result<(), Error> {
+        while let Some(point) = source.source()? {
+            let odometry = converter
+                .get_or_insert_with(|| Converter::new(point))
+                .odometry(&point);
+            self.write(&odometry)?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the bag by writing its index, and returns the underlying writer.
+    pub fn close(mut self) -> Result<W, Error> {
+        self.flush_chunk()?;
+        let index_position = self.writer.stream_position()?;
+        let mut connection = Vec::new();
+        self.write_connection(&mut connection)?;
+        self.writer.write_all(&connection)?;
+        for info in &self.chunk_infos {
+            let mut data = Vec::new();
+            data.write_u32::<LittleEndian>(0)?;
+            data.write_u32::<LittleEndian>(info.count)?;
+            write_record(
+                &mut self.writer,
+                &[
+                    ("op", &[OP_CHUNK_INFO]),
+                    ("ver", &1u32.to_le_bytes()),
+                    ("chunk_pos", &info.position.to_le_bytes()),
+                    ("start_time", &info.start.to_bytes()),
+                    ("end_time", &info.end.to_bytes()),
+                    ("count", &1u32.to_le_bytes()),
+                ],
+                &data,
+            )?;
+        }
+        let _ = self.writer.seek(SeekFrom::Start(BAG_MAGIC.len() as u64))?;
+        write_bag_header(
+            &mut self.writer,
+            index_position,
+            1,
+            self.chunk_infos.len() as u32,
+        )?;
+        let _ = self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_connection_to_chunk(&mut self) -> Result<(), Error> {
+        let mut connection = Vec::new();
+        self.write_connection(&mut connection)?;
+        self.chunk.extend_from_slice(&connection);
+        Ok(())
+    }
+
+    fn write_connection(&self, write: &mut Vec<u8>) -> Result<(), std::io::Error> {
+        let mut data = Vec::new();
+        write_fields(
+            &mut data,
+            &[
+                ("topic", self.topic.as_bytes()),
+                ("type", ODOMETRY_TYPE.as_bytes()),
+                ("md5sum", ODOMETRY_MD5SUM.as_bytes()),
+                ("message_definition", ODOMETRY_DEFINITION.as_bytes()),
+            ],
+        )?;
+        write_record(
+            write,
+            &[
+                ("op", &[OP_CONNECTION]),
+                ("conn", &0u32.to_le_bytes()),
+                ("topic", self.topic.as_bytes()),
+            ],
+            &data,
+        )
+    }
+
+    fn flush_chunk(&mut self) -> Result<(), Error> {
+        if self.index.is_empty() {
+            return Ok(());
+        }
+        let position = self.writer.stream_position()?;
+        write_record(
+            &mut self.writer,
+            &[
+                ("op", &[OP_CHUNK]),
+                ("compression", b"none"),
+                ("size", &(self.chunk.len() as u32).to_le_bytes()),
+            ],
+            &self.chunk,
+        )?;
+        let mut data = Vec::new();
+        for (time, offset) in &self.index {
+            time.write_to(&mut data)?;
+            data.write_u32::<LittleEndian>(*offset)?;
+        }
+        write_record(
+            &mut self.writer,
+            &[
+                ("op", &[OP_INDEX_DATA]),
+                ("ver", &1u32.to_le_bytes()),
+                ("conn", &0u32.to_le_bytes()),
+                ("count", &(self.index.len() as u32).to_le_bytes()),
+            ],
+            &data,
+        )?;
+        let times = self.index.iter().map(|(time, _)| *time);
+        let start = times.clone().min_by_key(|t| (t.sec, t.nsec)).unwrap();
+        let end = times.max_by_key(|t| (t.sec, t.nsec)).unwrap();
+        self.chunk_infos.push(ChunkInfo {
+            position,
+            start,
+            end,
+            count: self.index.len() as u32,
+        });
+        self.chunk.clear();
+        self.index.clear();
+        Ok(())
+    }
+}
+
+fn write_bag_header<W: Write>(
+    write: &mut W,
+    index_position: u64,
+    connection_count: u32,
+    chunk_count: u32,
+) -> Result<(), std::io::Error> {
+    let mut header = Vec::new();
+    write_fields(
+        &mut header,
+        &[
+            ("op", &[OP_BAG_HEADER]),
+            ("index_pos", &index_position.to_le_bytes()),
+            ("conn_count", &connection_count.to_le_bytes()),
+            ("chunk_count", &chunk_count.to_le_bytes()),
+        ],
+    )?;
+    let padding = vec![b' '; BAG_HEADER_LENGTH - header.len() - 8];
+    write.write_u32::<LittleEndian>(header.len() as u32)?;
+    write.write_all(&header)?;
+    write.write_u32::<LittleEndian>(padding.len() as u32)?;
+    write.write_all(&padding)
+}
+
+fn write_record<W: Write>(
+    write: &mut W,
+    fields: &[(&str, &[u8])],
+    data: &[u8],
+) -> Result<(), std::io::Error> {
+    let mut header = Vec::new();
+    write_fields(&mut header, fields)?;
+    write.write_u32::<LittleEndian>(header.len() as u32)?;
+    write.write_all(&header)?;
+    write.write_u32::<LittleEndian>(data.len() as u32)?;
+    write.write_all(data)
+}
+
+fn write_fields<W: Write>(write: &mut W, fields: &[(&str, &[u8])]) -> Result<(), std::io::Error> {
+    for (name, value) in fields {
+        write.write_u32::<LittleEndian>((name.len() + 1 + value.len()) as u32)?;
+        write.write_all(name.as_bytes())?;
+        write.write_all(b"=")?;
+        write.write_all(value)?;
+    }
+    Ok(())
+}
+
+fn write_string<W: Write>(write: &mut W, s: &str) -> Result<(), std::io::Error> {
+    write.write_u32::<LittleEndian>(s.len() as u32)?;
+    write.write_all(s.as_bytes())
+}
+
+#[cfg(all(test, feature = "sbet"))]
+mod tests {
+    use super::*;
+    use crate::sbet;
+    use crate::units::Radians;
+    use byteorder::ReadBytesExt;
+    use std::io::{Cursor, Read};
+
+    fn read_record(cursor: &mut Cursor<Vec<u8>>) -> (Vec<(String, Vec<u8>)>, Vec<u8>) {
+        let header_length = cursor.read_u32::<LittleEndian>().unwrap() as usize;
+        let mut header = vec![0; header_length];
+        cursor.read_exact(&mut header).unwrap();
+        let mut fields = Vec::new();
+        let mut header = Cursor::new(header);
+        while (header.position() as usize) < header_length {
+            let length = header.read_u32::<LittleEndian>().unwrap() as usize;
+            let mut field = vec![0; length];
+            header.read_exact(&mut field).unwrap();
+            let split = field.iter().position(|&b| b == b'=').unwrap();
+            let value = field.split_off(split + 1);
+            let _ = field.pop();
+            fields.push((String::from_utf8(field).unwrap(), value));
+        }
+        let data_length = cursor.read_u32::<LittleEndian>().unwrap() as usize;
+        let mut data = vec![0; data_length];
+        cursor.read_exact(&mut data).unwrap();
+        (fields, data)
+    }
+
+    fn field<'a>(fields: &'a [(String, Vec<u8>)], name: &str) -> &'a [u8] {
+        &fields.iter().find(|(n, _)| n == name).unwrap().1
+    }
+
+    #[test]
+    fn orientation() {
+        let mut converter = Converter::new(Point::default());
+        let odometry = converter.odometry(&Point::default());
+        let expected = Quaternion::from_euler(0.0, 0.0, FRAC_PI_2);
+        assert_eq!(expected, odometry.pose.orientation);
+
+        let odometry = converter.odometry(&Point {
+            yaw: Radians::from_degrees(90.0),
+            ..Default::default()
+        });
+        assert!((odometry.pose.orientation.w - 1.0).abs() < 1e-12);
+        assert_eq!(1, odometry.header.seq);
+    }
+
+    #[test]
+    fn covariance() {
+        let mut matrix = [[0.0; 6]; 6];
+        matrix[0][0] = 0.04;
+        matrix[0][5] = 0.001;
+        matrix[5][0] = 0.001;
+        matrix[4][5] = 0.002;
+        let mut converter = Converter::new(Point::default());
+        let odometry = converter.odometry(&Point {
+            covariance: Some(Covariance::from_matrix(matrix)),
+            ..Default::default()
+        });
+        assert_eq!(0.04, odometry.pose_covariance[0]);
+        assert_eq!(-0.001, odometry.pose_covariance[5]);
+        assert_eq!(-0.001, odometry.pose_covariance[30]);
+        assert_eq!(0.002, odometry.pose_covariance[4 * 6 + 5]);
+        assert_eq!(0.0, odometry.pose_covariance[7]);
+    }
+
+    #[test]
+    fn bag() {
+        let mut reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+        let mut writer = BagWriter::new(Cursor::new(Vec::new()), "/odom").unwrap();
+        writer.write_source(&mut reader, &mut None).unwrap();
+        let mut cursor = writer.close().unwrap();
+
+        cursor.set_position(0);
+        let mut magic = vec![0; BAG_MAGIC.len()];
+        cursor.read_exact(&mut magic).unwrap();
+        assert_eq!(BAG_MAGIC, magic.as_slice());
+        let (fields, _) = read_record(&mut cursor);
+        assert_eq!(
+            BAG_MAGIC.len() + BAG_HEADER_LENGTH,
+            cursor.position() as usize
+        );
+        assert_eq!(&[1, 0, 0, 0], field(&fields, "chunk_count"));
+        let mut index_position = [0; 8];
+        index_position.copy_from_slice(field(&fields, "index_pos"));
+
+        let (fields, chunk) = read_record(&mut cursor);
+        assert_eq!(&[OP_CHUNK], field(&fields, "op"));
+        let mut chunk = Cursor::new(chunk);
+        let (fields, _) = read_record(&mut chunk);
+        assert_eq!(&[OP_CONNECTION], field(&fields, "op"));
+        let (fields, message) = read_record(&mut chunk);
+        assert_eq!(&[OP_MESSAGE_DATA], field(&fields, "op"));
+        assert_eq!(4 + 8 + 4 + 3 + 4 + 9 + 8 * (7 + 36 + 6 + 36), message.len());
+        let (_, _) = read_record(&mut chunk);
+        assert_eq!(chunk.get_ref().len() as u64, chunk.position());
+
+        let (fields, index) = read_record(&mut cursor);
+        assert_eq!(&[OP_INDEX_DATA], field(&fields, "op"));
+        assert_eq!(24, index.len());
+
+        cursor.set_position(u64::from_le_bytes(index_position));
+        let (fields, _) = read_record(&mut cursor);
+        assert_eq!(&[OP_CONNECTION], field(&fields, "op"));
+        let (fields, _) = read_record(&mut cursor);
+        assert_eq!(&[OP_CHUNK_INFO], field(&fields, "op"));
+        assert_eq!(cursor.get_ref().len() as u64, cursor.position());
+    }
+}
@@ -0,0 +1,157 @@
+//! Split output into files by time.
+//!
+//! Long continuous acquisitions are easier to archive in fixed pieces, such as one file per hour.
+//! [Rotating] writes points to a new file whenever they cross into a new time chunk. Chunks are
+//! aligned to multiples of their duration, and each file is named after the start of its chunk,
+//! e.g. `mission-151200.pos` for the hourly chunk that starts at 151200 seconds.
+
+use crate::point::Point;
+use crate::sink::Sink;
+use crate::source::Source;
+use crate::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Writes points to a new file for every time chunk.
+///
+/// Points should be in time order. A point from a chunk before the current one is written to the
+/// current file, rather than reopening (and truncating) an earlier file.
+pub struct Rotating<W, F> {
+    path: PathBuf,
+    duration: f64,
+    open: F,
+    current: Option<(i64, W)>,
+    paths: Vec<PathBuf>,
+}
+
+impl<W: Sink, F: FnMut(&Path) -> Result<W, Error>> Rotating<W, F> {
+    /// Creates a new rotating writer.
+    ///
+    /// Each file's name is `path`'s file stem, a dash, and the start time of its chunk, followed
+    /// by `path`'s extension. `open` creates the writer for each file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::rotate::Rotating;
+    /// use pos::Point;
+    /// let mut writer = Rotating::new("mission.pos", 3600.0, |path| {
+    ///     Ok(pos::pos::Writer::from_path(path)?)
+    /// });
+    /// writer.write_point(&Point::default()).unwrap();
+    /// let paths = writer.close().unwrap();
+    /// ```
+    pub fn new<P: Into<PathBuf>>(path: P, duration: f64, open: F) -> Rotating<W, F> {
+        Rotating {
+            path: path.into(),
+            duration,
+            open,
+            current: None,
+            paths: Vec::new(),
+        }
+    }
+
+    /// Writes a point, first starting a new file if the point is in a new chunk.
+    pub fn write_point(&mut self, point: &Point) -> Result<(), Error> {
+        let chunk = (point.time / self.duration).floor() as i64;
+        let rotate = match self.current {
+            Some((current, _)) => chunk > current,
+            None => true,
+        };
+        if rotate {
+            if let Some((_, mut writer)) = self.current.take() {
+                writer.finish()?;
+            }
+            let path = self.chunk_path(chunk);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(path = %path.display(), "starting new output file");
+            let writer = (self.open)(&path)?;
+            self.paths.push(path);
+            self.current = Some((chunk, writer));
+        }
+        self.current
+            .as_mut()
+            .expect("a writer was just opened")
+            .1
+            .sink(point)
+    }
+
+    /// Writes every point from a source, returning the number of points written.
+    pub fn write_source(&mut self, source: &mut dyn Source) -> Result<u64, Error> {
+        let mut count = 0;
+        while let Some(point) = source.source()? {
+            self.write_point(&point)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Closes the current file and returns the paths of every file written, in order.
+    pub fn close(mut self) -> Result<Vec<PathBuf>, Error> {
+        if let Some((_, mut writer)) = self.current.take() {
+            writer.finish()?;
+        }
+        Ok(self.paths)
+    }
+
+    fn chunk_path(&self, chunk: i64) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut file_name = format!("{}-{}", stem, chunk as f64 * self.duration);
+        if let Some(extension) = self.path.extension() {
+            file_name.push('.');
+            file_name.push_str(&extension.to_string_lossy());
+        }
+        self.path.with_file_name(file_name)
+    }
+}
+
+impl<W, F> fmt::Debug for Rotating<W, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rotating")
+            .field("path", &self.path)
+            .field("duration", &self.duration)
+            .field("chunk", &self.current.as_ref().map(|(chunk, _)| *chunk))
+            .field("paths", &self.paths)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate() {
+        let mut files: Vec<Vec<Point>> = vec![Vec::new(); 3];
+        let mut sinks = files.iter_mut();
+        let mut writer = Rotating::new("out/mission.pos", 10.0, |_: &Path| {
+            Ok(sinks.next().unwrap())
+        });
+        for time in [5.0, 9.5, 12.0, 11.0, 35.0] {
+            writer
+                .write_point(&Point {
+                    time,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let paths = writer.close().unwrap();
+        assert_eq!(
+            vec![
+                PathBuf::from("out/mission-0.pos"),
+                PathBuf::from("out/mission-10.pos"),
+                PathBuf::from("out/mission-30.pos")
+            ],
+            paths
+        );
+        let times: Vec<Vec<f64>> = files
+            .iter()
+            .map(|points| points.iter().map(|point| point.time).collect())
+            .collect();
+        assert_eq!(vec![vec![5.0, 9.5], vec![12.0, 11.0], vec![35.0]], times);
+    }
+}
@@ -1,20 +1,50 @@
 //! SBET file format.
 
 use crate::point::Point;
-use crate::source::Source;
-use crate::units::Radians;
-use crate::Error;
-use byteorder::{LittleEndian, ReadBytesExt};
+use crate::sink::Sink;
+use crate::source::{SeekableSource, Source};
+use crate::units::{AngleUnit, LengthUnit, Radians};
+use crate::{Error, Location};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::fmt::Debug;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::iter::IntoIterator;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// The size of one sbet record, in bytes.
+pub(crate) const RECORD_SIZE: u64 = 136;
+
+/// The number of records checked when inferring a layout.
+const INFER_RECORDS: u64 = 16;
+
+/// The largest step between consecutive times that's plausible when inferring a layout, in
+/// seconds.
+const INFER_MAX_INTERVAL: f64 = 10.0;
+
+/// The layout of the records in an sbet-like file.
+///
+/// Files with no metadata come in a few record sizes, which [infer_layout] can tell apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// Standard 136-byte records with seventeen fields, ending in the angular rates.
+    #[default]
+    Standard,
+    /// 112-byte records, the standard layout without the three angular rates.
+    NoAngularRates,
+}
 
 /// An SBET reader.
 #[derive(Debug)]
 pub struct Reader<R: Read> {
     reader: R,
+    layout: Layout,
+    angle_unit: AngleUnit,
+    height_unit: LengthUnit,
+    remaining: Option<u64>,
+    start: u64,
+    record: u64,
+    path: Option<PathBuf>,
 }
 
 impl Reader<BufReader<File>> {
@@ -26,14 +56,102 @@ impl Reader<BufReader<File>> {
     /// use pos::sbet::Reader;
     /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, std::io::Error> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(records = length / RECORD_SIZE, "opened sbet file");
         Ok(Reader {
-            reader: BufReader::new(File::open(path)?),
+            remaining: Some(length),
+            path: Some(path.to_path_buf()),
+            ..Reader::new(BufReader::new(file))
         })
     }
 }
 
 impl<R: Read> Reader<R> {
+    /// Creates a new reader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use std::fs::File;
+    /// let file = File::open("data/2-points.sbet").unwrap();
+    /// let reader = Reader::new(file);
+    /// ```
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            layout: Layout::Standard,
+            angle_unit: AngleUnit::Radians,
+            height_unit: LengthUnit::Meters,
+            remaining: None,
+            start: 0,
+            record: 0,
+            path: None,
+        }
+    }
+
+    /// Sets the record layout of the file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::{Layout, Reader};
+    /// let reader = Reader::from_path("data/2-points.sbet")
+    ///     .unwrap()
+    ///     .with_layout(Layout::Standard);
+    /// ```
+    pub fn with_layout(mut self, layout: Layout) -> Reader<R> {
+        self.layout = layout;
+        self
+    }
+
+    /// Returns the record layout of this reader.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Sets the unit of the angles and angular rates in the file.
+    ///
+    /// Sbet files store radians, but some sbet-like exports use degrees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::units::AngleUnit;
+    /// let reader = Reader::from_path("data/2-points.sbet")
+    ///     .unwrap()
+    ///     .with_angle_unit(AngleUnit::Degrees);
+    /// ```
+    pub fn with_angle_unit(mut self, angle_unit: AngleUnit) -> Reader<R> {
+        self.angle_unit = angle_unit;
+        self
+    }
+
+    /// Sets the unit of the altitudes in the file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::units::LengthUnit;
+    /// let reader = Reader::from_path("data/2-points.sbet")
+    ///     .unwrap()
+    ///     .with_height_unit(LengthUnit::Feet);
+    /// ```
+    pub fn with_height_unit(mut self, height_unit: LengthUnit) -> Reader<R> {
+        self.height_unit = height_unit;
+        self
+    }
+
     /// Reads a point from this reader.
     ///
     /// Returns none if the file is at its end when this reader starts reading. We have to do it
@@ -46,6 +164,7 @@ impl<R: Read> Reader<R> {
     /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
     /// let point = reader.read_point().unwrap().unwrap();
     /// ```
+    #[allow(clippy::useless_conversion)]
     pub fn read_point(&mut self) -> Result<Option<Point>, std::io::Error> {
         use std::io::ErrorKind;
 
@@ -56,29 +175,177 @@ impl<R: Read> Reader<R> {
                 _ => return Err(err.into()),
             },
         };
-        Ok(Some(Point {
+        let angle = self.angle_unit;
+        let point = Point {
             time,
-            latitude: Radians(self.reader.read_f64::<LittleEndian>()?),
-            longitude: Radians(self.reader.read_f64::<LittleEndian>()?),
-            altitude: self.reader.read_f64::<LittleEndian>()?,
+            latitude: angle.to_radians(self.reader.read_f64::<LittleEndian>()?),
+            longitude: angle.to_radians(self.reader.read_f64::<LittleEndian>()?),
+            altitude: self
+                .height_unit
+                .to_meters(self.reader.read_f64::<LittleEndian>()?),
             x_velocity: Some(self.reader.read_f64::<LittleEndian>()?),
             y_velocity: Some(self.reader.read_f64::<LittleEndian>()?),
             z_velocity: Some(self.reader.read_f64::<LittleEndian>()?),
-            roll: Radians(self.reader.read_f64::<LittleEndian>()?),
-            pitch: Radians(self.reader.read_f64::<LittleEndian>()?),
-            yaw: Radians(self.reader.read_f64::<LittleEndian>()?),
-            wander_angle: Some(Radians(self.reader.read_f64::<LittleEndian>()?)),
+            roll: angle.to_radians(self.reader.read_f64::<LittleEndian>()?),
+            pitch: angle.to_radians(self.reader.read_f64::<LittleEndian>()?),
+            yaw: angle.to_radians(self.reader.read_f64::<LittleEndian>()?),
+            wander_angle: Some(angle.to_radians(self.reader.read_f64::<LittleEndian>()?)),
             x_acceleration: Some(self.reader.read_f64::<LittleEndian>()?),
             y_acceleration: Some(self.reader.read_f64::<LittleEndian>()?),
             z_acceleration: Some(self.reader.read_f64::<LittleEndian>()?),
-            x_angular_rate: Some(Radians(self.reader.read_f64::<LittleEndian>()?)),
-            y_angular_rate: Some(Radians(self.reader.read_f64::<LittleEndian>()?)),
-            z_angular_rate: Some(Radians(self.reader.read_f64::<LittleEndian>()?)),
+            x_angular_rate: self.read_angular_rate()?,
+            y_angular_rate: self.read_angular_rate()?,
+            z_angular_rate: self.read_angular_rate()?,
             ..Default::default()
-        }))
+        };
+        self.remaining = self
+            .remaining
+            .map(|remaining| remaining.saturating_sub(self.layout.record_size()));
+        self.record += 1;
+        Ok(Some(point))
+    }
+
+    /// Returns the number of records left in the file, if its length is known.
+    fn remaining(&self) -> Option<usize> {
+        self.remaining
+            .and_then(|remaining| usize::try_from(remaining / self.layout.record_size()).ok())
+    }
+
+    fn read_angular_rate(&mut self) -> Result<Option<Radians<f64>>, std::io::Error> {
+        match self.layout {
+            Layout::Standard => Ok(Some(
+                self.angle_unit
+                    .to_radians(self.reader.read_f64::<LittleEndian>()?),
+            )),
+            Layout::NoAngularRates => Ok(None),
+        }
+    }
+}
+
+impl<R: Read + Seek> Reader<R> {
+    /// Creates a new reader, inferring the record layout from the start of the file.
+    ///
+    /// Returns [Error::SbetLayout] if no known layout fits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::{Layout, Reader};
+    /// use std::fs::File;
+    /// let file = File::open("data/2-points.sbet").unwrap();
+    /// let reader = Reader::infer(file).unwrap();
+    /// assert_eq!(Layout::Standard, reader.layout());
+    /// ```
+    pub fn infer(mut reader: R) -> Result<Reader<R>, Error> {
+        let layout = infer_layout(&mut reader)?.ok_or(Error::SbetLayout)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?layout, "inferred sbet layout");
+        let start = reader.stream_position()?;
+        let length = reader.seek(SeekFrom::End(0))? - start;
+        let _ = reader.seek(SeekFrom::Start(start))?;
+        Ok(Reader {
+            remaining: Some(length),
+            start,
+            ..Reader::new(reader).with_layout(layout)
+        })
+    }
+
+    /// Moves to a record.
+    ///
+    /// Records are counted from where the stream was when this reader was created by
+    /// [Reader::infer], or from the start of the stream otherwise.
+    fn seek_to_record(&mut self, index: u64) -> Result<(), Error> {
+        let length = self
+            .reader
+            .seek(SeekFrom::End(0))?
+            .saturating_sub(self.start);
+        let offset = (index * self.layout.record_size()).min(length);
+        let _ = self.reader.seek(SeekFrom::Start(self.start + offset))?;
+        self.remaining = Some(length - offset);
+        self.record = offset / self.layout.record_size();
+        Ok(())
+    }
+}
+
+impl Layout {
+    /// Returns every known layout, in the order they're tried when inferring.
+    pub fn all() -> &'static [Layout] {
+        &[Layout::Standard, Layout::NoAngularRates]
+    }
+
+    /// Returns the size of one record, in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Layout;
+    /// assert_eq!(136, Layout::Standard.record_size());
+    /// assert_eq!(112, Layout::NoAngularRates.record_size());
+    /// ```
+    pub fn record_size(&self) -> u64 {
+        match *self {
+            Layout::Standard => RECORD_SIZE,
+            Layout::NoAngularRates => 112,
+        }
     }
 }
 
+/// Infers the record layout of an sbet-like stream.
+///
+/// Each known layout is tried in turn. A layout fits if the stream's length is a multiple of its
+/// record size, and the first few records have increasing times no more than ten seconds apart
+/// and plausible latitudes and longitudes, in either radians or degrees. At least two records
+/// are needed. Returns `None` if no layout fits. The reader is left at the start of the stream.
+///
+/// # Examples
+///
+/// ```
+/// use pos::sbet::{self, Layout};
+/// use std::fs::File;
+/// let mut file = File::open("data/2-points.sbet").unwrap();
+/// assert_eq!(Some(Layout::Standard), sbet::infer_layout(&mut file).unwrap());
+/// ```
+pub fn infer_layout<R: Read + Seek>(reader: &mut R) -> Result<Option<Layout>, std::io::Error> {
+    let start = reader.stream_position()?;
+    let length = reader.seek(SeekFrom::End(0))? - start;
+    let _ = reader.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    let _ = reader
+        .by_ref()
+        .take(INFER_RECORDS * RECORD_SIZE)
+        .read_to_end(&mut bytes)?;
+    let _ = reader.seek(SeekFrom::Start(start))?;
+    let value = |offset: usize| {
+        bytes
+            .get(offset..offset + 8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+    };
+    Ok(Layout::all().iter().copied().find(|layout| {
+        let size = layout.record_size();
+        if length % size != 0 || length / size < 2 {
+            return false;
+        }
+        let mut previous: Option<f64> = None;
+        for offset in (0..bytes.len()).step_by(size as usize) {
+            let (time, latitude, longitude) =
+                match (value(offset), value(offset + 8), value(offset + 16)) {
+                    (Some(time), Some(latitude), Some(longitude)) => (time, latitude, longitude),
+                    _ => break,
+                };
+            if !time.is_finite() || latitude.abs() > 90.0 || longitude.abs() > 360.0 {
+                return false;
+            }
+            if let Some(previous) = previous {
+                if time <= previous || time - previous > INFER_MAX_INTERVAL {
+                    return false;
+                }
+            }
+            previous = Some(time);
+        }
+        true
+    }))
+}
+
 impl<R: Read> IntoIterator for Reader<R> {
     type Item = Point;
     type IntoIter = ReaderIterator<R>;
@@ -88,6 +355,9 @@ impl<R: Read> IntoIterator for Reader<R> {
 }
 
 /// An iterator over an sbet reader.
+///
+/// Panics if a point can't be read. Use [Source::try_iter](crate::Source::try_iter) to handle
+/// errors.
 #[derive(Debug)]
 pub struct ReaderIterator<R: Read> {
     reader: Reader<R>,
@@ -98,11 +368,164 @@ impl<R: Read> Iterator for ReaderIterator<R> {
     fn next(&mut self) -> Option<Self::Item> {
         self.reader.read_point().unwrap()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        crate::source::size_hint(self.reader.remaining())
+    }
 }
 
 impl<R: Debug + Read> Source for Reader<R> {
     fn source(&mut self) -> Result<Option<Point>, Error> {
-        self.read_point().map_err(Error::from)
+        let result = self.read_point().map_err(|err| {
+            Error::from(err).at(Location {
+                path: self.path.clone(),
+                record: Some(self.record),
+            })
+        });
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read sbet record");
+        }
+        result
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        self.remaining()
+    }
+}
+
+impl<R: Debug + Read + Seek> SeekableSource for Reader<R> {
+    fn rewind(&mut self) -> Result<(), Error> {
+        self.seek_to_record(0)
+    }
+
+    fn seek_to_time(&mut self, time: f64) -> Result<(), Error> {
+        let record_size = self.layout.record_size();
+        let length = self
+            .reader
+            .seek(SeekFrom::End(0))?
+            .saturating_sub(self.start);
+        let index = crate::source::search_time(
+            &mut self.reader,
+            self.start,
+            record_size,
+            length / record_size,
+            time,
+        )?;
+        self.seek_to_record(index)
+    }
+}
+
+/// An SBET writer.
+///
+/// Optional fields that a point doesn't have, such as the velocities of a point read from a pos
+/// file, are written as zero.
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    writer: W,
+    layout: Layout,
+}
+
+impl Writer<BufWriter<File>> {
+    /// Creates a new writer for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::sbet::Writer;
+    /// let writer = Writer::from_path("out.sbet").unwrap();
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Writer<BufWriter<File>>, std::io::Error> {
+        Ok(Writer::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new writer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Writer;
+    /// let writer = Writer::new(Vec::new());
+    /// ```
+    pub fn new(writer: W) -> Writer<W> {
+        Writer {
+            writer,
+            layout: Layout::Standard,
+        }
+    }
+
+    /// Sets the record layout to write.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::{Layout, Writer};
+    /// let writer = Writer::new(Vec::new()).with_layout(Layout::NoAngularRates);
+    /// ```
+    pub fn with_layout(mut self, layout: Layout) -> Writer<W> {
+        self.layout = layout;
+        self
+    }
+
+    /// Writes a point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Writer;
+    /// use pos::Point;
+    /// let mut writer = Writer::new(Vec::new());
+    /// writer.write_point(&Point::default()).unwrap();
+    /// assert_eq!(136, writer.into_inner().unwrap().len());
+    /// ```
+    pub fn write_point(&mut self, point: &Point) -> Result<(), std::io::Error> {
+        for value in [
+            point.time,
+            point.latitude.0,
+            point.longitude.0,
+            point.altitude,
+            point.x_velocity.unwrap_or_default(),
+            point.y_velocity.unwrap_or_default(),
+            point.z_velocity.unwrap_or_default(),
+            point.roll.0,
+            point.pitch.0,
+            point.yaw.0,
+            point.wander_angle.unwrap_or_default().0,
+            point.x_acceleration.unwrap_or_default(),
+            point.y_acceleration.unwrap_or_default(),
+            point.z_acceleration.unwrap_or_default(),
+        ] {
+            self.writer.write_f64::<LittleEndian>(value)?;
+        }
+        if self.layout == Layout::Standard {
+            for rate in [
+                point.x_angular_rate,
+                point.y_angular_rate,
+                point.z_angular_rate,
+            ] {
+                self.writer
+                    .write_f64::<LittleEndian>(rate.unwrap_or_default().0)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, std::io::Error> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write + Debug> Sink for Writer<W> {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        self.write_point(point).map_err(Error::from)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(Error::from)
     }
 }
 
@@ -128,4 +551,133 @@ mod tests {
             points[1].time
         );
     }
+
+    #[test]
+    fn unit_overrides() {
+        let point = Point {
+            latitude: Radians(45.0),
+            altitude: 100.0,
+            z_angular_rate: Some(Radians(2.0)),
+            ..Default::default()
+        };
+        let bytes = crate::testutil::sbet(&[point]);
+        let point = Reader::new(bytes.as_slice())
+            .with_angle_unit(AngleUnit::Degrees)
+            .with_height_unit(LengthUnit::Feet)
+            .read_point()
+            .unwrap()
+            .unwrap();
+        assert!((45.0 - point.latitude.to_degrees()).abs() < 1e-12);
+        assert!((2.0 - point.z_angular_rate.unwrap().to_degrees()).abs() < 1e-12);
+        assert!((30.48 - point.altitude).abs() < 1e-12);
+    }
+
+    #[test]
+    fn infer() {
+        let points: Vec<_> = (0..3)
+            .map(|i| Point {
+                time: 100.0 + 0.005 * i as f64,
+                latitude: Radians(0.5),
+                longitude: Radians(-2.0),
+                x_angular_rate: Some(Radians(0.1)),
+                ..Default::default()
+            })
+            .collect();
+        let standard = crate::testutil::sbet(&points);
+        let mut short = Vec::new();
+        for record in standard.chunks(RECORD_SIZE as usize) {
+            short.extend_from_slice(&record[..112]);
+        }
+        let mut reader = Reader::infer(std::io::Cursor::new(standard)).unwrap();
+        assert_eq!(Layout::Standard, reader.layout());
+        assert_eq!(
+            Some(Radians(0.1)),
+            reader.read_point().unwrap().unwrap().x_angular_rate
+        );
+        let reader = Reader::infer(std::io::Cursor::new(short)).unwrap();
+        assert_eq!(Layout::NoAngularRates, reader.layout);
+        assert_eq!(Some(3), reader.len_hint());
+        let iter = reader.into_iter();
+        assert_eq!((0, Some(3)), iter.size_hint());
+        let read: Vec<_> = iter.collect();
+        assert_eq!(3, read.len());
+        assert_eq!(points[2].time, read[2].time);
+        assert_eq!(None, read[2].x_angular_rate);
+        assert!(matches!(
+            Reader::infer(std::io::Cursor::new(vec![0; 100])),
+            Err(Error::SbetLayout)
+        ));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let bytes = std::fs::read("data/2-points.sbet").unwrap();
+        let mut writer = Writer::new(Vec::new());
+        for point in Reader::new(bytes.as_slice()) {
+            writer.write_point(&point).unwrap();
+        }
+        assert_eq!(bytes, writer.into_inner().unwrap());
+    }
+
+    #[test]
+    fn try_iter_truncated() {
+        let bytes = std::fs::read("data/2-points.sbet").unwrap();
+        let results: Vec<_> = Reader::new(&bytes[..bytes.len() - 8]).try_iter().collect();
+        assert_eq!(2, results.len());
+        assert!(results[0].is_ok());
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(crate::ErrorKind::Io, err.kind());
+        assert_eq!(Some(1), err.location().unwrap().record);
+    }
+
+    #[test]
+    fn write_layout() {
+        let point = Point {
+            time: 1.0,
+            x_velocity: Some(2.0),
+            z_angular_rate: Some(Radians(0.5)),
+            ..Default::default()
+        };
+        let mut writer = Writer::new(Vec::new()).with_layout(Layout::NoAngularRates);
+        writer.write_point(&point).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(112, bytes.len());
+        let read = Reader::new(bytes.as_slice())
+            .with_layout(Layout::NoAngularRates)
+            .read_point()
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(2.0), read.x_velocity);
+        assert_eq!(None, read.z_angular_rate);
+    }
+
+    #[test]
+    fn seek() {
+        let points: Vec<_> = [1.0, 2.0, 3.0]
+            .into_iter()
+            .map(|time| Point {
+                time,
+                ..Default::default()
+            })
+            .collect();
+        let mut reader = Reader::new(std::io::Cursor::new(crate::testutil::sbet(&points)));
+        reader.seek_to_time(1.5).unwrap();
+        assert_eq!(Some(2), reader.len_hint());
+        assert_eq!(2.0, reader.source().unwrap().unwrap().time);
+        reader.rewind().unwrap();
+        assert_eq!(1.0, reader.source().unwrap().unwrap().time);
+        reader.seek_to_time(4.0).unwrap();
+        assert!(reader.source().unwrap().is_none());
+
+        let mut bytes = b"prefix".to_vec();
+        bytes.extend(crate::testutil::sbet(&points));
+        let mut cursor = std::io::Cursor::new(bytes);
+        cursor.set_position(6);
+        let mut reader = Reader::infer(cursor).unwrap();
+        reader.seek_to_time(1.5).unwrap();
+        assert_eq!(Some(2), reader.len_hint());
+        assert_eq!(2.0, reader.source().unwrap().unwrap().time);
+        reader.rewind().unwrap();
+        assert_eq!(1.0, reader.source().unwrap().unwrap().time);
+    }
 }
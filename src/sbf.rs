@@ -0,0 +1,439 @@
+//! Read Septentrio Binary Format (SBF) logs.
+//!
+//! Each SBF block starts with the sync characters `$@`, followed by a CRC-16-CCITT of the rest of
+//! the block, a block id, and the block's length, including the eight-byte header. Blocks with a
+//! bad CRC or an invalid length are skipped, and the reader resynchronizes on the next sync
+//! characters, so garbage between blocks is tolerated.
+//!
+//! PVTGeodetic blocks become points, and AttEuler blocks from the same epoch add the attitude and
+//! angular rates. Other blocks are skipped. Point times are GPS seconds since the GPS epoch,
+//! `week * 604800 + time_of_week`.
+
+use crate::point::{Accuracy, FixQuality, Point, SatelliteCount};
+use crate::source::Source;
+use crate::units::Radians;
+use crate::{Error, Location};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// The sync characters at the start of every block.
+pub const SYNC: [u8; 2] = *b"$@";
+
+/// The block number of PVTGeodetic blocks.
+const PVT_GEODETIC: u16 = 4007;
+
+/// The block number of AttEuler blocks.
+const ATT_EULER: u16 = 5938;
+
+/// The value of single-precision fields that aren't available.
+const DO_NOT_USE_F32: f32 = -2e10;
+
+/// The value of double-precision fields that aren't available.
+const DO_NOT_USE_F64: f64 = -2e10;
+
+/// An SBF reader.
+#[derive(Debug)]
+pub struct Reader<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    pending: Option<(u64, Point)>,
+    attitude: Option<Attitude>,
+    record: u64,
+    path: Option<PathBuf>,
+}
+
+/// An AttEuler block.
+#[derive(Clone, Copy, Debug)]
+struct Attitude {
+    epoch: u64,
+    roll: Option<Radians<f64>>,
+    pitch: Option<Radians<f64>>,
+    heading: Option<Radians<f64>>,
+    rates: [Option<Radians<f64>>; 3],
+}
+
+impl Reader<BufReader<File>> {
+    /// Opens a reader for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::sbf::Reader;
+    /// let reader = Reader::from_path("flight.sbf").unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, std::io::Error> {
+        let path = path.as_ref();
+        let mut reader = Reader::new(BufReader::new(File::open(path)?));
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
+    }
+}
+
+impl<R: Read> Reader<R> {
+    /// Creates a new reader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbf::Reader;
+    /// let reader = Reader::new(std::io::empty());
+    /// ```
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            buffer: Vec::new(),
+            pending: None,
+            attitude: None,
+            record: 0,
+            path: None,
+        }
+    }
+
+    /// Reads the next epoch's point.
+    ///
+    /// Epochs without a PVT solution are skipped. Altitudes are ellipsoidal heights, velocities
+    /// are east, north, and up, and the accuracy holds the horizontal and vertical accuracy
+    /// estimates and the number of satellites. Without an AttEuler block, the attitude is zero.
+    ///
+    /// A truncated block at the end of the input is ignored.
+    pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
+        while let Some((number, block)) = self.read_block()? {
+            match (number, block.len()) {
+                (PVT_GEODETIC, 95..) => {
+                    let epoch = epoch(&block);
+                    let point = pvt_geodetic(&block);
+                    let done = self.pending.take().map(|(_, point)| point);
+                    if let (Some(epoch), Some(mut point)) = (epoch, point) {
+                        if let Some(attitude) = self.attitude.filter(|a| a.epoch == epoch) {
+                            attitude.apply(&mut point);
+                        }
+                        self.pending = Some((epoch, point));
+                    }
+                    if done.is_some() {
+                        return Ok(done);
+                    }
+                }
+                (ATT_EULER, 44..) => {
+                    if let Some(attitude) = Attitude::new(&block) {
+                        match self.pending.as_mut() {
+                            Some((epoch, point)) if *epoch == attitude.epoch => {
+                                attitude.apply(point)
+                            }
+                            _ => self.attitude = Some(attitude),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(self.pending.take().map(|(_, point)| point))
+    }
+
+    /// Reads the next block with a valid CRC, returning its block number and the whole block.
+    fn read_block(&mut self) -> Result<Option<(u16, Vec<u8>)>, std::io::Error> {
+        loop {
+            if !self.fill(2)? {
+                return Ok(None);
+            }
+            match self.buffer.windows(2).position(|window| window == SYNC) {
+                Some(start) => {
+                    let _ = self.buffer.drain(..start);
+                }
+                None => {
+                    let keep = usize::from(self.buffer.last() == Some(&SYNC[0]));
+                    let _ = self.buffer.drain(..self.buffer.len() - keep);
+                    continue;
+                }
+            }
+            if !self.fill(8)? {
+                return Ok(None);
+            }
+            let length = usize::from(u16_at(&self.buffer, 6));
+            if length >= 8 && length % 4 == 0 {
+                if !self.fill(length)? {
+                    return Ok(None);
+                }
+                if crc(&self.buffer[4..length]) == u16_at(&self.buffer, 2) {
+                    let block: Vec<u8> = self.buffer.drain(..length).collect();
+                    return Ok(Some((u16_at(&block, 4) & 0x1fff, block)));
+                }
+            }
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                id = u16_at(&self.buffer, 4),
+                length,
+                "skipping sbf block with a bad crc or length"
+            );
+            let _ = self.buffer.drain(..1);
+        }
+    }
+
+    /// Reads until the buffer holds at least `length` bytes, returning false at the end of the
+    /// input.
+    fn fill(&mut self, length: usize) -> Result<bool, std::io::Error> {
+        let mut chunk = [0; 4096];
+        while self.buffer.len() < length {
+            match self.reader.read(&mut chunk)? {
+                0 => return Ok(false),
+                n => self.buffer.extend_from_slice(&chunk[..n]),
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Attitude {
+    fn new(block: &[u8]) -> Option<Attitude> {
+        if block[15] != 0 {
+            return None;
+        }
+        let angle = |offset: usize| f32_at(block, offset).map(|v| Radians::from_degrees(v.into()));
+        Some(Attitude {
+            epoch: epoch(block)?,
+            heading: angle(20),
+            pitch: angle(24),
+            roll: angle(28),
+            rates: [angle(36), angle(32), angle(40)],
+        })
+    }
+
+    fn apply(&self, point: &mut Point) {
+        point.roll = self.roll.unwrap_or_default();
+        point.pitch = self.pitch.unwrap_or_default();
+        point.yaw = self.heading.unwrap_or_default();
+        point.x_angular_rate = self.rates[0];
+        point.y_angular_rate = self.rates[1];
+        point.z_angular_rate = self.rates[2];
+    }
+}
+
+/// Returns a block's time in milliseconds since the GPS epoch, if it's set.
+fn epoch(block: &[u8]) -> Option<u64> {
+    let time_of_week = u32_at(block, 8);
+    let week = u16_at(block, 12);
+    if time_of_week == u32::MAX || week == u16::MAX {
+        None
+    } else {
+        Some(u64::from(week) * 604_800_000 + u64::from(time_of_week))
+    }
+}
+
+fn pvt_geodetic(block: &[u8]) -> Option<Point> {
+    if block[15] != 0 {
+        return None;
+    }
+    let fix_quality = FixQuality::from_sbf(block[14])?;
+    let time = epoch(block)? as f64 / 1000.0;
+    let latitude = f64_at(block, 16)?;
+    let longitude = f64_at(block, 24)?;
+    let altitude = f64_at(block, 32)?;
+    let velocity = |offset: usize| f32_at(block, offset).map(f64::from);
+    let accuracy = match (u16_at(block, 90), u16_at(block, 92)) {
+        (u16::MAX, _) | (_, u16::MAX) => None,
+        (horizontal, vertical) => Some(Accuracy {
+            time,
+            x: f64::from(horizontal) * 0.01,
+            y: f64::from(horizontal) * 0.01,
+            z: f64::from(vertical) * 0.01,
+            satellite_count: (block[74] != u8::MAX)
+                .then(|| SatelliteCount::Unspecified(block[74].into())),
+            ..Default::default()
+        }),
+    };
+    Some(Point {
+        time,
+        latitude: Radians(latitude),
+        longitude: Radians(longitude),
+        altitude,
+        x_velocity: velocity(48),
+        y_velocity: velocity(44),
+        z_velocity: velocity(52),
+        accuracy,
+        fix_quality: Some(fix_quality),
+        ..Default::default()
+    })
+}
+
+impl<R: Read> IntoIterator for Reader<R> {
+    type Item = Point;
+    type IntoIter = ReaderIterator<R>;
+    fn into_iter(self) -> Self::IntoIter {
+        ReaderIterator { reader: self }
+    }
+}
+
+/// An iterator over an SBF reader.
+#[derive(Debug)]
+pub struct ReaderIterator<R: Read> {
+    reader: Reader<R>,
+}
+
+impl<R: Read> Iterator for ReaderIterator<R> {
+    type Item = Point;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_point().unwrap()
+    }
+}
+
+impl<R: Debug + Read> Source for Reader<R> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(self.record),
+            })
+        });
+        if let Ok(Some(_)) = result {
+            self.record += 1;
+        }
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read sbf block");
+        }
+        result
+    }
+}
+
+/// Computes the CRC-16-CCITT, with a zero initial value, of a block's id, length, and body.
+fn crc(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in bytes {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+fn f32_at(bytes: &[u8], offset: usize) -> Option<f32> {
+    let value = f32::from_bits(u32_at(bytes, offset));
+    (value != DO_NOT_USE_F32).then_some(value)
+}
+
+fn f64_at(bytes: &[u8], offset: usize) -> Option<f64> {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[offset..offset + 8]);
+    let value = f64::from_le_bytes(buf);
+    (value != DO_NOT_USE_F64).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: u16, mut body: Vec<u8>) -> Vec<u8> {
+        body.resize(body.len().next_multiple_of(4), 0);
+        let mut block = SYNC.to_vec();
+        block.extend_from_slice(&[0, 0]);
+        block.extend_from_slice(&number.to_le_bytes());
+        block.extend_from_slice(&(body.len() as u16 + 8).to_le_bytes());
+        block.extend(body);
+        let crc = crc(&block[4..]);
+        block[2..4].copy_from_slice(&crc.to_le_bytes());
+        block
+    }
+
+    fn pvt(time_of_week: u32, mode: u8) -> Vec<u8> {
+        let mut body = vec![0; 87];
+        let mut put = |offset: usize, bytes: &[u8]| {
+            body[offset - 8..offset - 8 + bytes.len()].copy_from_slice(bytes)
+        };
+        put(8, &time_of_week.to_le_bytes());
+        put(12, &2000u16.to_le_bytes());
+        put(14, &[mode, 0]);
+        put(16, &40f64.to_radians().to_le_bytes());
+        put(24, &(-105f64).to_radians().to_le_bytes());
+        put(32, &1600f64.to_le_bytes());
+        put(44, &1f32.to_le_bytes());
+        put(48, &2f32.to_le_bytes());
+        put(52, &DO_NOT_USE_F32.to_le_bytes());
+        put(74, &[14]);
+        put(90, &2u16.to_le_bytes());
+        put(92, &5u16.to_le_bytes());
+        block(PVT_GEODETIC, body)
+    }
+
+    fn att(time_of_week: u32) -> Vec<u8> {
+        let mut body = vec![0; 36];
+        body[..4].copy_from_slice(&time_of_week.to_le_bytes());
+        body[4..6].copy_from_slice(&2000u16.to_le_bytes());
+        body[12..16].copy_from_slice(&270f32.to_le_bytes());
+        body[16..20].copy_from_slice(&(-2f32).to_le_bytes());
+        body[20..24].copy_from_slice(&1f32.to_le_bytes());
+        body[32..36].copy_from_slice(&DO_NOT_USE_F32.to_le_bytes());
+        block(ATT_EULER, body)
+    }
+
+    #[test]
+    fn epochs() {
+        let mut log = b"garbage$".to_vec();
+        log.extend(pvt(1000, 4));
+        log.extend(block(4001, vec![0; 8]));
+        log.extend(att(1000));
+        log.extend(pvt(1100, 0));
+        log.extend(pvt(1200, 0x81));
+        let points: Vec<_> = Reader::new(log.as_slice()).into_iter().collect();
+        assert_eq!(2, points.len());
+
+        let point = points[0];
+        assert_eq!(2000.0 * 604_800.0 + 1.0, point.time);
+        assert_eq!(Some(FixQuality::RtkFixed), point.fix_quality);
+        assert!((point.latitude_degrees() - 40.0).abs() < 1e-9);
+        assert!((point.longitude_degrees() + 105.0).abs() < 1e-9);
+        assert_eq!(1600.0, point.altitude);
+        assert_eq!((Some(2.0), Some(1.0)), (point.x_velocity, point.y_velocity));
+        assert_eq!(None, point.z_velocity);
+        assert!((point.yaw.to_degrees() - 270.0).abs() < 1e-6);
+        assert!((point.pitch.to_degrees() + 2.0).abs() < 1e-6);
+        assert!((point.roll.to_degrees() - 1.0).abs() < 1e-6);
+        assert_eq!(None, point.z_angular_rate);
+        let accuracy = point.accuracy.unwrap();
+        assert_eq!((0.02, 0.05), (accuracy.x, accuracy.z));
+        assert_eq!(
+            Some(SatelliteCount::Unspecified(14)),
+            accuracy.satellite_count
+        );
+
+        let point = points[1];
+        assert_eq!(Some(FixQuality::Sps), point.fix_quality);
+        assert_eq!(0.0, point.yaw.0);
+    }
+
+    #[test]
+    fn resynchronize() {
+        let mut corrupt = pvt(1000, 1);
+        corrupt[20] ^= 0xff;
+        let mut log = corrupt;
+        log.extend(pvt(2000, 1));
+        log.extend(&pvt(3000, 1)[..50]);
+        let points: Vec<_> = Reader::new(log.as_slice()).into_iter().collect();
+        assert_eq!(1, points.len());
+        assert_eq!(2000.0 * 604_800.0 + 2.0, points[0].time);
+    }
+
+    #[test]
+    fn crc_check_value() {
+        assert_eq!(0x31c3, crc(b"123456789"));
+    }
+}
@@ -0,0 +1,261 @@
+//! Split trajectories into flight lines and turns.
+//!
+//! Each interval between consecutive points is classified by its heading rate and speed, and
+//! runs of intervals with the same classification become segments. Lines that are too short
+//! are treated as part of the surrounding turns.
+
+use crate::geodesy;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+
+/// The default maximum heading rate for a flight line, in degrees per second.
+pub const DEFAULT_MAX_HEADING_RATE: f64 = 2.0;
+
+/// The default minimum speed for a flight line or turn, in meters per second.
+pub const DEFAULT_MIN_SPEED: f64 = 5.0;
+
+/// The default minimum duration of a flight line, in seconds.
+pub const DEFAULT_MIN_LINE_DURATION: f64 = 10.0;
+
+/// Splits trajectories into segments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segmenter {
+    max_heading_rate: Radians<f64>,
+    min_speed: f64,
+    min_line_duration: f64,
+}
+
+/// The kind of a segment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A straight, steady flight line.
+    Line,
+    /// A turn, or any other movement that isn't a flight line.
+    Turn,
+    /// Moving slower than the minimum speed, e.g. on the ground.
+    Stationary,
+}
+
+/// A contiguous part of a trajectory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    /// The kind of this segment.
+    pub kind: Kind,
+    /// The time of the first point.
+    pub start: f64,
+    /// The time of the last point.
+    pub end: f64,
+    /// The mean heading (yaw) over the segment, wrapped to `[-π, π)`.
+    pub heading: Radians<f64>,
+    sin: f64,
+    cos: f64,
+}
+
+impl Default for Segmenter {
+    fn default() -> Segmenter {
+        Segmenter {
+            max_heading_rate: Radians::from_degrees(DEFAULT_MAX_HEADING_RATE),
+            min_speed: DEFAULT_MIN_SPEED,
+            min_line_duration: DEFAULT_MIN_LINE_DURATION,
+        }
+    }
+}
+
+impl Segmenter {
+    /// Creates a new segmenter with the default thresholds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::segment::Segmenter;
+    /// use pos::Radians;
+    /// let segmenter = Segmenter::new()
+    ///     .max_heading_rate(Radians::from_degrees(1.0))
+    ///     .min_speed(40.0);
+    /// ```
+    pub fn new() -> Segmenter {
+        Segmenter::default()
+    }
+
+    /// Sets the maximum heading rate of a flight line, per second.
+    pub fn max_heading_rate(mut self, max_heading_rate: Radians<f64>) -> Segmenter {
+        self.max_heading_rate = max_heading_rate;
+        self
+    }
+
+    /// Sets the minimum speed, in meters per second, below which points are stationary.
+    pub fn min_speed(mut self, min_speed: f64) -> Segmenter {
+        self.min_speed = min_speed;
+        self
+    }
+
+    /// Sets the minimum duration of a flight line, in seconds.
+    pub fn min_line_duration(mut self, min_line_duration: f64) -> Segmenter {
+        self.min_line_duration = min_line_duration;
+        self
+    }
+
+    /// Splits every point from a source into segments, in time order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::segment::Segmenter;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let segments = Segmenter::new().segment(&mut reader).unwrap();
+    /// ```
+    pub fn segment(&self, source: &mut dyn Source) -> Result<Vec<Segment>, Error> {
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut previous = match source.source()? {
+            Some(point) => point,
+            None => return Ok(segments),
+        };
+        while let Some(point) = source.source()? {
+            let interval = point.time - previous.time;
+            if interval <= 0.0 {
+                continue;
+            }
+            let speed = geodesy::distance(&previous, &point) / interval;
+            let heading_rate = (point.yaw - previous.yaw).wrapped().0.abs() / interval;
+            let kind = if speed < self.min_speed {
+                Kind::Stationary
+            } else if heading_rate > self.max_heading_rate.0 {
+                Kind::Turn
+            } else {
+                Kind::Line
+            };
+            match segments.last_mut() {
+                Some(segment) if segment.kind == kind => segment.add(point.time, point.yaw),
+                _ => {
+                    let mut segment = Segment::new(kind, previous.time, previous.yaw);
+                    segment.add(point.time, point.yaw);
+                    segments.push(segment);
+                }
+            }
+            previous = point;
+        }
+        for segment in &mut segments {
+            if segment.kind == Kind::Line && segment.duration() < self.min_line_duration {
+                segment.kind = Kind::Turn;
+            }
+        }
+        let mut merged: Vec<Segment> = Vec::with_capacity(segments.len());
+        for segment in segments {
+            match merged.last_mut() {
+                Some(last) if last.kind == segment.kind => last.merge(&segment),
+                _ => merged.push(segment),
+            }
+        }
+        Ok(merged)
+    }
+}
+
+impl Segment {
+    fn new(kind: Kind, time: f64, heading: Radians<f64>) -> Segment {
+        let (sin, cos) = heading.0.sin_cos();
+        Segment {
+            kind,
+            start: time,
+            end: time,
+            heading: heading.wrapped(),
+            sin,
+            cos,
+        }
+    }
+
+    fn add(&mut self, time: f64, heading: Radians<f64>) {
+        let (sin, cos) = heading.0.sin_cos();
+        self.end = time;
+        self.sin += sin;
+        self.cos += cos;
+        self.heading = Radians(self.sin.atan2(self.cos)).wrapped();
+    }
+
+    fn merge(&mut self, other: &Segment) {
+        self.end = other.end;
+        self.sin += other.sin;
+        self.cos += other.cos;
+        self.heading = Radians(self.sin.atan2(self.cos)).wrapped();
+    }
+
+    /// Returns the duration of this segment.
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Point;
+    use crate::source::MemorySource;
+
+    /// Flies north at about 50 m/s for 20 seconds, turns east over 9 seconds, then flies east
+    /// for 20 seconds.
+    fn points() -> Vec<Point> {
+        let mut points = Vec::new();
+        let step = Radians::from_degrees(50.0 / 111_000.0);
+        let (mut latitude, mut longitude) = (Radians(0.0), Radians(0.0));
+        for i in 0..50 {
+            let yaw = match i {
+                0..=20 => 0.0,
+                21..=29 => 10.0 * (i - 20) as f64,
+                _ => 90.0,
+            };
+            let yaw = Radians::from_degrees(yaw);
+            points.push(Point {
+                time: i as f64,
+                latitude,
+                longitude,
+                altitude: 1000.0,
+                yaw,
+                ..Default::default()
+            });
+            latitude = latitude + yaw.0.cos() * step;
+            longitude = longitude + yaw.0.sin() * step;
+        }
+        points
+    }
+
+    #[test]
+    fn lines_and_turns() {
+        let segments = Segmenter::new()
+            .segment(&mut MemorySource::new(points()))
+            .unwrap();
+        let kinds: Vec<_> = segments.iter().map(|s| s.kind).collect();
+        assert_eq!(vec![Kind::Line, Kind::Turn, Kind::Line], kinds);
+        assert_eq!(0.0, segments[0].start);
+        assert_eq!(20.0, segments[0].end);
+        assert_eq!(29.0, segments[1].end);
+        assert_eq!(49.0, segments[2].end);
+        assert!(segments[0].heading.to_degrees().abs() < 1e-9);
+        assert!((segments[2].heading.to_degrees() - 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn short_lines() {
+        let segments = Segmenter::new()
+            .min_line_duration(30.0)
+            .segment(&mut MemorySource::new(points()))
+            .unwrap();
+        assert_eq!(1, segments.len());
+        assert_eq!(Kind::Turn, segments[0].kind);
+    }
+
+    #[test]
+    fn stationary() {
+        let points: Vec<_> = (0..10)
+            .map(|i| Point {
+                time: i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let segments = Segmenter::new()
+            .segment(&mut MemorySource::new(points))
+            .unwrap();
+        assert_eq!(1, segments.len());
+        assert_eq!(Kind::Stationary, segments[0].kind);
+    }
+}
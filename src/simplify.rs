@@ -0,0 +1,166 @@
+//! Simplify trajectories with the Douglas-Peucker algorithm.
+//!
+//! Positions are compared in earth-centered, earth-fixed coordinates, so tolerances are true 3D
+//! distances in meters. Simplification always keeps the first and last points and never
+//! reorders points, so the simplified trajectory follows the same path in the same direction.
+//!
+//! The time-synchronized variant measures each point against the position the simplified
+//! trajectory would have *at that point's time*, rather than against the nearest point on the
+//! simplified line. This bounds the error of interpolating the simplified trajectory, not just
+//! its shape, which matters when it'll be used to look up positions by time.
+
+use crate::geodesy;
+use crate::point::Point;
+
+/// How to measure the distance from a point to a simplified segment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Distance {
+    /// The shortest distance to the segment.
+    #[default]
+    Perpendicular,
+    /// The distance to the position on the segment at the point's time.
+    Synchronized,
+}
+
+/// Simplifies points so that no removed point is farther than `tolerance` meters from the
+/// simplified trajectory.
+///
+/// # Examples
+///
+/// ```
+/// use pos::simplify::{self, Distance};
+/// use pos::Point;
+/// let points: Vec<_> = [0.0, 0.1, 0.0, 5.0, 10.0]
+///     .iter()
+///     .enumerate()
+///     .map(|(i, &altitude)| Point { time: i as f64, altitude, ..Default::default() })
+///     .collect();
+/// let simplified = simplify::simplify(&points, 1.0, Distance::Synchronized);
+/// assert_eq!(3, simplified.len());
+/// ```
+pub fn simplify(points: &[Point], tolerance: f64, distance: Distance) -> Vec<Point> {
+    indices(points, tolerance, distance)
+        .into_iter()
+        .map(|i| points[i])
+        .collect()
+}
+
+/// Returns the indices of the points kept by [simplify].
+pub fn indices(points: &[Point], tolerance: f64, distance: Distance) -> Vec<usize> {
+    if points.len() < 3 {
+        return (0..points.len()).collect();
+    }
+    let positions: Vec<_> = points
+        .iter()
+        .map(|p| geodesy::to_ecef(p.latitude, p.longitude, p.altitude))
+        .collect();
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    // An explicit stack, since trajectories can be far longer than the call stack is deep.
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = stack.pop() {
+        let mut farthest = (0.0, start);
+        for i in start + 1..end {
+            let d = match distance {
+                Distance::Perpendicular => {
+                    perpendicular(positions[i], positions[start], positions[end])
+                }
+                Distance::Synchronized => {
+                    let span = points[end].time - points[start].time;
+                    let t = if span > 0.0 {
+                        (points[i].time - points[start].time) / span
+                    } else {
+                        0.0
+                    };
+                    norm(sub(positions[i], lerp(positions[start], positions[end], t)))
+                }
+            };
+            if d > farthest.0 {
+                farthest = (d, i);
+            }
+        }
+        if farthest.0 > tolerance {
+            keep[farthest.1] = true;
+            stack.push((start, farthest.1));
+            stack.push((farthest.1, end));
+        }
+    }
+    keep.iter()
+        .enumerate()
+        .filter_map(|(i, &keep)| keep.then_some(i))
+        .collect()
+}
+
+fn perpendicular(p: [f64; 3], a: [f64; 3], b: [f64; 3]) -> f64 {
+    let ab = sub(b, a);
+    let length_squared = dot(ab, ab);
+    if length_squared == 0.0 {
+        return norm(sub(p, a));
+    }
+    let t = (dot(sub(p, a), ab) / length_squared).clamp(0.0, 1.0);
+    norm(sub(p, lerp(a, b, t)))
+}
+
+fn lerp(a: [f64; 3], b: [f64; 3], t: f64) -> [f64; 3] {
+    [
+        a[0] + t * (b[0] - a[0]),
+        a[1] + t * (b[1] - a[1]),
+        a[2] + t * (b[2] - a[2]),
+    ]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn norm(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(time: f64, altitude: f64) -> Point {
+        Point {
+            time,
+            altitude,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn straight_line() {
+        let points: Vec<_> = (0..100).map(|i| point(i as f64, i as f64)).collect();
+        assert_eq!(vec![0, 99], indices(&points, 1e-6, Distance::Perpendicular));
+    }
+
+    #[test]
+    fn synchronized() {
+        // Same path, but the platform pauses halfway, so the synchronized distance is large
+        // even though the perpendicular distance is zero.
+        let points = vec![
+            point(0.0, 0.0),
+            point(1.0, 5.0),
+            point(9.0, 5.0),
+            point(10.0, 10.0),
+        ];
+        assert_eq!(vec![0, 3], indices(&points, 1.0, Distance::Perpendicular));
+        assert_eq!(
+            vec![0, 1, 2, 3],
+            indices(&points, 1.0, Distance::Synchronized)
+        );
+    }
+
+    #[test]
+    fn short() {
+        let points = vec![point(0.0, 0.0), point(1.0, 100.0)];
+        assert_eq!(2, simplify(&points, 1.0, Distance::Perpendicular).len());
+        assert!(simplify(&[], 1.0, Distance::Perpendicular).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+//! Sinks of position points.
+
+#[cfg(feature = "pof")]
+use crate::pof;
+use crate::point::Point;
+#[cfg(feature = "pos")]
+use crate::pos;
+#[cfg(feature = "sbet")]
+use crate::sbet;
+use crate::source::Source;
+use crate::Error;
+use std::fmt::Debug;
+#[cfg(any(feature = "pof", feature = "pos", feature = "sbet"))]
+use std::fs::File;
+#[cfg(any(feature = "pof", feature = "pos", feature = "sbet"))]
+use std::io::BufWriter;
+use std::path::Path;
+
+/// A sink of points.
+pub trait Sink: Debug {
+    /// Writes one point to the sink.
+    fn sink(&mut self, point: &Point) -> Result<(), Error>;
+
+    /// Finishes writing.
+    ///
+    /// This flushes buffered output and completes anything that depends on every point, such as
+    /// a pof header. Call it once all points have been written.
+    fn finish(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for &mut S {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        (**self).sink(point)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        (**self).finish()
+    }
+}
+
+/// Collects points in memory.
+impl Sink for Vec<Point> {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        self.push(*point);
+        Ok(())
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Box<S> {
+    fn sink(&mut self, point: &Point) -> Result<(), Error> {
+        (**self).sink(point)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        (**self).finish()
+    }
+}
+
+/// A sink of points that writes to a file.
+pub trait FileSink {
+    /// Creates a new file sink, truncating the file if it exists.
+    fn create_file_sink<P: AsRef<Path>>(path: P) -> Result<Box<dyn Sink>, Error>;
+}
+
+#[cfg(feature = "pof")]
+impl FileSink for pof::Writer<BufWriter<File>> {
+    fn create_file_sink<P: AsRef<Path>>(path: P) -> Result<Box<dyn Sink>, Error> {
+        Ok(Box::new(pof::Writer::from_path(path)?))
+    }
+}
+
+#[cfg(feature = "pos")]
+impl FileSink for pos::Writer<BufWriter<File>> {
+    fn create_file_sink<P: AsRef<Path>>(path: P) -> Result<Box<dyn Sink>, Error> {
+        Ok(Box::new(pos::Writer::from_path(path)?))
+    }
+}
+
+#[cfg(feature = "sbet")]
+impl FileSink for sbet::Writer<BufWriter<File>> {
+    fn create_file_sink<P: AsRef<Path>>(path: P) -> Result<Box<dyn Sink>, Error> {
+        Ok(Box::new(sbet::Writer::from_path(path)?))
+    }
+}
+
+/// Writes every point from a source to a sink, then finishes the sink.
+///
+/// Returns the number of points written.
+///
+/// # Examples
+///
+/// ```
+/// use pos::sbet::{Reader, Writer};
+/// use pos::sink;
+/// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+/// let mut writer = Writer::new(Vec::new());
+/// assert_eq!(2, sink::pipe(&mut reader, &mut writer).unwrap());
+/// ```
+pub fn pipe(source: &mut dyn Source, sink: &mut dyn Sink) -> Result<u64, Error> {
+    let mut count = 0;
+    while let Some(point) = source.source()? {
+        sink.sink(&point)?;
+        count += 1;
+    }
+    sink.finish()?;
+    #[cfg(feature = "tracing")]
+    tracing::debug!(count, "piped points");
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    #[test]
+    fn pipe_boxed() {
+        let points: Vec<_> = (0..3)
+            .map(|i| Point {
+                time: i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let mut sunk = Vec::new();
+        {
+            let mut sink: Box<dyn Sink + '_> = Box::new(&mut sunk);
+            assert_eq!(
+                3,
+                pipe(&mut MemorySource::new(points.clone()), &mut sink).unwrap()
+            );
+        }
+        assert_eq!(points, sunk);
+    }
+}
@@ -0,0 +1,428 @@
+//! Smooth trajectories.
+
+use crate::frames;
+use crate::geodesy;
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+use std::collections::VecDeque;
+
+/// A source that smooths the positions and attitudes of another source with a centered moving
+/// average.
+///
+/// Each output point is the average of the points `half_width` before and after it. Near the
+/// ends of the source the window shrinks so that it stays centered. Attitudes and longitude
+/// are averaged as angles, so they behave across the ±180° wrap. Time and every other field
+/// are passed through from the center point.
+#[derive(Debug)]
+pub struct MovingAverage {
+    window: Window,
+}
+
+impl MovingAverage {
+    /// Creates a new moving average over `2 * half_width + 1` points.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::smooth::MovingAverage;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let source = MovingAverage::new(Box::new(reader), 5);
+    /// ```
+    pub fn new(source: Box<dyn Source>, half_width: usize) -> MovingAverage {
+        MovingAverage {
+            window: Window::new(source, half_width),
+        }
+    }
+}
+
+impl Source for MovingAverage {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        if !self.window.fill()? {
+            return Ok(None);
+        }
+        let (points, index) = (&self.window.points, self.window.index);
+        let half_width = self
+            .window
+            .half_width
+            .min(index)
+            .min(points.len() - 1 - index);
+        let point = average(
+            &points[index],
+            points.range(index - half_width..=index + half_width),
+        );
+        self.window.advance();
+        Ok(Some(point))
+    }
+}
+
+/// A buffer of the points around the current point of a source.
+#[derive(Debug)]
+struct Window {
+    source: Box<dyn Source>,
+    half_width: usize,
+    points: VecDeque<Point>,
+    index: usize,
+    done: bool,
+}
+
+impl Window {
+    fn new(source: Box<dyn Source>, half_width: usize) -> Window {
+        Window {
+            source,
+            half_width,
+            points: VecDeque::with_capacity(2 * half_width + 1),
+            index: 0,
+            done: false,
+        }
+    }
+
+    /// Reads until there are `half_width` points after the current point, or the source is
+    /// exhausted. Returns false if there is no current point.
+    fn fill(&mut self) -> Result<bool, Error> {
+        while !self.done && self.points.len() <= self.index + self.half_width {
+            match self.source.source()? {
+                Some(point) => self.points.push_back(point),
+                None => self.done = true,
+            }
+        }
+        Ok(self.index < self.points.len())
+    }
+
+    fn advance(&mut self) {
+        if self.index < self.half_width {
+            self.index += 1;
+        } else {
+            let _ = self.points.pop_front();
+        }
+    }
+}
+
+/// A source that smooths another source with a Savitzky-Golay filter, optionally estimating
+/// first derivatives.
+///
+/// For each point, a polynomial is fit by least squares to the points within `half_width` on
+/// either side, using their actual times, so irregular sampling is handled. Near the ends of
+/// the source the fit uses whatever neighbors are available. Latitude, longitude, altitude,
+/// roll, pitch, and yaw are smoothed; angles are unwrapped around the center point first.
+///
+/// With [SavitzkyGolay::with_derivatives], the slopes of the fits fill in the point's missing
+/// velocities and angular rates, like [Derive](crate::dynamics::Derive): `x_velocity`,
+/// `y_velocity`, and `z_velocity` are the east, north, and up velocities in meters per second,
+/// and the roll, pitch, and yaw rates are converted to body-frame angular rates with
+/// [euler_rates_to_body](crate::frames::euler_rates_to_body). Fields the source already has are
+/// kept, and points with filled fields are marked as [derived](Point::derived).
+#[derive(Debug)]
+pub struct SavitzkyGolay {
+    window: Window,
+    order: usize,
+    derivatives: bool,
+}
+
+impl SavitzkyGolay {
+    /// Creates a new filter with a window of `2 * half_width + 1` points and a polynomial of
+    /// the given order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::smooth::SavitzkyGolay;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let source = SavitzkyGolay::new(Box::new(reader), 5, 2).with_derivatives(true);
+    /// ```
+    pub fn new(source: Box<dyn Source>, half_width: usize, order: usize) -> SavitzkyGolay {
+        SavitzkyGolay {
+            window: Window::new(source, half_width),
+            order,
+            derivatives: false,
+        }
+    }
+
+    /// Sets whether to fill in missing velocities and angular rates from the smoothed first
+    /// derivatives.
+    pub fn with_derivatives(mut self, derivatives: bool) -> SavitzkyGolay {
+        self.derivatives = derivatives;
+        self
+    }
+}
+
+impl Source for SavitzkyGolay {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        if !self.window.fill()? {
+            return Ok(None);
+        }
+        let points = &self.window.points;
+        let center = points[self.window.index];
+        let start = self.window.index.saturating_sub(self.window.half_width);
+        let end = (self.window.index + self.window.half_width).min(points.len() - 1);
+        let window: Vec<_> = points.range(start..=end).collect();
+        let times: Vec<_> = window.iter().map(|p| p.time - center.time).collect();
+        let channel = |f: &dyn Fn(&Point) -> f64| -> (f64, f64) {
+            let values: Vec<_> = window.iter().map(|p| f(p)).collect();
+            fit(&times, &values, self.order)
+        };
+        let angle = |f: &dyn Fn(&Point) -> Radians<f64>| -> (f64, f64) {
+            let (value, rate) = channel(&|p| (f(p) - f(&center)).wrapped().0);
+            (f(&center).0 + value, rate)
+        };
+        let latitude = channel(&|p| p.latitude.0);
+        let longitude = angle(&|p| p.longitude);
+        let altitude = channel(&|p| p.altitude);
+        let roll = angle(&|p| p.roll);
+        let pitch = angle(&|p| p.pitch);
+        let yaw = angle(&|p| p.yaw);
+
+        let mut point = center;
+        point.latitude = Radians(latitude.0);
+        point.longitude = Radians(longitude.0).wrapped();
+        point.altitude = altitude.0;
+        point.roll = Radians(roll.0).wrapped();
+        point.pitch = Radians(pitch.0).wrapped();
+        point.yaw = Radians(yaw.0).wrapped();
+        if self.derivatives {
+            let sin_lat = latitude.0.sin();
+            let denominator = 1.0 - geodesy::WGS84_E2 * sin_lat * sin_lat;
+            let meridional = geodesy::WGS84_A * (1.0 - geodesy::WGS84_E2) / denominator.powf(1.5);
+            let normal = geodesy::WGS84_A / denominator.sqrt();
+            let velocity = [
+                longitude.1 * (normal + altitude.0) * latitude.0.cos(),
+                latitude.1 * (meridional + altitude.0),
+                altitude.1,
+            ];
+            let rates = frames::euler_rates_to_body(&point, [roll.1, pitch.1, yaw.1]);
+            let mut derived = false;
+            for (field, value) in [
+                &mut point.x_velocity,
+                &mut point.y_velocity,
+                &mut point.z_velocity,
+            ]
+            .into_iter()
+            .zip(velocity)
+            {
+                if field.is_none() {
+                    *field = Some(value);
+                    derived = true;
+                }
+            }
+            for (field, value) in [
+                &mut point.x_angular_rate,
+                &mut point.y_angular_rate,
+                &mut point.z_angular_rate,
+            ]
+            .into_iter()
+            .zip(rates)
+            {
+                if field.is_none() {
+                    *field = Some(Radians(value));
+                    derived = true;
+                }
+            }
+            point.derived |= derived;
+        }
+        self.window.advance();
+        Ok(Some(point))
+    }
+}
+
+/// Fits a polynomial to values by least squares, returning its value and slope at zero.
+///
+/// The order is reduced if there aren't enough distinct values to support it.
+fn fit(times: &[f64], values: &[f64], order: usize) -> (f64, f64) {
+    let order = order.min(times.len() - 1);
+    if order == 0 {
+        return (values.iter().sum::<f64>() / values.len() as f64, 0.0);
+    }
+    // Scale times to [-1, 1] to keep the normal equations well-conditioned.
+    let scale = times.iter().fold(0.0f64, |max, t| max.max(t.abs()));
+    if scale == 0.0 {
+        return (values.iter().sum::<f64>() / values.len() as f64, 0.0);
+    }
+    let n = order + 1;
+    let mut matrix = vec![vec![0.0; n + 1]; n];
+    for (&time, &value) in times.iter().zip(values) {
+        let t = time / scale;
+        let powers: Vec<_> = (0..2 * n - 1).map(|i| t.powi(i as i32)).collect();
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().take(n).enumerate() {
+                *cell += powers[i + j];
+            }
+            row[n] += powers[i] * value;
+        }
+    }
+    match solve(matrix) {
+        Some(coefficients) => (coefficients[0], coefficients[1] / scale),
+        None => (values.iter().sum::<f64>() / values.len() as f64, 0.0),
+    }
+}
+
+/// Solves an augmented linear system by Gaussian elimination with partial pivoting.
+fn solve(mut matrix: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+    let n = matrix.len();
+    for column in 0..n {
+        let pivot = (column..n)
+            .max_by(|&a, &b| matrix[a][column].abs().total_cmp(&matrix[b][column].abs()))?;
+        if matrix[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(column, pivot);
+        let (above, below) = matrix.split_at_mut(column + 1);
+        let pivot_row = &above[column];
+        for row in below {
+            let factor = row[column] / pivot_row[column];
+            for (cell, pivot) in row.iter_mut().zip(pivot_row).skip(column) {
+                *cell -= factor * pivot;
+            }
+        }
+    }
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (matrix[row][n] - sum) / matrix[row][row];
+    }
+    Some(solution)
+}
+
+fn average<'a, I: ExactSizeIterator<Item = &'a Point>>(center: &Point, points: I) -> Point {
+    let count = points.len() as f64;
+    let mut point = *center;
+    let mut latitude = 0.0;
+    let mut altitude = 0.0;
+    let mut angles = [(0.0, 0.0); 4];
+    for p in points {
+        latitude += p.latitude.0;
+        altitude += p.altitude;
+        for (sum, angle) in angles.iter_mut().zip([p.longitude, p.roll, p.pitch, p.yaw]) {
+            let (sin, cos) = angle.0.sin_cos();
+            sum.0 += sin;
+            sum.1 += cos;
+        }
+    }
+    point.latitude = Radians(latitude / count);
+    point.altitude = altitude / count;
+    let [longitude, roll, pitch, yaw] = angles.map(|(sin, cos)| Radians(sin.atan2(cos)));
+    point.longitude = longitude;
+    point.roll = roll;
+    point.pitch = pitch;
+    point.yaw = yaw;
+    point
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    fn smooth(points: Vec<Point>, half_width: usize) -> Vec<Point> {
+        let mut source = MovingAverage::new(Box::new(MemorySource::new(points)), half_width);
+        let mut points = Vec::new();
+        while let Some(point) = source.source().unwrap() {
+            points.push(point);
+        }
+        points
+    }
+
+    #[test]
+    fn moving_average() {
+        let points: Vec<_> = [0.0, 3.0, 0.0, 3.0, 0.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &altitude)| Point {
+                time: i as f64,
+                altitude,
+                ..Default::default()
+            })
+            .collect();
+        let smoothed = smooth(points, 1);
+        let altitudes: Vec<_> = smoothed.iter().map(|p| p.altitude).collect();
+        assert_eq!(vec![0.0, 1.0, 2.0, 1.0, 0.0], altitudes);
+        let times: Vec<_> = smoothed.iter().map(|p| p.time).collect();
+        assert_eq!(vec![0.0, 1.0, 2.0, 3.0, 4.0], times);
+    }
+
+    #[test]
+    fn yaw_wrap() {
+        let points: Vec<_> = [179.0, -179.0, 179.0]
+            .iter()
+            .map(|&yaw| Point {
+                yaw: Radians::from_degrees(yaw),
+                ..Default::default()
+            })
+            .collect();
+        let smoothed = smooth(points, 1);
+        assert!((smoothed[1].yaw.to_degrees().abs() - 179.666666).abs() < 1e-3);
+    }
+
+    #[test]
+    fn savitzky_golay() {
+        let points: Vec<_> = (0..10)
+            .map(|i| {
+                let time = i as f64 * 0.5;
+                Point {
+                    time,
+                    altitude: 2.0 * time * time + 3.0 * time,
+                    yaw: Radians::from_degrees(175.0 + 2.0 * time).wrapped(),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let mut source = SavitzkyGolay::new(Box::new(MemorySource::new(points.clone())), 2, 2)
+            .with_derivatives(true);
+        for expected in points {
+            let point = source.source().unwrap().unwrap();
+            let time = expected.time;
+            assert!((point.altitude - expected.altitude).abs() < 1e-9);
+            assert!((point.z_velocity.unwrap() - (4.0 * time + 3.0)).abs() < 1e-9);
+            assert!((point.yaw - expected.yaw).wrapped().0.abs() < 1e-9);
+            assert!((point.z_angular_rate.unwrap().to_degrees() - 2.0).abs() < 1e-9);
+        }
+        assert!(source.source().unwrap().is_none());
+    }
+
+    #[test]
+    fn savitzky_golay_velocity() {
+        let points: Vec<_> = (0..5)
+            .map(|i| Point {
+                time: i as f64,
+                latitude: Radians(i as f64 * 1e-6),
+                ..Default::default()
+            })
+            .collect();
+        let mut source =
+            SavitzkyGolay::new(Box::new(MemorySource::new(points)), 2, 1).with_derivatives(true);
+        let point = source.source().unwrap().unwrap();
+        assert!((point.y_velocity.unwrap() - 6.335439).abs() < 1e-6);
+        assert!(point.x_velocity.unwrap().abs() < 1e-9);
+    }
+
+    #[test]
+    fn savitzky_golay_keeps_recorded_fields() {
+        let points: Vec<_> = (0..5)
+            .map(|i| Point {
+                time: i as f64,
+                altitude: i as f64,
+                roll: Radians::from_degrees(90.0),
+                yaw: Radians::from_degrees(i as f64),
+                z_velocity: Some(10.0),
+                ..Default::default()
+            })
+            .collect();
+        let mut source =
+            SavitzkyGolay::new(Box::new(MemorySource::new(points)), 2, 1).with_derivatives(true);
+        let point = source.source().unwrap().unwrap();
+        assert_eq!(Some(10.0), point.z_velocity);
+        assert!(point.x_velocity.unwrap().abs() < 1e-9);
+        // Rolled onto its side, yawing turns about the body's y axis.
+        assert!((point.y_angular_rate.unwrap().to_degrees() - 1.0).abs() < 1e-9);
+        assert!(point.z_angular_rate.unwrap().0.abs() < 1e-9);
+        assert!(point.derived);
+    }
+
+    #[test]
+    fn zero_width() {
+        let points = vec![Point::default(); 3];
+        assert_eq!(points, smooth(points.clone(), 0));
+    }
+}
@@ -0,0 +1,55 @@
+//! Smoothed accuracy files that accompany POSPac sbet files.
+//!
+//! Applanix POSPac writes an `smrmsg` file next to each smoothed sbet, holding the smoothed
+//! standard deviations of the solution. Its records have the same layout as an
+//! [rmsmsg](crate::rmsmsg) file's, so they're read with the same [Reader], which is an
+//! [AccuracySource](crate::AccuracySource).
+
+use crate::sbet;
+use crate::source::{CombinedSource, FileAccuracySource};
+use crate::Error;
+use std::path::Path;
+
+pub use crate::rmsmsg::{Reader, ReaderIterator};
+
+/// Opens an sbet file combined with its smrmsg file.
+///
+/// Each point gets the accuracy interpolated at its time.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pos::smrmsg;
+/// use pos::Source;
+/// let mut source = smrmsg::open("sbet_mission.out", "smrmsg_mission.out").unwrap();
+/// let point = source.source().unwrap().unwrap();
+/// assert!(point.accuracy.is_some());
+/// ```
+pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(sbet: P, smrmsg: Q) -> Result<CombinedSource, Error> {
+    let source = Box::new(sbet::Reader::from_path(sbet)?);
+    let accuracy_source = Reader::open_file_accuracy_source(smrmsg)?;
+    CombinedSource::new(source, accuracy_source)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::Source;
+
+    #[test]
+    fn open() {
+        let path = std::env::temp_dir().join("pos-rs-smrmsg_2-points.out");
+        let mut bytes = Vec::new();
+        for time in [151_630.0f64, 151_640.0] {
+            for value in [time, 0.02, 0.01, 0.05, 0.1, 0.1, 0.2, 0.6, 1.2, 3.0] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        std::fs::write(&path, bytes).unwrap();
+        let mut source = super::open("data/2-points.sbet", &path).unwrap();
+        let point = source.source().unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let accuracy = point.accuracy.unwrap();
+        assert_eq!((0.01, 0.02, 0.05), (accuracy.x, accuracy.y, accuracy.z));
+        assert!((accuracy.yaw.to_degrees() - 0.05).abs() < 1e-12);
+    }
+}
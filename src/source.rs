@@ -1,11 +1,19 @@
 //! Sources of position points.
 
+use crate::clip::ClipByTime;
+#[cfg(feature = "pof")]
 use crate::pof;
 use crate::point::{Accuracy, Point};
+#[cfg(feature = "pof")]
 use crate::poq;
+#[cfg(feature = "sbet")]
+use crate::rmsmsg;
 use crate::Error;
+use std::collections::VecDeque;
 use std::fmt::Debug;
+#[cfg(any(feature = "pof", feature = "sbet"))]
 use std::fs::File;
+#[cfg(any(feature = "pof", feature = "sbet"))]
 use std::io::{BufReader, Read, Seek};
 use std::iter::IntoIterator;
 use std::path::Path;
@@ -14,6 +22,113 @@ use std::path::Path;
 pub trait Source: Debug {
     /// Reads one point from the source.
     fn source(&mut self) -> Result<Option<Point>, Error>;
+
+    /// Returns the number of points left to read, if it's known without reading them.
+    ///
+    /// This is a hint for preallocating, e.g. from an sbet file's size or a pof header's entry
+    /// count, so a truncated or corrupt file can yield fewer points. The default returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// assert_eq!(Some(2), reader.len_hint());
+    /// let _ = reader.source().unwrap();
+    /// assert_eq!(Some(1), reader.len_hint());
+    /// ```
+    fn len_hint(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns an iterator over this source's points that yields read errors instead of
+    /// panicking.
+    ///
+    /// The readers' iterators unwrap every read, so a truncated or corrupt file panics partway
+    /// through. This iterator yields the error instead, and then ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let points = reader.try_iter().collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(2, points.len());
+    /// ```
+    fn try_iter(self) -> TryIter<Self>
+    where
+        Self: Sized,
+    {
+        TryIter {
+            source: self,
+            done: false,
+        }
+    }
+
+    /// Returns a source that only passes through points from `start` through `end`, inclusive.
+    ///
+    /// Reading stops at the first point after `end`, so the rest of a long file isn't read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut clip = reader.clip(151631.005, f64::INFINITY);
+    /// assert_eq!(1, clip.try_iter().count());
+    /// ```
+    fn clip(self, start: f64, end: f64) -> ClipByTime<Self>
+    where
+        Self: Sized,
+    {
+        ClipByTime::new(self, start, end)
+    }
+
+    /// Returns a source that applies a function to each point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut source = reader.map_points(|mut point| {
+    ///     point.time += 18.0;
+    ///     point
+    /// });
+    /// let point = source.source().unwrap().unwrap();
+    /// assert!(point.time > 151649.0);
+    /// ```
+    fn map_points<F>(self, f: F) -> MapSource<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Point) -> Point,
+    {
+        MapSource::new(self, f)
+    }
+
+    /// Returns a source that applies a function to each point, dropping the points for which
+    /// it returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let source = reader.filter_map_points(|point| (point.time > 151631.005).then_some(point));
+    /// assert_eq!(1, source.try_iter().count());
+    /// ```
+    fn filter_map_points<F>(self, f: F) -> FilterMapSource<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Point) -> Option<Point>,
+    {
+        FilterMapSource::new(self, f)
+    }
 }
 
 impl IntoIterator for Box<dyn Source> {
@@ -25,6 +140,8 @@ impl IntoIterator for Box<dyn Source> {
 }
 
 /// An iterator over a boxed point source.
+///
+/// Panics if a point can't be read. Use [Source::try_iter] to handle errors.
 #[derive(Debug)]
 pub struct SourceIterator {
     source: Box<dyn Source>,
@@ -35,17 +152,350 @@ impl Iterator for SourceIterator {
     fn next(&mut self) -> Option<Point> {
         self.source.source().unwrap()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        size_hint(self.source.len_hint())
+    }
+}
+
+/// A fallible iterator over a source, created by [Source::try_iter].
+#[derive(Debug)]
+pub struct TryIter<S> {
+    source: S,
+    done: bool,
+}
+
+impl<S: Source> Iterator for TryIter<S> {
+    type Item = Result<Point, Error>;
+    fn next(&mut self) -> Option<Result<Point, Error>> {
+        if self.done {
+            return None;
+        }
+        let result = self.source.source().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            (0, Some(0))
+        } else {
+            size_hint(self.source.len_hint())
+        }
+    }
+}
+
+impl<S: Source> std::iter::FusedIterator for TryIter<S> {}
+
+impl<S: Source + ?Sized> Source for &mut S {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        (**self).source()
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        (**self).len_hint()
+    }
+}
+
+impl<S: Source + ?Sized> Source for Box<S> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        (**self).source()
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        (**self).len_hint()
+    }
+}
+
+/// A source that can move back to its start, or to a time, without reading every point.
+///
+/// The sbet and pof readers binary-search their records and the pos reader reads forward from
+/// the start of its file, so a consumer such as
+/// [Interpolator::with_seekable](crate::Interpolator::with_seekable) can re-read a window of
+/// points instead of holding on to every point it has read. Points are assumed to be sorted by
+/// time.
+pub trait SeekableSource: Source {
+    /// Moves back to the first point.
+    fn rewind(&mut self) -> Result<(), Error>;
+
+    /// Moves to the first point at or after a time.
+    ///
+    /// Seeking past the last point leaves the source at its end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::source::SeekableSource;
+    /// use pos::Source;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// reader.seek_to_time(151631.005).unwrap();
+    /// let second = reader.source().unwrap().unwrap();
+    /// reader.rewind().unwrap();
+    /// let first = reader.source().unwrap().unwrap();
+    /// assert!(first.time < second.time);
+    /// ```
+    fn seek_to_time(&mut self, time: f64) -> Result<(), Error>;
+}
+
+impl<S: SeekableSource + ?Sized> SeekableSource for &mut S {
+    fn rewind(&mut self) -> Result<(), Error> {
+        (**self).rewind()
+    }
+
+    fn seek_to_time(&mut self, time: f64) -> Result<(), Error> {
+        (**self).seek_to_time(time)
+    }
+}
+
+impl<S: SeekableSource + ?Sized> SeekableSource for Box<S> {
+    fn rewind(&mut self) -> Result<(), Error> {
+        (**self).rewind()
+    }
+
+    fn seek_to_time(&mut self, time: f64) -> Result<(), Error> {
+        (**self).seek_to_time(time)
+    }
+}
+
+/// Returns the index of the first fixed-size record at or after a time, leaving the reader at
+/// an unspecified position.
+///
+/// Records start at `offset` and begin with a little-endian time.
+#[cfg(any(feature = "pof", feature = "sbet"))]
+pub(crate) fn search_time<R: Read + Seek>(
+    read: &mut R,
+    offset: u64,
+    record_size: u64,
+    count: u64,
+    time: f64,
+) -> Result<u64, Error> {
+    use byteorder::{LittleEndian, ReadBytesExt};
+    use std::io::{ErrorKind, SeekFrom};
+
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let _ = read.seek(SeekFrom::Start(offset + middle * record_size))?;
+        let record_time = match read.read_f64::<LittleEndian>() {
+            Ok(record_time) => record_time,
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        };
+        if record_time < time {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    Ok(low)
+}
+
+/// Converts a [Source::len_hint] into an [Iterator::size_hint].
+///
+/// The hint is only an upper bound, since a truncated or corrupt file can yield fewer points.
+pub(crate) fn size_hint(len_hint: Option<usize>) -> (usize, Option<usize>) {
+    (0, len_hint)
+}
+
+/// A source that calls a function with each point before passing it on.
+///
+/// This can attach streaming accumulators, such as [Channels](crate::stats::Channels), to a
+/// source without buffering its points.
+pub struct Inspect<S: Source, F: FnMut(&Point)> {
+    source: S,
+    f: F,
+}
+
+impl<S: Source, F: FnMut(&Point)> Inspect<S, F> {
+    /// Creates a new inspecting source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::source::Inspect;
+    /// use pos::Source;
+    /// let mut count = 0;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut source = Inspect::new(reader, |_| count += 1);
+    /// while let Some(_) = source.source().unwrap() {}
+    /// drop(source);
+    /// assert_eq!(2, count);
+    /// ```
+    pub fn new(source: S, f: F) -> Inspect<S, F> {
+        Inspect { source, f }
+    }
+
+    /// Consumes this source, returning the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: Source, F: FnMut(&Point)> Debug for Inspect<S, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Inspect")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Source, F: FnMut(&Point)> Source for Inspect<S, F> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let point = self.source.source()?;
+        if let Some(point) = &point {
+            (self.f)(point);
+        }
+        Ok(point)
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        self.source.len_hint()
+    }
+}
+
+/// A source that applies a function to each point, created by [Source::map_points].
+///
+/// This can apply a correction, such as a time offset or a datum shift, without writing a
+/// whole [Source] implementation.
+pub struct MapSource<S: Source, F: FnMut(Point) -> Point> {
+    source: S,
+    f: F,
+}
+
+impl<S: Source, F: FnMut(Point) -> Point> MapSource<S, F> {
+    /// Creates a new mapping source.
+    pub fn new(source: S, f: F) -> MapSource<S, F> {
+        MapSource { source, f }
+    }
+
+    /// Consumes this source, returning the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: Source, F: FnMut(Point) -> Point> Debug for MapSource<S, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapSource")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Source, F: FnMut(Point) -> Point> Source for MapSource<S, F> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        Ok(self.source.source()?.map(&mut self.f))
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        self.source.len_hint()
+    }
+}
+
+/// A source that applies a function to each point and drops the points it returns `None` for,
+/// created by [Source::filter_map_points].
+pub struct FilterMapSource<S: Source, F: FnMut(Point) -> Option<Point>> {
+    source: S,
+    f: F,
+}
+
+impl<S: Source, F: FnMut(Point) -> Option<Point>> FilterMapSource<S, F> {
+    /// Creates a new filtering and mapping source.
+    pub fn new(source: S, f: F) -> FilterMapSource<S, F> {
+        FilterMapSource { source, f }
+    }
+
+    /// Consumes this source, returning the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: Source, F: FnMut(Point) -> Option<Point>> Debug for FilterMapSource<S, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilterMapSource")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Source, F: FnMut(Point) -> Option<Point>> Source for FilterMapSource<S, F> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        while let Some(point) = self.source.source()? {
+            if let Some(point) = (self.f)(point) {
+                return Ok(Some(point));
+            }
+        }
+        Ok(None)
+    }
 }
 
 /// A source of accuracy information.
 pub trait AccuracySource: Debug {
     /// Reads an accuracy reading from this accuracy source.
     fn source(&mut self) -> Result<Option<Accuracy>, Error>;
+
+    /// Returns an iterator over this source's accuracies that yields read errors instead of
+    /// panicking, like [Source::try_iter].
+    fn try_iter(self) -> TryAccuracyIter<Self>
+    where
+        Self: Sized,
+    {
+        TryAccuracyIter {
+            source: self,
+            done: false,
+        }
+    }
+}
+
+/// A fallible iterator over an accuracy source, created by [AccuracySource::try_iter].
+#[derive(Debug)]
+pub struct TryAccuracyIter<A> {
+    source: A,
+    done: bool,
+}
+
+impl<A: AccuracySource> Iterator for TryAccuracyIter<A> {
+    type Item = Result<Accuracy, Error>;
+    fn next(&mut self) -> Option<Result<Accuracy, Error>> {
+        if self.done {
+            return None;
+        }
+        let result = self.source.source().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
 }
 
+impl<A: AccuracySource> std::iter::FusedIterator for TryAccuracyIter<A> {}
+
+impl<A: AccuracySource + ?Sized> AccuracySource for &mut A {
+    fn source(&mut self) -> Result<Option<Accuracy>, Error> {
+        (**self).source()
+    }
+}
+
+impl<A: AccuracySource + ?Sized> AccuracySource for Box<A> {
+    fn source(&mut self) -> Result<Option<Accuracy>, Error> {
+        (**self).source()
+    }
+}
+
+#[cfg(feature = "pof")]
 impl<R: Debug + Seek + Read> AccuracySource for poq::Reader<R> {
     fn source(&mut self) -> Result<Option<Accuracy>, Error> {
-        self.read_accuracy().map_err(Error::from)
+        self.read_accuracy()
+            .map_err(|err| Error::from(err).at(self.location()))
+    }
+}
+
+#[cfg(feature = "sbet")]
+impl<R: Debug + Read> AccuracySource for rmsmsg::Reader<R> {
+    fn source(&mut self) -> Result<Option<Accuracy>, Error> {
+        self.read_accuracy()
+            .map_err(|err| Error::from(err).at(self.location()))
     }
 }
 
@@ -55,6 +505,7 @@ pub trait FileSource {
     fn open_file_source<P: AsRef<Path>>(path: P) -> Result<Box<dyn Source>, Error>;
 }
 
+#[cfg(feature = "pof")]
 impl FileSource for pof::Reader<BufReader<File>> {
     fn open_file_source<P: AsRef<Path>>(path: P) -> Result<Box<dyn Source>, Error> {
         Ok(Box::new(pof::Reader::from_path(path)?))
@@ -68,6 +519,7 @@ pub trait FileAccuracySource {
         -> Result<Box<dyn AccuracySource>, Error>;
 }
 
+#[cfg(feature = "pof")]
 impl FileAccuracySource for poq::Reader<BufReader<File>> {
     fn open_file_accuracy_source<P: AsRef<Path>>(
         path: P,
@@ -76,11 +528,24 @@ impl FileAccuracySource for poq::Reader<BufReader<File>> {
     }
 }
 
+#[cfg(feature = "sbet")]
+impl FileAccuracySource for rmsmsg::Reader<BufReader<File>> {
+    fn open_file_accuracy_source<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<Box<dyn AccuracySource>, Error> {
+        Ok(Box::new(rmsmsg::Reader::from_path(path)?))
+    }
+}
+
 /// A source of points that includes accuracy information.
+///
+/// The source and accuracy source can be any types, so statically typed pipelines avoid dynamic
+/// dispatch. The type parameters default to boxes, so `CombinedSource` alone is the boxed,
+/// dynamic version that [CombinedSource::new] creates.
 #[derive(Debug)]
-pub struct CombinedSource {
-    source: Box<dyn Source>,
-    accuracy_source: Box<dyn AccuracySource>,
+pub struct CombinedSource<S = Box<dyn Source>, A = Box<dyn AccuracySource>> {
+    source: S,
+    accuracy_source: A,
     accuracies: (Option<Accuracy>, Option<Accuracy>),
 }
 
@@ -88,8 +553,24 @@ impl CombinedSource {
     /// Creates a new combined source from two boxes.
     pub fn new(
         source: Box<dyn Source>,
-        mut accuracy_source: Box<dyn AccuracySource>,
+        accuracy_source: Box<dyn AccuracySource>,
     ) -> Result<CombinedSource, Error> {
+        CombinedSource::from_sources(source, accuracy_source)
+    }
+}
+
+impl<S: Source, A: AccuracySource> CombinedSource<S, A> {
+    /// Creates a new combined source from a source and an accuracy source of any type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::{rmsmsg, sbet, CombinedSource};
+    /// let source = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+    /// let accuracy_source = rmsmsg::Reader::new(std::io::empty());
+    /// let source = CombinedSource::from_sources(source, accuracy_source).unwrap();
+    /// ```
+    pub fn from_sources(source: S, mut accuracy_source: A) -> Result<CombinedSource<S, A>, Error> {
         let accuracies = (accuracy_source.source()?, accuracy_source.source()?);
         Ok(CombinedSource {
             source,
@@ -99,7 +580,7 @@ impl CombinedSource {
     }
 }
 
-impl Source for CombinedSource {
+impl<S: Source, A: AccuracySource> Source for CombinedSource<S, A> {
     fn source(&mut self) -> Result<Option<Point>, Error> {
         let mut point = match self.source.source()? {
             Some(point) => point,
@@ -132,36 +613,400 @@ impl Source for CombinedSource {
         );
         Ok(Some(point))
     }
+
+    fn len_hint(&self) -> Option<usize> {
+        self.source.len_hint()
+    }
 }
 
-impl IntoIterator for CombinedSource {
+impl<S: Source, A: AccuracySource> IntoIterator for CombinedSource<S, A> {
     type Item = Point;
-    type IntoIter = CombinedSourceIterator;
+    type IntoIter = CombinedSourceIterator<S, A>;
     fn into_iter(self) -> Self::IntoIter {
         CombinedSourceIterator { source: self }
     }
 }
 
 /// Iterator over a combined source.
+///
+/// Panics if a point can't be read. Use [Source::try_iter] to handle errors.
 #[derive(Debug)]
-pub struct CombinedSourceIterator {
-    source: CombinedSource,
+pub struct CombinedSourceIterator<S = Box<dyn Source>, A = Box<dyn AccuracySource>> {
+    source: CombinedSource<S, A>,
 }
 
-impl Iterator for CombinedSourceIterator {
+impl<S: Source, A: AccuracySource> Iterator for CombinedSourceIterator<S, A> {
     type Item = Point;
     fn next(&mut self) -> Option<Point> {
         self.source.source().unwrap()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        size_hint(self.source.len_hint())
+    }
+}
+
+/// A source that reads several sources one after another.
+///
+/// Long missions are often split across files, such as `sbet_mission_1.out` and
+/// `sbet_mission_2.out`. By default points are passed through as they are, but
+/// [ChainSource::check_times] makes the source fail if a source's first point isn't after the
+/// previous source's last point.
+#[derive(Debug)]
+pub struct ChainSource {
+    sources: VecDeque<Box<dyn Source>>,
+    check_times: bool,
+    last_time: Option<f64>,
+    boundary: bool,
+}
+
+impl ChainSource {
+    /// Creates a new chain of sources.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::source::ChainSource;
+    /// use pos::Source;
+    /// let first = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let second = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let source = ChainSource::new(vec![Box::new(first), Box::new(second)]);
+    /// assert_eq!(4, source.try_iter().count());
+    /// ```
+    pub fn new(sources: Vec<Box<dyn Source>>) -> ChainSource {
+        ChainSource {
+            sources: sources.into(),
+            check_times: false,
+            last_time: None,
+            boundary: false,
+        }
+    }
+
+    /// Opens a chain of files, detecting their formats from their extensions.
+    ///
+    /// Every file is opened up front, so missing files are reported before any points are read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::source::ChainSource;
+    /// let source = ChainSource::open(&["data/2-points.sbet"]).unwrap();
+    /// ```
+    pub fn open<P: AsRef<Path>>(paths: &[P]) -> Result<ChainSource, Error> {
+        paths
+            .iter()
+            .map(crate::format::open)
+            .collect::<Result<_, _>>()
+            .map(ChainSource::new)
+    }
+
+    /// Fails with [Error::ChainTime] if a source's first point isn't after the previous source's
+    /// last point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::source::ChainSource;
+    /// use pos::{Error, Source};
+    /// let paths = ["data/2-points.sbet", "data/2-points.sbet"];
+    /// let mut source = ChainSource::open(&paths).unwrap().check_times();
+    /// assert!(source.source().unwrap().is_some());
+    /// assert!(source.source().unwrap().is_some());
+    /// assert!(matches!(source.source(), Err(Error::ChainTime(..))));
+    /// ```
+    pub fn check_times(mut self) -> ChainSource {
+        self.check_times = true;
+        self
+    }
+}
+
+impl Source for ChainSource {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        while let Some(source) = self.sources.front_mut() {
+            let point = match source.source()? {
+                Some(point) => point,
+                None => {
+                    let _ = self.sources.pop_front();
+                    self.boundary = true;
+                    continue;
+                }
+            };
+            if self.check_times && self.boundary {
+                if let Some(last_time) = self.last_time.filter(|&time| point.time <= time) {
+                    return Err(Error::ChainTime(last_time, point.time));
+                }
+            }
+            self.boundary = false;
+            self.last_time = Some(point.time);
+            return Ok(Some(point));
+        }
+        Ok(None)
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        self.sources.iter().map(|source| source.len_hint()).sum()
+    }
+}
+
+/// How a [MergeSource] handles points from different sources at the same time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tie {
+    /// Keeps every point, in the order of their sources.
+    #[default]
+    KeepAll,
+    /// Keeps only the point from the first source, in the order of the sources.
+    First,
+    /// Blends the points into one with [blend](crate::merge::blend), in the order of the sources.
+    Blend,
+}
+
+/// A source that merges several time-sorted sources into one time-sorted stream.
+///
+/// This suits installs that record parallel solutions, such as dual-antenna or dual-IMU systems.
+/// Each source must be sorted by time. Points at the same time are handled by a [Tie] policy.
+#[derive(Debug)]
+pub struct MergeSource {
+    sources: Vec<Box<dyn Source>>,
+    heads: Vec<Option<Point>>,
+    tie: Tie,
+    started: bool,
+}
+
+impl MergeSource {
+    /// Creates a new merging source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::source::{MergeSource, Tie};
+    /// use pos::Source;
+    /// let first = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let second = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let source = MergeSource::new(vec![Box::new(first), Box::new(second)], Tie::First);
+    /// assert_eq!(2, source.try_iter().count());
+    /// ```
+    pub fn new(sources: Vec<Box<dyn Source>>, tie: Tie) -> MergeSource {
+        MergeSource {
+            heads: vec![None; sources.len()],
+            sources,
+            tie,
+            started: false,
+        }
+    }
+
+    fn advance(&mut self, index: usize) -> Result<Option<Point>, Error> {
+        let next = self.sources[index].source()?;
+        Ok(std::mem::replace(&mut self.heads[index], next))
+    }
+}
+
+impl Source for MergeSource {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        if !self.started {
+            for index in 0..self.sources.len() {
+                let _ = self.advance(index)?;
+            }
+            self.started = true;
+        }
+        let mut first: Option<(usize, f64)> = None;
+        for (index, head) in self.heads.iter().enumerate() {
+            if let Some(head) = head {
+                if first.is_none_or(|(_, time)| head.time < time) {
+                    first = Some((index, head.time));
+                }
+            }
+        }
+        let (index, time) = match first {
+            Some(first) => first,
+            None => return Ok(None),
+        };
+        let mut point = self.advance(index)?;
+        if self.tie != Tie::KeepAll {
+            for other in index + 1..self.sources.len() {
+                while self.heads[other].is_some_and(|head| head.time == time) {
+                    let head = self.advance(other)?;
+                    if let (Tie::Blend, Some(point), Some(head)) = (self.tie, point.as_mut(), head)
+                    {
+                        *point = crate::merge::blend(point, &head);
+                    }
+                }
+            }
+        }
+        Ok(point)
+    }
+}
+
+/// A source that reads points from memory.
+///
+/// This feeds synthetic or already-loaded trajectories to anything that takes a source, such
+/// as an [Interpolator](crate::Interpolator), without writing them to a file.
+#[derive(Clone, Debug, Default)]
+pub struct MemorySource {
+    points: Vec<Point>,
+    index: usize,
+}
+
+impl MemorySource {
+    /// Creates a new memory source that yields the points in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::source::MemorySource;
+    /// use pos::{Interpolator, Point};
+    /// let points = vec![
+    ///     Point { time: 1.0, altitude: 10.0, ..Default::default() },
+    ///     Point { time: 2.0, altitude: 20.0, ..Default::default() },
+    /// ];
+    /// let mut interpolator = Interpolator::new(Box::new(MemorySource::new(points))).unwrap();
+    /// assert_eq!(15.0, interpolator.interpolate(1.5).unwrap().altitude);
+    /// ```
+    pub fn new(points: Vec<Point>) -> MemorySource {
+        MemorySource { points, index: 0 }
+    }
+
+    /// Returns the points that haven't been read yet.
+    pub fn as_slice(&self) -> &[Point] {
+        &self.points[self.index..]
+    }
+}
+
+impl Source for MemorySource {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let point = self.points.get(self.index).copied();
+        if point.is_some() {
+            self.index += 1;
+        }
+        Ok(point)
+    }
+
+    fn len_hint(&self) -> Option<usize> {
+        Some(self.points.len() - self.index)
+    }
+}
+
+impl SeekableSource for MemorySource {
+    fn rewind(&mut self) -> Result<(), Error> {
+        self.index = 0;
+        Ok(())
+    }
+
+    fn seek_to_time(&mut self, time: f64) -> Result<(), Error> {
+        self.index = self.points.partition_point(|point| point.time < time);
+        Ok(())
+    }
+}
+
+impl From<Vec<Point>> for MemorySource {
+    fn from(points: Vec<Point>) -> MemorySource {
+        MemorySource::new(points)
+    }
+}
+
+impl From<Vec<Point>> for Box<dyn Source> {
+    fn from(points: Vec<Point>) -> Box<dyn Source> {
+        Box::new(MemorySource::new(points))
+    }
+}
+
+impl FromIterator<Point> for MemorySource {
+    fn from_iter<I: IntoIterator<Item = Point>>(iter: I) -> MemorySource {
+        MemorySource::new(iter.into_iter().collect())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use pof;
-    use poq;
+    fn vec_source(times: &[f64]) -> Box<dyn Source> {
+        times
+            .iter()
+            .map(|&time| Point {
+                time,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[test]
+    fn chain() {
+        let sources = vec![vec_source(&[1.0, 2.0]), vec_source(&[]), vec_source(&[2.5])];
+        let points = ChainSource::new(sources)
+            .check_times()
+            .try_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let times: Vec<_> = points.iter().map(|point| point.time).collect();
+        assert_eq!(vec![1.0, 2.0, 2.5], times);
+
+        let sources = vec![vec_source(&[1.0, 2.0]), vec_source(&[2.0])];
+        let mut source = ChainSource::new(sources).check_times();
+        let results: Vec<_> = (&mut source).try_iter().collect();
+        assert!(matches!(results[2], Err(Error::ChainTime(2.0, 2.0))));
+    }
+
+    #[test]
+    fn merge() {
+        let times = |tie: Tie| {
+            let sources = vec![
+                vec_source(&[1.0, 3.0]),
+                vec_source(&[]),
+                vec_source(&[1.0, 2.0]),
+            ];
+            let points = MergeSource::new(sources, tie)
+                .try_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            points.iter().map(|point| point.time).collect::<Vec<_>>()
+        };
+        assert_eq!(vec![1.0, 1.0, 2.0, 3.0], times(Tie::KeepAll));
+        assert_eq!(vec![1.0, 2.0, 3.0], times(Tie::First));
+        assert_eq!(vec![1.0, 2.0, 3.0], times(Tie::Blend));
+    }
+
+    #[test]
+    fn map_points() {
+        let source = vec_source(&[1.0, 2.0, 3.0]).map_points(|mut point| {
+            point.time *= 10.0;
+            point
+        });
+        let times: Vec<_> = source.try_iter().map(|point| point.unwrap().time).collect();
+        assert_eq!(vec![10.0, 20.0, 30.0], times);
+
+        let source = vec_source(&[1.0, 2.0, 3.0])
+            .filter_map_points(|point| (point.time != 2.0).then_some(point));
+        let times: Vec<_> = source.try_iter().map(|point| point.unwrap().time).collect();
+        assert_eq!(vec![1.0, 3.0], times);
+    }
+
+    #[test]
+    fn memory_source() {
+        let mut source: MemorySource = [1.0, 2.0]
+            .into_iter()
+            .map(|time| Point {
+                time,
+                ..Default::default()
+            })
+            .collect();
+        assert_eq!(2, source.as_slice().len());
+        assert_eq!(1.0, source.source().unwrap().unwrap().time);
+        assert_eq!(1, source.as_slice().len());
+        assert_eq!(2.0, source.source().unwrap().unwrap().time);
+        assert!(source.source().unwrap().is_none());
+
+        source.seek_to_time(1.5).unwrap();
+        assert_eq!(Some(1), source.len_hint());
+        source.rewind().unwrap();
+        assert_eq!(1.0, source.source().unwrap().unwrap().time);
+        source.seek_to_time(3.0).unwrap();
+        assert!(source.source().unwrap().is_none());
+    }
 
+    #[cfg(feature = "pof")]
     #[test]
     fn read_pof() {
         let source = pof::Reader::open_file_source("data/sbet_mission_1.pof").unwrap();
@@ -169,6 +1014,7 @@ mod tests {
         assert_eq!(1114521, points.len());
     }
 
+    #[cfg(feature = "pof")]
     #[test]
     fn read_pof_with_poq() {
         let source = pof::Reader::open_file_source("data/sbet_mission_1.pof").unwrap();
@@ -0,0 +1,372 @@
+//! Spill points to a temporary file.
+//!
+//! Points are written in fixed-size records, so a point's position in the file is its index
+//! times [RECORD_SIZE], and segments can be read back without a separate offset table. Every
+//! entry of [Point::FIELDS] is encoded through [Point::fields] and decoded through
+//! [Point::set_field], so new fields round-trip without changes here.
+
+use crate::point::{Accuracy, Covariance, FixQuality, Point, SatelliteCount};
+use crate::units::Radians;
+use crate::Error;
+use byteorder::{ByteOrder, LittleEndian};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The size of one encoded point, in bytes.
+const RECORD_SIZE: usize = 4 + 8 * VALUES + 9 + 1 + 4 + 1;
+
+/// The number of fields in [Point::FIELDS].
+const FIELDS: usize = Point::FIELDS.len();
+
+/// The number of `f64` values: the fields, the accuracy, and the covariance.
+const VALUES: usize = FIELDS + 10 + 21;
+
+const ACCURACY: u32 = 1 << FIELDS;
+const HDOP: u32 = ACCURACY << 1;
+const VDOP: u32 = ACCURACY << 2;
+const EVENT: u32 = ACCURACY << 3;
+const COVARIANCE: u32 = ACCURACY << 4;
+
+// The field and flag bits have to fit in the mask.
+const _: () = assert!(FIELDS + 5 <= 32);
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Full segments of points in a temporary file, which is deleted on drop.
+#[derive(Debug)]
+pub(crate) struct Spill {
+    file: File,
+    path: PathBuf,
+    segment_size: usize,
+    starts: Vec<f64>,
+    cache: Option<(usize, Vec<Point>)>,
+}
+
+impl Spill {
+    /// Creates a new, empty spill file in the system's temporary directory.
+    pub(crate) fn new(segment_size: usize) -> Result<Spill, Error> {
+        let path = std::env::temp_dir().join(format!(
+            "pos-rs-spill-{}-{}.bin",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(path = %path.display(), segment_size, "created spill file");
+        Ok(Spill {
+            file,
+            path,
+            segment_size,
+            starts: Vec::new(),
+            cache: None,
+        })
+    }
+
+    /// Returns the number of points in each segment.
+    pub(crate) fn segment_size(&self) -> usize {
+        self.segment_size
+    }
+
+    /// Returns the number of points in the file.
+    pub(crate) fn len(&self) -> usize {
+        self.starts.len() * self.segment_size
+    }
+
+    /// Appends a full segment of points to the end of the file.
+    pub(crate) fn push(&mut self, points: &[Point]) -> Result<(), Error> {
+        debug_assert_eq!(self.segment_size, points.len());
+        let mut bytes = vec![0; RECORD_SIZE * points.len()];
+        for (point, record) in points.iter().zip(bytes.chunks_exact_mut(RECORD_SIZE)) {
+            encode(point, record);
+        }
+        let _ = self.file.seek(SeekFrom::End(0))?;
+        self.file.write_all(&bytes)?;
+        self.starts.push(points[0].time);
+        Ok(())
+    }
+
+    /// Returns the point at an index, reading its segment if it isn't cached.
+    pub(crate) fn get(&mut self, index: usize) -> Result<Point, Error> {
+        let segment = index / self.segment_size;
+        if self.cache.as_ref().map(|(s, _)| *s) != Some(segment) {
+            let mut bytes = vec![0; RECORD_SIZE * self.segment_size];
+            let _ = self
+                .file
+                .seek(SeekFrom::Start((segment * bytes.len()) as u64))?;
+            self.file.read_exact(&mut bytes)?;
+            let points = bytes.chunks_exact(RECORD_SIZE).map(decode).collect();
+            #[cfg(feature = "tracing")]
+            tracing::trace!(segment, "read spilled segment");
+            self.cache = Some((segment, points));
+        }
+        Ok(self.cache.as_ref().unwrap().1[index % self.segment_size])
+    }
+
+    /// Returns the index of the first point of the last segment that starts at or before a time,
+    /// or `None` if every segment starts after it.
+    pub(crate) fn seek(&self, time: f64) -> Option<usize> {
+        match self.starts.partition_point(|&start| start <= time) {
+            0 => None,
+            n => Some((n - 1) * self.segment_size),
+        }
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn encode(point: &Point, record: &mut [u8]) {
+    let mut values = [0.0; VALUES];
+    let mut mask = 0;
+    let mut fields = point.fields().into_iter().peekable();
+    for (i, &(name, _)) in Point::FIELDS.iter().enumerate() {
+        if let Some((_, value, _)) = fields.next_if(|field| field.0 == name) {
+            mask |= 1 << i;
+            values[i] = value;
+        }
+    }
+    let mut count = [0u8; 9];
+    if let Some(accuracy) = point.accuracy {
+        mask |= ACCURACY;
+        values[FIELDS..VALUES - 21].copy_from_slice(&[
+            accuracy.time,
+            accuracy.x,
+            accuracy.y,
+            accuracy.z,
+            accuracy.roll.0,
+            accuracy.pitch.0,
+            accuracy.yaw.0,
+            accuracy.pdop,
+            accuracy.hdop.unwrap_or_default(),
+            accuracy.vdop.unwrap_or_default(),
+        ]);
+        if accuracy.hdop.is_some() {
+            mask |= HDOP;
+        }
+        if accuracy.vdop.is_some() {
+            mask |= VDOP;
+        }
+        let (tag, counts) = match accuracy.satellite_count {
+            None => (0, [0; 4]),
+            Some(SatelliteCount::Unspecified(n)) => (1, [n, 0, 0, 0]),
+            Some(SatelliteCount::Specified { gps, glonass }) => (2, [gps, glonass, 0, 0]),
+            Some(SatelliteCount::Extended {
+                gps,
+                glonass,
+                galileo,
+                beidou,
+            }) => (3, [gps, glonass, galileo, beidou]),
+        };
+        count[0] = tag;
+        LittleEndian::write_u16_into(&counts, &mut count[1..]);
+    }
+    if point.event.is_some() {
+        mask |= EVENT;
+    }
+    if let Some(covariance) = point.covariance {
+        mask |= COVARIANCE;
+        values[VALUES - 21..].copy_from_slice(covariance.upper());
+    }
+    LittleEndian::write_u32(&mut record[0..4], mask);
+    let end = 4 + 8 * values.len();
+    LittleEndian::write_f64_into(&values, &mut record[4..end]);
+    record[end..end + 9].copy_from_slice(&count);
+    record[end + 9] = match point.fix_quality {
+        None => 0,
+        Some(FixQuality::DeadReckoning) => 1,
+        Some(FixQuality::Sps) => 2,
+        Some(FixQuality::Dgps) => 3,
+        Some(FixQuality::RtkFloat) => 4,
+        Some(FixQuality::RtkFixed) => 5,
+    };
+    LittleEndian::write_u32(
+        &mut record[end + 10..end + 14],
+        point.event.unwrap_or_default(),
+    );
+    record[end + 14] = u8::from(point.derived);
+}
+
+fn decode(record: &[u8]) -> Point {
+    let mask = LittleEndian::read_u32(&record[0..4]);
+    let mut values = [0.0; VALUES];
+    let end = 4 + 8 * values.len();
+    LittleEndian::read_f64_into(&record[4..end], &mut values);
+    let accuracy = (mask & ACCURACY != 0).then(|| {
+        let a = &values[FIELDS..];
+        let mut counts = [0; 4];
+        LittleEndian::read_u16_into(&record[end + 1..end + 9], &mut counts);
+        Accuracy {
+            time: a[0],
+            x: a[1],
+            y: a[2],
+            z: a[3],
+            roll: Radians(a[4]),
+            pitch: Radians(a[5]),
+            yaw: Radians(a[6]),
+            pdop: a[7],
+            hdop: (mask & HDOP != 0).then_some(a[8]),
+            vdop: (mask & VDOP != 0).then_some(a[9]),
+            satellite_count: match record[end] {
+                1 => Some(SatelliteCount::Unspecified(counts[0])),
+                2 => Some(SatelliteCount::Specified {
+                    gps: counts[0],
+                    glonass: counts[1],
+                }),
+                3 => Some(SatelliteCount::Extended {
+                    gps: counts[0],
+                    glonass: counts[1],
+                    galileo: counts[2],
+                    beidou: counts[3],
+                }),
+                _ => None,
+            },
+        }
+    });
+    let mut point = Point {
+        accuracy,
+        covariance: (mask & COVARIANCE != 0).then(|| {
+            let mut upper = [0.0; 21];
+            upper.copy_from_slice(&values[VALUES - 21..]);
+            Covariance::from_upper(upper)
+        }),
+        fix_quality: match record[end + 9] {
+            1 => Some(FixQuality::DeadReckoning),
+            2 => Some(FixQuality::Sps),
+            3 => Some(FixQuality::Dgps),
+            4 => Some(FixQuality::RtkFloat),
+            5 => Some(FixQuality::RtkFixed),
+            _ => None,
+        },
+        event: (mask & EVENT != 0).then(|| LittleEndian::read_u32(&record[end + 10..end + 14])),
+        derived: record[end + 14] != 0,
+        ..Default::default()
+    };
+    for (i, &(name, _)) in Point::FIELDS.iter().enumerate() {
+        if mask & (1 << i) != 0 {
+            point
+                .set_field(name, values[i])
+                .expect("names are taken from Point::FIELDS");
+        }
+    }
+    point
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let point = Point {
+            time: 1.0,
+            latitude: Radians(0.5),
+            x_velocity: Some(2.0),
+            height_above_ground: Some(-3.0),
+            heave: Some(0.25),
+            accuracy: Some(Accuracy {
+                pdop: 1.5,
+                vdop: Some(2.5),
+                satellite_count: Some(SatelliteCount::Specified { gps: 8, glonass: 5 }),
+                ..Default::default()
+            }),
+            covariance: Some(Covariance::from_upper(std::array::from_fn(|i| i as f64))),
+            fix_quality: Some(FixQuality::RtkFloat),
+            event: Some(42),
+            derived: true,
+            ..Default::default()
+        };
+        let mut record = [0; RECORD_SIZE];
+        encode(&point, &mut record);
+        assert_eq!(point, decode(&record));
+        encode(&Point::default(), &mut record);
+        assert_eq!(Point::default(), decode(&record));
+    }
+
+    #[test]
+    fn roundtrip_every_field() {
+        let qualities = [
+            None,
+            Some(FixQuality::DeadReckoning),
+            Some(FixQuality::Sps),
+            Some(FixQuality::Dgps),
+            Some(FixQuality::RtkFloat),
+            Some(FixQuality::RtkFixed),
+        ];
+        let counts = [
+            None,
+            Some(SatelliteCount::Unspecified(12)),
+            Some(SatelliteCount::Specified { gps: 8, glonass: 5 }),
+            Some(SatelliteCount::Extended {
+                gps: 8,
+                glonass: 5,
+                galileo: 6,
+                beidou: 7,
+            }),
+        ];
+        for (i, (fix_quality, satellite_count)) in qualities
+            .into_iter()
+            .zip(counts.into_iter().cycle())
+            .enumerate()
+        {
+            let mut point = Point {
+                accuracy: Some(Accuracy {
+                    time: 1.5,
+                    x: 0.1,
+                    y: 0.2,
+                    z: 0.3,
+                    roll: Radians(0.01),
+                    pitch: Radians(0.02),
+                    yaw: Radians(0.03),
+                    pdop: 1.1,
+                    hdop: Some(1.2),
+                    vdop: Some(1.3),
+                    satellite_count,
+                }),
+                covariance: Some(Covariance::from_upper(std::array::from_fn(|j| {
+                    j as f64 + 0.5
+                }))),
+                fix_quality,
+                event: Some(7 + i as u32),
+                derived: true,
+                ..Default::default()
+            };
+            for (j, (name, _)) in Point::FIELDS.iter().enumerate() {
+                point.set_field(name, (i + j) as f64 + 0.25).unwrap();
+            }
+            assert_eq!(Point::FIELDS.len(), point.fields().len());
+            let mut record = [0; RECORD_SIZE];
+            encode(&point, &mut record);
+            assert_eq!(point, decode(&record));
+        }
+    }
+
+    #[test]
+    fn segments() {
+        let points: Vec<_> = (0..6)
+            .map(|i| Point {
+                time: i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let mut spill = Spill::new(3).unwrap();
+        let path = spill.path.clone();
+        spill.push(&points[..3]).unwrap();
+        spill.push(&points[3..]).unwrap();
+        assert_eq!(6, spill.len());
+        assert_eq!(4.0, spill.get(4).unwrap().time);
+        assert_eq!(1.0, spill.get(1).unwrap().time);
+        assert_eq!(Some(3), spill.seek(3.5));
+        assert_eq!(None, spill.seek(-1.0));
+        drop(spill);
+        assert!(!path.exists());
+    }
+}
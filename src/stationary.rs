@@ -0,0 +1,198 @@
+//! Detect stationary periods.
+//!
+//! Stationary periods are runs of points where the platform is neither moving nor rotating, such
+//! as IMU static initializations before and after a flight, or stops on the ground. They're
+//! detected from the speed and angular rates between consecutive points.
+
+use crate::geodesy;
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+
+/// The default maximum speed while stationary, in meters per second.
+pub const DEFAULT_MAX_SPEED: f64 = 0.2;
+
+/// The default maximum angular rate while stationary, in degrees per second.
+pub const DEFAULT_MAX_ANGULAR_RATE: f64 = 1.0;
+
+/// The default minimum duration of a stationary period, in seconds.
+pub const DEFAULT_MIN_DURATION: f64 = 10.0;
+
+/// Detects stationary periods.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Detector {
+    max_speed: f64,
+    max_angular_rate: Radians<f64>,
+    min_duration: f64,
+}
+
+/// A stationary period.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Period {
+    /// The time of the first stationary point.
+    pub start: f64,
+    /// The time of the last stationary point.
+    pub end: f64,
+}
+
+impl Default for Detector {
+    fn default() -> Detector {
+        Detector {
+            max_speed: DEFAULT_MAX_SPEED,
+            max_angular_rate: Radians::from_degrees(DEFAULT_MAX_ANGULAR_RATE),
+            min_duration: DEFAULT_MIN_DURATION,
+        }
+    }
+}
+
+impl Detector {
+    /// Creates a new detector with the default thresholds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::stationary::Detector;
+    /// let detector = Detector::new().max_speed(0.1).min_duration(60.0);
+    /// ```
+    pub fn new() -> Detector {
+        Detector::default()
+    }
+
+    /// Sets the maximum speed while stationary, in meters per second.
+    pub fn max_speed(mut self, max_speed: f64) -> Detector {
+        self.max_speed = max_speed;
+        self
+    }
+
+    /// Sets the maximum roll, pitch, and yaw rate while stationary, per second.
+    pub fn max_angular_rate(mut self, max_angular_rate: Radians<f64>) -> Detector {
+        self.max_angular_rate = max_angular_rate;
+        self
+    }
+
+    /// Sets the minimum duration of a stationary period, in seconds.
+    pub fn min_duration(mut self, min_duration: f64) -> Detector {
+        self.min_duration = min_duration;
+        self
+    }
+
+    /// Returns every stationary period in a source, in time order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::stationary::Detector;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let periods = Detector::new().detect(&mut reader).unwrap();
+    /// assert!(periods.is_empty());
+    /// ```
+    pub fn detect(&self, source: &mut dyn Source) -> Result<Vec<Period>, Error> {
+        let mut periods = Vec::new();
+        let mut current: Option<Period> = None;
+        let mut previous: Option<Point> = None;
+        while let Some(point) = source.source()? {
+            if let Some(previous) = previous {
+                if self.is_stationary(&previous, &point) {
+                    current
+                        .get_or_insert(Period {
+                            start: previous.time,
+                            end: previous.time,
+                        })
+                        .end = point.time;
+                } else if let Some(period) = current.take() {
+                    self.push(&mut periods, period);
+                }
+            }
+            previous = Some(point);
+        }
+        if let Some(period) = current {
+            self.push(&mut periods, period);
+        }
+        Ok(periods)
+    }
+
+    fn is_stationary(&self, previous: &Point, point: &Point) -> bool {
+        let interval = point.time - previous.time;
+        if interval <= 0.0 {
+            return false;
+        }
+        let max_angle = self.max_angular_rate.0 * interval;
+        geodesy::distance(previous, point) / interval <= self.max_speed
+            && [
+                point.roll - previous.roll,
+                point.pitch - previous.pitch,
+                point.yaw - previous.yaw,
+            ]
+            .iter()
+            .all(|angle| angle.wrapped().0.abs() <= max_angle)
+    }
+
+    fn push(&self, periods: &mut Vec<Period>, period: Period) {
+        if period.duration() >= self.min_duration {
+            periods.push(period);
+        }
+    }
+}
+
+impl Period {
+    /// Returns the duration of this period, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    /// Sits still for 20 seconds, moves for 10, then sits still for 5.
+    fn points() -> Vec<Point> {
+        (0..=35)
+            .map(|i| {
+                let moving = (i - 20).clamp(0, 10) as f64;
+                Point {
+                    time: i as f64,
+                    altitude: 10.0 * moving,
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn periods() {
+        let periods = Detector::new()
+            .detect(&mut MemorySource::new(points()))
+            .unwrap();
+        assert_eq!(
+            vec![Period {
+                start: 0.0,
+                end: 20.0
+            }],
+            periods
+        );
+        let periods = Detector::new()
+            .min_duration(5.0)
+            .detect(&mut MemorySource::new(points()))
+            .unwrap();
+        assert_eq!(2, periods.len());
+        assert_eq!(30.0, periods[1].start);
+        assert_eq!(5.0, periods[1].duration());
+    }
+
+    #[test]
+    fn rotating() {
+        let points = (0..=20).map(|i| Point {
+            time: i as f64,
+            yaw: Radians::from_degrees(5.0 * i as f64),
+            ..Default::default()
+        });
+        let periods = Detector::new()
+            .detect(&mut MemorySource::new(points.collect::<Vec<_>>()))
+            .unwrap();
+        assert!(periods.is_empty());
+    }
+}
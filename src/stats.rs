@@ -0,0 +1,486 @@
+//! Trajectory statistics.
+//!
+//! [Stats] accumulates distributions over a stream of points in a single pass. With the `serde`
+//! feature, the resulting [Summary] can be serialized.
+//!
+//! [Stats] keeps every value to compute percentiles. For files too large for that, [Channels]
+//! keeps only the running [Moments] of selected fields, and can be fed from any source with an
+//! [Inspect](crate::source::Inspect) adaptor.
+
+use crate::geodesy;
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+
+/// The default gap threshold, as a multiple of the median interval between points.
+pub const DEFAULT_GAP_FACTOR: f64 = 2.0;
+
+/// Summary statistics of a set of values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub struct Distribution {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+/// A period of time with no points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gap {
+    /// The time of the last point before the gap.
+    pub start: f64,
+    /// The time of the first point after the gap.
+    pub end: f64,
+}
+
+/// Accumulates statistics over a stream of points.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    gap_threshold: Option<f64>,
+    count: u64,
+    start: Option<f64>,
+    length: f64,
+    last: Option<Point>,
+    intervals: Vec<(f64, f64)>,
+    speed: Vec<f64>,
+    altitude: Vec<f64>,
+    roll_rate: Vec<f64>,
+    pitch_rate: Vec<f64>,
+    yaw_rate: Vec<f64>,
+    horizontal_accuracy: Vec<f64>,
+    vertical_accuracy: Vec<f64>,
+}
+
+/// The running mean, variance, minimum, and maximum of a series of values.
+///
+/// Values are accumulated with Welford's algorithm, so no values are kept.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Moments {
+    /// The number of values.
+    pub count: u64,
+    /// The mean of the values.
+    pub mean: f64,
+    /// The smallest value.
+    pub min: f64,
+    /// The largest value.
+    pub max: f64,
+    m2: f64,
+}
+
+/// Accumulates the [Moments] of selected point fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Channels {
+    channels: Vec<(&'static str, Moments)>,
+}
+
+/// The statistics of a trajectory.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Summary {
+    /// The number of points.
+    pub count: u64,
+    /// The time of the first point.
+    pub start: f64,
+    /// The time of the last point.
+    pub end: f64,
+    /// The total path length, in meters.
+    pub length: f64,
+    /// The speed between consecutive points, in meters per second.
+    pub speed: Option<Distribution>,
+    /// The altitude, in meters.
+    pub altitude: Option<Distribution>,
+    /// The interval between consecutive points, in seconds.
+    pub interval: Option<Distribution>,
+    /// The absolute roll rate between consecutive points, in radians per second.
+    pub roll_rate: Option<Distribution>,
+    /// The absolute pitch rate between consecutive points, in radians per second.
+    pub pitch_rate: Option<Distribution>,
+    /// The absolute yaw rate between consecutive points, in radians per second.
+    pub yaw_rate: Option<Distribution>,
+    /// The periods where the interval between points exceeds the gap threshold.
+    pub gaps: Vec<Gap>,
+    /// The horizontal accuracy, in meters, if any points have accuracy information.
+    pub horizontal_accuracy: Option<Distribution>,
+    /// The vertical accuracy, in meters, if any points have accuracy information.
+    pub vertical_accuracy: Option<Distribution>,
+}
+
+impl Distribution {
+    /// Computes the distribution of some values, or `None` if there are no values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::stats::Distribution;
+    /// let distribution = Distribution::from_values(vec![3.0, 1.0, 2.0]).unwrap();
+    /// assert_eq!(2.0, distribution.mean);
+    /// assert_eq!(2.0, distribution.p50);
+    /// ```
+    pub fn from_values(mut values: Vec<f64>) -> Option<Distribution> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        Some(Distribution {
+            min: values[0],
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            p50: percentile(&values, 50.0),
+            p95: percentile(&values, 95.0),
+            p99: percentile(&values, 99.0),
+        })
+    }
+}
+
+impl Moments {
+    /// Creates new, empty moments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::stats::Moments;
+    /// let mut moments = Moments::new();
+    /// for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+    ///     moments.add(value);
+    /// }
+    /// assert_eq!(5.0, moments.mean);
+    /// assert_eq!(2.0, moments.std_dev());
+    /// ```
+    pub fn new() -> Moments {
+        Moments {
+            count: 0,
+            mean: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            m2: 0.0,
+        }
+    }
+
+    /// Adds a value.
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Returns the population variance, or zero if there are no values.
+    pub fn variance(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+
+    /// Returns the population standard deviation.
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+impl Default for Moments {
+    fn default() -> Moments {
+        Moments::new()
+    }
+}
+
+impl Channels {
+    /// Creates an accumulator for the named fields.
+    ///
+    /// Names are those of [Point::FIELDS]. Returns [Error::UnknownField] for any other name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::source::Inspect;
+    /// use pos::stats::Channels;
+    /// use pos::Source;
+    /// let mut channels = Channels::new(&["altitude", "x_velocity"]).unwrap();
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut source = Inspect::new(&mut reader, |point| channels.add(point));
+    /// while let Some(_) = source.source().unwrap() {}
+    /// assert_eq!(2, channels.get("altitude").unwrap().count);
+    /// ```
+    pub fn new(names: &[&str]) -> Result<Channels, Error> {
+        names
+            .iter()
+            .map(|name| {
+                Point::FIELDS
+                    .iter()
+                    .find(|(field, _)| field == name)
+                    .map(|&(field, _)| (field, Moments::new()))
+                    .ok_or_else(|| Error::UnknownField(name.to_string()))
+            })
+            .collect::<Result<_, _>>()
+            .map(|channels| Channels { channels })
+    }
+
+    /// Adds a point's values.
+    ///
+    /// Optional fields that are `None` are skipped, so each channel can have its own count.
+    pub fn add(&mut self, point: &Point) {
+        for (name, value, _) in point.fields() {
+            if let Some((_, moments)) = self.channels.iter_mut().find(|(n, _)| *n == name) {
+                moments.add(value);
+            }
+        }
+    }
+
+    /// Returns the moments of a field, or `None` if it wasn't selected.
+    pub fn get(&self, name: &str) -> Option<&Moments> {
+        self.channels
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, moments)| moments)
+    }
+
+    /// Returns the name and moments of every selected field, in the order they were selected.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &Moments)> {
+        self.channels.iter().map(|(name, moments)| (*name, moments))
+    }
+}
+
+impl Gap {
+    /// Returns the duration of this gap.
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+impl Stats {
+    /// Creates a new, empty accumulator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::stats::Stats;
+    /// let stats = Stats::new();
+    /// ```
+    pub fn new() -> Stats {
+        Stats::default()
+    }
+
+    /// Sets the interval, in seconds, above which a gap is reported.
+    ///
+    /// Defaults to [DEFAULT_GAP_FACTOR] times the median interval.
+    pub fn with_gap_threshold(mut self, gap_threshold: f64) -> Stats {
+        self.gap_threshold = Some(gap_threshold);
+        self
+    }
+
+    /// Adds a point.
+    pub fn add(&mut self, point: &Point) {
+        self.count += 1;
+        let _ = self.start.get_or_insert(point.time);
+        self.altitude.push(point.altitude);
+        if let Some(accuracy) = point.accuracy {
+            self.horizontal_accuracy
+                .push((accuracy.x * accuracy.x + accuracy.y * accuracy.y).sqrt());
+            self.vertical_accuracy.push(accuracy.z);
+        }
+        if let Some(last) = self.last {
+            let distance = geodesy::distance(&last, point);
+            let interval = point.time - last.time;
+            self.length += distance;
+            self.intervals.push((last.time, interval));
+            if interval > 0.0 {
+                self.speed.push(distance / interval);
+                self.roll_rate
+                    .push((point.roll - last.roll).wrapped().0.abs() / interval);
+                self.pitch_rate
+                    .push((point.pitch - last.pitch).wrapped().0.abs() / interval);
+                self.yaw_rate
+                    .push((point.yaw - last.yaw).wrapped().0.abs() / interval);
+            }
+        }
+        self.last = Some(*point);
+    }
+
+    /// Adds every point from a source.
+    pub fn add_source(&mut self, source: &mut dyn Source) -> Result<(), Error> {
+        while let Some(point) = source.source()? {
+            self.add(&point);
+        }
+        Ok(())
+    }
+
+    /// Returns the summary of the points added so far, or `None` if there aren't any.
+    pub fn summary(&self) -> Option<Summary> {
+        let start = self.start?;
+        let end = self.last.map(|p| p.time)?;
+        let interval = Distribution::from_values(self.intervals.iter().map(|&(_, i)| i).collect());
+        let gap_threshold = self
+            .gap_threshold
+            .or_else(|| interval.map(|interval| DEFAULT_GAP_FACTOR * interval.p50));
+        let gaps = match gap_threshold {
+            Some(threshold) => self
+                .intervals
+                .iter()
+                .filter(|&&(_, interval)| interval > threshold)
+                .map(|&(start, interval)| Gap {
+                    start,
+                    end: start + interval,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        Some(Summary {
+            count: self.count,
+            start,
+            end,
+            length: self.length,
+            speed: Distribution::from_values(self.speed.clone()),
+            altitude: Distribution::from_values(self.altitude.clone()),
+            interval,
+            roll_rate: Distribution::from_values(self.roll_rate.clone()),
+            pitch_rate: Distribution::from_values(self.pitch_rate.clone()),
+            yaw_rate: Distribution::from_values(self.yaw_rate.clone()),
+            gaps,
+            horizontal_accuracy: Distribution::from_values(self.horizontal_accuracy.clone()),
+            vertical_accuracy: Distribution::from_values(self.vertical_accuracy.clone()),
+        })
+    }
+}
+
+impl Summary {
+    /// Computes the summary of every point in a source, or `None` if the source is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::stats::Summary;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let summary = Summary::from_source(&mut reader).unwrap().unwrap();
+    /// assert_eq!(2, summary.count);
+    /// ```
+    pub fn from_source(source: &mut dyn Source) -> Result<Option<Summary>, Error> {
+        let mut stats = Stats::new();
+        stats.add_source(source)?;
+        Ok(stats.summary())
+    }
+
+    /// Returns the duration between the first and last points.
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Returns the nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], percentile: f64) -> f64 {
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Radians;
+
+    fn point(time: f64, latitude: f64) -> Point {
+        Point {
+            time,
+            latitude: Radians::from_degrees(latitude),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn gaps() {
+        let mut stats = Stats::new();
+        for point in [
+            point(0.0, 0.0),
+            point(1.0, 0.0),
+            point(2.0, 0.0),
+            point(5.0, 0.0),
+            point(6.0, 0.0),
+        ] {
+            stats.add(&point);
+        }
+        let summary = stats.summary().unwrap();
+        assert_eq!(5, summary.count);
+        assert_eq!(6.0, summary.duration());
+        assert_eq!(
+            vec![Gap {
+                start: 2.0,
+                end: 5.0
+            }],
+            summary.gaps
+        );
+        assert!(summary.horizontal_accuracy.is_none());
+    }
+
+    #[test]
+    fn speed() {
+        let mut stats = Stats::new();
+        stats.add(&point(0.0, 0.0));
+        stats.add(&point(10.0, 0.001));
+        let summary = stats.summary().unwrap();
+        let speed = summary.speed.unwrap();
+        assert!((summary.length - 110.574).abs() < 1e-2);
+        assert!((speed.mean - 11.0574).abs() < 1e-3);
+    }
+
+    #[test]
+    fn attitude_rate() {
+        let mut stats = Stats::new();
+        stats.add(&Point {
+            yaw: Radians::from_degrees(359.0),
+            ..Default::default()
+        });
+        stats.add(&Point {
+            time: 2.0,
+            yaw: Radians::from_degrees(1.0),
+            ..Default::default()
+        });
+        let summary = stats.summary().unwrap();
+        assert_eq!(2.0, summary.interval.unwrap().max);
+        assert!((Radians(summary.yaw_rate.unwrap().max).to_degrees() - 1.0).abs() < 1e-9);
+        assert_eq!(0.0, summary.roll_rate.unwrap().max);
+    }
+
+    #[test]
+    fn percentiles() {
+        let distribution = Distribution::from_values((1..=100).map(f64::from).collect()).unwrap();
+        assert_eq!(1.0, distribution.min);
+        assert_eq!(100.0, distribution.max);
+        assert_eq!(50.0, distribution.p50);
+        assert_eq!(95.0, distribution.p95);
+        assert_eq!(99.0, distribution.p99);
+        assert!(Distribution::from_values(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn channels() {
+        let mut channels = Channels::new(&["altitude", "x_velocity"]).unwrap();
+        for (altitude, x_velocity) in [(1.0, Some(2.0)), (3.0, None), (5.0, Some(4.0))] {
+            channels.add(&Point {
+                altitude,
+                x_velocity,
+                ..Default::default()
+            });
+        }
+        let altitude = channels.get("altitude").unwrap();
+        assert_eq!(3, altitude.count);
+        assert_eq!(3.0, altitude.mean);
+        assert_eq!(1.0, altitude.min);
+        assert_eq!(5.0, altitude.max);
+        assert!((altitude.variance() - 8.0 / 3.0).abs() < 1e-12);
+        assert_eq!(2, channels.get("x_velocity").unwrap().count);
+        assert!(channels.get("time").is_none());
+        assert!(matches!(
+            Channels::new(&["swell"]),
+            Err(Error::UnknownField(_))
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+//! Height above ground, from a user-provided terrain model.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::Error;
+use std::fmt;
+
+/// A source that annotates each point with its height above ground.
+///
+/// The ground elevation is provided by a sampler closure, which takes a latitude and longitude
+/// and returns the ground elevation at that location, e.g. by looking it up in a DEM. The
+/// elevation should be in the same vertical reference as the point altitudes. If the sampler
+/// returns `None`, the point's `height_above_ground` is left empty.
+pub struct TerrainSource<F>
+where
+    F: FnMut(Radians<f64>, Radians<f64>) -> Option<f64>,
+{
+    source: Box<dyn Source>,
+    sampler: F,
+}
+
+impl<F> TerrainSource<F>
+where
+    F: FnMut(Radians<f64>, Radians<f64>) -> Option<f64>,
+{
+    /// Creates a new terrain source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::terrain::TerrainSource;
+    /// use pos::sbet;
+    /// let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+    /// let source = TerrainSource::new(Box::new(reader), |_latitude, _longitude| Some(1600.0));
+    /// ```
+    pub fn new(source: Box<dyn Source>, sampler: F) -> TerrainSource<F> {
+        TerrainSource { source, sampler }
+    }
+}
+
+impl<F> Source for TerrainSource<F>
+where
+    F: FnMut(Radians<f64>, Radians<f64>) -> Option<f64>,
+{
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        match self.source.source()? {
+            Some(mut point) => {
+                point.height_above_ground =
+                    (self.sampler)(point.latitude, point.longitude).map(|e| point.altitude - e);
+                Ok(Some(point))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<F> fmt::Debug for TerrainSource<F>
+where
+    F: FnMut(Radians<f64>, Radians<f64>) -> Option<f64>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TerrainSource")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "sbet"))]
+mod tests {
+    use super::*;
+    use crate::sbet;
+
+    #[test]
+    fn height_above_ground() {
+        let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+        let mut source = TerrainSource::new(Box::new(reader), |_, _| Some(100.0));
+        let point = source.source().unwrap().unwrap();
+        assert_eq!(point.altitude - 100.0, point.height_above_ground.unwrap());
+
+        let reader = sbet::Reader::from_path("data/2-points.sbet").unwrap();
+        let mut source = TerrainSource::new(Box::new(reader), |_, _| None);
+        assert_eq!(None, source.source().unwrap().unwrap().height_above_ground);
+    }
+}
@@ -0,0 +1,285 @@
+//! Synthesize in-memory files for tests.
+//!
+//! These functions write valid sbet, pof, and poq byte streams from points and accuracies, so
+//! tests don't need binary fixtures on disk. Read them back with each format's `Reader::new`,
+//! wrapping the bytes in a [Cursor](std::io::Cursor) for the formats that need to seek.
+//!
+//! This module is only available with the `testutil` feature.
+
+#[cfg(any(feature = "pof", feature = "sbet"))]
+use crate::point::Point;
+#[cfg(feature = "pof")]
+use crate::point::{Accuracy, SatelliteCount};
+#[cfg(any(feature = "pof", feature = "sbet"))]
+use byteorder::{LittleEndian, WriteBytesExt};
+
+/// Writes points as an sbet byte stream.
+///
+/// Missing velocities, accelerations, angular rates, and wander angles are written as zero.
+///
+/// # Examples
+///
+/// ```
+/// use pos::sbet::Reader;
+/// use pos::{testutil, Point};
+/// let bytes = testutil::sbet(&[Point::default(), Point::default()]);
+/// let points: Vec<_> = Reader::new(bytes.as_slice()).into_iter().collect();
+/// assert_eq!(2, points.len());
+/// ```
+#[cfg(feature = "sbet")]
+pub fn sbet(points: &[Point]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(points.len() * crate::sbet::RECORD_SIZE as usize);
+    for point in points {
+        for value in [
+            point.time,
+            point.latitude.0,
+            point.longitude.0,
+            point.altitude,
+            point.x_velocity.unwrap_or_default(),
+            point.y_velocity.unwrap_or_default(),
+            point.z_velocity.unwrap_or_default(),
+            point.roll.0,
+            point.pitch.0,
+            point.yaw.0,
+            point.wander_angle.unwrap_or_default().0,
+            point.x_acceleration.unwrap_or_default(),
+            point.y_acceleration.unwrap_or_default(),
+            point.z_acceleration.unwrap_or_default(),
+            point.x_angular_rate.unwrap_or_default().0,
+            point.y_angular_rate.unwrap_or_default().0,
+            point.z_angular_rate.unwrap_or_default().0,
+        ] {
+            bytes.write_f64::<LittleEndian>(value).unwrap();
+        }
+    }
+    bytes
+}
+
+/// Writes points as a version 1.1 pof byte stream.
+///
+/// The header's bounds and time intervals are computed from the points, and missing distances
+/// are written as zero.
+///
+/// # Examples
+///
+/// ```
+/// use pos::pof::Reader;
+/// use pos::{testutil, Point};
+/// use std::io::Cursor;
+/// let bytes = testutil::pof(&[Point::default(), Point::default()]);
+/// let reader = Reader::new(Cursor::new(bytes)).unwrap();
+/// assert_eq!(2, reader.entries);
+/// ```
+#[cfg(feature = "pof")]
+pub fn pof(points: &[Point]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&[0; 27]);
+    write_u16s(&mut bytes, &[1, 1]);
+    bytes
+        .write_u32::<LittleEndian>(crate::pof::HEADER_SIZE)
+        .unwrap();
+    write_u16s(&mut bytes, &[2000, 1, 1]);
+    bytes
+        .write_i64::<LittleEndian>(points.len() as i64)
+        .unwrap();
+    let range = |f: fn(&Point) -> f64| {
+        points
+            .iter()
+            .map(f)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                (min.min(value), max.max(value))
+            })
+    };
+    let (minlon, maxlon) = range(|p| p.longitude.to_degrees());
+    let (minlat, maxlat) = range(|p| p.latitude.to_degrees());
+    let (minalt, maxalt) = range(|p| p.altitude);
+    let (avgint, maxint, devint) = intervals(points.iter().map(|p| p.time));
+    write_f64s(
+        &mut bytes,
+        &[
+            minlon, maxlon, minlat, maxlat, minalt, maxalt, avgint, maxint, devint,
+        ],
+    );
+    bytes.extend_from_slice(&[0; 2 + 16 + 16 + 32 + 32 + 32 + 32 + 32]);
+    debug_assert_eq!(crate::pof::HEADER_SIZE as usize, bytes.len());
+    for point in points {
+        write_f64s(
+            &mut bytes,
+            &[
+                point.time,
+                point.longitude.to_degrees(),
+                point.latitude.to_degrees(),
+                point.altitude,
+                point.roll.to_degrees(),
+                point.pitch.to_degrees(),
+                point.yaw.to_degrees(),
+                point.distance.unwrap_or_default(),
+            ],
+        );
+    }
+    bytes
+}
+
+/// Writes accuracies as a poq byte stream.
+///
+/// The stream is version 1.2 if any accuracy has [extended](SatelliteCount::Extended) satellite
+/// counts, and version 1.1 otherwise. Unspecified satellite counts are written as GPS
+/// satellites.
+///
+/// # Examples
+///
+/// ```
+/// use pos::point::Accuracy;
+/// use pos::poq::Reader;
+/// use pos::testutil;
+/// use std::io::Cursor;
+/// let bytes = testutil::poq(&[Accuracy::default()]);
+/// let accuracies: Vec<_> = Reader::new(Cursor::new(bytes)).unwrap().into_iter().collect();
+/// assert_eq!(1, accuracies.len());
+/// ```
+#[cfg(feature = "pof")]
+pub fn poq(accuracies: &[Accuracy]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let extended = accuracies
+        .iter()
+        .any(|a| matches!(a.satellite_count, Some(SatelliteCount::Extended { .. })));
+    bytes.extend_from_slice(&[0; 35]);
+    write_u16s(&mut bytes, &[1, if extended { 2 } else { 1 }]);
+    let (avgint, maxint, devint) = intervals(accuracies.iter().map(|a| a.time));
+    write_f64s(&mut bytes, &[avgint, maxint, devint]);
+    for accuracy in accuracies {
+        write_f64s(
+            &mut bytes,
+            &[
+                accuracy.time,
+                accuracy.y,
+                accuracy.x,
+                accuracy.z,
+                accuracy.roll.to_degrees(),
+                accuracy.pitch.to_degrees(),
+                accuracy.yaw.to_degrees(),
+                accuracy.pdop,
+            ],
+        );
+        let counts = match accuracy.satellite_count.unwrap_or_default() {
+            SatelliteCount::Unspecified(n) => [n, 0, 0, 0],
+            SatelliteCount::Specified { gps, glonass } => [gps, glonass, 0, 0],
+            SatelliteCount::Extended {
+                gps,
+                glonass,
+                galileo,
+                beidou,
+            } => [gps, glonass, galileo, beidou],
+        };
+        write_u16s(&mut bytes, if extended { &counts } else { &counts[..2] });
+    }
+    bytes
+}
+
+#[cfg(feature = "pof")]
+fn write_u16s(bytes: &mut Vec<u8>, values: &[u16]) {
+    for &value in values {
+        bytes.write_u16::<LittleEndian>(value).unwrap();
+    }
+}
+
+#[cfg(feature = "pof")]
+fn write_f64s(bytes: &mut Vec<u8>, values: &[f64]) {
+    for &value in values {
+        bytes.write_f64::<LittleEndian>(value).unwrap();
+    }
+}
+
+/// Returns the average, maximum, and standard deviation of the intervals between times.
+#[cfg(feature = "pof")]
+fn intervals(times: impl Iterator<Item = f64>) -> (f64, f64, f64) {
+    let times: Vec<_> = times.collect();
+    let intervals: Vec<_> = times.windows(2).map(|w| w[1] - w[0]).collect();
+    if intervals.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+    let n = intervals.len() as f64;
+    let average = intervals.iter().sum::<f64>() / n;
+    let maximum = intervals.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let variance = intervals.iter().map(|i| (i - average).powi(2)).sum::<f64>() / n;
+    (average, maximum, variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "pof", feature = "sbet"))]
+    use super::*;
+    #[cfg(any(feature = "pof", feature = "sbet"))]
+    use crate::units::Radians;
+
+    #[cfg(any(feature = "pof", feature = "sbet"))]
+    fn points() -> Vec<Point> {
+        (0..3)
+            .map(|i| Point {
+                time: 100.0 + i as f64,
+                latitude: Radians::from_degrees(45.0 + 0.001 * i as f64),
+                longitude: Radians::from_degrees(-105.0),
+                altitude: 1000.0 + i as f64,
+                yaw: Radians::from_degrees(90.0),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    #[cfg(feature = "sbet")]
+    fn sbet_roundtrip() {
+        let points = points();
+        let read: Vec<_> = crate::sbet::Reader::new(sbet(&points).as_slice())
+            .into_iter()
+            .collect();
+        assert_eq!(points.len(), read.len());
+        for (expected, actual) in points.iter().zip(&read) {
+            assert_eq!(expected.time, actual.time);
+            assert_eq!(expected.latitude, actual.latitude);
+            assert_eq!(expected.yaw, actual.yaw);
+            assert_eq!(Some(0.0), actual.x_velocity);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "pof")]
+    fn pof_roundtrip() {
+        use std::io::Cursor;
+
+        let points = points();
+        let reader = crate::pof::Reader::new(Cursor::new(pof(&points))).unwrap();
+        assert_eq!(3, reader.entries);
+        assert_eq!(1.0, reader.avgint);
+        assert_eq!(1002.0, reader.maxalt);
+        let read: Vec<_> = reader.into_iter().collect();
+        assert_eq!(points.len(), read.len());
+        for (expected, actual) in points.iter().zip(&read) {
+            assert_eq!(expected.time, actual.time);
+            assert!((expected.latitude.0 - actual.latitude.0).abs() < 1e-12);
+            assert!((expected.yaw.0 - actual.yaw.0).abs() < 1e-12);
+            assert_eq!(expected.altitude, actual.altitude);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "pof")]
+    fn poq_roundtrip() {
+        use std::io::Cursor;
+
+        let accuracy = Accuracy {
+            time: 100.0,
+            x: 0.1,
+            y: 0.2,
+            z: 0.3,
+            pdop: 1.5,
+            satellite_count: Some(SatelliteCount::Specified { gps: 8, glonass: 5 }),
+            ..Default::default()
+        };
+        let read: Vec<_> = crate::poq::Reader::new(Cursor::new(poq(&[accuracy])))
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(vec![accuracy], read);
+    }
+}
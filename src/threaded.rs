@@ -0,0 +1,161 @@
+//! Read a source on a background thread.
+//!
+//! Reading a large file and processing its points in the same thread means that processing
+//! stalls while waiting on the disk. [Threaded] moves a source to a background thread, which
+//! reads batches of points into a bounded channel while the consumer works through the batches
+//! that are already there.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+
+/// The number of points sent across the channel at once.
+const BATCH_SIZE: usize = 1024;
+
+/// The default number of batches that can wait in the channel.
+pub const DEFAULT_CAPACITY: usize = 16;
+
+/// A source that reads another source on a background thread.
+///
+/// The background thread stops after the wrapped source is exhausted or returns an error, or
+/// once this source is dropped. If the background thread panics, the panic is resumed in the
+/// thread that calls [Source::source].
+#[derive(Debug)]
+pub struct Threaded {
+    receiver: Receiver<Result<Vec<Point>, Error>>,
+    handle: Option<JoinHandle<()>>,
+    batch: std::vec::IntoIter<Point>,
+}
+
+impl Threaded {
+    /// Starts reading a source on a background thread, with the default capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::threaded::Threaded;
+    /// use pos::Source;
+    /// let reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let mut threaded = Threaded::new(reader);
+    /// let point = threaded.source().unwrap().unwrap();
+    /// ```
+    pub fn new<S: Source + Send + 'static>(source: S) -> Threaded {
+        Threaded::with_capacity(source, DEFAULT_CAPACITY)
+    }
+
+    /// Starts reading a source on a background thread.
+    ///
+    /// At most `capacity` batches of points are read ahead of the consumer.
+    pub fn with_capacity<S: Source + Send + 'static>(mut source: S, capacity: usize) -> Threaded {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let handle = thread::spawn(move || loop {
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            let result = loop {
+                match source.source() {
+                    Ok(Some(point)) => {
+                        batch.push(point);
+                        if batch.len() == BATCH_SIZE {
+                            break Ok(true);
+                        }
+                    }
+                    Ok(None) => break Ok(false),
+                    Err(err) => break Err(err),
+                }
+            };
+            #[cfg(feature = "tracing")]
+            tracing::trace!(count = batch.len(), "read batch");
+            let more = match result {
+                Ok(more) => more,
+                Err(err) => {
+                    if !batch.is_empty() && sender.send(Ok(batch)).is_err() {
+                        return;
+                    }
+                    let _ = sender.send(Err(err));
+                    return;
+                }
+            };
+            if !batch.is_empty() && sender.send(Ok(batch)).is_err() {
+                return;
+            }
+            if !more {
+                return;
+            }
+        });
+        Threaded {
+            receiver,
+            handle: Some(handle),
+            batch: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl Source for Threaded {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        loop {
+            if let Some(point) = self.batch.next() {
+                return Ok(Some(point));
+            }
+            match self.receiver.recv() {
+                Ok(batch) => self.batch = batch?.into_iter(),
+                Err(_) => {
+                    if let Some(handle) = self.handle.take() {
+                        if let Err(payload) = handle.join() {
+                            std::panic::resume_unwind(payload);
+                        }
+                    }
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    #[derive(Debug)]
+    struct Failing(usize);
+
+    impl Source for Failing {
+        fn source(&mut self) -> Result<Option<Point>, Error> {
+            if self.0 == 0 {
+                Err(Error::OnePoint)
+            } else {
+                self.0 -= 1;
+                Ok(Some(Point::default()))
+            }
+        }
+    }
+
+    #[test]
+    fn order() {
+        let points: Vec<_> = (0..2500)
+            .map(|i| Point {
+                time: i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let mut threaded = Threaded::with_capacity(MemorySource::new(points.clone()), 1);
+        let mut read = Vec::new();
+        while let Some(point) = threaded.source().unwrap() {
+            read.push(point);
+        }
+        assert_eq!(points, read);
+        assert!(threaded.source().unwrap().is_none());
+    }
+
+    #[test]
+    fn error() {
+        let mut threaded = Threaded::new(Failing(3));
+        for _ in 0..3 {
+            assert!(threaded.source().unwrap().is_some());
+        }
+        assert!(matches!(threaded.source(), Err(Error::OnePoint)));
+        assert!(threaded.source().unwrap().is_none());
+    }
+}
@@ -0,0 +1,471 @@
+//! Read MAVLink telemetry logs.
+//!
+//! A telemetry log, or tlog, is what ground control stations such as Mission Planner and
+//! QGroundControl record: each MAVLink packet, version 1 or 2, prefixed by the big-endian
+//! microseconds since the Unix epoch at which the packet was received. Packets with a bad
+//! checksum are skipped, and the reader resynchronizes on the next packet.
+//!
+//! GLOBAL_POSITION_INT messages become points, and the attitude from ATTITUDE messages is
+//! interpolated to each position's time. Both messages are stamped with the autopilot's time
+//! since boot, so point times are seconds since boot; [Reader::boot_time] gives the Unix time of
+//! the boot, as estimated from the log's timestamps.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::{Error, Location};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// The id of ATTITUDE messages.
+const ATTITUDE: u32 = 30;
+
+/// The id of GLOBAL_POSITION_INT messages.
+const GLOBAL_POSITION_INT: u32 = 33;
+
+/// The payload length of both messages.
+const PAYLOAD_LENGTH: usize = 28;
+
+/// A MAVLink telemetry log reader.
+#[derive(Debug)]
+pub struct Reader<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    positions: VecDeque<(u32, Point)>,
+    points: VecDeque<Point>,
+    attitude: Option<Attitude>,
+    boot_time: Option<f64>,
+    record: u64,
+    path: Option<PathBuf>,
+}
+
+/// An ATTITUDE message.
+#[derive(Clone, Copy, Debug)]
+struct Attitude {
+    time: u32,
+    angles: [Radians<f64>; 3],
+    rates: [Radians<f64>; 3],
+}
+
+/// A MAVLink packet.
+#[derive(Debug)]
+struct Packet {
+    timestamp: u64,
+    id: u32,
+    payload: Vec<u8>,
+}
+
+impl Reader<BufReader<File>> {
+    /// Opens a reader for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::tlog::Reader;
+    /// let reader = Reader::from_path("flight.tlog").unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, std::io::Error> {
+        let path = path.as_ref();
+        let mut reader = Reader::new(BufReader::new(File::open(path)?));
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
+    }
+}
+
+impl<R: Read> Reader<R> {
+    /// Creates a new reader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::tlog::Reader;
+    /// let reader = Reader::new(std::io::empty());
+    /// ```
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            buffer: Vec::new(),
+            positions: VecDeque::new(),
+            points: VecDeque::new(),
+            attitude: None,
+            boot_time: None,
+            record: 0,
+            path: None,
+        }
+    }
+
+    /// Returns the Unix time, in seconds, at which the autopilot booted.
+    ///
+    /// This is estimated from the first GLOBAL_POSITION_INT message, so it includes the telemetry
+    /// link's latency, and it's `None` until that message has been read.
+    pub fn boot_time(&self) -> Option<f64> {
+        self.boot_time
+    }
+
+    /// Reads the next position's point.
+    ///
+    /// Altitudes are above mean sea level, velocities are east, north, and up, and angular rates
+    /// are the autopilot's body rates. A position is held until an attitude at or after its time
+    /// has been read, and positions at the end of the log take the last attitude. Positions
+    /// before the first attitude take it unchanged.
+    pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
+        loop {
+            if let Some(point) = self.points.pop_front() {
+                return Ok(Some(point));
+            }
+            let packet = match self.read_packet()? {
+                Some(packet) => packet,
+                None => {
+                    let attitude = self.attitude;
+                    return Ok(self.positions.pop_front().map(|(_, mut point)| {
+                        if let Some(attitude) = attitude {
+                            attitude.apply(&mut point);
+                        }
+                        point
+                    }));
+                }
+            };
+            match packet.id {
+                GLOBAL_POSITION_INT => {
+                    let (time, point) = position(&packet.payload);
+                    let _ = self
+                        .boot_time
+                        .get_or_insert(packet.timestamp as f64 / 1e6 - point.time);
+                    match self.attitude {
+                        Some(attitude) if attitude.time == time => {
+                            let mut point = point;
+                            attitude.apply(&mut point);
+                            self.points.push_back(point);
+                        }
+                        _ => self.positions.push_back((time, point)),
+                    }
+                }
+                ATTITUDE => {
+                    let attitude = Attitude::new(&packet.payload);
+                    while self
+                        .positions
+                        .front()
+                        .is_some_and(|(time, _)| *time <= attitude.time)
+                    {
+                        let (time, mut point) = self.positions.pop_front().unwrap();
+                        match self.attitude.filter(|previous| previous.time < time) {
+                            Some(previous) => previous.interpolate(&attitude, time),
+                            None => attitude,
+                        }
+                        .apply(&mut point);
+                        self.points.push_back(point);
+                    }
+                    self.attitude = Some(attitude);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Reads the next packet, checking the checksum of the messages that become points.
+    fn read_packet(&mut self) -> Result<Option<Packet>, std::io::Error> {
+        loop {
+            if !self.fill(9)? {
+                return Ok(None);
+            }
+            match self.buffer[8..]
+                .iter()
+                .position(|&byte| byte == 0xfe || byte == 0xfd)
+            {
+                Some(start) => {
+                    let _ = self.buffer.drain(..start);
+                }
+                None => {
+                    let _ = self.buffer.drain(..self.buffer.len() - 8);
+                    continue;
+                }
+            }
+            let version_2 = self.buffer[8] == 0xfd;
+            let header_length = if version_2 { 10 } else { 6 };
+            if !self.fill(8 + header_length)? {
+                return Ok(None);
+            }
+            let payload_length = usize::from(self.buffer[9]);
+            let signature_length = if version_2 && self.buffer[10] & 0x01 != 0 {
+                13
+            } else {
+                0
+            };
+            let end = 8 + header_length + payload_length;
+            if !self.fill(end + 2 + signature_length)? {
+                return Ok(None);
+            }
+            let id = if version_2 {
+                u32::from_le_bytes([self.buffer[15], self.buffer[16], self.buffer[17], 0])
+            } else {
+                u32::from(self.buffer[13])
+            };
+            let extra = match id {
+                ATTITUDE => Some(39),
+                GLOBAL_POSITION_INT => Some(104),
+                _ => None,
+            };
+            let valid = match extra {
+                Some(extra) => {
+                    crc(self.buffer[9..end].iter().chain(&[extra]))
+                        == u16::from_le_bytes([self.buffer[end], self.buffer[end + 1]])
+                }
+                None => {
+                    // Other messages can't be checked without their CRC extra bytes, so they're
+                    // trusted if the next record starts right after them.
+                    let next = end + 2 + signature_length + 8;
+                    !self.fill(next + 1)? || matches!(self.buffer[next], 0xfe | 0xfd)
+                }
+            };
+            if valid {
+                let packet: Vec<u8> = self.buffer.drain(..end + 2 + signature_length).collect();
+                let mut timestamp = [0; 8];
+                timestamp.copy_from_slice(&packet[..8]);
+                let mut payload = packet[8 + header_length..end].to_vec();
+                if payload.len() < PAYLOAD_LENGTH {
+                    // MAVLink 2 truncates trailing zeros.
+                    payload.resize(PAYLOAD_LENGTH, 0);
+                }
+                return Ok(Some(Packet {
+                    timestamp: u64::from_be_bytes(timestamp),
+                    id,
+                    payload,
+                }));
+            }
+            #[cfg(feature = "tracing")]
+            tracing::warn!(id, "skipping mavlink packet with a bad checksum");
+            let _ = self.buffer.drain(..1);
+        }
+    }
+
+    /// Reads until the buffer holds at least `length` bytes, returning false at the end of the
+    /// input.
+    fn fill(&mut self, length: usize) -> Result<bool, std::io::Error> {
+        let mut chunk = [0; 4096];
+        while self.buffer.len() < length {
+            match self.reader.read(&mut chunk)? {
+                0 => return Ok(false),
+                n => self.buffer.extend_from_slice(&chunk[..n]),
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Attitude {
+    fn new(payload: &[u8]) -> Attitude {
+        let angle = |offset: usize| Radians(f64::from(f32_at(payload, offset)));
+        Attitude {
+            time: u32_at(payload, 0),
+            angles: [angle(4), angle(8), angle(12)],
+            rates: [angle(16), angle(20), angle(24)],
+        }
+    }
+
+    /// Interpolates the attitude between this one and a later one, taking the shorter way
+    /// around for each angle.
+    fn interpolate(&self, other: &Attitude, time: u32) -> Attitude {
+        let factor = f64::from(time - self.time) / f64::from(other.time - self.time);
+        let angle = |a: Radians<f64>, b: Radians<f64>| Radians(a.0 + (b - a).wrapped().0 * factor);
+        let rate = |a: Radians<f64>, b: Radians<f64>| Radians(a.0 + (b.0 - a.0) * factor);
+        Attitude {
+            time,
+            angles: [0, 1, 2].map(|i| angle(self.angles[i], other.angles[i])),
+            rates: [0, 1, 2].map(|i| rate(self.rates[i], other.rates[i])),
+        }
+    }
+
+    fn apply(&self, point: &mut Point) {
+        point.roll = self.angles[0];
+        point.pitch = self.angles[1];
+        point.yaw = Radians(self.angles[2].0.rem_euclid(std::f64::consts::TAU));
+        point.x_angular_rate = Some(self.rates[0]);
+        point.y_angular_rate = Some(self.rates[1]);
+        point.z_angular_rate = Some(self.rates[2]);
+    }
+}
+
+/// Converts a GLOBAL_POSITION_INT payload into its time since boot, in milliseconds, and a point.
+fn position(payload: &[u8]) -> (u32, Point) {
+    let time = u32_at(payload, 0);
+    let scaled = |offset: usize, scale: f64| f64::from(u32_at(payload, offset) as i32) * scale;
+    let velocity = |offset: usize| {
+        f64::from(i16::from_le_bytes([payload[offset], payload[offset + 1]])) * 0.01
+    };
+    let point = Point {
+        time: f64::from(time) / 1000.0,
+        latitude: Radians::from_degrees(scaled(4, 1e-7)),
+        longitude: Radians::from_degrees(scaled(8, 1e-7)),
+        altitude: scaled(12, 1e-3),
+        x_velocity: Some(velocity(22)),
+        y_velocity: Some(velocity(20)),
+        z_velocity: Some(-velocity(24)),
+        ..Default::default()
+    };
+    (time, point)
+}
+
+impl<R: Read> IntoIterator for Reader<R> {
+    type Item = Point;
+    type IntoIter = ReaderIterator<R>;
+    fn into_iter(self) -> Self::IntoIter {
+        ReaderIterator { reader: self }
+    }
+}
+
+/// An iterator over a MAVLink telemetry log reader.
+#[derive(Debug)]
+pub struct ReaderIterator<R: Read> {
+    reader: Reader<R>,
+}
+
+impl<R: Read> Iterator for ReaderIterator<R> {
+    type Item = Point;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_point().unwrap()
+    }
+}
+
+impl<R: Debug + Read> Source for Reader<R> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(self.record),
+            })
+        });
+        if let Ok(Some(_)) = result {
+            self.record += 1;
+        }
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read mavlink packet");
+        }
+        result
+    }
+}
+
+/// Computes MAVLink's CRC-16/MCRF4XX checksum.
+fn crc<'a, I: IntoIterator<Item = &'a u8>>(bytes: I) -> u16 {
+    let mut crc = 0xffffu16;
+    for &byte in bytes {
+        let mut tmp = byte ^ crc as u8;
+        tmp ^= tmp << 4;
+        let tmp = u16::from(tmp);
+        crc = (crc >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4);
+    }
+    crc
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+    f32::from_bits(u32_at(bytes, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: u64, id: u32, payload: &[u8], version_2: bool) -> Vec<u8> {
+        let mut record = timestamp.to_be_bytes().to_vec();
+        let mut payload = payload.to_vec();
+        if version_2 {
+            while payload.last() == Some(&0) {
+                let _ = payload.pop();
+            }
+            record.extend_from_slice(&[0xfd, payload.len() as u8, 0, 0, 0, 1, 1]);
+            record.extend_from_slice(&id.to_le_bytes()[..3]);
+        } else {
+            record.extend_from_slice(&[0xfe, payload.len() as u8, 0, 1, 1, id as u8]);
+        }
+        record.extend_from_slice(&payload);
+        let extra = if id == ATTITUDE { 39 } else { 104 };
+        let crc = crc(record[9..].iter().chain(&[extra]));
+        record.extend_from_slice(&crc.to_le_bytes());
+        record
+    }
+
+    fn position(time: u32, version_2: bool) -> Vec<u8> {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&time.to_le_bytes());
+        payload.extend_from_slice(&400_000_000i32.to_le_bytes());
+        payload.extend_from_slice(&(-1_050_000_000i32).to_le_bytes());
+        payload.extend_from_slice(&1_600_000i32.to_le_bytes());
+        payload.extend_from_slice(&100_000i32.to_le_bytes());
+        for velocity in [100i16, 200, 50] {
+            payload.extend_from_slice(&velocity.to_le_bytes());
+        }
+        payload.extend_from_slice(&[0, 0]);
+        let timestamp = 1_700_000_000_000_000 + u64::from(time) * 1000;
+        record(timestamp, GLOBAL_POSITION_INT, &payload, version_2)
+    }
+
+    fn attitude(time: u32, yaw: f32) -> Vec<u8> {
+        let mut payload = time.to_le_bytes().to_vec();
+        for value in [0.1f32, -0.2, yaw, 0.01, 0.0, 0.0] {
+            payload.extend_from_slice(&value.to_le_bytes());
+        }
+        record(0, ATTITUDE, &payload, false)
+    }
+
+    #[test]
+    fn fuse() {
+        let mut log = b"garbage".to_vec();
+        log.extend(attitude(1000, 3.0));
+        log.extend(position(1100, false));
+        log.extend(record(0, 0, &[0; 9], false));
+        log.extend(attitude(1200, -3.0));
+        log.extend(position(1200, true));
+        log.extend(position(1300, true));
+        let mut reader = Reader::new(log.as_slice());
+        let point = reader.read_point().unwrap().unwrap();
+        assert!((reader.boot_time().unwrap() - 1_700_000_000.0).abs() < 1e-6);
+        assert_eq!(1.1, point.time);
+        assert!((point.latitude_degrees() - 40.0).abs() < 1e-9);
+        assert!((point.longitude_degrees() + 105.0).abs() < 1e-9);
+        assert_eq!(1600.0, point.altitude);
+        assert_eq!((Some(2.0), Some(1.0)), (point.x_velocity, point.y_velocity));
+        assert_eq!(Some(-0.5), point.z_velocity);
+        assert!((point.roll.0 - 0.1).abs() < 1e-6);
+        assert!((point.yaw.0 - std::f64::consts::PI).abs() < 1e-6);
+
+        let points: Vec<_> = reader.into_iter().collect();
+        assert_eq!(
+            vec![1.2, 1.3],
+            points.iter().map(|p| p.time).collect::<Vec<_>>()
+        );
+        assert!((points[1].yaw.0 - (std::f64::consts::TAU - 3.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn resynchronize() {
+        let mut corrupt = position(1000, false);
+        corrupt[12] ^= 0xff;
+        let mut log = corrupt;
+        log.extend(position(2000, false));
+        log.extend(&position(3000, false)[..20]);
+        let points: Vec<_> = Reader::new(log.as_slice()).into_iter().collect();
+        assert_eq!(1, points.len());
+        assert_eq!(2.0, points[0].time);
+    }
+
+    #[test]
+    fn crc_check_value() {
+        assert_eq!(0x6f91, crc(b"123456789"));
+    }
+}
@@ -0,0 +1,360 @@
+//! In-memory trajectories.
+
+use crate::datum::{Converter, VerticalDatum};
+use crate::geodesy;
+use crate::point::Point;
+use crate::source::Source;
+use crate::Error;
+
+/// A time-ordered sequence of points, held in memory.
+///
+/// Unlike an [Interpolator](crate::Interpolator), a trajectory can be queried in any order
+/// without re-reading its source.
+///
+/// A trajectory can be tagged with the [VerticalDatum] of its heights, which is unknown by
+/// default.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trajectory {
+    points: Vec<Point>,
+    vertical_datum: Option<VerticalDatum>,
+}
+
+/// Streaming length and duration of a sequence of points.
+///
+/// Lengths are summed between consecutive points in the order they're added, so this doesn't
+/// need to hold a whole trajectory in memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Measure {
+    start: Option<f64>,
+    end: Option<f64>,
+    length_2d: f64,
+    length_3d: f64,
+    last: Option<Point>,
+}
+
+impl Trajectory {
+    /// Creates a new trajectory from points, sorting them by time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::{Point, Trajectory};
+    /// let trajectory = Trajectory::new(vec![Point::default()]);
+    /// assert_eq!(1, trajectory.len());
+    /// ```
+    pub fn new(mut points: Vec<Point>) -> Trajectory {
+        points.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Trajectory {
+            points,
+            vertical_datum: None,
+        }
+    }
+
+    /// Tags this trajectory's heights with a vertical datum.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::datum::VerticalDatum;
+    /// use pos::Trajectory;
+    /// let trajectory = Trajectory::default().with_vertical_datum(VerticalDatum::Ellipsoidal);
+    /// assert_eq!(Some(&VerticalDatum::Ellipsoidal), trajectory.vertical_datum());
+    /// ```
+    pub fn with_vertical_datum(mut self, vertical_datum: VerticalDatum) -> Trajectory {
+        self.vertical_datum = Some(vertical_datum);
+        self
+    }
+
+    /// Returns the vertical datum of this trajectory's heights, if it's known.
+    pub fn vertical_datum(&self) -> Option<&VerticalDatum> {
+        self.vertical_datum.as_ref()
+    }
+
+    /// Converts every height to another vertical datum, and re-tags this trajectory.
+    ///
+    /// Returns [Error::VerticalDatumConversion] if this trajectory's datum is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::datum::VerticalDatum;
+    /// use pos::geoid::Geoid;
+    /// use pos::{Point, Trajectory};
+    /// let geoid = Geoid::new("-90 90 0 360 90 180\n3 3 3\n3 3 3\n3 3 3\n".as_bytes()).unwrap();
+    /// let mut trajectory = Trajectory::new(vec![Point { altitude: 10.0, ..Default::default() }])
+    ///     .with_vertical_datum(VerticalDatum::Ellipsoidal);
+    /// trajectory
+    ///     .convert_vertical_datum(VerticalDatum::Orthometric(None), &geoid)
+    ///     .unwrap();
+    /// assert_eq!(7.0, trajectory.points()[0].altitude);
+    /// ```
+    pub fn convert_vertical_datum(
+        &mut self,
+        to: VerticalDatum,
+        converter: &dyn Converter,
+    ) -> Result<(), Error> {
+        let from = self
+            .vertical_datum
+            .as_ref()
+            .ok_or_else(|| Error::VerticalDatumConversion("unknown".to_string(), to.to_string()))?;
+        let altitudes = self
+            .points
+            .iter()
+            .map(|point| from.convert(point, &to, converter))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (point, altitude) in self.points.iter_mut().zip(altitudes) {
+            point.altitude = altitude;
+        }
+        self.vertical_datum = Some(to);
+        Ok(())
+    }
+
+    /// Reads every point from a source into a trajectory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::Trajectory;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let trajectory = Trajectory::from_source(&mut reader).unwrap();
+    /// assert_eq!(2, trajectory.len());
+    /// ```
+    pub fn from_source(source: &mut dyn Source) -> Result<Trajectory, Error> {
+        let mut points = Vec::new();
+        while let Some(point) = source.source()? {
+            points.push(point);
+        }
+        Ok(Trajectory::new(points))
+    }
+
+    /// Returns this trajectory's points.
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// Returns the number of points in this trajectory.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns true if this trajectory has no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Linearly interpolates a point at the given time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::{Point, Trajectory};
+    /// let trajectory = Trajectory::new(vec![
+    ///     Point { time: 0.0, altitude: 0.0, ..Default::default() },
+    ///     Point { time: 1.0, altitude: 10.0, ..Default::default() },
+    /// ]);
+    /// let point = trajectory.interpolate(0.5).unwrap();
+    /// assert_eq!(5.0, point.altitude);
+    /// ```
+    pub fn interpolate(&self, time: f64) -> Result<Point, Error> {
+        if self.points.len() < 2 {
+            return Err(Error::OnePoint);
+        }
+        if time < self.points[0].time {
+            return Err(Error::TimeBelowMinimum(time));
+        }
+        if time > self.points[self.points.len() - 1].time {
+            return Err(Error::TimeAboveMaximum(time));
+        }
+        let index = self
+            .points
+            .partition_point(|p| p.time < time)
+            .clamp(1, self.points.len() - 1);
+        Ok(self.points[index - 1].interpolate(&self.points[index], time))
+    }
+
+    /// Returns the 3D path length of this trajectory, in meters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::{Point, Trajectory};
+    /// let trajectory = Trajectory::new(vec![
+    ///     Point { time: 0.0, altitude: 0.0, ..Default::default() },
+    ///     Point { time: 1.0, altitude: 10.0, ..Default::default() },
+    /// ]);
+    /// assert!((trajectory.length_3d() - 10.0).abs() < 1e-6);
+    /// assert_eq!(0.0, trajectory.length_2d());
+    /// assert_eq!(1.0, trajectory.duration());
+    /// ```
+    pub fn length_3d(&self) -> f64 {
+        self.measure().length_3d()
+    }
+
+    /// Returns the horizontal path length of this trajectory, in meters.
+    pub fn length_2d(&self) -> f64 {
+        self.measure().length_2d()
+    }
+
+    /// Returns the time between the first and last points, in seconds.
+    pub fn duration(&self) -> f64 {
+        match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+
+    fn measure(&self) -> Measure {
+        let mut measure = Measure::new();
+        for point in &self.points {
+            measure.add(point);
+        }
+        measure
+    }
+
+    /// Consumes this trajectory, returning its points.
+    pub fn into_points(self) -> Vec<Point> {
+        self.points
+    }
+}
+
+impl From<Vec<Point>> for Trajectory {
+    fn from(points: Vec<Point>) -> Trajectory {
+        Trajectory::new(points)
+    }
+}
+
+impl IntoIterator for Trajectory {
+    type Item = Point;
+    type IntoIter = std::vec::IntoIter<Point>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.into_iter()
+    }
+}
+
+impl Measure {
+    /// Creates a new, empty measure.
+    pub fn new() -> Measure {
+        Measure::default()
+    }
+
+    /// Measures every point from a source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::sbet::Reader;
+    /// use pos::trajectory::Measure;
+    /// let mut reader = Reader::from_path("data/2-points.sbet").unwrap();
+    /// let measure = Measure::from_source(&mut reader).unwrap();
+    /// assert!(measure.length_3d() > 0.0);
+    /// ```
+    pub fn from_source(source: &mut dyn Source) -> Result<Measure, Error> {
+        let mut measure = Measure::new();
+        while let Some(point) = source.source()? {
+            measure.add(&point);
+        }
+        Ok(measure)
+    }
+
+    /// Adds a point.
+    pub fn add(&mut self, point: &Point) {
+        let _ = self.start.get_or_insert(point.time);
+        self.end = Some(point.time);
+        if let Some(last) = &self.last {
+            self.length_2d += geodesy::horizontal_distance(last, point);
+            self.length_3d += geodesy::distance(last, point);
+        }
+        self.last = Some(*point);
+    }
+
+    /// Returns the 3D path length, in meters.
+    pub fn length_3d(&self) -> f64 {
+        self.length_3d
+    }
+
+    /// Returns the horizontal path length, in meters.
+    pub fn length_2d(&self) -> f64 {
+        self.length_2d
+    }
+
+    /// Returns the time between the first and last points, in seconds.
+    pub fn duration(&self) -> f64 {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => end - start,
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolate() {
+        let trajectory = Trajectory::new(vec![
+            Point {
+                time: 2.0,
+                altitude: 20.0,
+                ..Default::default()
+            },
+            Point {
+                time: 0.0,
+                altitude: 0.0,
+                ..Default::default()
+            },
+            Point {
+                time: 1.0,
+                altitude: 10.0,
+                ..Default::default()
+            },
+        ]);
+        assert_eq!(15.0, trajectory.interpolate(1.5).unwrap().altitude);
+        assert_eq!(0.0, trajectory.interpolate(0.0).unwrap().altitude);
+        assert_eq!(20.0, trajectory.interpolate(2.0).unwrap().altitude);
+        assert!(trajectory.interpolate(-1.0).is_err());
+        assert!(trajectory.interpolate(3.0).is_err());
+    }
+
+    #[test]
+    fn length() {
+        let trajectory = Trajectory::new(
+            (0..3)
+                .map(|i| Point {
+                    time: i as f64,
+                    latitude: crate::units::Radians::from_degrees(0.001 * i as f64),
+                    altitude: 10.0 * i as f64,
+                    ..Default::default()
+                })
+                .collect(),
+        );
+        let length_2d = trajectory.length_2d();
+        assert!((length_2d - 221.1).abs() < 0.1, "{length_2d}");
+        let expected = 2.0 * (length_2d * length_2d / 4.0 + 100.0).sqrt();
+        assert!((trajectory.length_3d() - expected).abs() < 0.01);
+        assert_eq!(2.0, trajectory.duration());
+        assert_eq!(0.0, Trajectory::default().duration());
+    }
+
+    #[test]
+    fn vertical_datum() {
+        let geoid =
+            crate::geoid::Geoid::new("-90 90 0 360 90 180\n2 2 2\n2 2 2\n2 2 2\n".as_bytes())
+                .unwrap();
+        let mut trajectory = Trajectory::new(vec![Point::default()]);
+        assert!(matches!(
+            trajectory.convert_vertical_datum(VerticalDatum::Ellipsoidal, &geoid),
+            Err(Error::VerticalDatumConversion(..))
+        ));
+        trajectory = trajectory.with_vertical_datum(VerticalDatum::Orthometric(None));
+        trajectory
+            .convert_vertical_datum(VerticalDatum::Ellipsoidal, &geoid)
+            .unwrap();
+        assert_eq!(2.0, trajectory.points()[0].altitude);
+        assert_eq!(
+            Some(&VerticalDatum::Ellipsoidal),
+            trajectory.vertical_datum()
+        );
+    }
+}
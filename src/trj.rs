@@ -0,0 +1,397 @@
+//! Read TerraScan binary trajectory files.
+//!
+//! A `.trj` file, as written by Terrasolid's TerraScan and TerraMatch, starts with a header,
+//! beginning with the signature `TSCANTRJ`, followed by fixed-size little-endian records. Each
+//! record holds a time, an `x`, `y`, and `z` position, the heading, roll, and pitch in degrees,
+//! quality values, and a mark and flag.
+//!
+//! TerraScan trajectories are often in a projected system. [Record]s keep the raw coordinates,
+//! while [Point]s assume that `x` and `y` are longitude and latitude in degrees; use
+//! [Record::to_point_with] for projected trajectories.
+
+use crate::point::Point;
+use crate::source::Source;
+use crate::units::Radians;
+use crate::{Error, Location};
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// The signature at the start of every trajectory file.
+pub const SIGNATURE: &[u8; 8] = b"TSCANTRJ";
+
+/// The size of the fields of a record that are read, without the reserved bytes and padding.
+const RECORD_FIELDS_SIZE: u32 = 74;
+
+/// The size of the fields of a header, up to and including the group.
+const HEADER_FIELDS_SIZE: u32 = 1384;
+
+/// A trajectory file header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header {
+    /// The file version, as a date, e.g. `20010715`.
+    pub version: i32,
+    /// The size of the header, in bytes.
+    pub header_size: u32,
+    /// The number of records.
+    pub record_count: u32,
+    /// The size of each record, in bytes.
+    pub record_size: u32,
+    /// A description of the trajectory.
+    pub description: String,
+    /// The identifier of the laser system.
+    pub system: u8,
+    /// The quality of the trajectory, from 1 (best) to 5.
+    pub quality: u8,
+    /// The time of the first record.
+    pub begin_time: f64,
+    /// The time of the last record.
+    pub end_time: f64,
+    /// The trajectory's original number.
+    pub original_number: i32,
+    /// The trajectory's number.
+    pub number: i32,
+    /// The group that the trajectory belongs to.
+    pub group: String,
+}
+
+/// A trajectory record, as stored in the file.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Record {
+    /// The time.
+    pub time: f64,
+    /// The easting, or longitude in degrees.
+    pub x: f64,
+    /// The northing, or latitude in degrees.
+    pub y: f64,
+    /// The elevation.
+    pub z: f64,
+    /// The heading, in degrees.
+    pub heading: f64,
+    /// The roll, in degrees.
+    pub roll: f64,
+    /// The pitch, in degrees.
+    pub pitch: f64,
+    /// The quality of the horizontal position.
+    pub quality_xy: i32,
+    /// The quality of the elevation.
+    pub quality_z: i32,
+    /// The quality of the heading.
+    pub quality_heading: i32,
+    /// The quality of the roll and pitch.
+    pub quality_roll_pitch: i32,
+    /// A user mark.
+    pub mark: u8,
+    /// A flag.
+    pub flag: u8,
+}
+
+/// A trajectory reader.
+#[derive(Debug)]
+pub struct Reader<R: Read> {
+    reader: R,
+    header: Header,
+    remaining: u32,
+    path: Option<PathBuf>,
+}
+
+impl Reader<BufReader<File>> {
+    /// Opens a reader for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::trj::Reader;
+    /// let reader = Reader::from_path("trajectory.trj").unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, Error> {
+        let path = path.as_ref();
+        let mut reader = Reader::new(BufReader::new(File::open(path)?))
+            .map_err(|err| err.at(Location::from_path(path)))?;
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
+    }
+}
+
+impl<R: Read> Reader<R> {
+    /// Creates a new reader, reading the header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::trj::Reader;
+    /// assert!(Reader::new(&b"not a trajectory"[..]).is_err());
+    /// ```
+    pub fn new(mut reader: R) -> Result<Reader<R>, Error> {
+        let mut signature = [0; 8];
+        reader.read_exact(&mut signature)?;
+        if &signature != SIGNATURE {
+            return Err(Error::TrjSignature(
+                String::from_utf8_lossy(&signature).into_owned(),
+            ));
+        }
+        let version = reader.read_i32::<LittleEndian>()?;
+        let header_size = reader.read_u32::<LittleEndian>()?;
+        let record_count = reader.read_u32::<LittleEndian>()?;
+        let record_size = reader.read_u32::<LittleEndian>()?;
+        let description = string(&mut reader, 79)?;
+        let system = reader.read_u8()?;
+        let quality = reader.read_u8()?;
+        skip(&mut reader, 7)?;
+        let begin_time = reader.read_f64::<LittleEndian>()?;
+        let end_time = reader.read_f64::<LittleEndian>()?;
+        let original_number = reader.read_i32::<LittleEndian>()?;
+        let number = reader.read_i32::<LittleEndian>()?;
+        // Video and waveform file names and times.
+        skip(&mut reader, 1232)?;
+        let group = string(&mut reader, 16)?;
+        skip(&mut reader, header_size.saturating_sub(HEADER_FIELDS_SIZE))?;
+        let header = Header {
+            version,
+            header_size,
+            record_count,
+            record_size,
+            description,
+            system,
+            quality,
+            begin_time,
+            end_time,
+            original_number,
+            number,
+            group,
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?header, "read trj header");
+        Ok(Reader {
+            reader,
+            remaining: header.record_count,
+            header,
+            path: None,
+        })
+    }
+
+    /// Returns this file's header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Reads a record.
+    ///
+    /// Returns `None` once the header's number of records have been read.
+    pub fn read_record(&mut self) -> Result<Option<Record>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let mut read = || self.reader.read_f64::<LittleEndian>();
+        let record = Record {
+            time: read()?,
+            x: read()?,
+            y: read()?,
+            z: read()?,
+            heading: read()?,
+            roll: read()?,
+            pitch: read()?,
+            quality_xy: self.reader.read_i32::<LittleEndian>()?,
+            quality_z: self.reader.read_i32::<LittleEndian>()?,
+            quality_heading: self.reader.read_i32::<LittleEndian>()?,
+            quality_roll_pitch: self.reader.read_i32::<LittleEndian>()?,
+            mark: self.reader.read_u8()?,
+            flag: self.reader.read_u8()?,
+        };
+        skip(
+            &mut self.reader,
+            self.header.record_size.saturating_sub(RECORD_FIELDS_SIZE),
+        )?;
+        Ok(Some(record))
+    }
+
+    /// Reads a record as a point, with [Record::to_point].
+    pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
+        Ok(self.read_record()?.map(|record| record.to_point()))
+    }
+}
+
+impl Record {
+    /// Converts this record to a point, treating `x` and `y` as longitude and latitude in
+    /// degrees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::trj::Record;
+    /// let record = Record { x: -105.0, y: 40.0, heading: 90.0, ..Default::default() };
+    /// let point = record.to_point();
+    /// assert!((point.latitude_degrees() - 40.0).abs() < 1e-12);
+    /// assert!((point.yaw_degrees() - 90.0).abs() < 1e-12);
+    /// ```
+    pub fn to_point(&self) -> Point {
+        self.to_point_with(|x, y| (x, y))
+    }
+
+    /// Converts this record to a point, using a function from `x` and `y` to longitude and
+    /// latitude in degrees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::trj::Record;
+    /// let record = Record { x: 500_000.0, y: 0.0, ..Default::default() };
+    /// let point = record.to_point_with(|x, y| ((x - 500_000.0) / 111_000.0 - 105.0, y));
+    /// assert!((point.longitude_degrees() + 105.0).abs() < 1e-12);
+    /// ```
+    pub fn to_point_with<F: Fn(f64, f64) -> (f64, f64)>(&self, unproject: F) -> Point {
+        let (longitude, latitude) = unproject(self.x, self.y);
+        Point {
+            time: self.time,
+            longitude: Radians::from_degrees(longitude),
+            latitude: Radians::from_degrees(latitude),
+            altitude: self.z,
+            roll: Radians::from_degrees(self.roll),
+            pitch: Radians::from_degrees(self.pitch),
+            yaw: Radians::from_degrees(self.heading),
+            event: if self.mark == 0 {
+                None
+            } else {
+                Some(u32::from(self.mark))
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl<R: Read> IntoIterator for Reader<R> {
+    type Item = Point;
+    type IntoIter = ReaderIterator<R>;
+    fn into_iter(self) -> Self::IntoIter {
+        ReaderIterator { reader: self }
+    }
+}
+
+/// An iterator over a trajectory reader.
+#[derive(Debug)]
+pub struct ReaderIterator<R: Read> {
+    reader: Reader<R>,
+}
+
+impl<R: Read> Iterator for ReaderIterator<R> {
+    type Item = Point;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_point().unwrap()
+    }
+}
+
+impl<R: Debug + Read> Source for Reader<R> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let record = u64::from(self.header.record_count - self.remaining);
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(record),
+            })
+        });
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read trj record");
+        }
+        result
+    }
+}
+
+fn string<R: Read>(reader: &mut R, length: usize) -> Result<String, std::io::Error> {
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(length);
+    Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+}
+
+fn skip<R: Read>(reader: &mut R, count: u32) -> Result<(), std::io::Error> {
+    let skipped = std::io::copy(&mut reader.take(count.into()), &mut std::io::sink())?;
+    if skipped < count.into() {
+        Err(std::io::ErrorKind::UnexpectedEof.into())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes() -> Vec<u8> {
+        let mut bytes = SIGNATURE.to_vec();
+        for value in [20_010_715i32, 1384, 2, 80] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut description = b"strip 1".to_vec();
+        description.resize(79, 0);
+        bytes.extend_from_slice(&description);
+        bytes.extend_from_slice(&[3, 1]);
+        bytes.resize(112, 0);
+        for value in [10.0f64, 11.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [4i32, 5] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.resize(1368, 0);
+        let mut group = b"north".to_vec();
+        group.resize(16, 0);
+        bytes.extend_from_slice(&group);
+        for (time, mark) in [(10.0f64, 0u8), (11.0, 7)] {
+            for value in [time, -105.0, 40.0, 1600.0, 270.0, 1.0, -2.0] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            for value in [1i32, 2, 3, 4] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&[mark, 0, 0, 0, 0, 0, 0, 0]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn header() {
+        let bytes = bytes();
+        let reader = Reader::new(bytes.as_slice()).unwrap();
+        let header = reader.header();
+        assert_eq!(20_010_715, header.version);
+        assert_eq!(2, header.record_count);
+        assert_eq!("strip 1", header.description);
+        assert_eq!((3, 1), (header.system, header.quality));
+        assert_eq!((10.0, 11.0), (header.begin_time, header.end_time));
+        assert_eq!((4, 5), (header.original_number, header.number));
+        assert_eq!("north", header.group);
+    }
+
+    #[test]
+    fn records() {
+        let bytes = bytes();
+        let mut reader = Reader::new(bytes.as_slice()).unwrap();
+        let record = reader.read_record().unwrap().unwrap();
+        assert_eq!(1, record.quality_xy);
+        assert_eq!(4, record.quality_roll_pitch);
+        let point = reader.read_point().unwrap().unwrap();
+        assert_eq!(11.0, point.time);
+        assert_eq!(1600.0, point.altitude);
+        assert!((point.pitch_degrees() + 2.0).abs() < 1e-12);
+        assert_eq!(Some(7), point.event);
+        assert!(reader.read_point().unwrap().is_none());
+    }
+
+    #[test]
+    fn signature() {
+        let mut bytes = bytes();
+        bytes[0] = b'X';
+        assert!(matches!(
+            Reader::new(bytes.as_slice()),
+            Err(Error::TrjSignature(_))
+        ));
+    }
+}
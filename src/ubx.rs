@@ -0,0 +1,423 @@
+//! Read u-blox UBX logs.
+//!
+//! UBX is u-blox's binary protocol. Each frame starts with the sync characters `0xb5 0x62`,
+//! followed by a message class and id, a little-endian payload length, the payload, and a
+//! two-byte Fletcher checksum. Frames with bad checksums are skipped, and the reader
+//! resynchronizes on the next sync characters, so garbage between frames is tolerated.
+//!
+//! NAV-PVT messages become points, and NAV-ATT messages from the same epoch add the roll, pitch,
+//! and heading. Other messages are skipped. Point times are GPS seconds of the week of the first
+//! message, and keep increasing past later week rollovers.
+
+use crate::point::{Accuracy, FixQuality, Point, SatelliteCount};
+use crate::source::Source;
+use crate::units::Radians;
+use crate::{Error, Location};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// The sync characters at the start of every frame.
+pub const SYNC: [u8; 2] = [0xb5, 0x62];
+
+/// The class of navigation messages.
+const CLASS_NAV: u8 = 0x01;
+
+/// The id of NAV-PVT messages.
+const ID_NAV_PVT: u8 = 0x07;
+
+/// The id of NAV-ATT messages.
+const ID_NAV_ATT: u8 = 0x05;
+
+/// The number of milliseconds in a GPS week.
+const MILLISECONDS_PER_WEEK: u64 = 604_800_000;
+
+/// A UBX reader.
+#[derive(Debug)]
+pub struct Reader<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    pending: Option<(u32, Point)>,
+    attitude: Option<Attitude>,
+    weeks: u64,
+    last_itow: Option<u32>,
+    record: u64,
+    path: Option<PathBuf>,
+}
+
+/// A NAV-ATT message.
+#[derive(Clone, Copy, Debug)]
+struct Attitude {
+    itow: u32,
+    roll: Radians<f64>,
+    pitch: Radians<f64>,
+    heading: Radians<f64>,
+    accuracy: [Radians<f64>; 3],
+}
+
+impl Reader<BufReader<File>> {
+    /// Opens a reader for a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pos::ubx::Reader;
+    /// let reader = Reader::from_path("flight.ubx").unwrap();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, std::io::Error> {
+        let path = path.as_ref();
+        let mut reader = Reader::new(BufReader::new(File::open(path)?));
+        reader.path = Some(path.to_path_buf());
+        Ok(reader)
+    }
+}
+
+impl<R: Read> Reader<R> {
+    /// Creates a new reader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::ubx::Reader;
+    /// let reader = Reader::new(std::io::empty());
+    /// ```
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            buffer: Vec::new(),
+            pending: None,
+            attitude: None,
+            weeks: 0,
+            last_itow: None,
+            record: 0,
+            path: None,
+        }
+    }
+
+    /// Reads the next epoch's point.
+    ///
+    /// Epochs without a position fix are skipped. Altitudes are ellipsoidal heights, velocities
+    /// are east, north, and up, and the accuracy holds the horizontal and vertical accuracy
+    /// estimates, the PDOP, and the number of satellites. Without a NAV-ATT message, the roll and
+    /// pitch are zero and the yaw is the vehicle heading, if the receiver reports one.
+    ///
+    /// A truncated frame at the end of the input is ignored.
+    pub fn read_point(&mut self) -> Result<Option<Point>, Error> {
+        while let Some((class, id, payload)) = self.read_frame()? {
+            match (class, id, payload.len()) {
+                (CLASS_NAV, ID_NAV_PVT, 92) => {
+                    let itow = u32_at(&payload, 0);
+                    let point = self.pvt(&payload);
+                    let done = self.pending.take().map(|(_, point)| point);
+                    if let Some(mut point) = point {
+                        if let Some(attitude) = self.attitude.filter(|a| a.itow == itow) {
+                            attitude.apply(&mut point);
+                        }
+                        self.pending = Some((itow, point));
+                    }
+                    if done.is_some() {
+                        return Ok(done);
+                    }
+                }
+                (CLASS_NAV, ID_NAV_ATT, 32) => {
+                    let attitude = Attitude::new(&payload);
+                    match self.pending.as_mut() {
+                        Some((itow, point)) if *itow == attitude.itow => attitude.apply(point),
+                        _ => self.attitude = Some(attitude),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(self.pending.take().map(|(_, point)| point))
+    }
+
+    fn pvt(&mut self, payload: &[u8]) -> Option<Point> {
+        let itow = u32_at(payload, 0);
+        if self.last_itow.is_some_and(|last| itow < last) {
+            self.weeks += 1;
+        }
+        self.last_itow = Some(itow);
+        let fix_type = payload[20];
+        let flags = payload[21];
+        let fix_quality = match (fix_type, flags >> 6) {
+            (0 | 5, _) => return None,
+            (_, 2) => FixQuality::RtkFixed,
+            (_, 1) => FixQuality::RtkFloat,
+            (1, _) => FixQuality::DeadReckoning,
+            _ if flags & 0x02 != 0 => FixQuality::Dgps,
+            _ => FixQuality::Sps,
+        };
+        let time = (self.weeks * MILLISECONDS_PER_WEEK + u64::from(itow)) as f64 / 1000.0;
+        let scaled = |offset: usize, scale: f64| f64::from(i32_at(payload, offset)) * scale;
+        let horizontal = f64::from(u32_at(payload, 40)) * 1e-3;
+        Some(Point {
+            time,
+            longitude: Radians::from_degrees(scaled(24, 1e-7)),
+            latitude: Radians::from_degrees(scaled(28, 1e-7)),
+            altitude: scaled(32, 1e-3),
+            yaw: if flags & 0x20 != 0 {
+                Radians::from_degrees(scaled(84, 1e-5))
+            } else {
+                Radians::default()
+            },
+            x_velocity: Some(scaled(52, 1e-3)),
+            y_velocity: Some(scaled(48, 1e-3)),
+            z_velocity: Some(-scaled(56, 1e-3)),
+            accuracy: Some(Accuracy {
+                time,
+                x: horizontal,
+                y: horizontal,
+                z: f64::from(u32_at(payload, 44)) * 1e-3,
+                pdop: f64::from(u16::from_le_bytes([payload[76], payload[77]])) * 0.01,
+                satellite_count: Some(SatelliteCount::Unspecified(payload[23].into())),
+                ..Default::default()
+            }),
+            fix_quality: Some(fix_quality),
+            ..Default::default()
+        })
+    }
+
+    /// Reads the next frame with a valid checksum, returning its class, id, and payload.
+    fn read_frame(&mut self) -> Result<Option<(u8, u8, Vec<u8>)>, std::io::Error> {
+        loop {
+            if !self.fill(2)? {
+                return Ok(None);
+            }
+            match self.buffer.windows(2).position(|window| window == SYNC) {
+                Some(start) => {
+                    let _ = self.buffer.drain(..start);
+                }
+                None => {
+                    let keep = usize::from(self.buffer.last() == Some(&SYNC[0]));
+                    let _ = self.buffer.drain(..self.buffer.len() - keep);
+                    continue;
+                }
+            }
+            if !self.fill(6)? {
+                return Ok(None);
+            }
+            let length = usize::from(u16::from_le_bytes([self.buffer[4], self.buffer[5]]));
+            if !self.fill(length + 8)? {
+                return Ok(None);
+            }
+            if checksum(&self.buffer[2..length + 6])
+                == [self.buffer[length + 6], self.buffer[length + 7]]
+            {
+                let frame: Vec<u8> = self.buffer.drain(..length + 8).collect();
+                return Ok(Some((frame[2], frame[3], frame[6..length + 6].to_vec())));
+            }
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                class = self.buffer[2],
+                id = self.buffer[3],
+                "skipping ubx frame with a bad checksum"
+            );
+            let _ = self.buffer.drain(..1);
+        }
+    }
+
+    /// Reads until the buffer holds at least `length` bytes, returning false at the end of the
+    /// input.
+    fn fill(&mut self, length: usize) -> Result<bool, std::io::Error> {
+        let mut chunk = [0; 4096];
+        while self.buffer.len() < length {
+            match self.reader.read(&mut chunk)? {
+                0 => return Ok(false),
+                n => self.buffer.extend_from_slice(&chunk[..n]),
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Attitude {
+    fn new(payload: &[u8]) -> Attitude {
+        let angle =
+            |offset: usize| Radians::from_degrees(f64::from(i32_at(payload, offset)) * 1e-5);
+        let accuracy =
+            |offset: usize| Radians::from_degrees(f64::from(u32_at(payload, offset)) * 1e-5);
+        Attitude {
+            itow: u32_at(payload, 0),
+            roll: angle(8),
+            pitch: angle(12),
+            heading: angle(16),
+            accuracy: [accuracy(20), accuracy(24), accuracy(28)],
+        }
+    }
+
+    fn apply(&self, point: &mut Point) {
+        point.roll = self.roll;
+        point.pitch = self.pitch;
+        point.yaw = self.heading;
+        if let Some(accuracy) = point.accuracy.as_mut() {
+            accuracy.roll = self.accuracy[0];
+            accuracy.pitch = self.accuracy[1];
+            accuracy.yaw = self.accuracy[2];
+        }
+    }
+}
+
+impl<R: Read> IntoIterator for Reader<R> {
+    type Item = Point;
+    type IntoIter = ReaderIterator<R>;
+    fn into_iter(self) -> Self::IntoIter {
+        ReaderIterator { reader: self }
+    }
+}
+
+/// An iterator over a UBX reader.
+#[derive(Debug)]
+pub struct ReaderIterator<R: Read> {
+    reader: Reader<R>,
+}
+
+impl<R: Read> Iterator for ReaderIterator<R> {
+    type Item = Point;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_point().unwrap()
+    }
+}
+
+impl<R: Debug + Read> Source for Reader<R> {
+    fn source(&mut self) -> Result<Option<Point>, Error> {
+        let result = self.read_point().map_err(|err| {
+            err.at(Location {
+                path: self.path.clone(),
+                record: Some(self.record),
+            })
+        });
+        if let Ok(Some(_)) = result {
+            self.record += 1;
+        }
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::warn!(%err, "could not read ubx frame");
+        }
+        result
+    }
+}
+
+/// Computes the 8-bit Fletcher checksum of a frame's class, id, length, and payload.
+fn checksum(bytes: &[u8]) -> [u8; 2] {
+    let mut a = 0u8;
+    let mut b = 0u8;
+    for &byte in bytes {
+        a = a.wrapping_add(byte);
+        b = b.wrapping_add(a);
+    }
+    [a, b]
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn i32_at(bytes: &[u8], offset: usize) -> i32 {
+    u32_at(bytes, offset) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = SYNC.to_vec();
+        frame.extend_from_slice(&[class, id]);
+        frame.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        frame.extend_from_slice(payload);
+        let checksum = checksum(&frame[2..]);
+        frame.extend_from_slice(&checksum);
+        frame
+    }
+
+    fn pvt(itow: u32, fix_type: u8, flags: u8) -> Vec<u8> {
+        let mut payload = vec![0; 92];
+        let mut put = |offset: usize, bytes: &[u8]| {
+            payload[offset..offset + bytes.len()].copy_from_slice(bytes)
+        };
+        put(0, &itow.to_le_bytes());
+        put(20, &[fix_type, flags, 0, 12]);
+        put(24, &(-1_050_000_000i32).to_le_bytes());
+        put(28, &400_000_000i32.to_le_bytes());
+        put(32, &1_600_000i32.to_le_bytes());
+        put(40, &20u32.to_le_bytes());
+        put(44, &50u32.to_le_bytes());
+        put(48, &1000i32.to_le_bytes());
+        put(52, &2000i32.to_le_bytes());
+        put(56, &500i32.to_le_bytes());
+        put(76, &150u16.to_le_bytes());
+        put(84, &9_000_000i32.to_le_bytes());
+        frame(CLASS_NAV, ID_NAV_PVT, &payload)
+    }
+
+    fn att(itow: u32) -> Vec<u8> {
+        let mut payload = vec![0; 32];
+        payload[..4].copy_from_slice(&itow.to_le_bytes());
+        payload[8..12].copy_from_slice(&100_000i32.to_le_bytes());
+        payload[16..20].copy_from_slice(&27_000_000i32.to_le_bytes());
+        payload[28..32].copy_from_slice(&50_000u32.to_le_bytes());
+        frame(CLASS_NAV, ID_NAV_ATT, &payload)
+    }
+
+    #[test]
+    fn epochs() {
+        let mut log = b"garbage\xb5".to_vec();
+        log.extend(att(1000));
+        log.extend(pvt(1000, 3, 0x83));
+        log.extend(frame(0x02, 0x15, &[1, 2, 3]));
+        log.extend(pvt(1200, 0, 0));
+        log.extend(pvt(1400, 3, 0x21));
+        let points: Vec<_> = Reader::new(log.as_slice()).into_iter().collect();
+        assert_eq!(2, points.len());
+
+        let point = points[0];
+        assert_eq!(1.0, point.time);
+        assert_eq!(Some(FixQuality::RtkFixed), point.fix_quality);
+        assert!((point.latitude_degrees() - 40.0).abs() < 1e-9);
+        assert!((point.longitude_degrees() + 105.0).abs() < 1e-9);
+        assert_eq!(1600.0, point.altitude);
+        assert_eq!((Some(2.0), Some(1.0)), (point.x_velocity, point.y_velocity));
+        assert_eq!(Some(-0.5), point.z_velocity);
+        assert!((point.roll.to_degrees() - 1.0).abs() < 1e-9);
+        assert!((point.yaw.to_degrees() - 270.0).abs() < 1e-9);
+        let accuracy = point.accuracy.unwrap();
+        assert_eq!((0.02, 0.05, 1.5), (accuracy.x, accuracy.z, accuracy.pdop));
+        assert!((accuracy.yaw.to_degrees() - 0.5).abs() < 1e-9);
+
+        let point = points[1];
+        assert_eq!(Some(FixQuality::Sps), point.fix_quality);
+        assert!((point.yaw.to_degrees() - 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn resynchronize() {
+        let mut corrupt = pvt(1000, 3, 0);
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xff;
+        let mut log = corrupt;
+        log.extend(pvt(2000, 3, 0));
+        log.extend(&pvt(3000, 3, 0)[..50]);
+        let points: Vec<_> = Reader::new(log.as_slice()).into_iter().collect();
+        assert_eq!(1, points.len());
+        assert_eq!(2.0, points[0].time);
+    }
+
+    #[test]
+    fn week_rollover() {
+        let mut log = pvt(604_799_000, 3, 0);
+        log.extend(pvt(0, 3, 0));
+        let points: Vec<_> = Reader::new(log.as_slice()).into_iter().collect();
+        assert_eq!(604_800.0, points[1].time);
+    }
+}
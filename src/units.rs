@@ -1,12 +1,16 @@
 //! Unit newtypes.
 
+use crate::Error;
 use std::f64::consts::PI;
+use std::fmt;
 use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
 
 /// Newtype wrapper around a radian value.
 ///
 /// It's so easy to forget if you're using radians or degrees.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Radians<T>(pub T);
 
 impl Radians<f64> {
@@ -37,6 +41,161 @@ impl Radians<f64> {
     pub fn to_degrees(self) -> f64 {
         self.0 * 180.0 / PI
     }
+
+    /// Wraps this angle into `[-π, π)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::units::Radians;
+    /// let wrapped = Radians::from_degrees(270.0).wrapped();
+    /// assert!((wrapped.to_degrees() + 90.0).abs() < 1e-9);
+    /// ```
+    pub fn wrapped(self) -> Radians<f64> {
+        Radians((self.0 + PI).rem_euclid(2.0 * PI) - PI)
+    }
+}
+
+/// A unit for angular values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AngleUnit {
+    /// Degrees.
+    #[default]
+    Degrees,
+    /// Radians.
+    Radians,
+}
+
+impl AngleUnit {
+    /// Expresses a radians value in this unit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::f64::consts::PI;
+    /// use pos::units::{AngleUnit, Radians};
+    /// assert_eq!(180.0, AngleUnit::Degrees.convert(Radians(PI)));
+    /// assert_eq!(PI, AngleUnit::Radians.convert(Radians(PI)));
+    /// ```
+    pub fn convert(self, radians: Radians<f64>) -> f64 {
+        match self {
+            AngleUnit::Degrees => radians.to_degrees(),
+            AngleUnit::Radians => radians.0,
+        }
+    }
+
+    /// Converts a value in this unit to radians.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::f64::consts::PI;
+    /// use pos::units::{AngleUnit, Radians};
+    /// assert_eq!(Radians(PI), AngleUnit::Degrees.to_radians(180.0));
+    /// ```
+    pub fn to_radians(self, value: f64) -> Radians<f64> {
+        match self {
+            AngleUnit::Degrees => Radians::from_degrees(value),
+            AngleUnit::Radians => Radians(value),
+        }
+    }
+}
+
+/// A unit for lengths, such as heights.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// Meters.
+    #[default]
+    Meters,
+    /// International feet, exactly 0.3048 meters.
+    Feet,
+    /// US survey feet, exactly 1200/3937 meters.
+    UsSurveyFeet,
+}
+
+impl LengthUnit {
+    /// Converts a value in this unit to meters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::units::LengthUnit;
+    /// assert_eq!(3.048, LengthUnit::Feet.to_meters(10.0));
+    /// ```
+    pub fn to_meters(self, value: f64) -> f64 {
+        match self {
+            LengthUnit::Meters => value,
+            LengthUnit::Feet => value * 0.3048,
+            LengthUnit::UsSurveyFeet => value * 1200.0 / 3937.0,
+        }
+    }
+}
+
+/// The unit of a point field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// Seconds, for times.
+    Seconds,
+    /// Meters, for altitudes and distances.
+    Meters,
+    /// Radians, for positions and attitudes.
+    Radians,
+    /// Meters per second, for velocities.
+    MetersPerSecond,
+    /// Meters per second squared, for accelerations.
+    MetersPerSecondSquared,
+    /// Radians per second, for angular rates.
+    RadiansPerSecond,
+}
+
+impl Unit {
+    /// Returns this unit's symbol.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pos::units::Unit;
+    /// assert_eq!("m/s", Unit::MetersPerSecond.symbol());
+    /// ```
+    pub fn symbol(&self) -> &'static str {
+        match *self {
+            Unit::Seconds => "s",
+            Unit::Meters => "m",
+            Unit::Radians => "rad",
+            Unit::MetersPerSecond => "m/s",
+            Unit::MetersPerSecondSquared => "m/s^2",
+            Unit::RadiansPerSecond => "rad/s",
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+impl FromStr for AngleUnit {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<AngleUnit, Error> {
+        match s.to_lowercase().as_str() {
+            "degrees" | "deg" => Ok(AngleUnit::Degrees),
+            "radians" | "rad" => Ok(AngleUnit::Radians),
+            _ => Err(Error::UnknownUnit(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for LengthUnit {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<LengthUnit, Error> {
+        match s.to_lowercase().as_str() {
+            "meters" | "m" => Ok(LengthUnit::Meters),
+            "feet" | "ft" => Ok(LengthUnit::Feet),
+            "us-feet" | "us-ft" => Ok(LengthUnit::UsSurveyFeet),
+            _ => Err(Error::UnknownUnit(s.to_string())),
+        }
+    }
 }
 
 impl Add for Radians<f64> {
@@ -0,0 +1,428 @@
+//! Verify the structural integrity of position files.
+//!
+//! [verify()] checks a file without converting it, for validating archives as they're ingested.
+//! Every problem found is returned as an [Issue] in a [Report], rather than stopping at the
+//! first one. With the `serde` feature, reports can be serialized.
+//!
+//! The checks are:
+//!
+//! - the length of a binary file is consistent with its record size and header,
+//! - a pof header's point count and bounds match its points,
+//! - times increase from one point to the next,
+//! - every value is finite, latitudes and longitudes are in range, and altitudes are between
+//!   [MIN_ALTITUDE] and [MAX_ALTITUDE].
+
+use crate::format::Format;
+#[cfg(any(feature = "pof", feature = "pos", feature = "sbet"))]
+use crate::point::Point;
+use crate::Error;
+use std::fs::File;
+#[cfg(feature = "pos")]
+use std::io::BufRead;
+#[cfg(any(feature = "pof", feature = "pos", feature = "sbet"))]
+use std::io::BufReader;
+#[cfg(any(feature = "pof", feature = "sbet"))]
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// The lowest plausible altitude, in meters.
+pub const MIN_ALTITUDE: f64 = -1_000.0;
+
+/// The highest plausible altitude, in meters.
+pub const MAX_ALTITUDE: f64 = 100_000.0;
+
+/// How close, in degrees or meters, a pof header's bounds must be to its points' bounds.
+#[cfg(feature = "pof")]
+const BOUNDS_TOLERANCE: f64 = 1e-6;
+
+/// The result of verifying a file.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// The number of records that were checked.
+    pub records: u64,
+    /// Every problem found, in the order they were found.
+    pub issues: Vec<Issue>,
+}
+
+/// A problem found while verifying a file.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Issue {
+    /// The file's length isn't what its record size and header say it should be.
+    Length {
+        /// The expected length, in bytes.
+        expected: u64,
+        /// The actual length, in bytes.
+        actual: u64,
+    },
+    /// A header field doesn't match the points.
+    Header {
+        /// The name of the header field.
+        field: String,
+        /// The value in the header.
+        header: f64,
+        /// The value computed from the points.
+        actual: f64,
+    },
+    /// A point's time is not greater than the time of the point before it.
+    NonMonotonic {
+        /// The zero-based index of the record.
+        record: u64,
+        /// The time of the record.
+        time: f64,
+        /// The time of the record before it.
+        previous_time: f64,
+    },
+    /// A value is not finite or is out of its plausible range.
+    Implausible {
+        /// The zero-based index of the record.
+        record: u64,
+        /// The name of the field, as in [Point::FIELDS].
+        field: String,
+        /// The value.
+        value: f64,
+    },
+    /// A record could not be read.
+    Unreadable {
+        /// The zero-based index of the record.
+        record: u64,
+        /// A description of the error.
+        message: String,
+    },
+}
+
+impl Report {
+    /// Returns true if no issues were found.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Checks every point of a file, in order.
+#[cfg(any(feature = "pof", feature = "pos", feature = "sbet"))]
+#[derive(Debug, Default)]
+struct Checker {
+    records: u64,
+    issues: Vec<Issue>,
+    previous_time: Option<f64>,
+}
+
+#[cfg(any(feature = "pof", feature = "pos", feature = "sbet"))]
+impl Checker {
+    fn add(&mut self, point: &Point) {
+        let record = self.records;
+        if let Some(previous_time) = self.previous_time {
+            if point.time <= previous_time {
+                self.issues.push(Issue::NonMonotonic {
+                    record,
+                    time: point.time,
+                    previous_time,
+                });
+            }
+        }
+        self.previous_time = Some(point.time);
+        for (field, value, _) in point.fields() {
+            let plausible = value.is_finite()
+                && match field {
+                    "latitude" => value.abs() <= std::f64::consts::FRAC_PI_2,
+                    "longitude" => value.abs() <= 2.0 * std::f64::consts::PI,
+                    "altitude" => (MIN_ALTITUDE..=MAX_ALTITUDE).contains(&value),
+                    _ => true,
+                };
+            if !plausible {
+                self.issues.push(Issue::Implausible {
+                    record,
+                    field: field.to_string(),
+                    value,
+                });
+            }
+        }
+        self.records += 1;
+    }
+
+    fn unreadable(&mut self, err: Error) {
+        self.issues.push(Issue::Unreadable {
+            record: self.records,
+            message: err.to_string(),
+        });
+        self.records += 1;
+    }
+
+    fn report(self) -> Report {
+        Report {
+            records: self.records,
+            issues: self.issues,
+        }
+    }
+}
+
+/// Verifies a file of the given format.
+///
+/// Returns an error only if the file can't be opened or its header can't be read. Returns
+/// [Error::DisabledFormat] if the format's cargo feature isn't enabled, and
+/// [Error::UnsupportedFormat] for formats other than sbet, pof, and pos.
+///
+/// # Examples
+///
+/// ```
+/// use pos::verify;
+/// use pos::Format;
+/// let report = verify::verify(Format::Sbet, "data/2-points.sbet").unwrap();
+/// assert_eq!(2, report.records);
+/// assert!(report.is_ok());
+/// ```
+pub fn verify<P: AsRef<Path>>(format: Format, path: P) -> Result<Report, Error> {
+    let file = File::open(path)?;
+    match format {
+        #[cfg(feature = "pos")]
+        Format::Pos => pos(BufReader::new(file)),
+        #[cfg(feature = "sbet")]
+        Format::Sbet => sbet(BufReader::new(file)),
+        #[cfg(feature = "pof")]
+        Format::Pof => pof(BufReader::new(file)),
+        #[allow(unreachable_patterns)]
+        Format::Pos | Format::Sbet | Format::Pof => {
+            let _ = file;
+            Err(Error::DisabledFormat(format))
+        }
+        format => {
+            let _ = file;
+            Err(Error::UnsupportedFormat(format, "verification"))
+        }
+    }
+}
+
+/// Verifies an sbet file.
+///
+/// Any bytes after the last complete record are reported as a length issue.
+#[cfg(feature = "sbet")]
+pub fn sbet<R: Read + Seek>(mut read: R) -> Result<Report, Error> {
+    let length = read.seek(SeekFrom::End(0))?;
+    let _ = read.seek(SeekFrom::Start(0))?;
+    let mut checker = Checker::default();
+    let records = length / crate::sbet::RECORD_SIZE;
+    if length % crate::sbet::RECORD_SIZE != 0 {
+        checker.issues.push(Issue::Length {
+            expected: records * crate::sbet::RECORD_SIZE,
+            actual: length,
+        });
+    }
+    let mut reader = crate::sbet::Reader::new(read);
+    for _ in 0..records {
+        match reader.read_point() {
+            Ok(Some(point)) => checker.add(&point),
+            Ok(None) => break,
+            Err(err) => {
+                checker.unreadable(err.into());
+                break;
+            }
+        }
+    }
+    Ok(checker.report())
+}
+
+/// Verifies a pof file.
+///
+/// The file must be long enough to hold every point its header declares, and the header's
+/// bounds must match those of the points. Bytes after the last point are allowed, since they
+/// can hold a trailer.
+#[cfg(feature = "pof")]
+pub fn pof<R: Read + Seek>(mut read: R) -> Result<Report, Error> {
+    let length = read.seek(SeekFrom::End(0))?;
+    let _ = read.seek(SeekFrom::Start(0))?;
+    let mut reader = crate::pof::Reader::new(read)?;
+    let mut checker = Checker::default();
+    let record_size = reader.version.record_size();
+    let data_offset = u64::from(reader.data_offset);
+    let available = length.saturating_sub(data_offset) / record_size;
+    let entries = reader.entries.max(0) as u64;
+    if reader.entries < 0 {
+        checker.issues.push(Issue::Header {
+            field: "entries".to_string(),
+            header: reader.entries as f64,
+            actual: available as f64,
+        });
+    } else if available < entries {
+        checker.issues.push(Issue::Length {
+            expected: data_offset + entries * record_size,
+            actual: length,
+        });
+    }
+    let header = [
+        ("minlon", reader.minlon),
+        ("maxlon", reader.maxlon),
+        ("minlat", reader.minlat),
+        ("maxlat", reader.maxlat),
+        ("minalt", reader.minalt),
+        ("maxalt", reader.maxalt),
+    ];
+    let mut bounds: Option<[f64; 6]> = None;
+    for _ in 0..entries.min(available) {
+        match reader.read_point() {
+            Ok(Some(point)) => {
+                checker.add(&point);
+                let (longitude, latitude) = (point.longitude_degrees(), point.latitude_degrees());
+                let bounds = bounds.get_or_insert([
+                    longitude,
+                    longitude,
+                    latitude,
+                    latitude,
+                    point.altitude,
+                    point.altitude,
+                ]);
+                bounds[0] = bounds[0].min(longitude);
+                bounds[1] = bounds[1].max(longitude);
+                bounds[2] = bounds[2].min(latitude);
+                bounds[3] = bounds[3].max(latitude);
+                bounds[4] = bounds[4].min(point.altitude);
+                bounds[5] = bounds[5].max(point.altitude);
+            }
+            Ok(None) => break,
+            Err(err) => {
+                checker.unreadable(err);
+                break;
+            }
+        }
+    }
+    if let Some(bounds) = bounds {
+        for ((field, header), actual) in header.into_iter().zip(bounds) {
+            if (header - actual).abs() > BOUNDS_TOLERANCE {
+                checker.issues.push(Issue::Header {
+                    field: field.to_string(),
+                    header,
+                    actual,
+                });
+            }
+        }
+    }
+    Ok(checker.report())
+}
+
+/// Verifies an ASCII pos file.
+///
+/// Lines that can't be parsed are reported as unreadable, and checking continues with the next
+/// line.
+#[cfg(feature = "pos")]
+pub fn pos<R: BufRead>(read: R) -> Result<Report, Error> {
+    let mut lines = read.lines();
+    let _ = lines.next().transpose()?;
+    let mut checker = Checker::default();
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.split_whitespace().count() < 7 {
+            checker.issues.push(Issue::Unreadable {
+                record: checker.records,
+                message: "Expected at least seven values".to_string(),
+            });
+            checker.records += 1;
+            continue;
+        }
+        let bytes = format!("\n{}", line);
+        match crate::pos::Reader::new(bytes.as_bytes())?.read_point() {
+            Ok(Some(point)) => checker.add(&point),
+            Ok(None) => {}
+            Err(err) => checker.unreadable(err),
+        }
+    }
+    Ok(checker.report())
+}
+
+#[cfg(all(test, any(feature = "pof", feature = "pos", feature = "sbet")))]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "pof", feature = "sbet"))]
+    use crate::units::Radians;
+    #[cfg(any(feature = "pof", feature = "sbet"))]
+    use std::io::Cursor;
+
+    #[cfg(any(feature = "pof", feature = "sbet"))]
+    fn points() -> Vec<Point> {
+        (0..4)
+            .map(|i| Point {
+                time: i as f64,
+                latitude: Radians::from_degrees(45.0),
+                longitude: Radians::from_degrees(-105.0 + 0.001 * i as f64),
+                altitude: 1000.0,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    #[cfg(feature = "sbet")]
+    fn sbet_issues() {
+        let mut points = points();
+        points[2].time = 0.5;
+        points[3].altitude = f64::NAN;
+        let mut bytes = crate::testutil::sbet(&points);
+        bytes.extend_from_slice(&[0; 10]);
+        let report = sbet(Cursor::new(bytes)).unwrap();
+        assert_eq!(4, report.records);
+        assert_eq!(
+            Issue::Length {
+                expected: 4 * 136,
+                actual: 4 * 136 + 10
+            },
+            report.issues[0]
+        );
+        assert_eq!(
+            Issue::NonMonotonic {
+                record: 2,
+                time: 0.5,
+                previous_time: 1.0
+            },
+            report.issues[1]
+        );
+        assert!(matches!(
+            &report.issues[2],
+            Issue::Implausible { record: 3, field, .. } if field == "altitude"
+        ));
+        assert_eq!(3, report.issues.len());
+    }
+
+    #[test]
+    #[cfg(feature = "pof")]
+    fn pof_ok() {
+        let report = pof(Cursor::new(crate::testutil::pof(&points()))).unwrap();
+        assert_eq!(4, report.records);
+        assert!(report.is_ok(), "{:?}", report.issues);
+    }
+
+    #[test]
+    #[cfg(feature = "pof")]
+    fn pof_truncated() {
+        let mut bytes = crate::testutil::pof(&points());
+        bytes.truncate(bytes.len() - 8);
+        let report = pof(Cursor::new(bytes)).unwrap();
+        assert_eq!(3, report.records);
+        assert!(matches!(report.issues[0], Issue::Length { .. }));
+        assert!(matches!(
+            &report.issues[1],
+            Issue::Header { field, .. } if field == "maxlon"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "pos")]
+    fn pos_unreadable() {
+        let bytes = b"header\n0 45 -105 1000 0 0 0\nnot a number\n1 x -105 1000 0 0 0\n2 95 -105 1000 0 0 0\n";
+        let report = pos(&bytes[..]).unwrap();
+        assert_eq!(4, report.records);
+        assert!(matches!(
+            report.issues[0],
+            Issue::Unreadable { record: 1, .. }
+        ));
+        assert!(matches!(
+            report.issues[1],
+            Issue::Unreadable { record: 2, .. }
+        ));
+        assert!(matches!(
+            &report.issues[2],
+            Issue::Implausible { record: 3, field, .. } if field == "latitude"
+        ));
+    }
+}
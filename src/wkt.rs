@@ -0,0 +1,159 @@
+//! Export flightlines as [well-known text](https://en.wikipedia.org/wiki/Well-known_text_representation_of_geometry).
+//!
+//! A trajectory becomes a single `LINESTRING Z` of longitude, latitude, and altitude, in degrees
+//! and meters. With an SRID, the line is written as PostGIS's extended WKT, e.g.
+//! `SRID=4326;LINESTRING Z (...)`, which `ST_GeomFromEWKT` can load directly. With the `proj`
+//! feature, [write_transformed] writes the line in another CRS.
+
+#[cfg(feature = "proj")]
+use crate::crs::Transformer;
+use crate::point::Point;
+use crate::Error;
+use std::io::Write;
+
+/// Writes points as a `LINESTRING Z`, with an optional SRID.
+///
+/// An empty iterator is written as `LINESTRING Z EMPTY`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pos::sbet::Reader;
+/// use pos::{wkt, Trajectory};
+/// use std::fs::File;
+/// let mut reader = Reader::from_path("mission.sbet").unwrap();
+/// let trajectory = Trajectory::from_source(&mut reader).unwrap();
+/// let file = File::create("mission.wkt").unwrap();
+/// wkt::write(file, trajectory.points(), Some(4326)).unwrap();
+/// ```
+pub fn write<'a, W, I>(write: W, points: I, srid: Option<u32>) -> Result<(), Error>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Point>,
+{
+    write_coordinates(
+        write,
+        points.into_iter().map(|point| {
+            Ok((
+                point.longitude_degrees(),
+                point.latitude_degrees(),
+                point.altitude,
+            ))
+        }),
+        srid,
+    )
+}
+
+/// Writes points as a `LINESTRING Z` in another CRS, with an optional SRID.
+///
+/// The SRID should be the EPSG code of the transformer's target CRS. Altitudes are written
+/// unchanged, so a datum shift doesn't change heights.
+///
+/// # Examples
+///
+/// ```
+/// use pos::crs::Transformer;
+/// use pos::{wkt, Point, Radians};
+/// let point = Point {
+///     longitude: Radians::from_degrees(-105.0),
+///     latitude: Radians::from_degrees(40.0),
+///     ..Default::default()
+/// };
+/// let transformer = Transformer::new("EPSG:32613").unwrap();
+/// let mut bytes = Vec::new();
+/// wkt::write_transformed(&mut bytes, &[point], &transformer, Some(32613)).unwrap();
+/// assert!(String::from_utf8(bytes).unwrap().starts_with("SRID=32613;LINESTRING Z ("));
+/// ```
+#[cfg(feature = "proj")]
+pub fn write_transformed<'a, W, I>(
+    write: W,
+    points: I,
+    transformer: &Transformer,
+    srid: Option<u32>,
+) -> Result<(), Error>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Point>,
+{
+    write_coordinates(
+        write,
+        points.into_iter().map(|point| {
+            let transformed = transformer.transform(point)?;
+            Ok((transformed.x, transformed.y, transformed.z))
+        }),
+        srid,
+    )
+}
+
+fn write_coordinates<W, I>(mut write: W, coordinates: I, srid: Option<u32>) -> Result<(), Error>
+where
+    W: Write,
+    I: IntoIterator<Item = Result<(f64, f64, f64), Error>>,
+{
+    if let Some(srid) = srid {
+        write!(write, "SRID={};", srid)?;
+    }
+    write!(write, "LINESTRING Z ")?;
+    let mut empty = true;
+    for coordinate in coordinates {
+        let (x, y, z) = coordinate?;
+        write!(write, "{}{} {} {}", if empty { "(" } else { ", " }, x, y, z)?;
+        empty = false;
+    }
+    write.write_all(if empty { b"EMPTY" } else { b")" })?;
+    Ok(())
+}
+
+/// Returns points as a `LINESTRING Z` string, with an optional SRID.
+///
+/// # Examples
+///
+/// ```
+/// use pos::{wkt, Point, Radians};
+/// let point = Point {
+///     longitude: Radians::from_degrees(-105.0),
+///     latitude: Radians::from_degrees(40.0),
+///     altitude: 1600.0,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     "SRID=4326;LINESTRING Z (-105 40 1600, -105 40 1600)",
+///     wkt::to_wkt(&[point, point], Some(4326))
+/// );
+/// ```
+pub fn to_wkt<'a, I>(points: I, srid: Option<u32>) -> String
+where
+    I: IntoIterator<Item = &'a Point>,
+{
+    let mut bytes = Vec::new();
+    write(&mut bytes, points, srid).expect("writing to a vector doesn't fail");
+    String::from_utf8(bytes).expect("well-known text is utf-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Radians;
+
+    #[test]
+    fn line_string() {
+        let points: Vec<_> = (0..3)
+            .map(|i| Point {
+                longitude: Radians::from_degrees(-105.0),
+                latitude: Radians::from_degrees(40.0),
+                altitude: 1000.0 + i as f64,
+                ..Default::default()
+            })
+            .collect();
+        assert_eq!(
+            "LINESTRING Z (-105 40 1000, -105 40 1001, -105 40 1002)",
+            to_wkt(&points, None)
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!("LINESTRING Z EMPTY", to_wkt(&[], None));
+        assert_eq!("SRID=4979;LINESTRING Z EMPTY", to_wkt(&[], Some(4979)));
+    }
+}